@@ -0,0 +1,349 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+#![forbid(unsafe_code)]
+
+#![deny(
+    non_camel_case_types,
+    non_snake_case,
+    path_statements,
+    trivial_numeric_casts,
+    unstable_features,
+    unused_allocation,
+    unused_import_braces,
+    unused_imports,
+    unused_must_use,
+    unused_mut,
+    unused_qualifications,
+    while_true,
+)]
+
+extern crate clap;
+#[macro_use]
+extern crate log;
+extern crate failure;
+
+extern crate libimagentryannotation;
+extern crate libimagentryedit;
+extern crate libimagerror;
+extern crate libimagrt;
+extern crate libimagstore;
+extern crate libimagutil;
+
+use std::io::Write;
+
+use clap::App;
+use clap::ArgMatches;
+
+use failure::Error;
+use failure::Fallible as Result;
+
+use libimagentryannotation::anchor::Anchor;
+use libimagentryannotation::annotateable::*;
+use libimagentryannotation::annotation::Annotation;
+use libimagentryannotation::annotation_fetcher::*;
+use libimagentryedit::edit::*;
+use libimagerror::trace::MapErrTrace;
+use libimagerror::exit::ExitUnwrap;
+use libimagerror::io::ToExitCode;
+use libimagerror::errors::ErrorMsg as EM;
+use libimagrt::runtime::Runtime;
+use libimagrt::application::ImagApplication;
+use libimagstore::store::Entry;
+use libimagstore::store::FileLockEntry;
+use libimagutil::date::datetime_to_string;
+
+mod ui;
+
+/// Marker enum for implementing ImagApplication on
+///
+/// This is used by binaries crates to execute business logic
+/// or to build a CLI completion.
+pub enum ImagAnnotate {}
+impl ImagApplication for ImagAnnotate {
+    fn run(rt: &Runtime) -> Result<()> {
+        rt.cli()
+            .subcommand_name()
+            .map(|name| {
+                match name {
+                    "add"    => add(&rt),
+                    "reply"  => reply(&rt),
+                    "remove" => remove(&rt),
+                    "list"   => list(&rt),
+                    other    => {
+                        debug!("Unknown command");
+                        let _ = rt.handle_unknown_subcommand("imag-annotation", other, rt.cli())
+                            .map_err_trace_exit_unwrap(1)
+                            .code()
+                            .map(::libimagerror::exit::exit);
+                    },
+                }
+            });
+
+        Ok(())
+    }
+
+    fn build_cli<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
+        ui::build_ui(app)
+    }
+
+    fn name() -> &'static str {
+        env!("CARGO_PKG_NAME")
+    }
+
+    fn description() -> &'static str {
+        "Add annotations to entries"
+    }
+
+    fn version() -> &'static str {
+        env!("CARGO_PKG_VERSION")
+    }
+}
+
+fn add(rt: &Runtime) {
+    let scmd            = rt.cli().subcommand_matches("add").unwrap(); // safed by main()
+    let annotation_name = scmd.value_of("annotation_name").unwrap(); // safed by clap
+    let ids             = rt.ids::<::ui::PathProvider>().map_err_trace_exit_unwrap(1);
+
+    ids.into_iter().for_each(|id| {
+        let mut entry = rt.store()
+            .get(id.clone())
+            .map_err_trace_exit_unwrap(1)
+            .ok_or_else(|| EM::EntryNotFound(id.local_display_string()))
+            .map_err(Error::from)
+            .map_err_trace_exit_unwrap(1);
+
+        let anchor = anchor_from_args(scmd, &entry);
+
+        let mut annotation = entry
+            .annotate(rt.store(), annotation_name)
+            .map_err_trace_exit_unwrap(1);
+
+        if let Some(anchor) = anchor {
+            let _ = annotation.set_anchor(&anchor).map_err_trace_exit_unwrap(1);
+        }
+
+        let _ = annotation
+            .edit_content(&rt)
+            .map_err_trace_exit_unwrap(1);
+    })
+
+}
+
+/// Get the anchor passed on the commandline, making sure it can be found in `entry`
+fn anchor_from_args(scmd: &ArgMatches, entry: &Entry) -> Option<Anchor> {
+    let anchor = if let Some(lines) = scmd.value_of("anchor-lines") {
+        Anchor::parse_lines(lines).map_err_trace_exit_unwrap(1)
+    } else if let Some(quote) = scmd.value_of("anchor-quote") {
+        Anchor::snippet(quote).map_err_trace_exit_unwrap(1)
+    } else {
+        return None
+    };
+
+    if anchor.locate(entry.get_content()).is_none() {
+        error!("Cannot anchor annotation to {}, not found in {}", anchor, entry.get_location());
+        ::libimagerror::exit::exit(1)
+    }
+
+    Some(anchor)
+}
+
+fn reply(rt: &Runtime) {
+    let scmd            = rt.cli().subcommand_matches("reply").unwrap(); // safed by main()
+    let annotation_name = scmd.value_of("annotation_name").unwrap(); // safed by clap
+    let ids             = rt.ids::<::ui::PathProvider>().map_err_trace_exit_unwrap(1);
+
+    ids.into_iter().for_each(|id| {
+        let _ = rt.store()
+            .get(id.clone())
+            .map_err_trace_exit_unwrap(1)
+            .ok_or_else(|| EM::EntryNotFound(id.local_display_string()))
+            .map_err(Error::from)
+            .map_err_trace_exit_unwrap(1)
+            .reply(rt.store(), annotation_name)
+            .map_err_trace_exit_unwrap(1)
+            .edit_content(&rt)
+            .map_err_trace_exit_unwrap(1);
+    })
+}
+
+fn remove(rt: &Runtime) {
+    let scmd            = rt.cli().subcommand_matches("remove").unwrap(); // safed by main()
+    let annotation_name = scmd.value_of("annotation_name").unwrap(); // safed by clap
+    let delete          = scmd.is_present("delete-annotation");
+    let ids       = rt.ids::<::ui::PathProvider>().map_err_trace_exit_unwrap(1);
+
+    ids.into_iter().for_each(|id| {
+        let mut entry = rt.store()
+            .get(id.clone())
+            .map_err_trace_exit_unwrap(1)
+            .ok_or_else(|| EM::EntryNotFound(id.local_display_string()))
+            .map_err(Error::from)
+            .map_err_trace_exit_unwrap(1);
+
+        let annotation = entry
+            .denotate(rt.store(), annotation_name)
+            .map_err_trace_exit_unwrap(1);
+
+        if delete {
+            debug!("Deleting annotation object");
+            if let Some(an) = annotation {
+                let loc = an.get_location().clone();
+                drop(an);
+
+                let _ = rt
+                    .store()
+                    .delete(loc)
+                    .map_err_trace_exit_unwrap(1);
+            } else {
+                warn!("Not having annotation object, cannot delete!");
+            }
+        } else {
+            debug!("Not deleting annotation object");
+        }
+    })
+
+}
+
+fn list(rt: &Runtime) {
+    let scmd      = rt.cli().subcommand_matches("list").unwrap(); // safed by clap
+    let with_text = scmd.is_present("list-with-text");
+    let ids       = rt.ids::<::ui::PathProvider>().map_err_trace_exit_unwrap(1);
+
+    if ids.len() != 0 {
+        let _ = ids
+            .into_iter()
+            .for_each(|id| {
+                let entry = rt
+                    .store()
+                    .get(id.clone())
+                    .map_err_trace_exit_unwrap(1)
+                    .ok_or_else(|| EM::EntryNotFound(id.local_display_string()))
+                    .map_err(Error::from)
+                    .map_err_trace_exit_unwrap(1);
+
+                // If the entry is a reply itself, the annotation it replies to is linked as well,
+                // but it is not an annotation of the entry
+                let parent = entry
+                    .get_reply_to()
+                    .map_err_trace_exit_unwrap(1)
+                    .map(|id| id.local().clone());
+
+                let mut annotations = entry
+                    .annotations(rt.store())
+                    .map_err_trace_exit_unwrap(1)
+                    .map(|a| a.map_err_trace_exit_unwrap(1))
+                    .filter(|a| Some(a.get_location().local()) != parent.as_ref())
+                    .collect::<Vec<_>>();
+
+                annotations.sort_by_key(|a| a.get_created().map_err_trace_exit_unwrap(1));
+
+                annotations
+                    .iter()
+                    .enumerate()
+                    .for_each(|(i, a)| {
+                        list_annotation_in_context(&rt, entry.get_content(), i, a, with_text, 0)
+                    });
+            });
+    } else { // ids.len() == 0
+        // show them all
+        let _ = rt
+            .store()
+            .all_annotations()
+            .map_err_trace_exit_unwrap(1)
+            .enumerate()
+            .map(|(i, a)| {
+                list_annotation(&rt, i, a.map_err_trace_exit_unwrap(1), with_text)
+            })
+            .collect::<Vec<_>>();
+    }
+}
+
+fn list_annotation<'a>(rt: &Runtime, i: usize, a: FileLockEntry<'a>, with_text: bool) {
+    let created = created_string(&a);
+    let _ = if with_text {
+        writeln!(rt.stdout(),
+                 "--- {i: >5} | {id} | {created}\n{text}\n\n",
+                 i = i,
+                 id = a.get_location(),
+                 created = created,
+                 text = a.get_content())
+    } else {
+        writeln!(rt.stdout(), "{: >5} | {} | {}", i, a.get_location(), created)
+    }
+    .to_exit_code()
+    .unwrap_or_exit();
+}
+
+/// Print an annotation next to the passage of `content` it is anchored to, followed by the replies
+/// to the annotation, indented by `depth` levels
+fn list_annotation_in_context(rt: &Runtime, content: &str, i: usize, a: &Entry, with_text: bool, depth: usize) {
+    let out         = rt.stdout();
+    let mut outlock = out.lock();
+    let indent      = "    ".repeat(depth);
+
+    writeln!(outlock, "{}--- {: >5} | {} | {}", indent, i, a.get_location(), created_string(a))
+        .to_exit_code()
+        .unwrap_or_exit();
+
+    if let Some(anchor) = a.get_anchor().map_err_trace_exit_unwrap(1) {
+        match anchor.locate(content) {
+            Some((start, end)) => content
+                .lines()
+                .enumerate()
+                .skip(start - 1)
+                .take(end - start + 1)
+                .for_each(|(n, line)| {
+                    writeln!(outlock, "{}{: >9} > {}", indent, n + 1, line)
+                        .to_exit_code()
+                        .unwrap_or_exit();
+                }),
+
+            None => writeln!(outlock, "{}          (anchored to {}, which was not found)", indent, anchor)
+                .to_exit_code()
+                .unwrap_or_exit(),
+        }
+    }
+
+    if with_text {
+        a.get_content()
+            .lines()
+            .for_each(|line| {
+                writeln!(outlock, "{}          {}", indent, line)
+                    .to_exit_code()
+                    .unwrap_or_exit();
+            });
+    }
+
+    drop(outlock);
+
+    a.replies(rt.store())
+        .map_err_trace_exit_unwrap(1)
+        .iter()
+        .enumerate()
+        .for_each(|(j, reply)| list_annotation_in_context(rt, content, j, reply, with_text, depth + 1));
+}
+
+fn created_string(a: &Entry) -> String {
+    a.get_created()
+        .map_err_trace_exit_unwrap(1)
+        .map(|c| datetime_to_string(&c))
+        .unwrap_or_else(|| String::from("unknown date"))
+}
+
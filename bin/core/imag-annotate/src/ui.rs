@@ -45,6 +45,42 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
                          .multiple(false)
                          .help("Name of the new annotation")
                          .value_name("NAME"))
+                    .arg(Arg::with_name("anchor-lines")
+                         .long("lines")
+                         .short("l")
+                         .takes_value(true)
+                         .required(false)
+                         .multiple(false)
+                         .conflicts_with("anchor-quote")
+                         .help("Anchor the annotation to these lines of the entry, either a single line (\"12\") or a range (\"12-14\")")
+                         .value_name("LINES"))
+                    .arg(Arg::with_name("anchor-quote")
+                         .long("quote")
+                         .short("q")
+                         .takes_value(true)
+                         .required(false)
+                         .multiple(false)
+                         .help("Anchor the annotation to this snippet of the content of the entry")
+                         .value_name("TEXT"))
+                   )
+
+        .subcommand(SubCommand::with_name("reply")
+                    .about("Reply to an annotation")
+                    .version("0.1")
+                    .arg(Arg::with_name("entry")
+                         .index(1)
+                         .takes_value(true)
+                         .required(true)
+                         .multiple(false)
+                         .help("The annotation to reply to")
+                         .value_name("ANNOTATION"))
+                    .arg(Arg::with_name("annotation_name")
+                         .index(2)
+                         .takes_value(true)
+                         .required(true)
+                         .multiple(false)
+                         .help("Name of the new annotation")
+                         .value_name("NAME"))
                    )
 
         .subcommand(SubCommand::with_name("remove")
@@ -81,7 +117,7 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
                          .takes_value(true)
                          .required(false)
                          .multiple(false)
-                         .help("The entry to list annotations for (all annotations if not passed)")
+                         .help("The entry to list annotations for, shown next to the passages they are anchored to, with replies (all annotations if not passed)")
                          .value_name("ENTRY"))
                     .arg(Arg::with_name("list-with-text")
                          .long("text")
@@ -101,7 +137,21 @@ impl IdPathProvider for PathProvider {
                 subm.values_of("entry")
                     .ok_or_else(|| {
                         error!("No StoreId found");
-                        ::std::process::exit(1)
+                        ::libimagerror::exit::exit(1)
+                    })
+                    .unwrap()
+                    .into_iter()
+                    .map(PathBuf::from)
+                    .map(|pb| pb.into_storeid())
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err_trace_exit_unwrap(1)
+            },
+
+            ("reply", Some(subm)) => {
+                subm.values_of("entry")
+                    .ok_or_else(|| {
+                        error!("No StoreId found");
+                        ::libimagerror::exit::exit(1)
                     })
                     .unwrap()
                     .into_iter()
@@ -115,7 +165,7 @@ impl IdPathProvider for PathProvider {
                 subm.values_of("entry")
                     .ok_or_else(|| {
                         error!("No StoreId found");
-                        ::std::process::exit(1)
+                        ::libimagerror::exit::exit(1)
                     })
                     .unwrap()
                     .into_iter()
@@ -129,7 +179,7 @@ impl IdPathProvider for PathProvider {
                 subm.values_of("entry")
                     .ok_or_else(|| {
                         error!("No StoreId found");
-                        ::std::process::exit(1)
+                        ::libimagerror::exit::exit(1)
                     })
                     .unwrap()
                     .into_iter()
@@ -141,7 +191,7 @@ impl IdPathProvider for PathProvider {
 
             (other, _) => {
                 error!("Not a known command: {}", other);
-                ::std::process::exit(1)
+                ::libimagerror::exit::exit(1)
             }
         }
     }
@@ -0,0 +1,293 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+#![forbid(unsafe_code)]
+
+#![deny(
+    non_camel_case_types,
+    non_snake_case,
+    path_statements,
+    trivial_numeric_casts,
+    unstable_features,
+    unused_allocation,
+    unused_import_braces,
+    unused_imports,
+    unused_must_use,
+    unused_mut,
+    unused_qualifications,
+    while_true,
+)]
+
+extern crate clap;
+extern crate failure;
+#[macro_use]
+extern crate log;
+
+extern crate libimagentrycategory;
+extern crate libimagerror;
+extern crate libimagrt;
+extern crate libimagstore;
+extern crate libimaginteraction;
+
+use clap::App;
+use failure::Fallible as Result;
+
+use libimagerror::trace::MapErrTrace;
+use libimagerror::exit::ExitUnwrap;
+use libimagerror::io::ToExitCode;
+use libimagrt::runtime::Runtime;
+use libimagrt::application::ImagApplication;
+use libimagrt::completion;
+
+mod ui;
+
+use std::collections::BTreeMap;
+use std::io::Write;
+
+use libimagentrycategory::store::CategoryStore;
+use libimagstore::storeid::StoreIdIterator;
+use libimagstore::iter::get::StoreIdGetIteratorExtension;
+use libimagerror::iter::TraceIterator;
+use libimagentrycategory::entry::EntryCategory;
+use libimagentrycategory::category::Category;
+use libimagentrycategory::category::Constraints;
+
+/// Marker enum for implementing ImagApplication on
+///
+/// This is used by binaries crates to execute business logic
+/// or to build a CLI completion.
+pub enum ImagCategory {}
+impl ImagApplication for ImagCategory {
+    fn run(rt: &Runtime) -> Result<()> {
+        rt.cli()
+            .subcommand_name()
+            .map(|name| {
+                match name {
+                    "set"               => set(&rt),
+                    "get"               => get(&rt),
+                    "list-category"     => list_category(&rt),
+                    "create-category"   => create_category(&rt),
+                    "move-category"     => move_category(&rt),
+                    "delete-category"   => delete_category(&rt),
+                    "list-categories"   => list_categories(&rt),
+                    other               => {
+                        debug!("Unknown command");
+                        let _ = rt.handle_unknown_subcommand("imag-category", other, rt.cli())
+                            .map_err_trace_exit_unwrap(1)
+                            .code()
+                            .map(::libimagerror::exit::exit);
+                    },
+                }
+            });
+
+        Ok(())
+    }
+
+    fn build_cli<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
+        ui::build_ui(app)
+    }
+
+    fn name() -> &'static str {
+        env!("CARGO_PKG_NAME")
+    }
+
+    fn description() -> &'static str {
+        "Add a category to entries and manage categories"
+    }
+
+    fn version() -> &'static str {
+        env!("CARGO_PKG_VERSION")
+    }
+
+    fn completions(rt: &Runtime, kind: &str) -> Result<Vec<String>> {
+        match kind {
+            "categories" => rt.store().all_category_names()?.collect(),
+            _            => completion::default_completions(rt, kind),
+        }
+    }
+}
+
+fn set(rt: &Runtime) {
+    let scmd = rt.cli().subcommand_matches("set").unwrap(); // safed by main()
+    let name = scmd.value_of("set-name").map(String::from).unwrap(); // safed by clap
+    let sids = rt.ids::<::ui::PathProvider>().map_err_trace_exit_unwrap(1);
+
+    StoreIdIterator::new(Box::new(sids.into_iter().map(Ok)))
+        .into_get_iter(rt.store())
+        .trace_unwrap_exit(1)
+        .map(|o| o.unwrap_or_else(|| {
+            error!("Did not find one entry");
+            ::libimagerror::exit::exit(1)
+        }))
+        .for_each(|mut entry| {
+            let _ = entry
+                .set_category_checked(rt.store(), &name)
+                .map_err_trace_exit_unwrap(1);
+        })
+}
+
+fn get(rt: &Runtime) {
+    let sids        = rt.ids::<::ui::PathProvider>().map_err_trace_exit_unwrap(1);
+    let out         = rt.stdout();
+    let mut outlock = out.lock();
+
+    StoreIdIterator::new(Box::new(sids.into_iter().map(Ok)))
+        .into_get_iter(rt.store())
+        .trace_unwrap_exit(1)
+        .map(|o| o.unwrap_or_else(|| {
+            error!("Did not find one entry");
+            ::libimagerror::exit::exit(1)
+        }))
+        .map(|entry| entry.get_category().map_err_trace_exit_unwrap(1))
+        .for_each(|name| {
+            let _ = writeln!(outlock, "{}", name).to_exit_code().unwrap_or_exit();
+        })
+}
+
+fn list_category(rt: &Runtime) {
+    let scmd = rt.cli().subcommand_matches("list-category").unwrap(); // safed by main()
+    let name = scmd.value_of("list-category-name").map(String::from).unwrap(); // safed by clap
+    let rec  = scmd.is_present("list-category-recursive");
+
+    if rt.store().get_category_by_name(&name).map_err_trace_exit_unwrap(1).is_none() {
+        info!("No category named '{}'", name);
+        ::libimagerror::exit::exit(1)
+    }
+
+    let out         = rt.stdout();
+    let mut outlock = out.lock();
+
+    rt.store()
+        .get_category_entries(&name, rec)
+        .map_err_trace_exit_unwrap(1)
+        .for_each(|entry| {
+            writeln!(outlock, "{}", entry.map_err_trace_exit_unwrap(1).get_location())
+                .to_exit_code()
+                .unwrap_or_exit();
+        })
+}
+
+fn create_category(rt: &Runtime) {
+    let scmd = rt.cli().subcommand_matches("create-category").unwrap(); // safed by main()
+    let name = scmd.value_of("create-category-name").map(String::from).unwrap(); // safed by clap
+    let vals = |arg: &str| {
+        scmd.values_of(arg)
+            .map(|vs| vs.map(String::from).collect())
+            .unwrap_or_else(Vec::new)
+    };
+    let constraints = Constraints::new(vals("create-category-require-field"),
+                                       vals("create-category-allow-collection"));
+
+    let mut category = match scmd.value_of("create-category-parent") {
+        Some(parent) => rt.store().create_subcategory(&name, parent),
+        None         => rt.store().create_category(&name),
+    }.map_err_trace_exit_unwrap(1);
+
+    if let Some(description) = scmd.value_of("create-category-description") {
+        let _ = category.set_description(description).map_err_trace_exit_unwrap(1);
+    }
+
+    if !constraints.is_empty() {
+        let _ = category.set_constraints(&constraints).map_err_trace_exit_unwrap(1);
+    }
+}
+
+fn move_category(rt: &Runtime) {
+    let scmd   = rt.cli().subcommand_matches("move-category").unwrap(); // safed by main()
+    let name   = scmd.value_of("move-category-name").unwrap(); // safed by clap
+    let parent = scmd.value_of("move-category-parent");
+
+    let _ = rt
+        .store()
+        .set_category_parent(name, parent)
+        .map_err_trace_exit_unwrap(1);
+}
+
+fn delete_category(rt: &Runtime) {
+    use libimaginteraction::ask::ask_bool;
+
+    let scmd   = rt.cli().subcommand_matches("delete-category").unwrap(); // safed by main()
+    let name   = scmd.value_of("delete-category-name").map(String::from).unwrap(); // safed by clap
+    let ques   = format!("Do you really want to delete category '{}' and remove links to all categorized enties?", name);
+
+    let mut input = rt.stdin().unwrap_or_else(|| {
+        error!("No input stream. Cannot ask for permission");
+        ::libimagerror::exit::exit(1)
+    });
+    let mut output = rt.stdout();
+    let answer = ask_bool(&ques, Some(false), &mut input, &mut output).map_err_trace_exit_unwrap(1);
+
+    if answer {
+        info!("Deleting category '{}'", name);
+        let _ = rt
+            .store()
+            .delete_category(&name)
+            .map_err_trace_exit_unwrap(1);
+    } else {
+        info!("Not doing anything");
+    }
+}
+
+fn list_categories(rt: &Runtime) {
+    let scmd        = rt.cli().subcommand_matches("list-categories").unwrap(); // safed by main()
+    let out         = rt.stdout();
+    let mut outlock = out.lock();
+
+    if scmd.is_present("list-categories-tree") {
+        let tree  = rt.store().get_category_tree().map_err_trace_exit_unwrap(1);
+        let roots = tree.get(&None).cloned().unwrap_or_else(Vec::new);
+
+        roots.iter().for_each(|name| print_category_tree(rt, &mut outlock, &tree, name, 0))
+    } else {
+        rt.store()
+            .all_category_names()
+            .map_err_trace_exit_unwrap(1)
+            .for_each(|name| {
+                writeln!(outlock, "{}", name.map_err_trace_exit_unwrap(1))
+                    .to_exit_code()
+                    .unwrap_or_exit();
+            })
+    }
+}
+
+/// Print a category and, indented below it, all its subcategories
+fn print_category_tree<W: Write>(rt: &Runtime,
+                                 out: &mut W,
+                                 tree: &BTreeMap<Option<String>, Vec<String>>,
+                                 name: &str,
+                                 depth: usize)
+{
+    let description = rt.store()
+        .get_category_by_name(name)
+        .map_err_trace_exit_unwrap(1)
+        .map(|category| category.get_description().map_err_trace_exit_unwrap(1))
+        .unwrap_or(None);
+
+    let indent = "  ".repeat(depth);
+    match description {
+        Some(d) => writeln!(out, "{}{} - {}", indent, name, d),
+        None    => writeln!(out, "{}{}", indent, name),
+    }.to_exit_code().unwrap_or_exit();
+
+    if let Some(children) = tree.get(&Some(String::from(name))) {
+        children
+            .iter()
+            .for_each(|child| print_category_tree(rt, out, tree, child, depth + 1));
+    }
+}
@@ -38,6 +38,57 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
                          .multiple(false)
                          .help("The name of the new category")
                          .value_name("NAME"))
+                    .arg(Arg::with_name("create-category-parent")
+                         .long("parent")
+                         .short("p")
+                         .takes_value(true)
+                         .required(false)
+                         .multiple(false)
+                         .help("Create the category as subcategory of this category")
+                         .value_name("PARENT"))
+                    .arg(Arg::with_name("create-category-description")
+                         .long("description")
+                         .short("d")
+                         .takes_value(true)
+                         .required(false)
+                         .multiple(false)
+                         .help("A description of the category")
+                         .value_name("DESCRIPTION"))
+                    .arg(Arg::with_name("create-category-require-field")
+                         .long("require-field")
+                         .takes_value(true)
+                         .required(false)
+                         .multiple(true)
+                         .number_of_values(1)
+                         .help("Only allow entries which have this header field (for example 'todo.due')")
+                         .value_name("FIELD"))
+                    .arg(Arg::with_name("create-category-allow-collection")
+                         .long("allow-collection")
+                         .takes_value(true)
+                         .required(false)
+                         .multiple(true)
+                         .number_of_values(1)
+                         .help("Only allow entries from this collection (for example 'notes'). Can be passed multiple times, defaults to all collections")
+                         .value_name("COLLECTION"))
+                   )
+
+        .subcommand(SubCommand::with_name("move-category")
+                    .about("Move a category below another category")
+                    .version("0.1")
+                    .arg(Arg::with_name("move-category-name")
+                         .index(1)
+                         .takes_value(true)
+                         .required(true)
+                         .multiple(false)
+                         .help("The name of the category to move")
+                         .value_name("NAME"))
+                    .arg(Arg::with_name("move-category-parent")
+                         .index(2)
+                         .takes_value(true)
+                         .required(false)
+                         .multiple(false)
+                         .help("The new parent category. If not passed, the category becomes a top-level category")
+                         .value_name("PARENT"))
                    )
 
         .subcommand(SubCommand::with_name("delete-category")
@@ -54,7 +105,15 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
 
         .subcommand(SubCommand::with_name("list-categories")
                     .about("Show all category names")
-                    .version("0.1"))
+                    .version("0.1")
+                    .arg(Arg::with_name("list-categories-tree")
+                         .long("tree")
+                         .short("t")
+                         .takes_value(false)
+                         .required(false)
+                         .multiple(false)
+                         .help("Show the categories as tree, with their descriptions"))
+                   )
 
         .subcommand(SubCommand::with_name("list-category")
                     .about("List all entries for a category")
@@ -66,6 +125,13 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
                          .multiple(false)
                          .help("The name of the category to list all entries for")
                          .value_name("NAME"))
+                    .arg(Arg::with_name("list-category-recursive")
+                         .long("recursive")
+                         .short("r")
+                         .takes_value(false)
+                         .required(false)
+                         .multiple(false)
+                         .help("Also list the entries of all subcategories"))
                    )
 
         .subcommand(SubCommand::with_name("set")
@@ -107,29 +173,34 @@ impl IdPathProvider for PathProvider {
         match matches.subcommand() {
             ("create-category", _) => {
                 error!("Command does not get IDs as input");
-                ::std::process::exit(1)
+                ::libimagerror::exit::exit(1)
+            },
+
+            ("move-category", _) => {
+                error!("Command does not get IDs as input");
+                ::libimagerror::exit::exit(1)
             },
 
             ("delete-category", _) => {
                 error!("Command does not get IDs as input");
-                ::std::process::exit(1)
+                ::libimagerror::exit::exit(1)
             },
 
             ("list-categories", _) => {
                 error!("Command does not get IDs as input");
-                ::std::process::exit(1)
+                ::libimagerror::exit::exit(1)
             },
 
             ("list-category", _) => {
                 error!("Command does not get IDs as input");
-                ::std::process::exit(1)
+                ::libimagerror::exit::exit(1)
             },
 
             ("set", Some(subm)) => {
                 subm.values_of("set-ids")
                     .ok_or_else(|| {
                         error!("No StoreId found");
-                        ::std::process::exit(1)
+                        ::libimagerror::exit::exit(1)
                     })
                     .unwrap()
                     .into_iter()
@@ -143,7 +214,7 @@ impl IdPathProvider for PathProvider {
                 subm.values_of("get-ids")
                     .ok_or_else(|| {
                         error!("No StoreId found");
-                        ::std::process::exit(1)
+                        ::libimagerror::exit::exit(1)
                     })
                     .unwrap()
                     .into_iter()
@@ -155,7 +226,7 @@ impl IdPathProvider for PathProvider {
 
             (other, _) => {
                     error!("Not a known command: {}", other);
-                    ::std::process::exit(1)
+                    ::libimagerror::exit::exit(1)
             }
         }
     }
@@ -0,0 +1,115 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+#![forbid(unsafe_code)]
+
+#![deny(
+    non_camel_case_types,
+    non_snake_case,
+    path_statements,
+    trivial_numeric_casts,
+    unstable_features,
+    unused_allocation,
+    unused_import_braces,
+    unused_imports,
+    unused_must_use,
+    unused_mut,
+    unused_qualifications,
+    while_true,
+)]
+
+extern crate clap;
+extern crate failure;
+#[macro_use] extern crate log;
+
+extern crate libimagentryedit;
+extern crate libimagerror;
+extern crate libimagrt;
+extern crate libimagstore;
+extern crate libimagutil;
+
+use clap::App;
+use failure::Error;
+use failure::Fallible as Result;
+
+use libimagerror::errors::ErrorMsg as EM;
+use libimagerror::trace::MapErrTrace;
+use libimagentryedit::edit::Edit;
+use libimagentryedit::edit::EditHeader;
+use libimagrt::runtime::Runtime;
+use libimagrt::application::ImagApplication;
+
+mod ui;
+
+/// Marker enum for implementing ImagApplication on
+///
+/// This is used by binaries crates to execute business logic
+/// or to build a CLI completion.
+pub enum ImagEdit {}
+impl ImagApplication for ImagEdit {
+    fn run(rt: &Runtime) -> Result<()> {
+        let edit_header = rt.cli().is_present("edit-header");
+        let edit_header_only = rt.cli().is_present("edit-header-only");
+
+        let sids = rt.ids::<::ui::PathProvider>().map_err_trace_exit_unwrap(1);
+
+        sids.into_iter()
+            .map(|id| {
+                rt.store()
+                    .get(id.clone())
+                    .map_err_trace_exit_unwrap(1)
+                    .ok_or_else(|| Error::from(EM::EntryNotFound(id.local_display_string())))
+                    .map_err_trace_exit_unwrap(1)
+            })
+            .for_each(|mut entry| {
+                if edit_header {
+                    let _ = entry
+                        .edit_header_and_content(&rt)
+                        .map_err_trace_exit_unwrap(1);
+                } else if edit_header_only {
+                    let _ = entry
+                        .edit_header(&rt)
+                        .map_err_trace_exit_unwrap(1);
+                } else {
+                    let _ = entry
+                        .edit_content(&rt)
+                        .map_err_trace_exit_unwrap(1);
+                }
+            });
+
+        Ok(())
+    }
+
+    fn build_cli<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
+        ui::build_ui(app)
+    }
+
+    fn name() -> &'static str {
+        env!("CARGO_PKG_NAME")
+    }
+
+    fn description() -> &'static str {
+        "Edit store entries with $EDITOR"
+    }
+
+    fn version() -> &'static str {
+        env!("CARGO_PKG_VERSION")
+    }
+}
+
@@ -59,7 +59,7 @@ impl IdPathProvider for PathProvider {
             .values_of("entry")
             .ok_or_else(|| {
                 error!("No StoreId found");
-                ::std::process::exit(1)
+                ::libimagerror::exit::exit(1)
             })
             .unwrap()
             .into_iter()
@@ -0,0 +1,410 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+#![forbid(unsafe_code)]
+
+#![deny(
+    non_camel_case_types,
+    non_snake_case,
+    path_statements,
+    trivial_numeric_casts,
+    unstable_features,
+    unused_allocation,
+    unused_import_braces,
+    unused_imports,
+    unused_must_use,
+    unused_mut,
+    unused_qualifications,
+    while_true,
+)]
+
+extern crate chrono;
+extern crate clap;
+#[macro_use]
+extern crate log;
+extern crate failure;
+
+extern crate libimagentrygps;
+extern crate libimagentrylink;
+extern crate libimagentrytag;
+extern crate libimagrt;
+extern crate libimagutil;
+extern crate libimagerror;
+extern crate libimagstore;
+
+use std::fs::File;
+use std::io::BufReader;
+use std::io::Write;
+use std::path::PathBuf;
+use libimagerror::exit::exit;
+use std::str::FromStr;
+
+use chrono::NaiveDate;
+use chrono::NaiveDateTime;
+use clap::App;
+
+use failure::Error;
+use failure::err_msg;
+use failure::Fallible as Result;
+
+use libimagentrygps::types::*;
+use libimagentrygps::entry::*;
+use libimagentrygps::store::GPSStore;
+use libimagentrygps::gpx::Gpx;
+use libimagentrygps::gpx::Waypoint;
+use libimagentrygps::geojson::to_geojson;
+use libimagentrylink::internal::InternalLinker;
+use libimagentrytag::tagable::Tagable;
+use libimagrt::runtime::Runtime;
+use libimagrt::application::ImagApplication;
+use libimagerror::trace::MapErrTrace;
+use libimagerror::exit::ExitUnwrap;
+use libimagerror::io::ToExitCode;
+use libimagstore::store::Entry;
+use libimagstore::storeid::IntoStoreId;
+
+mod ui;
+
+/// Marker enum for implementing ImagApplication on
+///
+/// This is used by binaries crates to execute business logic
+/// or to build a CLI completion.
+pub enum ImagGps {}
+impl ImagApplication for ImagGps {
+    fn run(rt: &Runtime) -> Result<()> {
+        rt.cli()
+            .subcommand_name()
+            .map(|name| {
+                match name {
+                    "add"    => add(&rt),
+                    "remove" => remove(&rt),
+                    "get"    => get(&rt),
+                    "near"   => near(&rt),
+                    "import" => import(&rt),
+                    "export" => export(&rt),
+                    other    => {
+                        debug!("Unknown command");
+                        let _ = rt.handle_unknown_subcommand("imag-gps", other, rt.cli())
+                            .map_err_trace_exit_unwrap(1)
+                            .code()
+                            .map(::libimagerror::exit::exit);
+                    }
+                }
+            });
+
+        Ok(())
+    }
+
+    fn build_cli<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
+        ui::build_ui(app)
+    }
+
+    fn name() -> &'static str {
+        env!("CARGO_PKG_NAME")
+    }
+
+    fn description() -> &'static str {
+        "Add GPS coordinates to entries"
+    }
+
+    fn version() -> &'static str {
+        env!("CARGO_PKG_VERSION")
+    }
+}
+
+fn add(rt: &Runtime) {
+    let scmd = rt.cli().subcommand_matches("add").unwrap(); // safed by main()
+
+    let c = if let Some(coordinates) = scmd.value_of("coordinates") {
+        Coordinates::from_str(coordinates).map_err_trace_exit_unwrap(1)
+    } else {
+        let parse = |value: &str| -> GPSValue {
+            let is_legacy = value.split(".").count() == 3 &&
+                value.split(".").all(|v| i64::from_str(v).is_ok());
+
+            if !is_legacy {
+                return GPSValue::from_str(value).map_err_trace_exit_unwrap(1)
+            }
+
+            debug!("Parsing '{}' into degree, minute and second", value);
+            let ary = value.split(".")
+                .map(|v| {debug!("Parsing = {}", v); v})
+                .map(FromStr::from_str)
+                .map(|elem| {
+                    elem.or_else(|_| Err(Error::from(err_msg("Error while converting number"))))
+                        .map_err_trace_exit_unwrap(1)
+                })
+                .collect::<Vec<i64>>();
+
+            let degree = ary.get(0).unwrap_or_else(|| {
+                error!("Degree missing. This value is required.");
+                exit(1)
+            });
+            let minute = ary.get(1).unwrap_or_else(|| {
+                error!("Degree missing. This value is required.");
+                exit(1)
+            });
+            let second = ary.get(2).unwrap_or(&0);
+
+            GPSValue::new(*degree, *minute, *second)
+        };
+
+        let long = parse(scmd.value_of("longitude").unwrap()); // unwrap safed by clap
+        let lati = parse(scmd.value_of("latitude").unwrap()); // unwrap safed by clap
+
+        let c = Coordinates::new(long, lati);
+        let _ = c.check().map_err_trace_exit_unwrap(1);
+        c
+    };
+
+    let c = match scmd.value_of("altitude") {
+        Some(alt) => {
+            let alt = f64::from_str(alt)
+                .map_err(|_| Error::from(err_msg("Altitude is not a number")))
+                .map_err_trace_exit_unwrap(1);
+            c.with_altitude(alt)
+        },
+        None => c,
+    };
+
+    rt.ids::<::ui::PathProvider>()
+        .map_err_trace_exit_unwrap(1)
+        .into_iter()
+        .for_each(|id| {
+            rt.store()
+                .get(id.clone())
+                .map_err_trace_exit_unwrap(1)
+                .unwrap_or_else(|| { // if we have Ok(None)
+                    error!("No such entry: {}", id);
+                    exit(1)
+                })
+                .set_coordinates(c.clone())
+                .map_err_trace_exit_unwrap(1);
+
+            let _ = rt
+                .report_touched(&id)
+                .map_err_trace_exit_unwrap(1);
+        });
+}
+
+fn remove(rt: &Runtime) {
+    let print_removed = rt
+        .cli()
+        .subcommand_matches("remove")
+        .unwrap()
+        .is_present("print-removed"); // safed by main()
+
+    rt.ids::<::ui::PathProvider>()
+        .map_err_trace_exit_unwrap(1)
+        .into_iter()
+        .for_each(|id| {
+            let removed_value = rt
+                .store()
+                .get(id.clone())
+                .map_err_trace_exit_unwrap(1)
+                .unwrap_or_else(|| { // if we have Ok(None)
+                    error!("No such entry: {}", id);
+                    exit(1)
+                })
+                .remove_coordinates()
+                .map_err_trace_exit_unwrap(1) // The delete action failed
+                .unwrap_or_else(|| { // if we have Ok(None)
+                    error!("Entry had no coordinates: {}", id);
+                    exit(1)
+                })
+                .map_err_trace_exit_unwrap(1); // The parsing of the deleted values failed
+
+            if print_removed {
+                let _ = writeln!(rt.stdout(), "{}", removed_value).to_exit_code().unwrap_or_exit();
+            }
+
+            let _ = rt
+                .report_touched(&id)
+                .map_err_trace_exit_unwrap(1);
+        });
+}
+
+fn get(rt: &Runtime) {
+    let mut stdout = rt.stdout();
+    rt.ids::<::ui::PathProvider>()
+        .map_err_trace_exit_unwrap(1)
+        .into_iter()
+        .for_each(|id| {
+            let value = rt
+                .store()
+                .get(id.clone())
+                .map_err_trace_exit_unwrap(1)
+                .unwrap_or_else(|| { // if we have Ok(None)
+                    error!("No such entry: {}", id);
+                    exit(1)
+                })
+                .get_coordinates()
+                .map_err_trace_exit_unwrap(1) // The get action failed
+                .unwrap_or_else(|| { // if we have Ok(None)
+                    error!("Entry has no coordinates: {}", id);
+                    exit(1)
+                });
+
+            let _ = writeln!(stdout, "{}", value).to_exit_code().unwrap_or_exit();
+
+            let _ = rt
+                .report_touched(&id)
+                .map_err_trace_exit_unwrap(1);
+        })
+
+}
+
+
+fn near(rt: &Runtime) {
+    let scmd       = rt.cli().subcommand_matches("near").unwrap(); // safed by main()
+    let mut stdout = rt.stdout();
+
+    if let Some(bbox) = scmd.value_of("bbox") {
+        let bbox = BoundingBox::from_str(bbox).map_err_trace_exit_unwrap(1);
+
+        rt.store()
+            .entries_within(&bbox)
+            .map_err_trace_exit_unwrap(1)
+            .into_iter()
+            .for_each(|id| {
+                let _ = writeln!(stdout, "{}", id).to_exit_code().unwrap_or_exit();
+                let _ = rt.report_touched(&id).map_err_trace_exit_unwrap(1);
+            })
+    } else {
+        let point  = scmd.value_of("point").unwrap(); // safed by clap
+        let point  = Coordinates::from_str(point).map_err_trace_exit_unwrap(1);
+        let radius = scmd.value_of("radius").unwrap(); // safed by clap
+        let radius = f64::from_str(radius)
+            .map_err(|_| Error::from(err_msg("Radius is not a number")))
+            .map_err_trace_exit_unwrap(1);
+
+        rt.store()
+            .entries_near(&point, radius)
+            .map_err_trace_exit_unwrap(1)
+            .into_iter()
+            .for_each(|(id, distance)| {
+                let _ = writeln!(stdout, "{} ({:.3} km)", id, distance).to_exit_code().unwrap_or_exit();
+                let _ = rt.report_touched(&id).map_err_trace_exit_unwrap(1);
+            })
+    }
+}
+
+fn import(rt: &Runtime) {
+    let scmd = rt.cli().subcommand_matches("import").unwrap(); // safed by main()
+    let path = PathBuf::from(scmd.value_of("file").unwrap()); // safed by clap
+    let name = scmd
+        .value_of("name")
+        .map(String::from)
+        .or_else(|| path.file_stem().map(|s| s.to_string_lossy().into_owned()))
+        .unwrap_or_else(|| {
+            error!("Cannot get a name from '{}', please pass --name", path.display());
+            exit(1)
+        });
+
+    let gpx = File::open(&path)
+        .map_err(Error::from)
+        .and_then(|file| Gpx::parse(BufReader::new(file)))
+        .map_err_trace_exit_unwrap(1);
+
+    let mut track = rt.store().import_gpx(&gpx, &name).map_err_trace_exit_unwrap(1);
+
+    if let Some(links) = scmd.values_of("link") {
+        for link in links {
+            let id = PathBuf::from(link).into_storeid().map_err_trace_exit_unwrap(1);
+            let mut entry = rt.store()
+                .get(id.clone())
+                .map_err_trace_exit_unwrap(1)
+                .unwrap_or_else(|| {
+                    error!("No such entry: {}", id);
+                    exit(1)
+                });
+
+            let _ = track.add_internal_link(&mut entry).map_err_trace_exit_unwrap(1);
+            let _ = rt.report_touched(&id).map_err_trace_exit_unwrap(1);
+        }
+    }
+
+    let _ = writeln!(rt.stdout(), "{}", track.get_location()).to_exit_code().unwrap_or_exit();
+    let _ = rt.report_touched(track.get_location()).map_err_trace_exit_unwrap(1);
+}
+
+fn export(rt: &Runtime) {
+    let scmd       = rt.cli().subcommand_matches("export").unwrap(); // safed by main()
+    let collection = scmd.value_of("collection").map(|c| c.split('/').map(String::from).collect::<Vec<_>>());
+    let tag        = scmd.value_of("tag");
+    let from       = scmd.value_of("from").map(|d| parse_date(d, false));
+    let until      = scmd.value_of("until").map(|d| parse_date(d, true));
+
+    let points = rt.store()
+        .geotagged(|entry: &Entry| {
+            let in_collection = collection
+                .as_ref()
+                .map(|c| entry.get_location().is_in_collection(c))
+                .unwrap_or(true);
+
+            match tag {
+                Some(tag) if in_collection => entry.has_tag_in_subtree(tag),
+                _                          => Ok(in_collection),
+            }
+        })
+        .map_err_trace_exit_unwrap(1)
+        .into_iter()
+        .filter(|p| {
+            // Entries without a date are only exported if no date is asked for
+            match p.time {
+                Some(ref time) => from.map(|f| f <= *time).unwrap_or(true) && until.map(|u| *time <= u).unwrap_or(true),
+                None           => from.is_none() && until.is_none(),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let format = scmd.value_of("format");
+
+    // The export is the output of this command, so it is written to stdout even if stdout is not a
+    // terminal, where `Runtime::stdout()` would write to stderr.
+    let _ = match scmd.value_of("output") {
+        Some(path) => File::create(path)
+            .map_err(Error::from)
+            .and_then(|mut file| write_export(&mut file, format, points)),
+        None => {
+            let stdout = ::std::io::stdout();
+            let mut lock = stdout.lock();
+            write_export(&mut lock, format, points)
+        },
+    }.map_err_trace_exit_unwrap(1);
+}
+
+fn write_export<W: Write>(w: &mut W, format: Option<&str>, points: Vec<Waypoint>) -> Result<()> {
+    if format == Some("gpx") {
+        Gpx { waypoints: points, tracks: vec![] }.write(w)
+    } else {
+        let geojson = to_geojson(&points)?;
+        writeln!(w, "{}", geojson).map_err(Error::from)
+    }
+}
+
+/// Parse a date with or without time, a date without time means the start or the end of the day
+fn parse_date(s: &str, end_of_day: bool) -> NaiveDateTime {
+    NaiveDateTime::from_str(s)
+        .or_else(|_| {
+            NaiveDate::from_str(s).map(|d| if end_of_day { d.and_hms(23, 59, 59) } else { d.and_hms(0, 0, 0) })
+        })
+        .map_err(|_| Error::from(err_msg(format!("Cannot parse date: '{}'", s))))
+        .map_err_trace_exit_unwrap(1)
+}
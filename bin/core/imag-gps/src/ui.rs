@@ -34,17 +34,35 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
                     .arg(Arg::with_name("longitude")
                          .long("long")
                          .takes_value(true)
-                         .required(true)
+                         .required_unless("coordinates")
+                         .conflicts_with("coordinates")
                          .multiple(false)
-                         .help("Set the longitude value. Format: <degrees>.<minutes>.<seconds>")
+                         .help("Set the longitude value. Format: <degrees>.<minutes>.<seconds>, decimal degrees (\"-11.5756\") or degrees, minutes and seconds with hemisphere (\"11°34'32\"W\")")
                          .value_name("LONGITUDE"))
                     .arg(Arg::with_name("latitude")
                          .long("lat")
                          .takes_value(true)
-                         .required(true)
+                         .required_unless("coordinates")
+                         .conflicts_with("coordinates")
                          .multiple(false)
-                         .help("Set the latitude. Format: <degrees>.<minutes>.<seconds>")
+                         .help("Set the latitude. Format: <degrees>.<minutes>.<seconds>, decimal degrees (\"48.1372\") or degrees, minutes and seconds with hemisphere (\"48°8'14\"N\")")
                          .value_name("LATITUDE"))
+                    .arg(Arg::with_name("coordinates")
+                         .long("coordinates")
+                         .short("c")
+                         .takes_value(true)
+                         .required(false)
+                         .multiple(false)
+                         .help("Set latitude and longitude at once, either as decimal pair (\"48.1372,11.5756\"), as geo: URI (\"geo:48.1372,11.5756\") or in degrees, minutes and seconds (\"48°8'14\"N 11°34'32\"E\")")
+                         .value_name("COORDINATES"))
+                    .arg(Arg::with_name("altitude")
+                         .long("altitude")
+                         .short("a")
+                         .takes_value(true)
+                         .required(false)
+                         .multiple(false)
+                         .help("Set the altitude in meters")
+                         .value_name("METERS"))
                     .arg(Arg::with_name("entry")
                          .index(1)
                          .takes_value(true)
@@ -95,6 +113,118 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
                      .multiple(false)
                      .help("Print as <key>=<value> pairs (2 lines, default)"))
                 )
+
+        .subcommand(SubCommand::with_name("import")
+                .about("Import waypoints and tracks from a GPX file")
+                .version("0.1")
+                .arg(Arg::with_name("file")
+                     .index(1)
+                     .takes_value(true)
+                     .required(true)
+                     .multiple(false)
+                     .help("The GPX file to import")
+                     .value_name("FILE"))
+                .arg(Arg::with_name("name")
+                     .long("name")
+                     .short("n")
+                     .takes_value(true)
+                     .required(false)
+                     .multiple(false)
+                     .help("The name of the track entry to create. Defaults to the name of the file")
+                     .value_name("NAME"))
+                .arg(Arg::with_name("link")
+                     .long("link")
+                     .short("l")
+                     .takes_value(true)
+                     .required(false)
+                     .multiple(true)
+                     .number_of_values(1)
+                     .help("Link the track entry to this entry, for example a diary entry")
+                     .value_name("ENTRY"))
+                )
+
+        .subcommand(SubCommand::with_name("export")
+                .about("Export all entries with coordinates")
+                .version("0.1")
+                .arg(Arg::with_name("format")
+                     .long("format")
+                     .short("f")
+                     .takes_value(true)
+                     .required(false)
+                     .multiple(false)
+                     .possible_values(&["geojson", "gpx"])
+                     .default_value("geojson")
+                     .help("The format to export to")
+                     .value_name("FORMAT"))
+                .arg(Arg::with_name("output")
+                     .long("output")
+                     .short("o")
+                     .takes_value(true)
+                     .required(false)
+                     .multiple(false)
+                     .help("Write the export to this file, instead of stdout. An existing file is overwritten")
+                     .value_name("FILE"))
+                .arg(Arg::with_name("collection")
+                     .long("collection")
+                     .short("c")
+                     .takes_value(true)
+                     .required(false)
+                     .multiple(false)
+                     .help("Only export entries from this collection, like 'diary'")
+                     .value_name("COLLECTION"))
+                .arg(Arg::with_name("tag")
+                     .long("tag")
+                     .short("t")
+                     .takes_value(true)
+                     .required(false)
+                     .multiple(false)
+                     .help("Only export entries with this tag (or a tag below it)")
+                     .value_name("TAG"))
+                .arg(Arg::with_name("from")
+                     .long("from")
+                     .takes_value(true)
+                     .required(false)
+                     .multiple(false)
+                     .help("Only export entries dated at or after this date (YYYY-MM-DD or YYYY-MM-DDTHH:MM:SS)")
+                     .value_name("DATE"))
+                .arg(Arg::with_name("until")
+                     .long("until")
+                     .takes_value(true)
+                     .required(false)
+                     .multiple(false)
+                     .help("Only export entries dated at or before this date (YYYY-MM-DD or YYYY-MM-DDTHH:MM:SS)")
+                     .value_name("DATE"))
+                )
+
+        .subcommand(SubCommand::with_name("near")
+                .about("List entries near a point or inside a bounding box")
+                .version("0.1")
+                .arg(Arg::with_name("point")
+                     .index(1)
+                     .takes_value(true)
+                     .required_unless("bbox")
+                     .conflicts_with("bbox")
+                     .multiple(false)
+                     .help("The point to search around, in any format 'imag gps add --coordinates' understands")
+                     .value_name("COORDINATES"))
+                .arg(Arg::with_name("radius")
+                     .long("radius")
+                     .short("r")
+                     .takes_value(true)
+                     .required(false)
+                     .multiple(false)
+                     .default_value("10")
+                     .help("The radius to search in, in kilometers")
+                     .value_name("KM"))
+                .arg(Arg::with_name("bbox")
+                     .long("bbox")
+                     .short("b")
+                     .takes_value(true)
+                     .required(false)
+                     .multiple(false)
+                     .help("Search inside a bounding box instead, in decimal degrees")
+                     .value_name("SOUTH,WEST,NORTH,EAST"))
+                )
 }
 
 pub struct PathProvider;
@@ -105,7 +235,7 @@ impl IdPathProvider for PathProvider {
                 subm.values_of("entry")
                     .ok_or_else(|| {
                         error!("No StoreId found");
-                        ::std::process::exit(1)
+                        ::libimagerror::exit::exit(1)
                     })
                     .unwrap()
                     .into_iter()
@@ -119,7 +249,7 @@ impl IdPathProvider for PathProvider {
                 subm.values_of("entry")
                     .ok_or_else(|| {
                         error!("No StoreId found");
-                        ::std::process::exit(1)
+                        ::libimagerror::exit::exit(1)
                     })
                     .unwrap()
                     .into_iter()
@@ -133,7 +263,7 @@ impl IdPathProvider for PathProvider {
                 subm.values_of("get-ids")
                     .ok_or_else(|| {
                         error!("No StoreId found");
-                        ::std::process::exit(1)
+                        ::libimagerror::exit::exit(1)
                     })
                     .unwrap()
                     .into_iter()
@@ -143,9 +273,24 @@ impl IdPathProvider for PathProvider {
                     .map_err_trace_exit_unwrap(1)
             },
 
+            ("import", _) => {
+                error!("Command does not get IDs as input");
+                ::libimagerror::exit::exit(1)
+            },
+
+            ("export", _) => {
+                error!("Command does not get IDs as input");
+                ::libimagerror::exit::exit(1)
+            },
+
+            ("near", _) => {
+                error!("Command does not get IDs as input");
+                ::libimagerror::exit::exit(1)
+            },
+
             (other, _) => {
                     error!("Not a known command: {}", other);
-                    ::std::process::exit(1)
+                    ::libimagerror::exit::exit(1)
             }
         }
     }
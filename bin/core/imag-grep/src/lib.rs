@@ -36,18 +36,23 @@
 
 #[macro_use] extern crate log;
 extern crate clap;
+extern crate failure;
 extern crate regex;
 
 extern crate libimagstore;
-#[macro_use] extern crate libimagrt;
+extern crate libimagrt;
 extern crate libimagerror;
 
 use std::io::Write;
 
+use clap::App;
+
 use regex::Regex;
 
+use failure::Fallible as Result;
+
 use libimagrt::runtime::Runtime;
-use libimagrt::setup::generate_runtime_setup;
+use libimagrt::application::ImagApplication;
 use libimagstore::store::Entry;
 use libimagerror::trace::MapErrTrace;
 use libimagerror::exit::ExitUnwrap;
@@ -60,49 +65,68 @@ struct Options {
     count: bool,
 }
 
-fn main() {
-    let version = make_imag_version!();
-    let rt = generate_runtime_setup("imag-grep",
-                                    &version,
-                                    "grep through entries text",
-                                    ui::build_ui);
-
-    let opts = Options {
-        files_with_matches    : rt.cli().is_present("files-with-matches"),
-        count                 : rt.cli().is_present("count"),
-    };
-
-    let mut count : usize = 0;
-
-    let pattern = rt
-        .cli()
-        .value_of("pattern")
-        .map(Regex::new)
-        .unwrap() // ensured by clap
-        .unwrap_or_else(|e| {
-            error!("Regex building error: {:?}", e);
-            ::std::process::exit(1)
-        });
-
-    let overall_count = rt
-        .store()
-        .entries()
-        .map_err_trace_exit_unwrap(1)
-        .into_get_iter()
-        .filter_map(|res| res.map_err_trace_exit_unwrap(1))
-        .filter(|entry| pattern.is_match(entry.get_content()))
-        .map(|entry| show(&rt, &entry, &pattern, &opts, &mut count))
-        .count();
-
-    if opts.count {
-        let _ = writeln!(rt.stdout(), "{}", count).to_exit_code().unwrap_or_exit();
-    } else if !opts.files_with_matches {
-        let _ = writeln!(rt.stdout(), "Processed {} files, {} matches, {} nonmatches",
-                 overall_count,
-                 count,
-                 overall_count - count)
-            .to_exit_code()
-            .unwrap_or_exit();
+/// Marker enum for implementing ImagApplication on
+///
+/// This is used by binaries crates to execute business logic
+/// or to build a CLI completion.
+pub enum ImagGrep {}
+impl ImagApplication for ImagGrep {
+    fn run(rt: &Runtime) -> Result<()> {
+        let opts = Options {
+            files_with_matches    : rt.cli().is_present("files-with-matches"),
+            count                 : rt.cli().is_present("count"),
+        };
+
+        let mut count : usize = 0;
+
+        let pattern = rt
+            .cli()
+            .value_of("pattern")
+            .map(Regex::new)
+            .unwrap() // ensured by clap
+            .unwrap_or_else(|e| {
+                error!("Regex building error: {:?}", e);
+                ::libimagerror::exit::exit(1)
+            });
+
+        let overall_count = rt
+            .store()
+            .entries()
+            .map_err_trace_exit_unwrap(1)
+            .into_get_iter()
+            .filter_map(|res| res.map_err_trace_exit_unwrap(1))
+            .filter(|entry| pattern.is_match(entry.get_content()))
+            .map(|entry| show(&rt, &entry, &pattern, &opts, &mut count))
+            .count();
+
+        if opts.count {
+            let _ = writeln!(rt.stdout(), "{}", count).to_exit_code().unwrap_or_exit();
+        } else if !opts.files_with_matches {
+            let _ = writeln!(rt.stdout(), "Processed {} files, {} matches, {} nonmatches",
+                     overall_count,
+                     count,
+                     overall_count - count)
+                .to_exit_code()
+                .unwrap_or_exit();
+        }
+
+        Ok(())
+    }
+
+    fn build_cli<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
+        ui::build_ui(app)
+    }
+
+    fn name() -> &'static str {
+        env!("CARGO_PKG_NAME")
+    }
+
+    fn description() -> &'static str {
+        "grep through entries text"
+    }
+
+    fn version() -> &'static str {
+        env!("CARGO_PKG_VERSION")
     }
 }
 
@@ -47,7 +47,7 @@ impl<'a, A> Filter<StoreId> for IsInCollectionsFilter<'a, A>
 pub mod header_filter_lang {
     use std::str;
     use std::str::FromStr;
-    use std::process::exit;
+    use libimagerror::exit::exit;
 
     use nom::digit;
     use nom::multispace;
@@ -126,12 +126,12 @@ pub mod header_filter_lang {
     named!(int64<i64>, map!(digit, |r: &[u8]| {
         let val = str::from_utf8(r).unwrap_or_else(|e| {
             error!("Error = '{:?}'", e);
-            ::std::process::exit(1)
+            ::libimagerror::exit::exit(1)
         });
 
         i64::from_str(val).unwrap_or_else(|e| {
             error!("Error while parsing number: '{:?}'", e);
-            ::std::process::exit(1)
+            ::libimagerror::exit::exit(1)
         })
     }));
 
@@ -396,13 +396,20 @@ pub mod header_filter_lang {
 
     impl ::filters::filter::Filter<Entry> for Filter {
         fn filter(&self, entry: &Entry) -> bool {
+            trace!("Filtering {} at {}", entry.get_location(), self.selector.selector_str());
+            ::filters::filter::Filter::filter(self, entry.get_header())
+        }
+    }
+
+    /// Filtering a header directly, for example a header which was streamed via stdin
+    impl ::filters::filter::Filter<::toml::Value> for Filter {
+        fn filter(&self, header: &::toml::Value) -> bool {
             use toml_query::read::TomlValueReadExt;
 
             let selector_str = self.selector.selector_str();
-            trace!("Filtering {} at {}", entry.get_location(), selector_str);
+            trace!("Filtering header at {}", selector_str);
 
-            entry
-                .get_header()
+            header
                 .read(selector_str)
                 .map_err(Error::from)
                 .map_err_trace_exit_unwrap(1)
@@ -463,7 +470,15 @@ pub mod header_filter_lang {
 
         fn filter(&self, entry: &Entry) -> bool {
             trace!("Filtering = {}", entry.get_location());
-            let mut res = self.filter.filter(entry);
+            ::filters::filter::Filter::filter(self, entry.get_header())
+        }
+
+    }
+
+    impl ::filters::filter::Filter<::toml::Value> for Query {
+
+        fn filter(&self, entry: &::toml::Value) -> bool {
+            let mut res = ::filters::filter::Filter::filter(&self.filter, entry);
             trace!("First filter = {}", res);
 
             for &(ref operator, ref next) in self.next_filters.iter() {
@@ -501,12 +516,12 @@ pub mod header_filter_lang {
             ::nom::IResult::Error(e) => {
                 error!("Error during parsing the query");
                 error!("Error = {:?}", e);
-                ::std::process::exit(1)
+                ::libimagerror::exit::exit(1)
             },
             ::nom::IResult::Incomplete(needed) => {
                 error!("Error during parsing the query. Incomplete input.");
                 error!("Needed = {:?}", needed);
-                ::std::process::exit(1)
+                ::libimagerror::exit::exit(1)
             },
         }
     }
@@ -0,0 +1,242 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+#![forbid(unsafe_code)]
+
+#![deny(
+    non_camel_case_types,
+    non_snake_case,
+    path_statements,
+    trivial_numeric_casts,
+    unstable_features,
+    unused_allocation,
+    unused_import_braces,
+    unused_imports,
+    unused_must_use,
+    unused_mut,
+    unused_qualifications,
+    while_true,
+)]
+
+extern crate clap;
+extern crate filters;
+#[macro_use] extern crate nom;
+#[macro_use] extern crate log;
+#[macro_use] extern crate is_match;
+extern crate toml;
+extern crate toml_query;
+extern crate failure;
+
+#[cfg(test)]
+extern crate env_logger;
+
+extern crate libimagerror;
+extern crate libimagstore;
+extern crate libimagrt;
+extern crate libimagentrytag;
+extern crate libimagentryfilter;
+extern crate libimagentrydatetime;
+
+use std::io::Write;
+use libimagerror::exit::exit;
+
+use clap::App;
+
+use filters::filter::Filter;
+
+use failure::Fallible as Result;
+use toml::Value;
+
+use libimagstore::storeid::StoreId;
+use libimagrt::runtime::Runtime;
+use libimagrt::application::ImagApplication;
+use libimagerror::trace::MapErrTrace;
+use libimagerror::iter::TraceIterator;
+use libimagerror::exit::ExitUnwrap;
+use libimagerror::io::ToExitCode;
+use libimagentryfilter::tags::HasAllTags;
+use libimagentryfilter::tags::query::TagQuery;
+use libimagentryfilter::datetime::DatedBetween;
+use libimagentrydatetime::datetime::DateOrRange;
+use libimagentrydatetime::datetime::EntryDate;
+use libimagentrydatetime::range::DateTimeRange;
+
+mod id_filters;
+mod ui;
+
+use ui::build_ui;
+use id_filters::IsInCollectionsFilter;
+
+/// Marker enum for implementing ImagApplication on
+///
+/// This is used by binaries crates to execute business logic
+/// or to build a CLI completion.
+pub enum ImagIds {}
+impl ImagApplication for ImagIds {
+    fn run(rt: &Runtime) -> Result<()> {
+        let print_storepath = rt.cli().is_present("print-storepath");
+
+        let values = rt
+            .cli()
+            .values_of("in-collection-filter")
+            .map(|v| v.collect::<Vec<&str>>());
+
+        let collection_filter = IsInCollectionsFilter::new(values);
+        let query_filter      : Option<id_filters::header_filter_lang::Query> = rt
+            .cli()
+            .subcommand_matches("where")
+            .map(|matches| {
+                let query = matches.value_of("where-filter").unwrap(); // safe by clap
+                id_filters::header_filter_lang::parse(&query)
+            });
+
+        let tag_filter = rt
+            .cli()
+            .values_of("tag-filter")
+            .map(|tags| HasAllTags::new(tags.map(String::from).collect()));
+
+        let tag_query = rt
+            .cli()
+            .value_of("tag-query")
+            .map(|query| TagQuery::parse(query).map_err_trace_exit_unwrap(1));
+
+        let date_filter = rt
+            .cli()
+            .values_of("dated-between")
+            .map(|mut dates| {
+                let from  = dates.next().unwrap(); // safe by clap
+                let until = dates.next().unwrap(); // safe by clap
+                DateTimeRange::parse(from, until)
+                    .map(DatedBetween::new)
+                    .map_err_trace_exit_unwrap(1)
+            });
+
+        // If the entries are streamed via stdin with their full header, the filters are evaluated
+        // on that header instead of the entries in the store. Single streamed fields are not
+        // enough, as the filters may need any part of the header, so the entry is loaded then.
+        let iterator = if rt.ids_from_stdin() {
+            debug!("Fetching IDs from stdin...");
+            let entries = rt
+                .stream_entries::<::ui::PathProvider>()
+                .map_err_trace_exit_unwrap(1);
+
+            Box::new(entries.into_iter().map(|se| Ok((se.store_id()?, se.header))))
+                as Box<Iterator<Item = Result<(StoreId, Option<Value>)>>>
+        } else {
+            Box::new(rt.store().entries().map_err_trace_exit_unwrap(1).map(|id| id.map(|id| (id, None))))
+                as Box<Iterator<Item = Result<(StoreId, Option<Value>)>>>
+        }
+        .trace_unwrap_exit(1)
+        .filter(|&(ref id, _)| collection_filter.filter(id))
+        .filter(|&(ref id, ref header)| {
+            if query_filter.is_none() && tag_filter.is_none() && tag_query.is_none() && date_filter.is_none() {
+                return true
+            }
+
+            let matches = |header: &Value| {
+                query_filter.as_ref().map(|qf| qf.filter(header)).unwrap_or(true) &&
+                    tag_filter.as_ref().map(|tf| tf.filter(header)).unwrap_or(true) &&
+                    tag_query.as_ref().map(|tq| tq.filter(header)).unwrap_or(true) &&
+                    date_filter.as_ref().map(|df| df.filter(header)).unwrap_or(true)
+            };
+
+            match *header {
+                Some(ref header) => matches(header),
+                None             => {
+                    let entry = rt
+                        .store()
+                        .get(id.clone())
+                        .map_err_trace_exit_unwrap(1)
+                        .unwrap_or_else(|| {
+                            error!("Tried to get '{}', but it does not exist!", id);
+                            exit(1)
+                        });
+
+                    matches(entry.get_header())
+                },
+            }
+        });
+
+        let mut stdout = rt.stdout();
+        trace!("Got output: {:?}", stdout);
+
+        let print = |(id, _): (StoreId, Option<Value>)| {
+            let id = if print_storepath {
+                id
+            } else {
+                id.without_base()
+            };
+
+            rt.report_touched(&id).map_err_trace_exit_unwrap(1);
+            if !rt.output_is_pipe() {
+                let id = id.to_str().map_err_trace_exit_unwrap(1);
+                trace!("Writing to {:?}", stdout);
+                let _ = writeln!(stdout, "{}", id)
+                    .to_exit_code()
+                    .unwrap_or_exit();
+            }
+        };
+
+        if date_filter.is_some() {
+            // Dated entries are listed chronologically
+            let mut dated = iterator
+                .map(|(id, header)| {
+                    let date = match header {
+                        Some(ref header) => DateOrRange::from_header(header),
+                        None             => rt
+                            .store()
+                            .get(id.clone())
+                            .map_err_trace_exit_unwrap(1)
+                            .unwrap_or_else(|| {
+                                error!("Tried to get '{}', but it does not exist!", id);
+                                exit(1)
+                            })
+                            .read_date_or_range(),
+                    }.map_err_trace_exit_unwrap(1);
+
+                    (date, (id, header))
+                })
+                .collect::<Vec<_>>();
+
+            dated.sort_by(|a, b| a.0.as_ref().map(|d| d.start()).cmp(&b.0.as_ref().map(|d| d.start())));
+            dated.into_iter().map(|(_, item)| item).for_each(print);
+        } else {
+            iterator.for_each(print);
+        }
+
+        Ok(())
+    }
+
+    fn build_cli<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
+        build_ui(app)
+    }
+
+    fn name() -> &'static str {
+        env!("CARGO_PKG_NAME")
+    }
+
+    fn description() -> &'static str {
+        "print all ids"
+    }
+
+    fn version() -> &'static str {
+        env!("CARGO_PKG_VERSION")
+    }
+}
+
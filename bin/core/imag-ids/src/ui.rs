@@ -21,6 +21,8 @@ use clap::{Arg, ArgMatches, App, SubCommand};
 
 use libimagstore::storeid::StoreId;
 use libimagrt::runtime::IdPathProvider;
+use libimagentrytag::tag::is_tag_pattern;
+use libimagentryfilter::tags::query::is_tag_query;
 
 pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
     app
@@ -40,6 +42,35 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
              .value_names(&["COLLECTION"])
              .help("Filter for ids which are only in these collections"))
 
+        .arg(Arg::with_name("tag-filter")
+             .long("tag")
+             .short("t")
+             .required(false)
+             .takes_value(true)
+             .multiple(true)
+             .validator(is_tag_pattern)
+             .value_names(&["TAG"])
+             .help("Filter for ids of entries which have all of these tags. `project/**` matches the tag `project` and all tags below it"))
+
+        .arg(Arg::with_name("tag-query")
+             .long("tag-query")
+             .short("T")
+             .required(false)
+             .takes_value(true)
+             .multiple(false)
+             .validator(is_tag_query)
+             .value_names(&["QUERY"])
+             .help("Filter for ids of entries whose tags match a query like '(work and urgent) or (home and not done)'"))
+
+        .arg(Arg::with_name("dated-between")
+             .long("dated-between")
+             .required(false)
+             .takes_value(true)
+             .multiple(false)
+             .number_of_values(2)
+             .value_names(&["FROM", "UNTIL"])
+             .help("Filter for ids of entries whose date lies between FROM and UNTIL, or whose date range overlaps it, and list them chronologically. Dates are YYYY-MM-DD (the whole day) or YYYY-MM-DDTHH:MM:SS"))
+
         .subcommand(SubCommand::with_name("where")
                     .arg(Arg::with_name("where-filter")
                          .index(1)
@@ -56,6 +87,6 @@ pub struct PathProvider;
 impl IdPathProvider for PathProvider {
     fn get_ids(_matches: &ArgMatches) -> Vec<StoreId> {
         error!("imag-ids does not get IDs via CLI, only via stdin if applying a filter!");
-        ::std::process::exit(1)
+        ::libimagerror::exit::exit(1)
     }
 }
@@ -52,6 +52,7 @@ use std::process::Command;
 
 use libimagerror::exit::ExitUnwrap;
 use libimagerror::io::ToExitCode;
+use libimagerror::trace::MapErrTrace;
 use libimagrt::runtime::Runtime;
 
 const CONFIGURATION_STR : &'static str = include_str!("../imagrc.toml");
@@ -75,6 +76,13 @@ fn main() {
         "imag-init",
         version.as_str(),
         "Intializes the imag store, optionally with git"));
+
+    if ::libimagrt::describe::is_describe_request() {
+        ::libimagrt::describe::print_description(&app, version.as_str(), None)
+            .map_err_trace_exit_unwrap(1);
+        ::std::process::exit(0)
+    }
+
     let matches = app.get_matches();
     let mut out = ::std::io::stdout();
 
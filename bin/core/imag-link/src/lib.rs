@@ -37,16 +37,17 @@
 #[macro_use] extern crate log;
 extern crate clap;
 extern crate url;
-extern crate failure;
+#[macro_use] extern crate failure;
 #[macro_use] extern crate prettytable;
 #[cfg(test)] extern crate toml;
-#[cfg(test)] extern crate toml_query;
+extern crate toml_query;
 #[cfg(test)] extern crate env_logger;
 
 extern crate libimagentrylink;
-#[macro_use] extern crate libimagrt;
+extern crate libimagrt;
 extern crate libimagstore;
 extern crate libimagerror;
+extern crate libimaginteraction;
 
 #[cfg(test)]
 #[macro_use]
@@ -56,19 +57,31 @@ extern crate libimagutil;
 extern crate libimagutil;
 
 use std::io::Write;
+use std::io::Read;
 use std::path::PathBuf;
 
+use clap::App;
+
 use failure::Error;
 use failure::err_msg;
 
 use libimagentrylink::external::ExternalLinker;
 use libimagentrylink::internal::InternalLinker;
+use libimagentrylink::internal::LinkType;
 use libimagentrylink::internal::store_check::StoreLinkConsistentExt;
+use libimagentrylink::internal::store_check::ConsistencyReport;
+use libimagentrylink::internal::store_check::LinkProblem;
+use libimagentrylink::internal::store_check::RepairAction;
+use libimagentrylink::graph::LinkGraph;
+use libimagentrylink::external_check::LinkChecker;
+use libimagentrylink::external_check::CheckResult;
+use libimagerror::errors::ErrorMsg as EM;
 use libimagerror::trace::{MapErrTrace, trace_error};
 use libimagerror::exit::ExitUnwrap;
 use libimagerror::io::ToExitCode;
+use libimaginteraction::ask::ask_bool;
 use libimagrt::runtime::Runtime;
-use libimagrt::setup::generate_runtime_setup;
+use libimagrt::application::ImagApplication;
 use libimagstore::store::FileLockEntry;
 use libimagstore::storeid::StoreId;
 use libimagutil::warn_exit::warn_exit;
@@ -76,56 +89,82 @@ use libimagutil::warn_result::*;
 
 use url::Url;
 use failure::Fallible as Result;
+use toml_query::read::TomlValueReadExt;
 
 mod ui;
 
 use ui::build_ui;
 
-fn main() {
-    let version = make_imag_version!();
-    let rt = generate_runtime_setup("imag-link",
-                                    &version,
-                                    "Link entries",
-                                    build_ui);
-    if rt.cli().is_present("check-consistency") {
-        let exit_code = match rt.store().check_link_consistency() {
-            Ok(_) => {
+/// Marker enum for implementing ImagApplication on
+///
+/// This is used by binaries crates to execute business logic
+/// or to build a CLI completion.
+pub enum ImagLink {}
+impl ImagApplication for ImagLink {
+    fn run(rt: &Runtime) -> Result<()> {
+        if rt.cli().is_present("check-consistency") {
+            let report = rt.store().check_link_consistency().map_err_trace_exit_unwrap(1);
+            if report.is_consistent() {
                 info!("Store is consistent");
-                0
-            }
-            Err(e) => {
-                trace_error(&e);
-                1
+                ::libimagerror::exit::exit(0);
             }
-        };
-        ::std::process::exit(exit_code);
+
+            print_link_problems(&rt, &report);
+            ::libimagerror::exit::exit(1);
+        }
+
+        let _ = rt.cli()
+            .subcommand_name()
+            .map(|name| {
+                match name {
+                    "remove" => remove_linking(&rt),
+                    "unlink" => unlink(&rt),
+                    "list"   => list_linkings(&rt),
+                    "graph"  => graph(&rt),
+                    "annotate" => annotate(&rt),
+                    "check"  => check_consistency(&rt),
+                    "check-external" => check_external(&rt),
+                    other    => {
+                        debug!("Unknown command");
+                        let _ = rt.handle_unknown_subcommand("imag-link", other, rt.cli())
+                            .map_err_trace_exit_unwrap(1)
+                            .code()
+                            .map(::libimagerror::exit::exit);
+                    },
+                }
+            })
+            .or_else(|| {
+                if let (Some(from), Some(to)) = (rt.cli().value_of("from"), rt.cli().values_of("to")) {
+                    let link_type = rt.cli()
+                        .value_of("type")
+                        .map(|name| get_link_type(&rt, name).map_err_trace_exit_unwrap(1));
+                    let annotation = rt.cli().value_of("annotation").map(String::from);
+                    Some(link_from_to(&rt, from, to, link_type, annotation))
+                } else {
+                    warn_exit("No commandline call", 1)
+                }
+            })
+            .ok_or_else(|| Error::from(err_msg("No commandline call".to_owned())))
+            .map_err_trace_exit_unwrap(1);
+
+        Ok(())
     }
 
-    let _ = rt.cli()
-        .subcommand_name()
-        .map(|name| {
-            match name {
-                "remove" => remove_linking(&rt),
-                "unlink" => unlink(&rt),
-                "list"   => list_linkings(&rt),
-                other    => {
-                    debug!("Unknown command");
-                    let _ = rt.handle_unknown_subcommand("imag-link", other, rt.cli())
-                        .map_err_trace_exit_unwrap(1)
-                        .code()
-                        .map(::std::process::exit);
-                },
-            }
-        })
-        .or_else(|| {
-            if let (Some(from), Some(to)) = (rt.cli().value_of("from"), rt.cli().values_of("to")) {
-                Some(link_from_to(&rt, from, to))
-            } else {
-                warn_exit("No commandline call", 1)
-            }
-        })
-        .ok_or_else(|| Error::from(err_msg("No commandline call".to_owned())))
-        .map_err_trace_exit_unwrap(1);
+    fn build_cli<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
+        build_ui(app)
+    }
+
+    fn name() -> &'static str {
+        env!("CARGO_PKG_NAME")
+    }
+
+    fn description() -> &'static str {
+        "Link entries"
+    }
+
+    fn version() -> &'static str {
+        env!("CARGO_PKG_VERSION")
+    }
 }
 
 fn get_entry_by_name<'a>(rt: &'a Runtime, name: &str) -> Result<Option<FileLockEntry<'a>>> {
@@ -139,24 +178,63 @@ fn get_entry_by_name<'a>(rt: &'a Runtime, name: &str) -> Result<Option<FileLockE
     result
 }
 
-fn link_from_to<'a, I>(rt: &'a Runtime, from: &'a str, to: I)
+/// Get the link type named `name`
+///
+/// Besides the builtin types, types can be configured as `name = "inverse"` pairs in the
+/// `link.types` section of the configuration. Types which are not known are symmetric.
+fn get_link_type(rt: &Runtime, name: &str) -> Result<LinkType> {
+    if let Some(t) = LinkType::builtin(name) {
+        return Ok(t)
+    }
+
+    let types = match rt.config() {
+        Some(cfg) => cfg.read("link.types").map_err(Error::from)?,
+        None      => None,
+    };
+
+    let types = match types {
+        Some(t) => t.as_table().ok_or_else(|| err_msg("Type error at 'link.types', expected 'Table'"))?,
+        None    => return Ok(LinkType::symmetric(name)),
+    };
+
+    for (type_name, inverse) in types.iter() {
+        let inverse = inverse
+            .as_str()
+            .ok_or_else(|| format_err!("Type error at 'link.types.{}', expected 'String'", type_name))?;
+
+        if type_name == name {
+            return Ok(LinkType::new(name, inverse))
+        } else if inverse == name {
+            return Ok(LinkType::new(name, type_name.clone()))
+        }
+    }
+
+    Ok(LinkType::symmetric(name))
+}
+
+fn link_from_to<'a, I>(rt: &'a Runtime,
+                       from: &'a str,
+                       to: I,
+                       link_type: Option<LinkType>,
+                       annotation: Option<String>)
     where I: Iterator<Item = &'a str>
 {
-    let mut from_entry = match get_entry_by_name(rt, from).map_err_trace_exit_unwrap(1) {
-        Some(e) => e,
-        None    => {
-            debug!("No 'from' entry");
-            warn_exit("No 'from' entry", 1)
-        },
-    };
+    let mut from_entry = get_entry_by_name(rt, from)
+        .map_err_trace_exit_unwrap(1)
+        .ok_or_else(|| Error::from(EM::EntryNotFound(String::from(from))))
+        .map_err_trace_exit_unwrap(1);
 
     for entry in to {
         debug!("Handling 'to' entry: {:?}", entry);
         if !rt.store().get(PathBuf::from(entry)).map_err_trace_exit_unwrap(1).is_some() {
             debug!("Linking externally: {:?} -> {:?}", from, entry);
+            if link_type.is_some() || annotation.is_some() {
+                warn!("External links cannot have a type or annotation, ignoring them for {}", entry);
+            }
+
             let url = Url::parse(entry).unwrap_or_else(|e| {
                 error!("Error parsing URL: {:?}", e);
-                ::std::process::exit(1);
+                ::libimagerror::exit::exit(1);
             });
 
             let iter = from_entry
@@ -173,19 +251,25 @@ fn link_from_to<'a, I>(rt: &'a Runtime, from: &'a str, to: I)
 
             if from_id == entr_id {
                 error!("Cannot link entry with itself. Exiting");
-                ::std::process::exit(1)
+                ::libimagerror::exit::exit(1)
             }
 
-            let mut to_entry = match rt.store().get(entr_id).map_err_trace_exit_unwrap(1) {
-                Some(e) => e,
-                None    => {
-                    warn!("No 'to' entry: {}", entry);
-                    ::std::process::exit(1)
-                },
-            };
-            let _ = from_entry
-                .add_internal_link(&mut to_entry)
+            let mut to_entry = rt.store()
+                .get(entr_id)
+                .map_err_trace_exit_unwrap(1)
+                .ok_or_else(|| Error::from(EM::EntryNotFound(String::from(entry))))
                 .map_err_trace_exit_unwrap(1);
+            let _ = match (link_type.as_ref(), annotation.as_ref()) {
+                (Some(t), None) => from_entry.add_internal_typed_link(&mut to_entry, t.clone()),
+                (Some(t), Some(a)) => {
+                    let to_id = to_entry.get_location().clone();
+                    from_entry
+                        .add_internal_typed_link(&mut to_entry, t.clone())
+                        .and_then(|_| from_entry.set_internal_link_annotation(&to_id, a.clone()))
+                },
+                (None, Some(a)) => from_entry.add_internal_annotated_link(&mut to_entry, a.clone()),
+                (None, None)    => from_entry.add_internal_link(&mut to_entry),
+            }.map_err_trace_exit_unwrap(1);
 
             let _ = rt
                 .report_touched(to_entry.get_location())
@@ -209,10 +293,10 @@ fn remove_linking(rt: &Runtime) {
         .map(PathBuf::from)
         .map(|id| {
             rt.store()
-                .get(id)
+                .get(id.clone())
+                .map_err_trace_exit_unwrap(1)
+                .ok_or_else(|| Error::from(EM::EntryNotFound(id.display().to_string())))
                 .map_err_trace_exit_unwrap(1)
-                .ok_or_else(|| warn_exit("No 'from' entry", 1))
-                .unwrap() // safe by line above
         })
         .unwrap();
 
@@ -236,11 +320,11 @@ fn remove_linking(rt: &Runtime) {
                 if id.local().is_file() {
                     let pb = id.local().to_str().unwrap_or_else(|| {
                         warn!("Not StoreId and not a Path: {}", id);
-                        ::std::process::exit(1);
+                        ::libimagerror::exit::exit(1);
                     });
                     let url = Url::parse(pb).unwrap_or_else(|e| {
                         error!("Error parsing URL: {:?}", e);
-                        ::std::process::exit(1);
+                        ::libimagerror::exit::exit(1);
                     });
                     from.remove_external_link(rt.store(), url).map_err_trace_exit_unwrap(1);
                     info!("Ok: {}", id);
@@ -262,7 +346,7 @@ fn unlink(rt: &Runtime) {
             .map_err_trace_exit_unwrap(1)
             .unwrap_or_else(|| {
                 warn!("No entry for {}", id);
-                ::std::process::exit(1)
+                ::libimagerror::exit::exit(1)
             })
             .unlink(rt.store())
             .map_err_trace_exit_unwrap(1);
@@ -280,14 +364,27 @@ fn list_linkings(rt: &Runtime) {
 
     let list_externals  = cmd.is_present("list-externals-too");
     let list_plain      = cmd.is_present("list-plain");
+    let filter_type     = cmd.value_of("type");
 
     let mut tab = ::prettytable::Table::new();
-    tab.set_titles(row!["#", "Link"]);
+    tab.set_titles(row!["#", "Link", "Type", "Annotation"]);
 
     rt.ids::<::ui::PathProvider>().map_err_trace_exit_unwrap(1).into_iter().for_each(|id| {
         match rt.store().get(id.clone()) {
             Ok(Some(entry)) => {
-                for (i, link) in entry.get_internal_links().map_err_trace_exit_unwrap(1).enumerate() {
+                let links = match filter_type {
+                    Some(name) => entry.get_internal_links_of_type(name),
+                    None       => entry.get_internal_links(),
+                }.map_err_trace_exit_unwrap(1);
+
+                for (i, link) in links.enumerate() {
+                    let link_type = link
+                        .link_type()
+                        .map(|t| t.name().to_owned())
+                        .unwrap_or_else(String::new);
+
+                    let annotation = link.annotation().map(String::from).unwrap_or_else(String::new);
+
                     let link = link
                         .to_str()
                         .map_warn_err(|e| format!("Failed to convert StoreId to string: {:?}", e))
@@ -295,11 +392,19 @@ fn list_linkings(rt: &Runtime) {
 
                     if let Some(link) = link {
                         if list_plain {
-                            let _ = writeln!(rt.stdout(), "{: <3}: {}", i, link)
+                            let mut line = format!("{: <3}: {}", i, link);
+                            if !link_type.is_empty() {
+                                line.push_str(&format!(" ({})", link_type));
+                            }
+                            if !annotation.is_empty() {
+                                line.push_str(&format!(" \"{}\"", annotation));
+                            }
+
+                            let _ = writeln!(rt.stdout(), "{}", line)
                                 .to_exit_code()
                                 .unwrap_or_exit();
                         } else {
-                            tab.add_row(row![i, link]);
+                            tab.add_row(row![i, link, link_type, annotation]);
                         }
                     }
                 }
@@ -318,7 +423,7 @@ fn list_linkings(rt: &Runtime) {
                                     .to_exit_code()
                                     .unwrap_or_exit();
                             } else {
-                                tab.add_row(row![i, link]);
+                                tab.add_row(row![i, link, "", ""]);
                             }
                         })
                 }
@@ -346,6 +451,237 @@ fn list_linkings(rt: &Runtime) {
     }
 }
 
+fn check_consistency(rt: &Runtime) {
+    let cmd = rt.cli()
+        .subcommand_matches("check")
+        .unwrap(); // safed by clap
+
+    let report = rt.store().check_link_consistency().map_err_trace_exit_unwrap(1);
+    if report.is_consistent() {
+        info!("Store is consistent");
+        return;
+    }
+
+    if !cmd.is_present("fix") {
+        print_link_problems(rt, &report);
+        ::libimagerror::exit::exit(1);
+    }
+
+    let summary = if cmd.is_present("interactive") {
+        let mut input = rt.stdin().unwrap_or_else(|| {
+            error!("No input stream. Cannot ask what to do");
+            ::libimagerror::exit::exit(1)
+        });
+        let mut output = rt.stdout();
+
+        rt.store().repair_link_consistency_with(&report, |problem| {
+            ask_repair_action(problem, &mut input, &mut output)
+        })
+    } else {
+        rt.store().repair_link_consistency(&report)
+    }.map_err_trace_exit_unwrap(1);
+
+    for problem in report.problems() {
+        let _ = rt.report_touched(problem.from()).map_err_trace_exit_unwrap(1);
+    }
+
+    info!("Added {} backlinks, removed {} links, ignored {} problems",
+          summary.added_backlinks(),
+          summary.removed_links(),
+          summary.ignored());
+
+    if summary.ignored() > 0 {
+        ::libimagerror::exit::exit(1);
+    }
+}
+
+fn print_link_problems(rt: &Runtime, report: &ConsistencyReport) {
+    for problem in report.problems() {
+        let _ = writeln!(rt.stdout(), "{}", problem)
+            .to_exit_code()
+            .unwrap_or_exit();
+
+        let _ = rt.report_touched(problem.from()).map_err_trace_exit_unwrap(1);
+    }
+}
+
+fn ask_repair_action(problem: &LinkProblem, input: &mut Read, output: &mut Write) -> Result<RepairAction> {
+    let remove_by_default = match *problem {
+        LinkProblem::MissingBacklink { .. } => {
+            if ask_bool(&format!("{}. Add the missing backlink?", problem), Some(true), input, output)? {
+                return Ok(RepairAction::AddBacklink)
+            }
+            false
+        },
+        LinkProblem::DanglingLink { .. } => true,
+    };
+
+    if ask_bool(&format!("{}. Remove the link?", problem), Some(remove_by_default), input, output)? {
+        Ok(RepairAction::RemoveLink)
+    } else {
+        Ok(RepairAction::Ignore)
+    }
+}
+
+fn check_external(rt: &Runtime) {
+    use std::str::FromStr;
+    use std::time::Duration;
+
+    let cmd = rt.cli()
+        .subcommand_matches("check-external")
+        .unwrap(); // safed by clap
+
+    // safe by clap default values and validators
+    let timeout = u64::from_str(cmd.value_of("timeout").unwrap()).unwrap();
+    let jobs    = usize::from_str(cmd.value_of("jobs").unwrap()).unwrap();
+    let all     = cmd.is_present("all");
+
+    let results = LinkChecker::new(Duration::from_secs(timeout), jobs)
+        .and_then(|checker| checker.check_store(rt.store()))
+        .map_err_trace_exit_unwrap(1);
+
+    let mut broken = 0;
+    for (id, result) in results {
+        if result.is_broken() {
+            broken += 1;
+        } else if !all {
+            continue;
+        }
+
+        let linked_from = rt.store()
+            .get(id.clone())
+            .map_err_trace_exit_unwrap(1)
+            .map(|entry| {
+                entry.get_internal_links()
+                    .map_err_trace_exit_unwrap(1)
+                    .map(|l| l.get_store_id().local_display_string())
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_else(Vec::new);
+
+        let _ = writeln!(rt.stdout(),
+                         "{}\t{}\t{}",
+                         describe_check_result(&result),
+                         result.url(),
+                         linked_from.join(" "))
+            .to_exit_code()
+            .unwrap_or_exit();
+
+        let _ = rt.report_touched(&id).map_err_trace_exit_unwrap(1);
+    }
+
+    if broken > 0 {
+        info!("{} broken links", broken);
+        ::libimagerror::exit::exit(1);
+    }
+}
+
+/// Describe the result of a link check in a few words, for example "404" or "301 -> <url>"
+fn describe_check_result(result: &CheckResult) -> String {
+    match (result.status(), result.redirect(), result.error()) {
+        (_, _, Some(e))               => format!("error: {}", e),
+        (Some(s), Some(redirect), _)  => format!("{} -> {}", s, redirect),
+        (Some(s), None, _)            => format!("{}", s),
+        (None, _, None)               => String::from("unknown"),
+    }
+}
+
+fn annotate(rt: &Runtime) {
+    let cmd = rt.cli()
+        .subcommand_matches("annotate")
+        .unwrap(); // safed by clap
+
+    let get_entry = |name: &str| {
+        get_entry_by_name(rt, name)
+            .map_err_trace_exit_unwrap(1)
+            .ok_or_else(|| Error::from(EM::EntryNotFound(String::from(name))))
+            .map_err_trace_exit_unwrap(1)
+    };
+
+    let mut from = get_entry(cmd.value_of("from").unwrap()); // safe by clap
+    let to       = get_entry(cmd.value_of("to").unwrap()).get_location().clone(); // safe by clap
+
+    if cmd.is_present("remove") {
+        from.remove_internal_link_annotation(&to).map_err_trace_exit_unwrap(1);
+    } else if let Some(annotation) = cmd.value_of("annotation") {
+        from.set_internal_link_annotation(&to, String::from(annotation)).map_err_trace_exit_unwrap(1);
+    } else if let Some(annotation) = from.get_internal_link_annotation(&to).map_err_trace_exit_unwrap(1) {
+        let _ = writeln!(rt.stdout(), "{}", annotation)
+            .to_exit_code()
+            .unwrap_or_exit();
+    }
+
+    let _ = rt
+        .report_touched(from.get_location())
+        .map_err_trace_exit_unwrap(1);
+}
+
+fn graph(rt: &Runtime) {
+    use std::str::FromStr;
+
+    let cmd = rt.cli()
+        .subcommand_matches("graph")
+        .unwrap(); // safed by clap
+
+    let to_id = |s: &str| StoreId::new_baseless(PathBuf::from(s)).map_err_trace_exit_unwrap(1);
+
+    let graph = match cmd.value_of("collection") {
+        Some(collection) => LinkGraph::from_collection(rt.store(), collection),
+        None             => LinkGraph::from_store(rt.store()),
+    }.map_err_trace_exit_unwrap(1);
+
+    let graph = match cmd.value_of("around") {
+        Some(around) => {
+            let around = to_id(around);
+            let depth  = cmd
+                .value_of("depth")
+                .map(|d| usize::from_str(d).unwrap()) // safe by clap validator
+                .unwrap_or(1);
+
+            if !graph.contains(&around) {
+                warn_exit(&format!("Not in the graph: {}", around), 1);
+            }
+
+            graph.neighbourhood(&around, depth)
+        },
+        None => graph,
+    };
+
+    let out      = rt.stdout();
+    let mut lock = out.lock();
+
+    if let Some(mut ids) = cmd.values_of("path") {
+        let from = to_id(ids.next().unwrap()); // safe by clap
+        let to   = to_id(ids.next().unwrap()); // safe by clap
+
+        match graph.shortest_path(&from, &to) {
+            Some(path) => for id in path {
+                let _ = writeln!(lock, "{}", id).to_exit_code().unwrap_or_exit();
+                let _ = rt.report_touched(&id).map_err_trace_exit_unwrap(1);
+            },
+            None => warn_exit(&format!("No path from {} to {}", from, to), 1),
+        }
+    } else if cmd.is_present("components") {
+        for component in graph.connected_components() {
+            let ids = component.iter().map(StoreId::local_display_string).collect::<Vec<_>>();
+            let _ = writeln!(lock, "{}", ids.join(" ")).to_exit_code().unwrap_or_exit();
+        }
+    } else if cmd.is_present("orphans") {
+        for id in graph.orphans() {
+            let _ = writeln!(lock, "{}", id).to_exit_code().unwrap_or_exit();
+            let _ = rt.report_touched(id).map_err_trace_exit_unwrap(1);
+        }
+    } else {
+        let output = match cmd.value_of("format") {
+            Some("graphml") => graph.to_graphml(),
+            Some("json")    => graph.to_json().map_err_trace_exit_unwrap(1),
+            _               => graph.to_dot(),
+        };
+
+        let _ = write!(lock, "{}", output).to_exit_code().unwrap_or_exit();
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::link_from_to;
@@ -424,7 +760,7 @@ mod tests {
 
         debug!("Entries created");
 
-        link_from_to(&rt, "test1", vec!["test2"].into_iter());
+        link_from_to(&rt, "test1", vec!["test2"].into_iter(), None, None);
 
         debug!("Linking done");
 
@@ -455,7 +791,7 @@ mod tests {
 
         debug!("Test entries created");
 
-        link_from_to(&rt, "test1", vec!["test2"].into_iter());
+        link_from_to(&rt, "test1", vec!["test2"].into_iter(), None, None);
 
         debug!("Linking done");
 
@@ -484,8 +820,8 @@ mod tests {
 
         debug!("Test entries created");
 
-        link_from_to(&rt, "test1", vec!["test2"].into_iter());
-        link_from_to(&rt, "test1", vec!["test2"].into_iter());
+        link_from_to(&rt, "test1", vec!["test2"].into_iter(), None, None);
+        link_from_to(&rt, "test1", vec!["test2"].into_iter(), None, None);
 
         debug!("Linking done");
 
@@ -515,8 +851,8 @@ mod tests {
 
         debug!("Test entries created");
 
-        link_from_to(&rt, "test1", vec!["test2", "test3"].into_iter());
-        link_from_to(&rt, "test1", vec!["test2", "test3"].into_iter());
+        link_from_to(&rt, "test1", vec!["test2", "test3"].into_iter(), None, None);
+        link_from_to(&rt, "test1", vec!["test2", "test3"].into_iter(), None, None);
 
         debug!("Linking done");
 
@@ -551,7 +887,7 @@ mod tests {
 
         debug!("Test entries created");
 
-        link_from_to(&rt, "test1", vec!["test2"].into_iter());
+        link_from_to(&rt, "test1", vec!["test2"].into_iter(), None, None);
 
         debug!("Linking done");
 
@@ -588,7 +924,7 @@ mod tests {
 
         debug!("Test entries created");
 
-        link_from_to(&rt, "test1", vec!["test2", "test3"].into_iter());
+        link_from_to(&rt, "test1", vec!["test2", "test3"].into_iter(), None, None);
 
         debug!("linking done");
 
@@ -82,6 +82,161 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
                      .takes_value(false)
                      .required(false)
                      .help("List plain rather than in ASCII table"))
+
+                .arg(Arg::with_name("type")
+                     .long("type")
+                     .short("t")
+                     .takes_value(true)
+                     .required(false)
+                     .multiple(false)
+                     .help("List only links of this type")
+                     .value_name("TYPE"))
+                )
+
+        .subcommand(SubCommand::with_name("check")
+                .about("Check the consistency of the internal links in the store and print the problems")
+                .version("0.1")
+                .arg(Arg::with_name("fix")
+                     .long("fix")
+                     .takes_value(false)
+                     .required(false)
+                     .help("Repair the problems: Add missing backlinks and remove links to entries which do not exist"))
+                .arg(Arg::with_name("interactive")
+                     .long("interactive")
+                     .short("i")
+                     .takes_value(false)
+                     .required(false)
+                     .requires("fix")
+                     .help("Ask what to do about each problem"))
+                )
+
+        .subcommand(SubCommand::with_name("check-external")
+                .about("Check whether the URLs of external links are reachable and print the broken ones")
+                .version("0.1")
+                .arg(Arg::with_name("timeout")
+                     .long("timeout")
+                     .takes_value(true)
+                     .required(false)
+                     .multiple(false)
+                     .default_value("10")
+                     .validator(::libimagutil::cli_validators::is_unsigned_integer)
+                     .help("Give up on a URL after this many seconds")
+                     .value_name("SECONDS"))
+                .arg(Arg::with_name("jobs")
+                     .long("jobs")
+                     .short("j")
+                     .takes_value(true)
+                     .required(false)
+                     .multiple(false)
+                     .default_value("4")
+                     .validator(::libimagutil::cli_validators::is_unsigned_integer)
+                     .help("Check this many URLs at a time")
+                     .value_name("N"))
+                .arg(Arg::with_name("all")
+                     .long("all")
+                     .short("a")
+                     .takes_value(false)
+                     .required(false)
+                     .help("Print all checked links, not only the broken ones"))
+                )
+
+        .subcommand(SubCommand::with_name("annotate")
+                .about("Print, set or remove the annotation of a link")
+                .version("0.1")
+                .arg(Arg::with_name("from")
+                     .index(1)
+                     .takes_value(true)
+                     .required(true)
+                     .multiple(false)
+                     .help("The entry the link is stored in")
+                     .value_name("ENTRY"))
+                .arg(Arg::with_name("to")
+                     .index(2)
+                     .takes_value(true)
+                     .required(true)
+                     .multiple(false)
+                     .help("The entry the link points to")
+                     .value_name("ENTRY"))
+                .arg(Arg::with_name("annotation")
+                     .index(3)
+                     .takes_value(true)
+                     .required(false)
+                     .multiple(false)
+                     .help("Set the annotation to this text. If not given, the annotation is printed")
+                     .value_name("ANNOTATION"))
+                .arg(Arg::with_name("remove")
+                     .long("remove")
+                     .short("r")
+                     .takes_value(false)
+                     .required(false)
+                     .conflicts_with("annotation")
+                     .help("Remove the annotation"))
+                )
+
+        .subcommand(SubCommand::with_name("graph")
+                .about("Export the link graph of the store or query it")
+                .version("0.1")
+                .arg(Arg::with_name("collection")
+                     .long("collection")
+                     .short("c")
+                     .takes_value(true)
+                     .required(false)
+                     .multiple(false)
+                     .help("Only load the entries in this collection, for example 'wiki'")
+                     .value_name("COLLECTION"))
+
+                .arg(Arg::with_name("around")
+                     .long("around")
+                     .takes_value(true)
+                     .required(false)
+                     .multiple(false)
+                     .help("Only use the entries which are at most --depth links away from this entry")
+                     .value_name("ENTRY"))
+
+                .arg(Arg::with_name("depth")
+                     .long("depth")
+                     .short("d")
+                     .takes_value(true)
+                     .required(false)
+                     .multiple(false)
+                     .requires("around")
+                     .validator(::libimagutil::cli_validators::is_unsigned_integer)
+                     .help("Depth of the neighbourhood for --around, defaults to 1")
+                     .value_name("N"))
+
+                .arg(Arg::with_name("format")
+                     .long("format")
+                     .short("f")
+                     .takes_value(true)
+                     .required(false)
+                     .multiple(false)
+                     .possible_values(&["dot", "graphml", "json"])
+                     .default_value("dot")
+                     .help("Format to export the graph in")
+                     .value_name("FORMAT"))
+
+                .arg(Arg::with_name("path")
+                     .long("path")
+                     .takes_value(true)
+                     .required(false)
+                     .number_of_values(2)
+                     .conflicts_with_all(&["components", "orphans"])
+                     .help("Print the shortest path between two entries instead of the graph")
+                     .value_names(&["FROM", "TO"]))
+
+                .arg(Arg::with_name("components")
+                     .long("components")
+                     .takes_value(false)
+                     .required(false)
+                     .conflicts_with_all(&["path", "orphans"])
+                     .help("Print the connected components, one per line, instead of the graph"))
+
+                .arg(Arg::with_name("orphans")
+                     .long("orphans")
+                     .takes_value(false)
+                     .required(false)
+                     .conflicts_with_all(&["path", "components"])
+                     .help("Print the entries which are not linked to any other entry instead of the graph"))
                 )
 
         .arg(Arg::with_name("check-consistency")
@@ -108,6 +263,26 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
              .help("Link to this entries")
              .requires("from")
              .value_name("ENTRIES"))
+
+        .arg(Arg::with_name("annotation")
+             .long("annotation")
+             .short("a")
+             .takes_value(true)
+             .required(false)
+             .multiple(false)
+             .help("Annotate the links with this text, for example 'met at'. The annotation is stored in the 'from' entry only")
+             .requires("to")
+             .value_name("TEXT"))
+
+        .arg(Arg::with_name("type")
+             .long("type")
+             .short("t")
+             .takes_value(true)
+             .required(false)
+             .multiple(false)
+             .help("Type of the link, for example 'parent', 'child', 'depends-on', 'blocks', 'references' or 'referenced-by'. The linked entries link back with the inverse type. Further types can be configured in the 'link.types' section of the configuration")
+             .requires("to")
+             .value_name("TYPE"))
 }
 
 /// PathProvider
@@ -124,7 +299,7 @@ impl IdPathProvider for PathProvider {
                 let to = subm.values_of("to")
                     .ok_or_else(|| {
                         error!("No StoreId found");
-                        ::std::process::exit(1)
+                        ::libimagerror::exit::exit(1)
                     })
                     .unwrap()
                     .into_iter()
@@ -140,7 +315,7 @@ impl IdPathProvider for PathProvider {
                     .values_of("from")
                     .ok_or_else(|| {
                         error!("No StoreId found");
-                        ::std::process::exit(1)
+                        ::libimagerror::exit::exit(1)
                     })
                     .unwrap()
                     .into_iter()
@@ -157,7 +332,7 @@ impl IdPathProvider for PathProvider {
                     .values_of("entries")
                     .ok_or_else(|| {
                         error!("No StoreId found");
-                        ::std::process::exit(1)
+                        ::libimagerror::exit::exit(1)
                     })
                     .unwrap()
                     .into_iter()
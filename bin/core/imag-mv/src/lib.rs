@@ -0,0 +1,171 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+#![forbid(unsafe_code)]
+
+#![deny(
+    non_camel_case_types,
+    non_snake_case,
+    path_statements,
+    trivial_numeric_casts,
+    unstable_features,
+    unused_allocation,
+    unused_import_braces,
+    unused_imports,
+    unused_must_use,
+    unused_mut,
+    unused_qualifications,
+    while_true,
+)]
+
+#[macro_use] extern crate log;
+extern crate clap;
+extern crate failure;
+
+extern crate libimagrt;
+extern crate libimagstore;
+extern crate libimagerror;
+extern crate libimagentrylink;
+
+mod ui;
+use ui::build_ui;
+
+use std::path::PathBuf;
+
+use clap::App;
+use failure::Fallible as Result;
+use failure::Error;
+
+use libimagrt::runtime::Runtime;
+use libimagrt::application::ImagApplication;
+use libimagerror::trace::MapErrTrace;
+use libimagerror::iter::TraceIterator;
+use libimagerror::errors::ErrorMsg as EM;
+use libimagstore::storeid::StoreId;
+use libimagstore::store::Store;
+use libimagstore::store::FileLockEntry;
+use libimagentrylink::internal::InternalLinker;
+
+/// Marker enum for implementing ImagApplication on
+///
+/// This is used by binaries crates to execute business logic
+/// or to build a CLI completion.
+pub enum ImagMv {}
+impl ImagApplication for ImagMv {
+    fn run(rt: &Runtime) -> Result<()> {
+        debug!("mv");
+
+        let sourcename = rt
+            .cli()
+            .value_of("source")
+            .map(PathBuf::from)
+            .map(StoreId::new_baseless)
+            .unwrap() // unwrap safe by clap
+            .map_err_trace_exit_unwrap(1);
+
+        let destname = rt
+            .cli()
+            .value_of("dest")
+            .map(PathBuf::from)
+            .map(StoreId::new_baseless)
+            .unwrap() // unwrap safe by clap
+            .map_err_trace_exit_unwrap(1);
+
+        // remove links to entry, and re-add them later
+        let mut linked_entries = {
+            rt.store()
+                .get(sourcename.clone())
+                .map_err_trace_exit_unwrap(1)
+                .ok_or_else(|| Error::from(EM::EntryNotFound(sourcename.local_display_string())))
+                .map_err_trace_exit_unwrap(1)
+                .get_internal_links()
+                .map_err_trace_exit_unwrap(1)
+                .map(|link| {
+                    let id = link.get_store_id().clone();
+                    rt.store()
+                        .get(id.clone())?
+                        .ok_or_else(|| Error::from(EM::EntryNotFound(id.local_display_string())))
+                })
+                .trace_unwrap_exit(1)
+                .collect::<Vec<_>>()
+        };
+
+        { // remove links to linked entries from source
+            let mut entry = rt
+                .store()
+                .get(sourcename.clone())
+                .map_err_trace_exit_unwrap(1)
+                .ok_or_else(|| Error::from(EM::EntryNotFound(sourcename.local_display_string())))
+                .map_err_trace_exit_unwrap(1);
+
+            for link in linked_entries.iter_mut() {
+                let _ = entry.remove_internal_link(link).map_err_trace_exit_unwrap(1);
+            }
+        }
+
+        let _ = rt
+            .store()
+            .move_by_id(sourcename.clone(), destname.clone())
+            .map_err(|e| { // on error, re-add links
+                debug!("Re-adding links to source entry because moving failed");
+                relink(rt.store(), sourcename.clone(), &mut linked_entries);
+                e
+            })
+            .map_err_trace_exit_unwrap(1);
+
+        let _ = rt.report_touched(&destname)
+            .map_err_trace_exit_unwrap(1);
+
+        // re-add links to moved entry
+        relink(rt.store(), destname, &mut linked_entries);
+
+        info!("Ok.");
+
+        Ok(())
+    }
+
+    fn build_cli<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
+        build_ui(app)
+    }
+
+    fn name() -> &'static str {
+        env!("CARGO_PKG_NAME")
+    }
+
+    fn description() -> &'static str {
+        "Move things around in the store"
+    }
+
+    fn version() -> &'static str {
+        env!("CARGO_PKG_VERSION")
+    }
+}
+
+fn relink<'a>(store: &'a Store, target: StoreId, linked_entries: &mut Vec<FileLockEntry<'a>>) {
+    let mut entry = store
+        .get(target.clone())
+        .map_err_trace_exit_unwrap(1)
+        .ok_or_else(|| Error::from(EM::EntryNotFound(target.local_display_string())))
+        .map_err_trace_exit_unwrap(1);
+
+
+    for mut link in linked_entries {
+        let _ = entry.add_internal_link(&mut link).map_err_trace_exit_unwrap(1);
+    }
+}
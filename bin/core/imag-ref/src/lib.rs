@@ -0,0 +1,549 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+#![forbid(unsafe_code)]
+
+#![deny(
+    non_camel_case_types,
+    non_snake_case,
+    path_statements,
+    trivial_numeric_casts,
+    unstable_features,
+    unused_allocation,
+    unused_import_braces,
+    unused_imports,
+    unused_must_use,
+    unused_mut,
+    unused_qualifications,
+    while_true,
+)]
+
+#[macro_use] extern crate log;
+extern crate clap;
+extern crate failure;
+
+extern crate libimagstore;
+extern crate libimagrt;
+extern crate libimagentryref;
+extern crate libimagerror;
+extern crate libimaginteraction;
+extern crate libimagutil;
+
+mod ui;
+use ui::build_ui;
+
+use std::path::Path;
+use std::path::PathBuf;
+use std::str::FromStr;
+use libimagerror::exit::exit;
+use std::io::Write;
+
+use clap::{App, ArgMatches};
+use failure::Fallible as Result;
+use failure::Error;
+
+use libimagerror::trace::MapErrTrace;
+use libimagerror::io::ToExitCode;
+use libimagerror::exit::ExitUnwrap;
+use libimagrt::runtime::Runtime;
+use libimagrt::application::ImagApplication;
+use libimagstore::storeid::IntoStoreId;
+use libimagstore::storeid::StoreId;
+use libimagstore::store::FileLockEntry;
+use libimagerror::trace::trace_error;
+use libimagentryref::reference::Ref;
+use libimagentryref::reference::Config;
+use libimagentryref::refstore::RefStore;
+use libimagentryref::refstore::UniqueRefPathGenerator;
+use libimagentryref::refind::configured_search_roots;
+use libimagentryref::refind::dirs_below;
+use libimagentryref::refind::files_below;
+use libimagentryref::tree::Snapshot;
+use libimagentryref::generators::sha1::Sha1;
+use libimagentryref::generators::sha224::Sha224;
+use libimagentryref::generators::sha256::Sha256;
+use libimagentryref::generators::sha384::Sha384;
+use libimagentryref::generators::sha512::Sha512;
+use libimagentryref::generators::sha3::Sha3;
+use libimagentryref::generators::dirtree::DirTree;
+use libimagutil::warn_exit::warn_exit;
+
+/// Call a function generic over the `UniqueRefPathGenerator` with the one selected by `--hasher`
+macro_rules! with_hasher {
+    ($cmd:expr, $fun:ident($($arg:expr),*)) => {
+        match $cmd.value_of("hasher").unwrap() { // safe by clap
+            "sha1"    => $fun::<Sha1>($($arg),*),
+            "sha224"  => $fun::<Sha224>($($arg),*),
+            "sha256"  => $fun::<Sha256>($($arg),*),
+            "sha384"  => $fun::<Sha384>($($arg),*),
+            "sha512"  => $fun::<Sha512>($($arg),*),
+            "sha3"    => $fun::<Sha3>($($arg),*),
+            "dirtree" => $fun::<DirTree>($($arg),*),
+            _         => unreachable!(), // safe by clap
+        }
+    }
+}
+
+/// Marker enum for implementing ImagApplication on
+///
+/// This is used by binaries crates to execute business logic
+/// or to build a CLI completion.
+pub enum ImagRef {}
+impl ImagApplication for ImagRef {
+    fn run(rt: &Runtime) -> Result<()> {
+        rt.cli()
+            .subcommand_name()
+            .map(|name| {
+                debug!("Call: {}", name);
+                match name {
+                    "deref"   => deref(&rt),
+                    "remove"  => remove(&rt),
+                    "refind"  => refind(&rt),
+                    "migrate" => migrate(&rt),
+                    "show"    => show(&rt),
+                    "list"    => list(&rt),
+                    "refresh" => refresh(&rt),
+                    "changes" => changes(&rt),
+                    other => {
+                        debug!("Unknown command");
+                        let _ = rt.handle_unknown_subcommand("imag-ref", other, rt.cli())
+                            .map_err_trace_exit_unwrap(1)
+                            .code()
+                            .map(::libimagerror::exit::exit);
+                    },
+                };
+            });
+
+        Ok(())
+    }
+
+    fn build_cli<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
+        build_ui(app)
+    }
+
+    fn name() -> &'static str {
+        env!("CARGO_PKG_NAME")
+    }
+
+    fn description() -> &'static str {
+        "Reference files outside of the store"
+    }
+
+    fn version() -> &'static str {
+        env!("CARGO_PKG_VERSION")
+    }
+}
+
+fn deref(rt: &Runtime) {
+    let cmd  = rt.cli().subcommand_matches("deref").unwrap();
+    let id   = cmd.value_of("ID")
+        .map(String::from)
+        .map(PathBuf::from)
+        .unwrap() // saved by clap
+        .into_storeid()
+        .map_err_trace_exit_unwrap(1);
+
+    match rt.store().get(id.clone()).map_err_trace_exit_unwrap(1) {
+        Some(entry) => {
+            entry
+                .get_path(&ref_config(rt))
+                .map_err_trace_exit_unwrap(1)
+                .to_str()
+                .ok_or_else(|| {
+                    error!("Could not transform path into string!");
+                    exit(1)
+                })
+                .map(|s| info!("{}", s))
+                .ok(); // safe here because we exited already in the error case
+
+            let _ = rt.report_touched(&id).map_err_trace_exit_unwrap(1);
+        },
+        None => {
+            error!("No entry for id '{}' found", id);
+            exit(1)
+        },
+    };
+}
+
+fn remove(rt: &Runtime) {
+    use libimaginteraction::ask::ask_bool;
+
+    let cmd  = rt.cli().subcommand_matches("remove").unwrap();
+    let yes  = cmd.is_present("yes");
+    let id   = cmd.value_of("ID")
+        .map(String::from)
+        .map(PathBuf::from)
+        .unwrap() // saved by clap
+        .into_storeid()
+        .map_err_trace_exit_unwrap(1);
+
+    let mut input = rt.stdin().unwrap_or_else(|| {
+        error!("No input stream. Cannot ask for permission");
+        exit(1);
+    });
+
+    let mut output = rt.stdout();
+
+    match rt.store().get(id.clone()).map_err_trace_exit_unwrap(1) {
+        Some(mut entry) => {
+            if yes ||
+                ask_bool(&format!("Delete ref from entry '{}'", id), None, &mut input, &mut output)
+                    .map_err_trace_exit_unwrap(1)
+            {
+                let _ = entry.remove_ref().map_err_trace_exit_unwrap(1);
+                let _ = Snapshot::delete(rt.store(), &id).map_err_trace_exit_unwrap(1);
+            } else {
+                info!("Aborted");
+            }
+        },
+        None => {
+            error!("No entry for id '{}' found", id);
+            exit(1)
+        },
+    };
+}
+
+fn refind(rt: &Runtime) {
+    let cmd   = rt.cli().subcommand_matches("refind").unwrap();
+    let roots = match cmd.values_of("root") {
+        Some(roots) => roots.map(PathBuf::from).collect(),
+        None        => rt.config()
+            .map(|cfg| configured_search_roots(cfg).map_err_trace_exit_unwrap(1))
+            .unwrap_or_else(Vec::new),
+    };
+
+    if roots.is_empty() {
+        warn_exit("No search roots. Pass --root or configure 'ref.search_roots'", 1)
+    }
+
+    let hasher  = refind_hasher(cmd);
+    let config  = ref_config(rt);
+
+    // The dirtree hasher hashes directories, so directories are the candidates for it
+    let candidates = |roots: Vec<PathBuf>| if cmd.value_of("hasher") == Some("dirtree") {
+        dirs_below(roots)
+    } else {
+        files_below(roots)
+    };
+    let mut out = rt.stdout();
+
+    if cmd.is_present("all") {
+        let relocated = rt.store()
+            .refind_dangling_with("ref", candidates(roots), &config, |path: &Path| hasher(path))
+            .map_err_trace_exit_unwrap(1);
+
+        for (id, path) in relocated {
+            let _ = writeln!(out, "{} -> {}", id, path.display())
+                .to_exit_code()
+                .unwrap_or_exit();
+
+            let _ = rt.report_touched(&id).map_err_trace_exit_unwrap(1);
+        }
+
+        return;
+    }
+
+    for id in cmd.values_of("ID").unwrap() { // safe by clap
+        let id = PathBuf::from(id).into_storeid().map_err_trace_exit_unwrap(1);
+
+        let mut entry = match rt.store().get(id.clone()).map_err_trace_exit_unwrap(1) {
+            Some(entry) => entry,
+            None        => {
+                error!("No entry for id '{}' found", id);
+                exit(1)
+            },
+        };
+
+        if !entry.is_dangling(&config).map_err_trace_exit_unwrap(1) {
+            info!("Reference in '{}' is not dangling, skipping", id);
+            continue;
+        }
+
+        let found = entry
+            .refind_with(candidates(roots.clone()), &config, |path: &Path| hasher(path))
+            .map_err_trace_exit_unwrap(1);
+
+        match found {
+            Some(path) => {
+                let _ = writeln!(out, "{} -> {}", id, path.display())
+                    .to_exit_code()
+                    .unwrap_or_exit();
+
+                let _ = rt.report_touched(&id).map_err_trace_exit_unwrap(1);
+            },
+            None => warn!("Could not re-find the referenced file of '{}'", id),
+        }
+    }
+}
+
+/// Get the hash function selected by `--hasher` and `--bytes` for re-finding references
+fn refind_hasher(cmd: &ArgMatches) -> Box<Fn(&Path) -> Result<String>> {
+    let bytes = cmd
+        .value_of("bytes")
+        .map(|n| usize::from_str(n).map_err(Error::from).map_err_trace_exit_unwrap(1));
+
+    match (cmd.value_of("hasher").unwrap(), bytes) { // safe by clap
+        ("sha1",    None)    => Box::new(|path: &Path| Sha1::unique_hash(path)),
+        ("sha1",    Some(n)) => Box::new(move |path: &Path| Sha1::hash_n_bytes(path, n)),
+        ("sha224",  None)    => Box::new(|path: &Path| Sha224::unique_hash(path)),
+        ("sha224",  Some(n)) => Box::new(move |path: &Path| Sha224::hash_n_bytes(path, n)),
+        ("sha256",  None)    => Box::new(|path: &Path| Sha256::unique_hash(path)),
+        ("sha256",  Some(n)) => Box::new(move |path: &Path| Sha256::hash_n_bytes(path, n)),
+        ("sha384",  None)    => Box::new(|path: &Path| Sha384::unique_hash(path)),
+        ("sha384",  Some(n)) => Box::new(move |path: &Path| Sha384::hash_n_bytes(path, n)),
+        ("sha512",  None)    => Box::new(|path: &Path| Sha512::unique_hash(path)),
+        ("sha512",  Some(n)) => Box::new(move |path: &Path| Sha512::hash_n_bytes(path, n)),
+        ("sha3",    None)    => Box::new(|path: &Path| Sha3::unique_hash(path)),
+        ("sha3",    Some(n)) => Box::new(move |path: &Path| Sha3::hash_n_bytes(path, n)),
+        ("dirtree", None)    => Box::new(|path: &Path| DirTree::unique_hash(path)),
+        ("dirtree", Some(_)) => warn_exit("Directory trees cannot be hashed partially, '--bytes' does not work with 'dirtree'", 1),
+        _                    => unreachable!(), // safe by clap
+    }
+}
+
+fn migrate(rt: &Runtime) {
+    let config  = ref_config(rt);
+    let mut out = rt.stdout();
+
+    if config.is_empty() {
+        warn_exit("No base pathes configured at 'ref.basepathes', nothing to migrate to", 1)
+    }
+
+    for id in rt.store().entries().map_err_trace_exit_unwrap(1) {
+        let id = id.map_err_trace_exit_unwrap(1);
+
+        let mut entry = match rt.store().get(id.clone()).map_err_trace_exit_unwrap(1) {
+            Some(entry) => entry,
+            None        => continue,
+        };
+
+        if !entry.is_ref().map_err_trace_exit_unwrap(1) {
+            continue;
+        }
+
+        match entry.make_relative(&config) {
+            Ok(true) => {
+                let _ = writeln!(out, "{}", id)
+                    .to_exit_code()
+                    .unwrap_or_exit();
+
+                let _ = rt.report_touched(&id).map_err_trace_exit_unwrap(1);
+            },
+            Ok(false) => {},
+            Err(e)    => {
+                trace_error(&e);
+                warn!("Could not migrate '{}', skipping", id);
+            },
+        }
+    }
+}
+
+fn show(rt: &Runtime) {
+    let cmd     = rt.cli().subcommand_matches("show").unwrap();
+    let config  = ref_config(rt);
+    let mut out = rt.stdout();
+
+    for id in cmd.values_of("ID").unwrap() { // safe by clap
+        let id    = PathBuf::from(id).into_storeid().map_err_trace_exit_unwrap(1);
+        let entry = get_ref_entry(rt, &id);
+        let path  = entry.get_path(&config).map_err_trace_exit_unwrap(1);
+        let hash  = entry.get_hash().map_err_trace_exit_unwrap(1);
+
+        let _ = writeln!(out, "{}\n\tPath: {}\n\tHash: {}", id, path.display(), hash)
+            .to_exit_code()
+            .unwrap_or_exit();
+
+        match entry.get_metadata().map_err_trace_exit_unwrap(1) {
+            Some(meta) => {
+                let _ = writeln!(out, "\tType: {}\n\tSize: {} bytes", meta.mime(), meta.size())
+                    .to_exit_code()
+                    .unwrap_or_exit();
+
+                if let Some(modified) = meta.modified() {
+                    let _ = writeln!(out, "\tModified: {}", modified).to_exit_code().unwrap_or_exit();
+                }
+
+                if let Some(date) = meta.date() {
+                    let _ = writeln!(out, "\tDate: {}", date).to_exit_code().unwrap_or_exit();
+                }
+
+                if let Some(title) = meta.title() {
+                    let _ = writeln!(out, "\tTitle: {}", title).to_exit_code().unwrap_or_exit();
+                }
+            },
+            None => info!("No metadata recorded for '{}', use 'imag ref refresh' to record it", id),
+        }
+
+        let _ = rt.report_touched(&id).map_err_trace_exit_unwrap(1);
+    }
+}
+
+fn list(rt: &Runtime) {
+    let cmd        = rt.cli().subcommand_matches("list").unwrap();
+    let collection = cmd.value_of("collection").unwrap(); // safe by clap
+    let config     = ref_config(rt);
+    let mut out    = rt.stdout();
+
+    let entries = rt.store()
+        .entries()
+        .map_err_trace_exit_unwrap(1)
+        .in_collection(collection)
+        .into_get_iter();
+
+    for entry in entries {
+        let entry = match entry.map_err_trace_exit_unwrap(1) {
+            Some(entry) => entry,
+            None        => continue,
+        };
+
+        if !entry.is_ref().map_err_trace_exit_unwrap(1) {
+            continue;
+        }
+
+        let path = match entry.get_path(&config) {
+            Ok(path) => path.display().to_string(),
+            Err(e)   => {
+                trace_error(&e);
+                String::from("<error>")
+            },
+        };
+
+        let (mime, size) = match entry.get_metadata().map_err_trace_exit_unwrap(1) {
+            Some(meta) => (meta.mime().to_string(), meta.size().to_string()),
+            None       => (String::from("-"), String::from("-")),
+        };
+
+        let _ = writeln!(out, "{}\t{}\t{}\t{}", entry.get_location(), mime, size, path)
+            .to_exit_code()
+            .unwrap_or_exit();
+
+        let _ = rt.report_touched(entry.get_location()).map_err_trace_exit_unwrap(1);
+    }
+}
+
+fn refresh(rt: &Runtime) {
+    let cmd     = rt.cli().subcommand_matches("refresh").unwrap();
+    let force   = cmd.is_present("force");
+    let config  = ref_config(rt);
+    let mut out = rt.stdout();
+
+    let mut refresh_id = |id: StoreId| {
+        let mut entry = get_ref_entry(rt, &id);
+
+        if !entry.is_ref().map_err_trace_exit_unwrap(1) {
+            return;
+        }
+
+        match entry.is_dangling(&config) {
+            Ok(false) => {},
+            Ok(true)  => {
+                warn!("Reference in '{}' is dangling, use 'imag ref refind' first", id);
+                return;
+            },
+            Err(e)    => {
+                trace_error(&e);
+                warn!("Could not resolve the path of '{}', skipping", id);
+                return;
+            },
+        }
+
+        if force || entry.metadata_stale(&config).map_err_trace_exit_unwrap(1) {
+            let _ = entry.update_metadata(&config).map_err_trace_exit_unwrap(1);
+
+            let _ = writeln!(out, "{}", id)
+                .to_exit_code()
+                .unwrap_or_exit();
+
+            let _ = rt.report_touched(&id).map_err_trace_exit_unwrap(1);
+        }
+    };
+
+    match cmd.values_of("ID") {
+        Some(ids) => {
+            for id in ids {
+                refresh_id(PathBuf::from(id).into_storeid().map_err_trace_exit_unwrap(1));
+            }
+        },
+        None => {
+            let collection  = cmd.value_of("collection").unwrap(); // safe by clap
+            let mut counter = 0;
+
+            for id in rt.store().entries().map_err_trace_exit_unwrap(1).in_collection(collection) {
+                refresh_id(id.map_err_trace_exit_unwrap(1));
+
+                // All refs of the collection are loaded, flush the cache regularly so it does not
+                // grow with each of them
+                counter += 1;
+                if counter > 100 {
+                    let _ = rt.store().flush_cache().map_err_trace_exit_unwrap(1);
+                    counter = 0;
+                }
+            }
+        },
+    }
+}
+
+fn changes(rt: &Runtime) {
+    let cmd = rt.cli().subcommand_matches("changes").unwrap();
+    with_hasher!(cmd, changes_with(rt, cmd))
+}
+
+fn changes_with<RPG: UniqueRefPathGenerator>(rt: &Runtime, cmd: &ArgMatches) {
+    let config  = ref_config(rt);
+    let mut out = rt.stdout();
+
+    for id in cmd.values_of("ID").unwrap() { // safe by clap
+        let id      = PathBuf::from(id).into_storeid().map_err_trace_exit_unwrap(1);
+        let entry   = get_ref_entry(rt, &id);
+        let changes = entry.has_changed::<RPG>(rt.store(), &config).map_err_trace_exit_unwrap(1);
+
+        if changes.is_empty() {
+            info!("'{}' is unchanged", id);
+        } else {
+            let _ = writeln!(out, "{}", id).to_exit_code().unwrap_or_exit();
+
+            let lines = changes.added().iter().map(|p| ("A", p))
+                .chain(changes.removed().iter().map(|p| ("D", p)))
+                .chain(changes.modified().iter().map(|p| ("M", p)));
+
+            for (kind, path) in lines {
+                let _ = writeln!(out, "\t{} {}", kind, path).to_exit_code().unwrap_or_exit();
+            }
+        }
+
+        let _ = rt.report_touched(&id).map_err_trace_exit_unwrap(1);
+    }
+}
+
+fn get_ref_entry<'a>(rt: &'a Runtime, id: &StoreId) -> FileLockEntry<'a> {
+    match rt.store().get(id.clone()).map_err_trace_exit_unwrap(1) {
+        Some(entry) => entry,
+        None        => {
+            error!("No entry for id '{}' found", id);
+            exit(1)
+        },
+    }
+}
+
+/// Get the base pathes for refs from the configuration
+fn ref_config(rt: &Runtime) -> Config {
+    rt.config()
+        .map(|cfg| Config::from_config(cfg).map_err_trace_exit_unwrap(1))
+        .unwrap_or_else(Config::default)
+}
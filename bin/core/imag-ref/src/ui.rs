@@ -48,4 +48,116 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
                      .short("y")
                      .help("Don't ask whether this really should be done"))
                 )
+
+        .subcommand(SubCommand::with_name("refind")
+                .about("Search for the files of dangling references and update the references")
+                .version("0.1")
+                .arg(Arg::with_name("ID")
+                     .index(1)
+                     .takes_value(true)
+                     .required_unless("all")
+                     .multiple(true)
+                     .help("Re-find the referenced file of these store entries")
+                     .value_name("ENTRIES"))
+
+                .arg(Arg::with_name("all")
+                     .long("all")
+                     .short("a")
+                     .conflicts_with("ID")
+                     .help("Re-find the referenced files of all dangling references"))
+
+                .arg(Arg::with_name("root")
+                     .long("root")
+                     .short("r")
+                     .takes_value(true)
+                     .multiple(true)
+                     .help("Search below this directory. Overrides 'ref.search_roots' from the configuration")
+                     .value_name("DIR"))
+
+                .arg(hasher_arg("sha512"))
+
+                .arg(Arg::with_name("bytes")
+                     .long("bytes")
+                     .short("b")
+                     .takes_value(true)
+                     .validator(::libimagutil::cli_validators::is_integer)
+                     .help("The references were created from the first N bytes of the files only")
+                     .value_name("N"))
+                )
+
+        .subcommand(SubCommand::with_name("migrate")
+                .about("Rewrite references to absolute pathes relative to the base pathes from 'ref.basepathes'")
+                .version("0.1")
+                )
+        .subcommand(SubCommand::with_name("show")
+                .about("Show the referenced path and the recorded metadata of the referenced file")
+                .version("0.1")
+                .arg(Arg::with_name("ID")
+                     .index(1)
+                     .takes_value(true)
+                     .required(true)
+                     .multiple(true)
+                     .help("Show the reference of these store entries")
+                     .value_name("ENTRIES"))
+                )
+
+        .subcommand(SubCommand::with_name("list")
+                .about("List references with their recorded metadata")
+                .version("0.1")
+                .arg(Arg::with_name("collection")
+                     .long("collection")
+                     .short("c")
+                     .takes_value(true)
+                     .default_value("ref")
+                     .help("List the references in this collection, for example 'mail'")
+                     .value_name("COLLECTION"))
+                )
+
+        .subcommand(SubCommand::with_name("refresh")
+                .about("Update the recorded metadata of references whose file changed")
+                .version("0.1")
+                .arg(Arg::with_name("ID")
+                     .index(1)
+                     .takes_value(true)
+                     .multiple(true)
+                     .help("Refresh the metadata of these store entries. Defaults to all references in the collection")
+                     .value_name("ENTRIES"))
+
+                .arg(Arg::with_name("collection")
+                     .long("collection")
+                     .short("c")
+                     .takes_value(true)
+                     .default_value("ref")
+                     .help("Refresh the references in this collection, for example 'mail'")
+                     .value_name("COLLECTION"))
+
+                .arg(Arg::with_name("force")
+                     .long("force")
+                     .short("f")
+                     .help("Update the metadata even if it does not look stale"))
+                )
+        .subcommand(SubCommand::with_name("changes")
+                .about("Show which files were added, removed or modified since a reference was made")
+                .version("0.1")
+                .arg(Arg::with_name("ID")
+                     .index(1)
+                     .takes_value(true)
+                     .required(true)
+                     .multiple(true)
+                     .help("Check the references of these store entries")
+                     .value_name("ENTRIES"))
+
+                .arg(hasher_arg("dirtree"))
+                )
+}
+
+fn hasher_arg<'a>(default: &'a str) -> Arg<'a, 'a> {
+    Arg::with_name("hasher")
+        .long("hasher")
+        .short("H")
+        .takes_value(true)
+        .possible_values(&["sha1", "sha224", "sha256", "sha384", "sha512", "sha3", "dirtree"])
+        .default_value(default)
+        .help("The hash the references were created with")
+        .value_name("HASHER")
 }
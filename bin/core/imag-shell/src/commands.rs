@@ -0,0 +1,114 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! The imag commands which can be run in-process by the shell
+
+use clap::App;
+use failure::Fallible as Result;
+
+use libimagrt::application::ImagApplication;
+use libimagrt::runtime::Runtime;
+
+use libimagannotatefrontend::ImagAnnotate;
+use libimagbookmarkfrontend::ImagBookmark;
+use libimagcategoryfrontend::ImagCategory;
+use libimagcontactfrontend::ImagContact;
+use libimagdiagnosticsfrontend::ImagDiagnostics;
+use libimagdiaryfrontend::ImagDiary;
+use libimageditfrontend::ImagEdit;
+use libimaggitfrontend::ImagGit;
+use libimaggpsfrontend::ImagGps;
+use libimaggrepfrontend::ImagGrep;
+use libimaghabitfrontend::ImagHabit;
+use libimagidsfrontend::ImagIds;
+use libimaglinkfrontend::ImagLink;
+use libimaglogfrontend::ImagLog;
+use libimagmailfrontend::ImagMail;
+use libimagmvfrontend::ImagMv;
+use libimagnotesfrontend::ImagNotes;
+use libimagreffrontend::ImagRef;
+use libimagstorefrontend::ImagStore;
+use libimagtagfrontend::ImagTag;
+use libimagtimetrackfrontend::ImagTimetrack;
+use libimagtodofrontend::ImagTodo;
+use libimagundofrontend::ImagUndo;
+use libimagviewfrontend::ImagView;
+use libimagwikifrontend::ImagWiki;
+
+macro_rules! in_process_commands {
+    ($($name:expr => $app:ident),* $(,)*) => {
+        /// The names of the commands which are run in-process
+        pub const COMMANDS : &'static [&'static str] = &[$($name),*];
+
+        /// Build the commandline interface for the command `name`
+        ///
+        /// Returns `None` if the command cannot be run in-process.
+        pub fn build_cli(name: &str) -> Option<App<'static, 'static>> {
+            match name {
+                $(
+                    $name => Some({
+                        let app = Runtime::get_default_cli_builder($app::name(),
+                                                                   $app::version(),
+                                                                   $app::description());
+                        $app::build_cli(app)
+                    }),
+                )*
+                _ => None,
+            }
+        }
+
+        /// Run the command `name` with the passed runtime
+        ///
+        /// Returns `None` if the command cannot be run in-process.
+        pub fn run(name: &str, rt: &Runtime) -> Option<Result<()>> {
+            match name {
+                $( $name => Some($app::run(rt)), )*
+                _ => None,
+            }
+        }
+    };
+}
+
+in_process_commands! {
+    "annotate"    => ImagAnnotate,
+    "bookmark"    => ImagBookmark,
+    "category"    => ImagCategory,
+    "contact"     => ImagContact,
+    "diagnostics" => ImagDiagnostics,
+    "diary"       => ImagDiary,
+    "edit"        => ImagEdit,
+    "git"         => ImagGit,
+    "gps"         => ImagGps,
+    "grep"        => ImagGrep,
+    "habit"       => ImagHabit,
+    "ids"         => ImagIds,
+    "link"        => ImagLink,
+    "log"         => ImagLog,
+    "mail"        => ImagMail,
+    "mv"          => ImagMv,
+    "notes"       => ImagNotes,
+    "ref"         => ImagRef,
+    "store"       => ImagStore,
+    "tag"         => ImagTag,
+    "timetrack"   => ImagTimetrack,
+    "todo"        => ImagTodo,
+    "undo"        => ImagUndo,
+    "view"        => ImagView,
+    "wiki"        => ImagWiki,
+}
@@ -0,0 +1,205 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Completion for the shell
+
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::collections::BTreeSet;
+
+use rustyline::Result as RLResult;
+use rustyline::Helper;
+use rustyline::completion::Completer;
+use rustyline::hint::Hinter;
+use rustyline::highlight::Highlighter;
+
+use failure::Fallible as Result;
+
+use libimagstore::store::Store;
+use libimagstore::store::Entry;
+use libimagstore::storeid::StoreId;
+use libimagentrytag::tagable::Tagable;
+
+/// The words the shell can complete
+///
+/// This is shared between the shell loop, which updates it after each command, and the
+/// `ShellHelper`, which is used by readline to complete the current word.
+#[derive(Debug, Default)]
+pub struct Completions {
+    commands: BTreeSet<String>,
+    ids: BTreeSet<String>,
+    tags: BTreeSet<String>,
+    variables: BTreeSet<String>,
+}
+
+impl Completions {
+
+    pub fn new<I, S>(commands: I) -> Completions
+        where I: IntoIterator<Item = S>,
+              S: Into<String>
+    {
+        Completions {
+            commands: commands.into_iter().map(Into::into).collect(),
+            ..Completions::default()
+        }
+    }
+
+    /// Scan the whole store for IDs and tags
+    pub fn scan_store(&mut self, store: &Store) -> Result<()> {
+        let mut counter = 0;
+        self.ids.clear();
+        self.tags.clear();
+
+        for id in store.entries()? {
+            let id = id?.without_base();
+            if let Some(entry) = store.get(id.clone())? {
+                self.add_entry(&entry)?;
+            }
+
+            // Do not let the cache grow indefinitely on large stores
+            counter += 1;
+            if counter > 100 {
+                let _ = store.flush_cache()?;
+                counter = 0;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Add the ID and the tags of `entry`
+    pub fn add_entry(&mut self, entry: &Entry) -> Result<()> {
+        self.ids.insert(entry.get_location().local_display_string());
+        for tag in entry.get_tags()? {
+            self.tags.insert(tag);
+        }
+        Ok(())
+    }
+
+    /// Add the IDs and tags of the entries with the IDs `ids`
+    pub fn add_ids(&mut self, store: &Store, ids: &[StoreId]) -> Result<()> {
+        for id in ids {
+            match store.get(id.clone())? {
+                Some(entry) => self.add_entry(&entry)?,
+                None        => {
+                    let _ = self.ids.remove(&id.local_display_string());
+                },
+            }
+        }
+        Ok(())
+    }
+
+    pub fn add_variable<S: Into<String>>(&mut self, name: S) {
+        self.variables.insert(name.into());
+    }
+
+    fn candidates(&self, word: &str, first_word: bool) -> Vec<String> {
+        let matching = |set: &BTreeSet<String>| -> Vec<String> {
+            set.iter().filter(|s| s.starts_with(word)).cloned().collect()
+        };
+
+        if first_word {
+            matching(&self.commands)
+        } else if word.starts_with('$') {
+            self.variables
+                .iter()
+                .map(|v| format!("${}", v))
+                .filter(|v| v.starts_with(word))
+                .collect()
+        } else {
+            let mut v = matching(&self.ids);
+            v.append(&mut matching(&self.tags));
+            v
+        }
+    }
+}
+
+/// The readline helper of the shell
+pub struct ShellHelper(Rc<RefCell<Completions>>);
+
+impl ShellHelper {
+    pub fn new(completions: Rc<RefCell<Completions>>) -> ShellHelper {
+        ShellHelper(completions)
+    }
+}
+
+impl Completer for ShellHelper {
+    type Candidate = String;
+
+    fn complete(&self, line: &str, pos: usize) -> RLResult<(usize, Vec<String>)> {
+        let start = line[..pos]
+            .rfind(' ')
+            .map(|i| i + 1)
+            .unwrap_or(0);
+        let first_word = line[..start].trim().is_empty();
+
+        Ok((start, self.0.borrow().candidates(&line[start..pos], first_word)))
+    }
+}
+
+impl Hinter for ShellHelper {
+    fn hint(&self, _line: &str, _pos: usize) -> Option<String> {
+        None
+    }
+}
+
+impl Highlighter for ShellHelper {}
+
+impl Helper for ShellHelper {}
+
+#[cfg(test)]
+mod tests {
+    use super::Completions;
+
+    fn completions() -> Completions {
+        let mut c = Completions::new(vec!["tag", "tags", "ids", "exit"]);
+        c.ids.insert(String::from("notes/a"));
+        c.ids.insert(String::from("notes/b"));
+        c.ids.insert(String::from("diary/2018"));
+        c.tags.insert(String::from("nice"));
+        c.add_variable("notes");
+        c.add_variable("_");
+        c
+    }
+
+    #[test]
+    fn test_first_word_completes_commands() {
+        let c = completions();
+        assert_eq!(c.candidates("ta", true), vec!["tag", "tags"]);
+        assert_eq!(c.candidates("", true), vec!["exit", "ids", "tag", "tags"]);
+        assert!(c.candidates("notes", true).is_empty());
+    }
+
+    #[test]
+    fn test_other_words_complete_ids_and_tags() {
+        let c = completions();
+        assert_eq!(c.candidates("notes/", false), vec!["notes/a", "notes/b"]);
+        assert_eq!(c.candidates("n", false), vec!["notes/a", "notes/b", "nice"]);
+        assert!(c.candidates("ta", false).is_empty());
+    }
+
+    #[test]
+    fn test_dollar_completes_variables() {
+        let c = completions();
+        assert_eq!(c.candidates("$", false), vec!["$_", "$notes"]);
+        assert_eq!(c.candidates("$no", false), vec!["$notes"]);
+        assert!(c.candidates("$x", false).is_empty());
+    }
+
+}
@@ -0,0 +1,462 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+#![forbid(unsafe_code)]
+
+#![deny(
+    non_camel_case_types,
+    non_snake_case,
+    path_statements,
+    trivial_numeric_casts,
+    unstable_features,
+    unused_allocation,
+    unused_import_braces,
+    unused_imports,
+    unused_must_use,
+    unused_mut,
+    unused_qualifications,
+    while_true,
+)]
+
+extern crate clap;
+#[macro_use] extern crate log;
+extern crate toml;
+#[macro_use] extern crate failure;
+extern crate rustyline;
+extern crate shell_words;
+
+extern crate libimagstore;
+#[macro_use] extern crate libimagrt;
+extern crate libimagerror;
+extern crate libimagentrytag;
+extern crate libimaginteraction;
+
+extern crate libimagannotatefrontend;
+extern crate libimagbookmarkfrontend;
+extern crate libimagcategoryfrontend;
+extern crate libimagcontactfrontend;
+extern crate libimagdiagnosticsfrontend;
+extern crate libimagdiaryfrontend;
+extern crate libimageditfrontend;
+extern crate libimaggitfrontend;
+extern crate libimaggpsfrontend;
+extern crate libimaggrepfrontend;
+extern crate libimaghabitfrontend;
+extern crate libimagidsfrontend;
+extern crate libimaglinkfrontend;
+extern crate libimaglogfrontend;
+extern crate libimagmailfrontend;
+extern crate libimagmvfrontend;
+extern crate libimagnotesfrontend;
+extern crate libimagreffrontend;
+extern crate libimagstorefrontend;
+extern crate libimagtagfrontend;
+extern crate libimagtimetrackfrontend;
+extern crate libimagtodofrontend;
+extern crate libimagundofrontend;
+extern crate libimagviewfrontend;
+extern crate libimagwikifrontend;
+
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::io::BufRead;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::Command;
+use std::process::Stdio;
+
+use failure::Error;
+use failure::ResultExt;
+use failure::Fallible as Result;
+use failure::err_msg;
+use toml::Value;
+
+use libimagrt::runtime::Runtime;
+use libimagrt::setup::generate_runtime_setup;
+use libimagerror::trace::MapErrTrace;
+use libimagerror::trace::trace_error;
+use libimagerror::exit::ExitUnwrap;
+use libimagerror::exit::catch_exit;
+use libimagerror::io::ToExitCode;
+use libimaginteraction::readline::Readline;
+use libimagstore::store::Store;
+use libimagstore::storeid::StoreId;
+
+mod commands;
+mod helper;
+mod ui;
+
+use helper::{Completions, ShellHelper};
+
+/// Commands which are handled by the shell itself
+const BUILTINS : &'static [&'static str] = &["exit", "help", "quit", "rehash", "set", "vars"];
+
+/// The name of the variable which holds the IDs reported by the previous command
+const LAST_IDS_VARIABLE : &'static str = "_";
+
+/// Commands prefixed with this are always run as external `imag-<command>` process
+const EXTERNAL_PREFIX : &'static str = "!";
+
+/// The state of the shell
+///
+/// The store is moved into a `Runtime` object for each command and moved back out afterwards.
+struct Shell {
+    store: Option<Store>,
+    config: Option<Value>,
+    rtp: PathBuf,
+    variables: BTreeMap<String, Vec<StoreId>>,
+    completions: Rc<RefCell<Completions>>,
+}
+
+fn main() {
+    let version = make_imag_version!();
+    let rt = generate_runtime_setup("imag-shell",
+                                    &version,
+                                    "Interactive shell to run imag commands on one store",
+                                    ui::build_ui);
+
+    let completion_words = commands::COMMANDS.iter().chain(BUILTINS.iter()).cloned();
+    let completions      = Rc::new(RefCell::new(Completions::new(completion_words)));
+
+    if !rt.cli().is_present("no-store-scan") {
+        let _ = completions
+            .borrow_mut()
+            .scan_store(rt.store())
+            .map_err_trace_exit_unwrap(1);
+    }
+
+    let mut readline = {
+        let config = rt.config().unwrap_or_else(|| {
+            error!("No configuration found, cannot configure readline");
+            ::std::process::exit(1)
+        });
+
+        Readline::new(config, Some(ShellHelper::new(completions.clone())))
+            .map_err_trace_exit_unwrap(1)
+    };
+
+    let mut shell = Shell {
+        config: rt.config().cloned(),
+        rtp: rt.rtp().clone(),
+        store: None,
+        variables: BTreeMap::new(),
+        completions: completions,
+    };
+    shell.store = Some(rt.into_store());
+
+    while let Some(line) = readline.read_line().map_err_trace_exit_unwrap(1) {
+        match shell.execute_line(&line) {
+            Ok(true)  => {},
+            Ok(false) => break,
+            Err(e)    => trace_error(&e),
+        }
+    }
+}
+
+impl Shell {
+
+    /// Execute one line of input
+    ///
+    /// Returns `false` if the shell should exit.
+    fn execute_line(&mut self, line: &str) -> Result<bool> {
+        let words = shell_words::split(line).map_err(|e| format_err!("{}", e))?;
+        let (command, args) = match words.split_first() {
+            None               => return Ok(true),
+            Some((cmd, args)) => (cmd.clone(), self.expand_variables(args)?),
+        };
+
+        debug!("Command = {}, args = {:?}", command, args);
+
+        match command.as_str() {
+            "exit" | "quit" => return Ok(false),
+            "help"          => self.help()?,
+            "vars"          => self.list_variables()?,
+            "set"           => self.set_variable(&args)?,
+            "rehash"        => {
+                let store = self.store.as_ref().ok_or_else(|| err_msg("Store not available"))?;
+                self.completions.borrow_mut().scan_store(store)?;
+            },
+            other => {
+                let ids = if other.starts_with(EXTERNAL_PREFIX) {
+                    self.run_external(&other[EXTERNAL_PREFIX.len()..], args)?
+                } else {
+                    match commands::build_cli(other) {
+                        Some(app) => self.run_in_process(app, other, args)?,
+                        None      => self.run_external(other, args)?,
+                    }
+                };
+
+                {
+                    let store = self.store.as_ref().ok_or_else(|| err_msg("Store not available"))?;
+                    self.completions.borrow_mut().add_ids(store, &ids)?;
+                }
+                self.variables.insert(String::from(LAST_IDS_VARIABLE), ids);
+            },
+        }
+
+        Ok(true)
+    }
+
+    /// Replace the `$name` arguments with the IDs stored in the variable `name`
+    fn expand_variables(&self, args: &[String]) -> Result<Vec<String>> {
+        let mut v = vec![];
+        for arg in args {
+            if arg.starts_with('$') {
+                let ids = self
+                    .variables
+                    .get(&arg[1..])
+                    .ok_or_else(|| format_err!("No such variable: '{}'", arg))?;
+
+                v.extend(ids.iter().map(StoreId::local_display_string));
+            } else {
+                v.push(arg.clone());
+            }
+        }
+        Ok(v)
+    }
+
+    /// Run a command in-process, with the store of the shell
+    ///
+    /// Returns the IDs the command reported as touched.
+    ///
+    /// The commands exit on fatal errors. Inside of the shell, this is turned into an error of the
+    /// command, so the shell and its store cache survive a failing command.
+    fn run_in_process(&mut self, app: clap::App<'static, 'static>, name: &str, args: Vec<String>)
+        -> Result<Vec<StoreId>>
+    {
+        let command = ::std::iter::once(format!("imag-{}", name)).chain(args.iter().cloned()).collect();
+        let matches = match app.get_matches_from_safe(::std::iter::once(String::from(name)).chain(args)) {
+            Ok(matches) => matches,
+            Err(e) => {
+                // Help and version messages are "errors" as well, they are printed here
+                let _ = writeln!(::std::io::stderr(), "{}", e.message).to_exit_code().unwrap_or_exit();
+                return Ok(vec![])
+            },
+        };
+
+        let mut store = self.store.take().ok_or_else(|| err_msg("Store not available"))?;
+
+        // Each command is an invocation of its own in the operation log
+        let storepath = store.path().clone();
+        match Runtime::build_operation_log(&self.rtp, self.config.as_ref(), storepath, command) {
+            Ok(log) => store.set_operation_log(log),
+            Err(e)  => {
+                self.store = Some(store);
+                return Err(e)
+            },
+        }
+
+        let rt = Runtime::new_with_store(matches, self.config.clone(), self.rtp.clone(), store);
+
+        let result = catch_exit(|| {
+                commands::run(name, &rt).unwrap_or_else(|| Err(format_err!("Unknown command: {}", name)))
+            })
+            .unwrap_or_else(|exit_code| match exit_code.code() {
+                0    => Ok(()),
+                code => Err(format_err!("{} failed with exit code {}", name, code)),
+            });
+
+        let touched = rt.take_touched_ids();
+        self.store  = Some(rt.into_store());
+
+        result.map(|_| touched)
+    }
+
+    /// Run an external `imag-<name>` command
+    ///
+    /// The store cache is flushed before, so the command sees all changes. The IDs the command
+    /// prints to stdout (which is a pipe) are returned as the touched IDs.
+    fn run_external(&mut self, name: &str, args: Vec<String>) -> Result<Vec<StoreId>> {
+        let _ = self.store
+            .as_ref()
+            .ok_or_else(|| err_msg("Store not available"))?
+            .flush_cache()?;
+
+        let rtp_str = self.rtp
+            .to_str()
+            .map(String::from)
+            .ok_or_else(|| err_msg("Runtimepath is not valid UTF-8"))?;
+
+        let command = format!("imag-{}", name);
+        debug!("Calling '{}' with args: {:?}", command, args);
+
+        let mut child = Command::new(&command)
+            .stdin(Stdio::inherit())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .args(&args)
+            .env("IMAG_RTP", rtp_str)
+            .spawn()
+            .context(format!("Failed to spawn '{}'", command))?;
+
+        let ids = match child.stdout.take() {
+            None         => vec![],
+            Some(stdout) => ::std::io::BufReader::new(stdout)
+                .lines()
+                .map(|line| {
+                    line.map_err(Error::from)
+                        .and_then(|l| StoreId::new_baseless(PathBuf::from(l)))
+                })
+                .collect::<Result<Vec<StoreId>>>()?,
+        };
+
+        let status = child.wait()?;
+        if !status.success() {
+            return Err(format_err!("{} exited with non-zero exit code", command))
+        }
+
+        Ok(ids)
+    }
+
+    fn set_variable(&mut self, args: &[String]) -> Result<()> {
+        let name = match args.first() {
+            Some(name) if args.len() == 1 => name.clone(),
+            _ => return Err(err_msg("Usage: set <name>")),
+        };
+
+        let ids = self
+            .variables
+            .get(LAST_IDS_VARIABLE)
+            .cloned()
+            .unwrap_or_else(|| vec![]);
+
+        self.completions.borrow_mut().add_variable(name.clone());
+        self.variables.insert(name, ids);
+        Ok(())
+    }
+
+    fn list_variables(&self) -> Result<()> {
+        let out      = ::std::io::stdout();
+        let mut lock = out.lock();
+
+        for (name, ids) in self.variables.iter() {
+            writeln!(lock, "${} ({} IDs)", name, ids.len())?;
+            for id in ids {
+                writeln!(lock, "    {}", id)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn help(&self) -> Result<()> {
+        let out      = ::std::io::stdout();
+        let mut lock = out.lock();
+
+        writeln!(lock, "Run imag commands as '<command> <args...>', for example 'tag add foo <id>'.")?;
+        writeln!(lock, "These commands are run in-process, on the store opened by the shell:")?;
+        writeln!(lock, "")?;
+        writeln!(lock, "    {}", commands::COMMANDS.join(", "))?;
+        writeln!(lock, "")?;
+        writeln!(lock, "Other commands are executed as 'imag-<command>' from the $PATH.")?;
+        writeln!(lock, "")?;
+        writeln!(lock, "Prefix a command with '{}' to run it as 'imag-<command>' process instead,", EXTERNAL_PREFIX)?;
+        writeln!(lock, "for example '{}tag add foo <id>'.", EXTERNAL_PREFIX)?;
+        writeln!(lock, "")?;
+        writeln!(lock, "The IDs a command reports are stored in the variable '$_'.")?;
+        writeln!(lock, "Variables are expanded to the IDs they hold, for example 'tag add bar $_'.")?;
+        writeln!(lock, "")?;
+        writeln!(lock, "Builtins:")?;
+        writeln!(lock, "    set <name>  Store the IDs reported by the previous command in '$name'")?;
+        writeln!(lock, "    vars        List all variables")?;
+        writeln!(lock, "    rehash      Re-scan the store for IDs and tags to complete")?;
+        writeln!(lock, "    help        Show this help")?;
+        writeln!(lock, "    exit, quit  Leave the shell")?;
+        Ok(())
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+    use std::cell::RefCell;
+    use std::collections::BTreeMap;
+    use std::path::PathBuf;
+
+    use libimagstore::storeid::StoreId;
+
+    use helper::Completions;
+    use super::Shell;
+
+    fn id(s: &str) -> StoreId {
+        StoreId::new_baseless(PathBuf::from(s)).unwrap()
+    }
+
+    fn shell_with_variables(vars: Vec<(&str, Vec<StoreId>)>) -> Shell {
+        let variables = vars
+            .into_iter()
+            .map(|(name, ids)| (String::from(name), ids))
+            .collect::<BTreeMap<_, _>>();
+
+        Shell {
+            store: None,
+            config: None,
+            rtp: PathBuf::from("/tmp"),
+            variables: variables,
+            completions: Rc::new(RefCell::new(Completions::new(Vec::<String>::new()))),
+        }
+    }
+
+    fn strings(v: &[&str]) -> Vec<String> {
+        v.iter().map(|s| String::from(*s)).collect()
+    }
+
+    #[test]
+    fn test_expand_no_variables() {
+        let shell = shell_with_variables(vec![]);
+        let args  = strings(&["add", "foo", "notes/a"]);
+
+        assert_eq!(shell.expand_variables(&args).unwrap(), args);
+    }
+
+    #[test]
+    fn test_expand_named_variable() {
+        let shell = shell_with_variables(vec![("notes", vec![id("notes/a"), id("notes/b")])]);
+        let args  = strings(&["add", "foo", "$notes", "other"]);
+
+        let expanded = shell.expand_variables(&args).unwrap();
+        assert_eq!(expanded, strings(&["add", "foo", "notes/a", "notes/b", "other"]));
+    }
+
+    #[test]
+    fn test_expand_last_ids_variable() {
+        let shell = shell_with_variables(vec![("_", vec![id("diary/2018/01/01")])]);
+        let args  = strings(&["$_"]);
+
+        assert_eq!(shell.expand_variables(&args).unwrap(), strings(&["diary/2018/01/01"]));
+    }
+
+    #[test]
+    fn test_expand_empty_variable() {
+        let shell = shell_with_variables(vec![("_", vec![])]);
+        let args  = strings(&["add", "$_"]);
+
+        assert_eq!(shell.expand_variables(&args).unwrap(), strings(&["add"]));
+    }
+
+    #[test]
+    fn test_expand_unknown_variable() {
+        let shell = shell_with_variables(vec![("notes", vec![id("notes/a")])]);
+        let args  = strings(&["add", "$note"]);
+
+        assert!(shell.expand_variables(&args).is_err());
+    }
+
+}
@@ -0,0 +1,241 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+use std::path::PathBuf;
+use libimagerror::exit::exit;
+
+use failure::Fallible as Result;
+
+use libimagrt::runtime::Runtime;
+use libimagstore::store::Store;
+use libimagstore::storeid::StoreId;
+use libimagentrylink::internal::InternalLinker;
+use libimagentrylink::internal::Link;
+use libimagerror::trace::MapErrTrace;
+
+/// Copy (or move) entries from the store of another profile into the store in use
+///
+/// Links between the copied entries are kept. Links to other entries cannot be kept, as the
+/// linked entries do not exist in this store, so they are only removed with `--drop-links`.
+pub fn copy(rt: &Runtime) {
+    let scmd       = rt.cli().subcommand_matches("copy").unwrap();
+    let profile    = scmd.value_of("from-profile").unwrap(); // safe by clap
+    let remove     = scmd.is_present("move");
+    let drop_links = scmd.is_present("drop-links");
+    let ids        = scmd
+        .values_of("id")
+        .unwrap() // safe by clap
+        .map(|id| StoreId::new_baseless(PathBuf::from(id)))
+        .collect::<Result<Vec<StoreId>>>()
+        .map_err_trace_exit_unwrap(1);
+
+    let source = rt.profile_store(profile).map_err_trace_exit_unwrap(1);
+
+    if source.path() == rt.store().path() {
+        error!("Profile '{}' uses the same store, nothing to copy", profile);
+        exit(1)
+    }
+
+    // Check everything before copying anything
+    let _ = check(&source, rt.store(), &ids, drop_links).map_err_trace_exit_unwrap(1);
+    let _ = copy_entries(&source, rt.store(), &ids, remove).map_err_trace_exit_unwrap(1);
+
+    for id in ids.iter() {
+        let _ = rt.report_touched(id).map_err_trace_exit_unwrap(1);
+    }
+}
+
+/// Check whether the entries `ids` can be copied from `source` to `target`
+///
+/// They cannot be copied if they do not exist in `source` or exist in `target` already. Unless
+/// `drop_links` is true, they cannot be copied if they link to entries which are not copied.
+fn check(source: &Store, target: &Store, ids: &[StoreId], drop_links: bool) -> Result<()> {
+    for id in ids {
+        if !source.exists(id.clone())? {
+            return Err(format_err!("Entry does not exist in the other store: {}", id))
+        }
+
+        if target.exists(id.clone())? {
+            return Err(format_err!("Entry exists already: {}", id))
+        }
+
+        if !drop_links {
+            let not_copied = source
+                .get_copy(id.clone())?
+                .get_internal_links()?
+                .filter(|link| !is_copied(ids, link))
+                .map(|link| link.get_store_id().local_display_string())
+                .collect::<Vec<String>>();
+
+            if !not_copied.is_empty() {
+                return Err(format_err!("Entry {} links to entries which are not copied: {}. Pass --drop-links to copy it without these links",
+                                       id,
+                                       not_copied.join(", ")))
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Copy the entries `ids` from `source` to `target`, and remove them from `source` if `remove`
+/// is true
+///
+/// Links to entries which are not copied are removed from the copies. If the entries are
+/// removed from `source`, the links of the other entries in `source` to them are removed, too.
+fn copy_entries(source: &Store, target: &Store, ids: &[StoreId], remove: bool) -> Result<()> {
+    for id in ids {
+        debug!("Copying {}", id);
+        let original = source.get_copy(id.clone())?;
+        let mut copy = target.create(id.clone())?;
+
+        *copy.get_header_mut()  = original.get_header().clone();
+        *copy.get_content_mut() = original.get_content().clone();
+
+        for link in copy.retain_internal_links(|link| is_copied(ids, link))? {
+            info!("Not copying link {} -> {}, the linked entry is not copied", id, link.get_store_id());
+        }
+    }
+
+    if remove {
+        for id in ids {
+            let links = source
+                .get_copy(id.clone())?
+                .get_internal_links()?
+                .filter(|link| !is_copied(ids, link))
+                .collect::<Vec<Link>>();
+
+            for link in links {
+                if let Some(mut linked) = source.get(link.get_store_id().clone())? {
+                    let _ = linked.retain_internal_links(|l| !is_copied(ids, l))?;
+                }
+            }
+
+            debug!("Removing {} from the other store", id);
+            let _ = source.delete(id.clone())?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Whether `link` points to one of the entries `ids`
+fn is_copied(ids: &[StoreId], link: &Link) -> bool {
+    ids.iter().any(|id| id == link.get_store_id())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    use libimagstore::store::Store;
+    use libimagstore::storeid::StoreId;
+    use libimagstore::file_abstraction::InMemoryFileAbstraction;
+    use libimagentrylink::internal::InternalLinker;
+
+    use super::*;
+
+    fn store(path: &str) -> Store {
+        // The store directory only exists in the in-memory backend
+        let config  = "[store]\nimplicit-create = true".parse::<::toml::Value>().unwrap();
+        let backend = Arc::new(InMemoryFileAbstraction::default());
+        Store::new_with_backend(PathBuf::from(path), &Some(config), backend).unwrap()
+    }
+
+    fn id(s: &str) -> StoreId {
+        StoreId::new_baseless(PathBuf::from(s)).unwrap()
+    }
+
+    /// A store with the entries "a", "b" and "c", where "a" links to "b" and "c"
+    fn linked_store() -> Store {
+        let store = store("/source");
+        {
+            let mut a = store.create(id("a")).unwrap();
+            let mut b = store.create(id("b")).unwrap();
+            let mut c = store.create(id("c")).unwrap();
+
+            *a.get_content_mut() = String::from("content of a");
+            a.add_internal_link(&mut b).unwrap();
+            a.add_internal_link(&mut c).unwrap();
+        }
+        store
+    }
+
+    fn links(store: &Store, s: &str) -> Vec<String> {
+        store
+            .get_copy(id(s))
+            .unwrap()
+            .get_internal_links()
+            .unwrap()
+            .map(|l| l.get_store_id().local_display_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_check_missing_and_existing() {
+        let source = linked_store();
+        let target = store("/target");
+        let _      = target.create(id("b")).unwrap();
+
+        assert!(check(&source, &target, &[id("x")], true).is_err());
+        assert!(check(&source, &target, &[id("b")], true).is_err());
+        assert!(check(&source, &target, &[id("c")], true).is_ok());
+    }
+
+    #[test]
+    fn test_check_refuses_dropping_links() {
+        let source = linked_store();
+        let target = store("/target");
+
+        assert!(check(&source, &target, &[id("a"), id("b")], false).is_err());
+        assert!(check(&source, &target, &[id("a"), id("b")], true).is_ok());
+        assert!(check(&source, &target, &[id("a"), id("b"), id("c")], false).is_ok());
+    }
+
+    #[test]
+    fn test_copy_keeps_links_between_copies() {
+        let source = linked_store();
+        let target = store("/target");
+
+        copy_entries(&source, &target, &[id("a"), id("b")], false).unwrap();
+
+        assert_eq!(target.get_copy(id("a")).unwrap().get_content(), "content of a");
+        assert_eq!(links(&target, "a"), vec![String::from("b")]);
+        assert_eq!(links(&target, "b"), vec![String::from("a")]);
+        assert!(!target.exists(id("c")).unwrap());
+
+        // The source is not touched
+        assert_eq!(links(&source, "a").len(), 2);
+        assert_eq!(links(&source, "c"), vec![String::from("a")]);
+    }
+
+    #[test]
+    fn test_move_removes_links_to_moved_entries() {
+        let source = linked_store();
+        let target = store("/target");
+
+        copy_entries(&source, &target, &[id("a"), id("b")], true).unwrap();
+
+        assert!(target.exists(id("a")).unwrap());
+        assert!(target.exists(id("b")).unwrap());
+        assert!(!source.exists(id("a")).unwrap());
+        assert!(!source.exists(id("b")).unwrap());
+        assert!(links(&source, "c").is_empty());
+    }
+}
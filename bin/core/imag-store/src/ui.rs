@@ -186,6 +186,34 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
                         .value_name("PATH"))
                    )
 
+       .subcommand(SubCommand::with_name("copy")
+                   .about("Copy entries from the store of another profile into this store")
+                   .version("0.1")
+                   .arg(Arg::with_name("from-profile")
+                        .long("from-profile")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Copy from the store of this profile")
+                        .value_name("PROFILE"))
+                   .arg(Arg::with_name("move")
+                        .long("move")
+                        .takes_value(false)
+                        .required(false)
+                        .help("Remove the entries from the other store after copying them"))
+                   .arg(Arg::with_name("drop-links")
+                        .long("drop-links")
+                        .takes_value(false)
+                        .required(false)
+                        .help("Remove links to entries which are not copied, instead of refusing to copy"))
+                   .arg(Arg::with_name("id")
+                        .index(1)
+                        .takes_value(true)
+                        .required(true)
+                        .multiple(true)
+                        .help("The entries to copy. Links between them are kept")
+                        .value_name("ID"))
+                   )
+
        .subcommand(SubCommand::with_name("verify")
                    .about("Verify the store")
                    .version("0.1")
@@ -0,0 +1,721 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+#![forbid(unsafe_code)]
+
+#![deny(
+    non_camel_case_types,
+    non_snake_case,
+    path_statements,
+    trivial_numeric_casts,
+    unstable_features,
+    unused_allocation,
+    unused_import_braces,
+    unused_imports,
+    unused_must_use,
+    unused_mut,
+    unused_qualifications,
+    while_true,
+)]
+
+extern crate clap;
+#[macro_use] extern crate log;
+
+extern crate toml;
+extern crate toml_query;
+extern crate failure;
+
+extern crate libimagstore;
+extern crate libimagrt;
+extern crate libimagentrytag;
+extern crate libimagentryfilter;
+extern crate libimagerror;
+
+#[cfg(test)]
+#[macro_use]
+extern crate libimagutil;
+
+#[cfg(not(test))]
+extern crate libimagutil;
+
+#[cfg(test)]
+extern crate env_logger;
+
+use std::io::Write;
+
+use failure::Fallible as Result;
+use failure::Error;
+use toml::Value;
+use toml_query::read::TomlValueReadExt;
+
+use libimagrt::runtime::Runtime;
+use libimagrt::application::ImagApplication;
+use libimagrt::stream::StreamEntry;
+use libimagrt::completion;
+use libimagentrytag::tagable::Tagable;
+use libimagentrytag::tag::Tag;
+use libimagentrytag::tag::TAG_SEPARATOR;
+use libimagentrytag::store::TagStore;
+use libimagentryfilter::tags::query::TagQuery;
+use libimagerror::trace::trace_error;
+use libimagerror::trace::MapErrTrace;
+use libimagerror::io::ToExitCode;
+use libimagerror::exit::ExitUnwrap;
+use libimagerror::errors::ErrorMsg as EM;
+use libimagstore::storeid::StoreId;
+use libimagutil::warn_exit::warn_exit;
+
+use clap::{ArgMatches, App};
+
+mod ui;
+
+use ui::build_ui;
+
+/// Marker enum for implementing ImagApplication on
+///
+/// This is used by binaries crates to execute business logic
+/// or to build a CLI completion.
+pub enum ImagTag {}
+impl ImagApplication for ImagTag {
+    fn run(rt: &Runtime) -> Result<()> {
+        // These subcommands work on all entries of the store and do not take IDs
+        match rt.cli().subcommand_name() {
+            Some("rename") => return Ok(rename(&rt)),
+            Some("merge")  => return Ok(merge(&rt)),
+            Some("stats")  => return Ok(stats(&rt)),
+            Some("unused") => return Ok(unused(&rt)),
+            Some("rare")   => return Ok(rare(&rt)),
+            _              => {},
+        }
+
+        // Tags which were streamed via stdin together with the IDs can be listed without
+        // reading the entries from the store
+        let (ids, tags) = if rt.ids_from_stdin() {
+            let entries = rt
+                .stream_entries::<::ui::PathProvider>()
+                .map_err_trace_exit_unwrap(1);
+            let ids = entries
+                .iter()
+                .map(StreamEntry::store_id)
+                .collect::<Result<Vec<StoreId>>>()
+                .map_err_trace_exit_unwrap(1);
+            let tags = entries.iter().map(streamed_tags).collect::<Vec<_>>();
+
+            (ids, tags)
+        } else {
+            let ids  = rt.ids::<::ui::PathProvider>().map_err_trace_exit_unwrap(1);
+            let tags = ids.iter().map(|_| None).collect::<Vec<_>>();
+
+            (ids, tags)
+        };
+
+        rt.cli()
+            .subcommand_name()
+            .map(|name| match name {
+                "list" => for (id, tags) in ids.into_iter().zip(tags.into_iter()) {
+                    list(id, tags, &rt)
+                },
+                "remove" => for id in ids {
+                    let add = None;
+                    let rem = get_remove_tags(rt.cli());
+                    debug!("id = {:?}, add = {:?}, rem = {:?}", id, add, rem);
+                    alter(&rt, id, add, rem);
+                },
+                "add" => for id in ids {
+                    let add = get_add_tags(rt.cli());
+                    let rem = None;
+                    debug!("id = {:?}, add = {:?}, rem = {:?}", id, add, rem);
+                    alter(&rt, id, add, rem);
+                },
+                other => {
+                    debug!("Unknown command");
+                    let _ = rt.handle_unknown_subcommand("imag-tag", other, rt.cli())
+                        .map_err_trace_exit_unwrap(1)
+                        .code()
+                        .map(::libimagerror::exit::exit);
+                },
+            });
+
+        Ok(())
+    }
+
+    fn build_cli<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
+        build_ui(app)
+    }
+
+    fn name() -> &'static str {
+        env!("CARGO_PKG_NAME")
+    }
+
+    fn description() -> &'static str {
+        "Manage tags of entries"
+    }
+
+    fn version() -> &'static str {
+        env!("CARGO_PKG_VERSION")
+    }
+
+    fn completions(rt: &Runtime, kind: &str) -> Result<Vec<String>> {
+        use std::collections::BTreeSet;
+
+        match kind {
+            "tags" => {
+                let mut tags = BTreeSet::new();
+                for entry in rt.store().entries()?.into_get_iter() {
+                    if let Some(entry) = entry? {
+                        tags.extend(entry.get_tags()?);
+                    }
+                }
+
+                Ok(tags.into_iter().collect())
+            },
+            _ => completion::default_completions(rt, kind),
+        }
+    }
+}
+
+fn alter(rt: &Runtime, path: StoreId, add: Option<Vec<Tag>>, rem: Option<Vec<Tag>>) {
+    match rt.store().get(path.clone()) {
+        Ok(Some(mut e)) => {
+            debug!("Entry header now = {:?}", e.get_header());
+
+            add.map(|tags| {
+                    debug!("Adding tags = '{:?}'", tags);
+                    for tag in tags {
+                        debug!("Adding tag '{:?}'", tag);
+                        if let Err(e) = e.add_tag(tag) {
+                            trace_error(&e);
+                        } else {
+                            debug!("Adding tag worked");
+                        }
+                    }
+                }); // it is okay to ignore a None here
+
+            debug!("Entry header now = {:?}", e.get_header());
+
+            rem.map(|tags| {
+                debug!("Removing tags = '{:?}'", tags);
+                for tag in tags {
+                    debug!("Removing tag '{:?}'", tag);
+                    if let Err(e) = e.remove_tag(tag) {
+                        trace_error(&e);
+                    }
+                }
+            }); // it is okay to ignore a None here
+
+            debug!("Entry header now = {:?}", e.get_header());
+
+        },
+
+        Ok(None) => {
+            info!("No entry found.");
+        },
+
+        Err(e) => {
+            info!("No entry.");
+            trace_error(&e);
+        },
+    }
+
+    let _ = rt
+        .report_touched(&path)
+        .map_err_trace_exit_unwrap(1);
+}
+
+/// Get the tags of an entry from the data which was streamed with it, if the tags were streamed
+fn streamed_tags(entry: &StreamEntry) -> Option<Vec<Tag>> {
+    let has_tags = entry
+        .read("tag.values")
+        .map_err_trace_exit_unwrap(1)
+        .is_some();
+
+    if has_tags {
+        entry
+            .header_value()
+            .map_err_trace_exit_unwrap(1)
+            .map(|header| header.get_tags().map_err_trace_exit_unwrap(1))
+    } else {
+        None
+    }
+}
+
+fn list(path: StoreId, streamed_tags: Option<Vec<Tag>>, rt: &Runtime) {
+    let tags = match streamed_tags {
+        Some(tags) => tags,
+        None       => match rt.store().get(path.clone()).map_err_trace_exit_unwrap(1) {
+            Some(entry) => entry.get_tags().map_err_trace_exit_unwrap(1),
+            None        => Err(Error::from(EM::EntryNotFound(path.local_display_string())))
+                .map_err_trace_exit_unwrap(1),
+        },
+    };
+
+    let scmd = rt.cli().subcommand_matches("list").unwrap(); // safe, we checked in main()
+
+    if let Some(query) = scmd.value_of("tag-query") {
+        if !TagQuery::parse(query).map_err_trace_exit_unwrap(1).matches_tags(&tags) {
+            return
+        }
+    }
+
+    let json_out = scmd.is_present("json");
+    let line_out = scmd.is_present("linewise");
+    let sepp_out = scmd.is_present("sep");
+    let tree_out = scmd.is_present("tree");
+    let mut comm_out = scmd.is_present("commasep");
+
+    if !vec![json_out, line_out, comm_out, sepp_out, tree_out].iter().any(|v| *v) {
+        // None of the flags passed, go to default
+        comm_out = true;
+    }
+
+    if json_out {
+        unimplemented!()
+    }
+
+    if line_out {
+        for tag in &tags {
+            let _ = writeln!(rt.stdout(), "{}", tag)
+                .to_exit_code()
+                .unwrap_or_exit();
+        }
+    }
+
+    if sepp_out {
+        let sepp = scmd.value_of("sep").unwrap(); // we checked before
+        let _ = writeln!(rt.stdout(), "{}", tags.join(sepp))
+            .to_exit_code()
+            .unwrap_or_exit();
+    }
+
+    if comm_out {
+        let _ = writeln!(rt.stdout(), "{}", tags.join(", "))
+            .to_exit_code()
+            .unwrap_or_exit();
+    }
+
+    if tree_out {
+        for line in tag_tree(&tags) {
+            let _ = writeln!(rt.stdout(), "{}", line)
+                .to_exit_code()
+                .unwrap_or_exit();
+        }
+    }
+
+    let _ = rt
+        .report_touched(&path)
+        .map_err_trace_exit_unwrap(1);
+}
+
+/// Render hierarchical tags as a tree, one line per node, indented by its depth
+///
+/// The parents of a tag are shown even if the entry is not tagged with them.
+fn tag_tree(tags: &[Tag]) -> Vec<String> {
+    use std::collections::BTreeSet;
+
+    let mut nodes = BTreeSet::new();
+    for tag in tags {
+        let parts = tag.split(TAG_SEPARATOR).collect::<Vec<_>>();
+        for depth in 1..(parts.len() + 1) {
+            nodes.insert(parts[..depth].to_vec());
+        }
+    }
+
+    nodes.into_iter()
+        .map(|node| format!("{}{}", "  ".repeat(node.len() - 1), node[node.len() - 1]))
+        .collect()
+}
+
+fn rename(rt: &Runtime) {
+    let scmd = rt.cli().subcommand_matches("rename").unwrap(); // safe, we checked in run()
+    let old  = scmd.value_of("old").unwrap(); // safe by clap
+    let new  = scmd.value_of("new").unwrap(); // safe by clap
+
+    let changed = rt.store().rename_tag(old, new).map_err_trace_exit_unwrap(1);
+    info!("Renamed '{}' to '{}' in {} entries", old, new, changed.len());
+    report_all_touched(rt, &changed);
+}
+
+fn merge(rt: &Runtime) {
+    let scmd = rt.cli().subcommand_matches("merge").unwrap(); // safe, we checked in run()
+    let mut tags = scmd
+        .values_of("merge-tags")
+        .unwrap() // safe by clap
+        .map(String::from)
+        .collect::<Vec<_>>();
+
+    // The arguments are "<tag>... into <tag>"
+    let into = tags.pop().unwrap(); // safe by clap, there are at least three values
+    if tags.pop().map(|s| s != "into").unwrap_or(true) {
+        warn_exit("Expected 'into' before the last tag: merge <tag>... into <tag>", 1)
+    }
+
+    let changed = rt.store().merge_tags(&tags, &into).map_err_trace_exit_unwrap(1);
+    info!("Merged {} into '{}' in {} entries", tags.join(", "), into, changed.len());
+    report_all_touched(rt, &changed);
+}
+
+fn report_all_touched(rt: &Runtime, ids: &[StoreId]) {
+    for id in ids {
+        let _ = rt.report_touched(id).map_err_trace_exit_unwrap(1);
+    }
+}
+
+fn stats(rt: &Runtime) {
+    let stats = rt.store().tag_stats().map_err_trace_exit_unwrap(1);
+    let mut out = rt.stdout();
+
+    for tag in stats.tags() {
+        let by_collection = stats
+            .count_by_collection(tag)
+            .into_iter()
+            .flat_map(|counts| counts.iter())
+            .map(|(collection, count)| {
+                let collection = if collection.is_empty() { "-" } else { collection.as_str() };
+                format!("{}={}", collection, count)
+            })
+            .collect::<Vec<_>>();
+
+        let _ = writeln!(out, "{}\t{}\t{}", tag, stats.count(tag), by_collection.join(" "))
+            .to_exit_code()
+            .unwrap_or_exit();
+    }
+}
+
+fn unused(rt: &Runtime) {
+    let known = known_tags(rt);
+    if known.is_empty() {
+        warn!("No known tags configured at 'tag.known', so no tag can be unused");
+        warn!("Use 'imag tag rare' to find rarely used tags instead");
+        return
+    }
+
+    let stats = rt.store().tag_stats().map_err_trace_exit_unwrap(1);
+    let mut out = rt.stdout();
+
+    for tag in stats.unused(&known) {
+        let _ = writeln!(out, "{}", tag)
+            .to_exit_code()
+            .unwrap_or_exit();
+    }
+}
+
+fn rare(rt: &Runtime) {
+    use std::str::FromStr;
+
+    let scmd  = rt.cli().subcommand_matches("rare").unwrap(); // safe, we checked in run()
+    let max   = usize::from_str(scmd.value_of("max").unwrap()).unwrap(); // safe by clap
+    let stats = rt.store().tag_stats().map_err_trace_exit_unwrap(1);
+    let mut out = rt.stdout();
+
+    for tag in stats.rare(max) {
+        let _ = writeln!(out, "{}\t{}", tag, stats.count(tag))
+            .to_exit_code()
+            .unwrap_or_exit();
+    }
+}
+
+/// Get the tags from `tag.known` in the configuration
+///
+/// There are no known tags if there is no configuration or `tag.known` is not set.
+fn known_tags(rt: &Runtime) -> Vec<Tag> {
+    let known = match rt.config() {
+        Some(cfg) => cfg.read("tag.known").map_err(Error::from).map_err_trace_exit_unwrap(1),
+        None      => None,
+    };
+
+    match known {
+        Some(&Value::Array(ref tags)) => tags
+            .iter()
+            .map(|tag| match *tag {
+                Value::String(ref s) => s.clone(),
+                _ => warn_exit("Type error at 'tag.known', expected 'Array<String>'", 1),
+            })
+            .collect(),
+        Some(_) => warn_exit("Type error at 'tag.known', expected 'Array<String>'", 1),
+        None    => vec![],
+    }
+}
+
+/// Get the tags which should be added from the commandline
+///
+/// Returns none if the argument was not specified
+fn get_add_tags(matches: &ArgMatches) -> Option<Vec<Tag>> {
+    retrieve_tags(matches, "add", "add-tags")
+}
+
+/// Get the tags which should be removed from the commandline
+///
+/// Returns none if the argument was not specified
+fn get_remove_tags(matches: &ArgMatches) -> Option<Vec<Tag>> {
+    retrieve_tags(matches, "remove", "remove-tags")
+}
+
+fn retrieve_tags(m: &ArgMatches, s: &'static str, v: &'static str) -> Option<Vec<Tag>> {
+    Some(m
+         .subcommand_matches(s)
+         .unwrap_or_else(|| {
+             error!("Expected subcommand '{}', but was not specified", s);
+             ::libimagerror::exit::exit(1)
+         })
+         .values_of(v)
+         .unwrap() // enforced by clap
+         .into_iter()
+         .map(String::from)
+         .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::ffi::OsStr;
+
+    use toml::value::Value;
+    use toml_query::read::TomlValueReadExt;
+    use failure::Fallible as Result;
+    use failure::Error;
+
+    use libimagrt::runtime::Runtime;
+    use libimagstore::storeid::StoreId;
+    use libimagstore::store::{FileLockEntry, Entry};
+
+    use super::*;
+
+    make_mock_app! {
+        app "imag-tag";
+        modulename mock;
+        version env!("CARGO_PKG_VERSION");
+        with help "imag-tag mocking app";
+    }
+    use self::mock::generate_test_runtime;
+
+    fn create_test_default_entry<'a, S: AsRef<OsStr>>(rt: &'a Runtime, name: S) -> Result<StoreId> {
+        let mut path = PathBuf::new();
+        path.set_file_name(name);
+
+        let default_entry = Entry::new(StoreId::new_baseless(PathBuf::from("")).unwrap())
+            .to_str()
+            .unwrap();
+
+        let id = StoreId::new_baseless(path)?;
+        let mut entry = rt.store().create(id.clone())?;
+        entry.get_content_mut().push_str(&default_entry);
+
+        Ok(id)
+    }
+
+    fn get_entry_tags<'a>(entry: &'a FileLockEntry<'a>) -> Result<Option<&'a Value>> {
+        entry.get_header().read(&"tag.values".to_owned()).map_err(Error::from)
+    }
+
+    fn tags_toml_value<'a, I: IntoIterator<Item = &'static str>>(tags: I) -> Value {
+        Value::Array(tags.into_iter().map(|s| Value::String(s.to_owned())).collect())
+    }
+
+    fn setup_logging() {
+        let _ = ::env_logger::try_init();
+    }
+
+    #[test]
+    fn test_tag_add_adds_tag() {
+        setup_logging();
+        debug!("Generating runtime");
+        let name = "test-tag-add-adds-tags";
+        let rt = generate_test_runtime(vec![name, "add", "foo"]).unwrap();
+
+        debug!("Creating default entry");
+        create_test_default_entry(&rt, name).unwrap();
+        let id = PathBuf::from(String::from(name));
+
+        debug!("Getting 'add' tags");
+        let add = get_add_tags(rt.cli());
+        debug!("Add-tags: {:?}", add);
+
+        debug!("Altering things");
+        alter(&rt, StoreId::new_baseless(id.clone()).unwrap(), add, None);
+        debug!("Altered");
+
+        let test_entry = rt.store().get(id).unwrap().unwrap();
+
+        let test_tags  = get_entry_tags(&test_entry);
+        assert!(test_tags.is_ok(), "Should be Ok(_) = {:?}", test_tags);
+
+        let test_tags  = test_tags.unwrap();
+        assert!(test_tags.is_some(), "Should be Some(_) = {:?}", test_tags);
+        let test_tags  = test_tags.unwrap();
+
+        assert_ne!(*test_tags, tags_toml_value(vec![]));
+        assert_eq!(*test_tags, tags_toml_value(vec!["foo"]));
+    }
+
+    #[test]
+    fn test_tag_remove_removes_tag() {
+        setup_logging();
+        debug!("Generating runtime");
+        let name = "test-tag-remove-removes-tag";
+        let rt = generate_test_runtime(vec![name, "remove", "foo"]).unwrap();
+
+        debug!("Creating default entry");
+        create_test_default_entry(&rt, name).unwrap();
+        let id = PathBuf::from(String::from(name));
+
+        // Manually add tags
+        let add = Some(vec![ "foo".to_owned() ]);
+
+        debug!("Getting 'remove' tags");
+        let rem = get_remove_tags(rt.cli());
+        debug!("Rem-tags: {:?}", rem);
+
+        debug!("Altering things");
+        alter(&rt, StoreId::new_baseless(id.clone()).unwrap(), add, rem);
+        debug!("Altered");
+
+        let test_entry = rt.store().get(id).unwrap().unwrap();
+        let test_tags  = get_entry_tags(&test_entry).unwrap().unwrap();
+
+        assert_eq!(*test_tags, tags_toml_value(vec![]));
+    }
+
+    #[test]
+    fn test_tag_remove_removes_only_to_remove_tag() {
+        setup_logging();
+        debug!("Generating runtime");
+        let name = "test-tag-remove-removes-only-to-remove-tag-doesnt-crash-on-nonexistent-tag";
+        let rt = generate_test_runtime(vec![name, "remove", "foo"]).unwrap();
+
+        debug!("Creating default entry");
+        create_test_default_entry(&rt, name).unwrap();
+        let id = PathBuf::from(String::from(name));
+
+        // Manually add tags
+        let add = Some(vec![ "foo".to_owned(), "bar".to_owned() ]);
+
+        debug!("Getting 'remove' tags");
+        let rem = get_remove_tags(rt.cli());
+        debug!("Rem-tags: {:?}", rem);
+
+        debug!("Altering things");
+        alter(&rt, StoreId::new_baseless(id.clone()).unwrap(), add, rem);
+        debug!("Altered");
+
+        let test_entry = rt.store().get(id).unwrap().unwrap();
+        let test_tags  = get_entry_tags(&test_entry).unwrap().unwrap();
+
+        assert_eq!(*test_tags, tags_toml_value(vec!["bar"]));
+    }
+
+    #[test]
+    fn test_tag_remove_removes_but_doesnt_crash_on_nonexistent_tag() {
+        setup_logging();
+        debug!("Generating runtime");
+        let name = "test-tag-remove-removes-but-doesnt-crash-on-nonexistent-tag";
+        let rt = generate_test_runtime(vec![name, "remove", "foo", "bar"]).unwrap();
+
+        debug!("Creating default entry");
+        create_test_default_entry(&rt, name).unwrap();
+        let id = PathBuf::from(String::from(name));
+
+        // Manually add tags
+        let add = Some(vec![ "foo".to_owned() ]);
+
+        debug!("Getting 'remove' tags");
+        let rem = get_remove_tags(rt.cli());
+        debug!("Rem-tags: {:?}", rem);
+
+        debug!("Altering things");
+        alter(&rt, StoreId::new_baseless(id.clone()).unwrap(), add, rem);
+        debug!("Altered");
+
+        let test_entry = rt.store().get(id).unwrap().unwrap();
+        let test_tags  = get_entry_tags(&test_entry).unwrap().unwrap();
+
+        assert_eq!(*test_tags, tags_toml_value(vec![]));
+    }
+
+    #[test]
+    fn test_tag_rename_renames_subtree() {
+        setup_logging();
+        let name = "test-tag-rename-renames-subtree";
+        let rt   = generate_test_runtime(vec!["rename", "work", "job"]).unwrap();
+
+        let id = create_test_default_entry(&rt, name).unwrap();
+        {
+            let mut entry = rt.store().get(id.clone()).unwrap().unwrap();
+            entry.set_tags(&[String::from("work/meetings"), String::from("workshop")]).unwrap();
+        }
+
+        rename(&rt);
+
+        let entry = rt.store().get(id).unwrap().unwrap();
+        assert_eq!(entry.get_tags().unwrap(), vec!["job/meetings", "workshop"]);
+    }
+
+    #[test]
+    fn test_tag_merge_merges_tags() {
+        setup_logging();
+        let name = "test-tag-merge-merges-tags";
+        let rt   = generate_test_runtime(vec!["merge", "work", "office", "into", "job"]).unwrap();
+
+        let id = create_test_default_entry(&rt, name).unwrap();
+        {
+            let mut entry = rt.store().get(id.clone()).unwrap().unwrap();
+            entry.set_tags(&[String::from("work/meetings"), String::from("office"), String::from("home")]).unwrap();
+        }
+
+        merge(&rt);
+
+        let entry = rt.store().get(id).unwrap().unwrap();
+        assert_eq!(entry.get_tags().unwrap(), vec!["job/meetings", "job", "home"]);
+    }
+
+    #[test]
+    fn test_known_tags_without_config() {
+        setup_logging();
+        let rt = generate_test_runtime(vec!["unused"]).unwrap();
+        assert!(known_tags(&rt).is_empty());
+    }
+
+    #[test]
+    fn test_complete_tags() {
+        setup_logging();
+        let name = "test-complete-tags";
+        let rt   = generate_test_runtime(vec![name, "add", "foo"]).unwrap();
+
+        let id = create_test_default_entry(&rt, name).unwrap();
+        {
+            let mut entry = rt.store().get(id).unwrap().unwrap();
+            entry.set_tags(&[String::from("work"), String::from("project/imag")]).unwrap();
+        }
+
+        assert_eq!(ImagTag::completions(&rt, "tags").unwrap(), vec!["project/imag", "work"]);
+        assert_eq!(ImagTag::completions(&rt, "ids").unwrap(), vec![name]);
+        assert!(ImagTag::completions(&rt, "unknown").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_tag_tree() {
+        let tags = vec!["project/imag/bugs", "work", "project-x", "project/imag"]
+            .into_iter()
+            .map(String::from)
+            .collect::<Vec<_>>();
+
+        assert_eq!(tag_tree(&tags), vec!["project", "  imag", "    bugs", "project-x", "work"]);
+    }
+
+}
+
@@ -26,6 +26,7 @@ use libimagstore::storeid::IntoStoreId;
 use libimagrt::runtime::IdPathProvider;
 use libimagerror::trace::MapErrTrace;
 use libimagentrytag::tag::is_tag;
+use libimagentryfilter::tags::query::is_tag_query;
 
 pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
     app.arg(Arg::with_name("id")
@@ -62,6 +63,66 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
                            .help("Remove these tags"))
                    )
 
+        .subcommand(SubCommand::with_name("rename")
+                   .about("Rename a tag in all entries of the store. Tags below the tag are renamed as well")
+                   .version("0.1")
+                   .arg(Arg::with_name("old")
+                           .index(1)
+                           .takes_value(true)
+                           .required(true)
+                           .multiple(false)
+                           .value_name("OLD")
+                           .validator(is_tag)
+                           .help("The tag to rename"))
+                   .arg(Arg::with_name("new")
+                           .index(2)
+                           .takes_value(true)
+                           .required(true)
+                           .multiple(false)
+                           .value_name("NEW")
+                           .validator(is_tag)
+                           .help("The new name of the tag"))
+                   )
+
+        .subcommand(SubCommand::with_name("merge")
+                   .about("Merge tags into one tag in all entries of the store: merge <tag>... into <tag>")
+                   .version("0.1")
+                   .arg(Arg::with_name("merge-tags")
+                           .index(1)
+                           .takes_value(true)
+                           .required(true)
+                           .multiple(true)
+                           .min_values(3)
+                           .value_name("TAG")
+                           .validator(is_tag)
+                           .help("The tags to merge, followed by 'into' and the tag to merge them into"))
+                   )
+
+        .subcommand(SubCommand::with_name("stats")
+                   .about("Print how many entries use each tag, in total and per collection")
+                   .version("0.1")
+                   )
+
+        .subcommand(SubCommand::with_name("unused")
+                   .about("Print the tags configured in 'tag.known' which are not used by any entry. Prints nothing if 'tag.known' is empty")
+                   .version("0.1")
+                   )
+
+        .subcommand(SubCommand::with_name("rare")
+                   .about("Print the tags which are used by only a few entries")
+                   .version("0.1")
+                   .arg(Arg::with_name("max")
+                           .long("max")
+                           .short("m")
+                           .takes_value(true)
+                           .required(false)
+                           .multiple(false)
+                           .default_value("1")
+                           .value_name("N")
+                           .validator(::libimagutil::cli_validators::is_integer)
+                           .help("Print the tags which are used by at most N entries"))
+                   )
+
        .subcommand(SubCommand::with_name("list")
                    .about("List tags (default)")
                    .version("0.1")
@@ -90,6 +151,20 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
                         .required(false)
                         .help("Separated by string")
                         .value_name("SEP"))
+                   .arg(Arg::with_name("tree")
+                        .long("tree")
+                        .short("t")
+                        .takes_value(false)
+                        .required(false)
+                        .help("Show hierarchical tags as a tree"))
+                   .arg(Arg::with_name("tag-query")
+                        .long("tag-query")
+                        .short("T")
+                        .takes_value(true)
+                        .required(false)
+                        .validator(is_tag_query)
+                        .help("Only list the tags of entries whose tags match a query like '(work and urgent) or (home and not done)'")
+                        .value_name("QUERY"))
 
                    .group(ArgGroup::with_name("list-group")
                           .args(&[
@@ -97,6 +172,7 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
                                 "linewise",
                                 "commasep",
                                 "sep",
+                                "tree",
                           ])
                           .required(true))
                    )
@@ -0,0 +1,447 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+#![forbid(unsafe_code)]
+
+#![deny(
+    non_camel_case_types,
+    non_snake_case,
+    path_statements,
+    trivial_numeric_casts,
+    unstable_features,
+    unused_allocation,
+    unused_import_braces,
+    unused_imports,
+    unused_must_use,
+    unused_mut,
+    unused_qualifications,
+    while_true,
+)]
+
+#[macro_use] extern crate log;
+extern crate clap;
+#[macro_use] extern crate failure;
+extern crate chrono;
+
+extern crate libimagrt;
+extern crate libimagstore;
+extern crate libimagerror;
+extern crate libimagutil;
+
+#[cfg(test)] extern crate tempdir;
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use clap::App;
+use chrono::Local;
+use chrono::TimeZone;
+use failure::Fallible as Result;
+
+use libimagrt::runtime::Runtime;
+use libimagrt::application::ImagApplication;
+use libimagerror::exit::ExitUnwrap;
+use libimagerror::io::ToExitCode;
+use libimagstore::oplog;
+use libimagstore::oplog::Invocation;
+use libimagstore::oplog::Operation;
+use libimagstore::store::Entry;
+use libimagstore::store::Store;
+use libimagstore::storeid::StoreId;
+
+mod ui;
+
+/// Marker enum for implementing ImagApplication on
+///
+/// This is used by binaries crates to execute business logic
+/// or to build a CLI completion.
+pub enum ImagUndo {}
+impl ImagApplication for ImagUndo {
+    fn run(rt: &Runtime) -> Result<()> {
+        // Undoing is not recorded itself, so undoing twice does not revert the undo
+        if let Some(log) = rt.store().operation_log() {
+            log.set_enabled(false);
+        }
+
+        // Invocations on the store in use only, newest first
+        let mut invocations = oplog::invocations(&Runtime::operation_log_dir(rt.rtp()))?
+            .into_iter()
+            .filter(|i| i.header().store == *rt.store().path())
+            .collect::<Vec<_>>();
+        invocations.reverse();
+
+        if rt.cli().is_present("list") {
+            return list(rt, &invocations)
+        }
+
+        let count = match rt.cli().value_of("count") {
+            None    => 1,
+            Some(n) => usize::from_str(n).map_err(|_| format_err!("Not a valid number: {}", n))?,
+        };
+
+        if count > invocations.len() {
+            return Err(format_err!("Cannot undo {} invocations, only {} are recorded",
+                                   count,
+                                   invocations.len()))
+        }
+
+        invocations.truncate(count);
+
+        // Check all invocations before touching anything, so we either revert all or nothing
+        check(rt.store(), &invocations)?;
+
+        for invocation in invocations {
+            debug!("Reverting {:?}", invocation.path());
+            for op in invocation.operations().iter().rev() {
+                revert(rt.store(), op)?;
+            }
+
+            info!("Reverted: {}", invocation.header().command.join(" "));
+            let _ = invocation.remove()?;
+        }
+
+        Ok(())
+    }
+
+    fn build_cli<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
+        ui::build_ui(app)
+    }
+
+    fn name() -> &'static str {
+        env!("CARGO_PKG_NAME")
+    }
+
+    fn description() -> &'static str {
+        "Undo the changes of the last imag invocations"
+    }
+
+    fn version() -> &'static str {
+        env!("CARGO_PKG_VERSION")
+    }
+}
+
+fn list(rt: &Runtime, invocations: &[Invocation]) -> Result<()> {
+    let out         = rt.stdout();
+    let mut outlock = out.lock();
+
+    for (i, invocation) in invocations.iter().enumerate() {
+        let header = invocation.header();
+        let time   = Local.timestamp(header.timestamp as i64, 0).format("%Y-%m-%d %H:%M:%S");
+
+        let _ = writeln!(outlock, "{:>3}  {}  {}  ({} changes)",
+                         i + 1,
+                         time,
+                         header.command.join(" "),
+                         invocation.operations().len())
+            .to_exit_code()
+            .unwrap_or_exit();
+    }
+
+    Ok(())
+}
+
+/// The state of the entries while checking whether the invocations can be reverted
+///
+/// Entries which are not yet touched are read from the store. `None` means that the entry does
+/// not exist.
+struct Simulation<'a> {
+    store: &'a Store,
+    entries: HashMap<String, Option<Entry>>,
+}
+
+impl<'a> Simulation<'a> {
+
+    fn get(&mut self, id: &str) -> Result<Option<Entry>> {
+        if let Some(entry) = self.entries.get(id) {
+            return Ok(entry.clone())
+        }
+
+        let sid   = storeid(id)?;
+        let entry = if self.store.exists(sid.clone())? {
+            Some(self.store.get_copy(sid)?)
+        } else {
+            None
+        };
+
+        self.entries.insert(String::from(id), entry.clone());
+        Ok(entry)
+    }
+
+    fn set(&mut self, id: &str, entry: Option<Entry>) {
+        self.entries.insert(String::from(id), entry);
+    }
+
+}
+
+/// Check whether the invocations can be reverted, that is, whether the entries are still in the
+/// state the invocations left them in
+fn check(store: &Store, invocations: &[Invocation]) -> Result<()> {
+    let mut sim = Simulation { store, entries: HashMap::new() };
+
+    for invocation in invocations {
+        let changed = |id: &str| {
+            format_err!("Entry '{}' was changed after '{}', refusing to undo",
+                        id,
+                        invocation.header().command.join(" "))
+        };
+
+        for op in invocation.operations().iter().rev() {
+            match *op {
+                Operation::Create { ref id, ref after } => {
+                    if !is_in_state(sim.get(id)?, id, after)? {
+                        return Err(changed(id))
+                    }
+                    sim.set(id, None);
+                },
+
+                Operation::Update { ref id, ref before, ref after } => {
+                    if !is_in_state(sim.get(id)?, id, after)? {
+                        return Err(changed(id))
+                    }
+                    sim.set(id, Some(Entry::from_str(storeid(id)?, before)?));
+                },
+
+                Operation::Delete { ref id, ref before } => {
+                    if sim.get(id)?.is_some() {
+                        return Err(changed(id))
+                    }
+                    sim.set(id, Some(Entry::from_str(storeid(id)?, before)?));
+                },
+
+                Operation::Move { ref from, ref to } => {
+                    if sim.get(from)?.is_some() {
+                        return Err(changed(from))
+                    }
+
+                    let moved = sim.get(to)?.ok_or_else(|| changed(to))?;
+                    sim.set(from, Some(moved));
+                    sim.set(to, None);
+                },
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Check whether `entry` has the header and content of the serialized entry `expected`
+fn is_in_state(entry: Option<Entry>, id: &str, expected: &str) -> Result<bool> {
+    match entry {
+        None        => Ok(false),
+        Some(entry) => {
+            let expected = Entry::from_str(storeid(id)?, expected)?;
+            Ok(entry.get_header() == expected.get_header() &&
+               entry.get_content() == expected.get_content())
+        },
+    }
+}
+
+fn revert(store: &Store, op: &Operation) -> Result<()> {
+    match *op {
+        Operation::Create { ref id, .. } => {
+            debug!("Deleting {}", id);
+            store.delete(storeid(id)?)
+        },
+
+        Operation::Update { ref id, ref before, .. } => {
+            debug!("Restoring {}", id);
+            let before    = Entry::from_str(storeid(id)?, before)?;
+            let mut entry = store
+                .get(storeid(id)?)?
+                .ok_or_else(|| format_err!("Entry does not exist: {}", id))?;
+
+            *entry.get_header_mut()  = before.get_header().clone();
+            *entry.get_content_mut() = before.get_content().clone();
+            store.update(&mut entry)
+        },
+
+        Operation::Delete { ref id, ref before } => {
+            debug!("Re-creating {}", id);
+            let before    = Entry::from_str(storeid(id)?, before)?;
+            let mut entry = store.create(storeid(id)?)?;
+
+            *entry.get_header_mut()  = before.get_header().clone();
+            *entry.get_content_mut() = before.get_content().clone();
+            store.update(&mut entry)
+        },
+
+        Operation::Move { ref from, ref to } => {
+            debug!("Moving {} back to {}", to, from);
+            store.move_by_id(storeid(to)?, storeid(from)?)
+        },
+    }
+}
+
+fn storeid(id: &str) -> Result<StoreId> {
+    StoreId::new_baseless(PathBuf::from(id))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    use tempdir::TempDir;
+
+    use libimagstore::file_abstraction::InMemoryFileAbstraction;
+    use libimagstore::oplog::OperationLog;
+
+    use super::*;
+
+    fn store_with_log(dir: &TempDir) -> Store {
+        let backend   = Arc::new(InMemoryFileAbstraction::default());
+        let mut store = Store::new_with_backend(PathBuf::from("/"), &None, backend).unwrap();
+        let log       = OperationLog::new(dir.path().to_path_buf(),
+                                          PathBuf::from("/"),
+                                          vec![String::from("imag-test")]);
+
+        store.set_operation_log(Some(log));
+        store
+    }
+
+    fn set_recording(store: &Store, enabled: bool) {
+        store.operation_log().unwrap().set_enabled(enabled);
+    }
+
+    fn recorded(dir: &TempDir) -> Vec<Invocation> {
+        oplog::invocations(&dir.path().to_path_buf()).unwrap()
+    }
+
+    fn revert_all(store: &Store, invocations: &[Invocation]) {
+        for invocation in invocations.iter().rev() {
+            for op in invocation.operations().iter().rev() {
+                revert(store, op).unwrap();
+            }
+        }
+    }
+
+    fn create(store: &Store, id: &str, content: &str) {
+        let mut entry = store.create(PathBuf::from(id)).unwrap();
+        *entry.get_content_mut() = String::from(content);
+    }
+
+    fn content(store: &Store, id: &str) -> Option<String> {
+        store.get(PathBuf::from(id)).unwrap().map(|e| e.get_content().clone())
+    }
+
+    #[test]
+    fn test_revert_create_and_update() {
+        let dir   = TempDir::new("imag-undo").unwrap();
+        let store = store_with_log(&dir);
+
+        create(&store, "test", "first");
+        {
+            let mut entry = store.get(PathBuf::from("test")).unwrap().unwrap();
+            *entry.get_content_mut() = String::from("second");
+        }
+
+        let invocations = recorded(&dir);
+        assert_eq!(1, invocations.len());
+        let ops = invocations[0].operations();
+        assert_eq!(2, ops.len());
+        match (&ops[0], &ops[1]) {
+            (&Operation::Create { ref id, .. }, &Operation::Update { id: ref uid, ref before, ref after }) => {
+                assert_eq!(id, "test");
+                assert_eq!(uid, "test");
+                assert!(before.contains("first"));
+                assert!(after.contains("second"));
+            },
+            other => panic!("Unexpected operations: {:?}", other),
+        }
+
+        set_recording(&store, false);
+        check(&store, &invocations).unwrap();
+        revert_all(&store, &invocations);
+
+        assert!(!store.exists(storeid("test").unwrap()).unwrap());
+    }
+
+    #[test]
+    fn test_unchanged_entry_is_not_recorded() {
+        let dir   = TempDir::new("imag-undo").unwrap();
+        let store = store_with_log(&dir);
+
+        set_recording(&store, false);
+        create(&store, "test", "content");
+        set_recording(&store, true);
+
+        let _ = store.get(PathBuf::from("test")).unwrap().unwrap();
+        assert!(recorded(&dir).is_empty());
+    }
+
+    #[test]
+    fn test_revert_move_and_delete() {
+        let dir   = TempDir::new("imag-undo").unwrap();
+        let store = store_with_log(&dir);
+
+        set_recording(&store, false);
+        create(&store, "a", "moved");
+        create(&store, "c", "deleted");
+        set_recording(&store, true);
+
+        store.move_by_id(storeid("a").unwrap(), storeid("b").unwrap()).unwrap();
+        store.delete(storeid("c").unwrap()).unwrap();
+
+        let invocations = recorded(&dir);
+        set_recording(&store, false);
+        check(&store, &invocations).unwrap();
+        revert_all(&store, &invocations);
+
+        assert_eq!(content(&store, "a"), Some(String::from("moved")));
+        assert_eq!(content(&store, "b"), None);
+        assert_eq!(content(&store, "c"), Some(String::from("deleted")));
+    }
+
+    #[test]
+    fn test_check_refuses_changed_entries() {
+        let dir   = TempDir::new("imag-undo").unwrap();
+        let store = store_with_log(&dir);
+
+        create(&store, "test", "first");
+
+        set_recording(&store, false);
+        {
+            let mut entry = store.get(PathBuf::from("test")).unwrap().unwrap();
+            *entry.get_content_mut() = String::from("edited manually");
+        }
+
+        let invocations = recorded(&dir);
+        assert!(check(&store, &invocations).is_err());
+        assert_eq!(content(&store, "test"), Some(String::from("edited manually")));
+    }
+
+    #[test]
+    fn test_check_refuses_recreated_entries() {
+        let dir   = TempDir::new("imag-undo").unwrap();
+        let store = store_with_log(&dir);
+
+        set_recording(&store, false);
+        create(&store, "test", "content");
+        set_recording(&store, true);
+
+        store.delete(storeid("test").unwrap()).unwrap();
+
+        set_recording(&store, false);
+        create(&store, "test", "new content");
+
+        assert!(check(&store, &recorded(&dir)).is_err());
+    }
+}
@@ -91,8 +91,10 @@ gen_mods_buildui!(
     ("../../../bin/core/imag-link/src/ui.rs"        , imaglink)        ,
     ("../../../bin/core/imag-mv/src/ui.rs"          , imagmv)          ,
     ("../../../bin/core/imag-ref/src/ui.rs"         , imagref)         ,
+    ("../../../bin/core/imag-shell/src/ui.rs"       , imagshell)       ,
     ("../../../bin/core/imag-store/src/ui.rs"       , imagstore)       ,
     ("../../../bin/core/imag-tag/src/ui.rs"         , imagtag)         ,
+    ("../../../bin/core/imag-undo/src/ui.rs"        , imagundo)        ,
     ("../../../bin/core/imag-view/src/ui.rs"        , imagview)        ,
     ("../../../bin/domain/imag-bookmark/src/ui.rs"  , imagbookmark)    ,
     ("../../../bin/domain/imag-contact/src/ui.rs"   , imagcontact)     ,
@@ -107,43 +109,60 @@ gen_mods_buildui!(
 );
 
 fn main() {
+    let version     = make_imag_version!();
+    let out_dir     = "../../../target/";
+    let subcommands = vec![
+        build_subcommand!("annotate"    , imagannotate    , version),
+        build_subcommand!("bookmark"    , imagbookmark    , version),
+        build_subcommand!("category"    , imagcategory    , version),
+        build_subcommand!("contact"     , imagcontact     , version),
+        build_subcommand!("diagnostics" , imagdiagnostics , version),
+        build_subcommand!("diary"       , imagdiary       , version),
+        build_subcommand!("edit"        , imagedit        , version),
+        build_subcommand!("git"         , imaggit         , version),
+        build_subcommand!("gps"         , imaggps         , version),
+        build_subcommand!("grep"        , imaggrep        , version),
+        build_subcommand!("habit"       , imaghabit       , version),
+        build_subcommand!("ids"         , imagids         , version),
+        build_subcommand!("init"        , imaginit        , version),
+        build_subcommand!("link"        , imaglink        , version),
+        build_subcommand!("log"         , imaglog         , version),
+        build_subcommand!("mail"        , imagmail        , version),
+        build_subcommand!("mv"          , imagmv          , version),
+        build_subcommand!("notes"       , imagnotes       , version),
+        build_subcommand!("ref"         , imagref         , version),
+        build_subcommand!("shell"       , imagshell       , version),
+        build_subcommand!("store"       , imagstore       , version),
+        build_subcommand!("tag"         , imagtag         , version),
+        build_subcommand!("timetrack"   , imagtimetrack   , version),
+        build_subcommand!("todo"        , imagtodo        , version),
+        build_subcommand!("undo"        , imagundo        , version),
+        build_subcommand!("view"        , imagview        , version),
+        build_subcommand!("wiki"        , imagwiki        , version),
+    ];
+
     // Make the `imag`-App...
-    let version = make_imag_version!();
     let mut app = Runtime::get_default_cli_builder(
         "imag",
         &version[..],
-        "imag")
-        // and add all the subapps as subcommands.
-        .subcommand(build_subcommand!("annotate"    , imagannotate    , version))
-        .subcommand(build_subcommand!("bookmark"    , imagbookmark    , version))
-        .subcommand(build_subcommand!("category"    , imagcategory    , version))
-        .subcommand(build_subcommand!("contact"     , imagcontact     , version))
-        .subcommand(build_subcommand!("diagnostics" , imagdiagnostics , version))
-        .subcommand(build_subcommand!("diary"       , imagdiary       , version))
-        .subcommand(build_subcommand!("edit"        , imagedit        , version))
-        .subcommand(build_subcommand!("git"         , imaggit         , version))
-        .subcommand(build_subcommand!("gps"         , imaggps         , version))
-        .subcommand(build_subcommand!("grep"        , imaggrep        , version))
-        .subcommand(build_subcommand!("habit"       , imaghabit       , version))
-        .subcommand(build_subcommand!("ids"         , imagids         , version))
-        .subcommand(build_subcommand!("init"        , imaginit        , version))
-        .subcommand(build_subcommand!("link"        , imaglink        , version))
-        .subcommand(build_subcommand!("log"         , imaglog         , version))
-        .subcommand(build_subcommand!("mail"        , imagmail        , version))
-        .subcommand(build_subcommand!("mv"          , imagmv          , version))
-        .subcommand(build_subcommand!("notes"       , imagnotes       , version))
-        .subcommand(build_subcommand!("ref"         , imagref         , version))
-        .subcommand(build_subcommand!("store"       , imagstore       , version))
-        .subcommand(build_subcommand!("tag"         , imagtag         , version))
-        .subcommand(build_subcommand!("timetrack"   , imagtimetrack   , version))
-        .subcommand(build_subcommand!("todo"        , imagtodo        , version))
-        .subcommand(build_subcommand!("view"        , imagview        , version))
-        .subcommand(build_subcommand!("wiki"        , imagwiki        , version));
+        "imag");
+
+    for subcommand in subcommands {
+        // generate the completion files for the imag-* binary itself (on a clone, as generating
+        // completions sets the binary name of the app)...
+        let binary = format!("imag-{}", subcommand.get_name());
+        for shell in &[Shell::Bash, Shell::Fish, Shell::Zsh] {
+            subcommand.clone().gen_completions(binary.as_str(), *shell, out_dir);
+        }
+
+        // and add it as subcommand to the `imag`-App.
+        app = app.subcommand(subcommand);
+    }
 
     // Actually generates the completion files
-    app.gen_completions("imag", Shell::Bash, "../../../target/");
-    app.gen_completions("imag", Shell::Fish, "../../../target/");
-    app.gen_completions("imag", Shell::Zsh,  "../../../target/");
+    app.gen_completions("imag", Shell::Bash, out_dir);
+    app.gen_completions("imag", Shell::Fish, out_dir);
+    app.gen_completions("imag", Shell::Zsh,  out_dir);
 
     toplevelbuildscript::build();
 }
@@ -63,10 +63,17 @@ use libimagerror::io::ToExitCode;
 use libimagerror::exit::ExitUnwrap;
 use libimagerror::trace::trace_error;
 use libimagrt::configuration::InternalConfiguration;
+use libimagrt::describe::CommandDescription;
+use libimagrt::describe::DescriptionCache;
+use libimagrt::describe::DESCRIBE_FLAG;
+use libimagrt::describe::PROTOCOL_VERSION;
 
 /// Returns the helptext, putting the Strings in cmds as possible
 /// subcommands into it
-fn help_text(cmds: Vec<String>) -> String {
+///
+/// Commands which described themselves are listed with their description. If the version of a
+/// command differs from the version of imag, this is noted in the listing.
+fn help_text(cmds: Vec<(String, Option<CommandDescription>)>, version: &str) -> String {
     format!(r#"
 
      _
@@ -100,7 +107,18 @@ fn help_text(cmds: Vec<String>) -> String {
     (c) 2015-2018 Matthias Beyer and contributors"#,
         imagbins = cmds
             .into_iter()
-            .map(|cmd| format!("\t{}\n", cmd))
+            .map(|(cmd, desc)| match desc {
+                Some(desc) => {
+                    let mismatch = if desc.version != version {
+                        format!(" (version mismatch: {})", desc.version)
+                    } else {
+                        String::new()
+                    };
+
+                    format!("\t{:15} {}{}\n", cmd, desc.description.unwrap_or_default(), mismatch)
+                },
+                None => format!("\t{}\n", cmd),
+            })
             .fold(String::new(), |s, c| {
                 let s = s + c.as_str();
                 s
@@ -141,6 +159,107 @@ fn get_commands(out: &mut Stdout) -> Vec<String> {
     v
 }
 
+/// Find the executable of the command `imag-<command>` in $PATH
+fn command_path(command: &str) -> Option<PathBuf> {
+    let name = format!("imag-{}", command);
+    env::var_os("PATH").and_then(|path| {
+        env::split_paths(&path)
+            .map(|dir| dir.join(&name))
+            .find(|candidate| candidate.is_file())
+    })
+}
+
+/// Get the description of an imag command
+///
+/// The description is taken from the `cache` if the executable of the command did not change
+/// since it was cached. Otherwise the command is asked to describe itself and the result is
+/// cached.
+///
+/// Returns None if the command could not be called or does not implement the description
+/// protocol (see `libimagrt::describe`).
+fn describe_command(cache: &mut DescriptionCache, command: &str) -> Option<CommandDescription> {
+    let path = command_path(command);
+
+    if let Some(cached) = path.as_ref().and_then(|p| cache.get(p)) {
+        debug!("Using cached description of imag-{}", command);
+        return cached.cloned();
+    }
+
+    let description = call_describe(command);
+    if let Some(path) = path {
+        cache.insert(path, description.clone());
+    }
+    description
+}
+
+/// Ask an imag command to describe itself, by calling it
+fn call_describe(command: &str) -> Option<CommandDescription> {
+    let output = Command::new(format!("imag-{}", command))
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .arg(DESCRIBE_FLAG)
+        .output()
+        .map_err(|e| debug!("Failed calling imag-{} {}: {:?}", command, DESCRIBE_FLAG, e))
+        .ok()?;
+
+    if !output.status.success() {
+        debug!("imag-{} does not support {}", command, DESCRIBE_FLAG);
+        return None;
+    }
+
+    let description = String::from_utf8(output.stdout)
+        .map_err(|e| debug!("UTF8 Error while working with output of imag-{}: {:?}", command, e))
+        .ok()
+        .and_then(|s| {
+            CommandDescription::from_json(&s)
+                .map_err(|e| debug!("Invalid description from imag-{}: {:?}", command, e))
+                .ok()
+        })?;
+
+    if description.protocol > PROTOCOL_VERSION {
+        debug!("imag-{} uses a newer description protocol: {}", command, description.protocol);
+    }
+
+    Some(description)
+}
+
+/// Load the description cache from its default location
+fn load_description_cache() -> DescriptionCache {
+    DescriptionCache::default_path()
+        .map(DescriptionCache::load)
+        .unwrap_or_default()
+}
+
+/// Save the description cache, failing to do so is not fatal
+fn save_description_cache(cache: &DescriptionCache) {
+    if let Some(path) = DescriptionCache::default_path() {
+        if let Err(e) = cache.save(&path) {
+            debug!("Could not save description cache to '{}': {:?}", path.display(), e);
+        }
+    }
+}
+
+/// Checks whether the helptext of imag is going to be printed
+///
+/// Describing all commands means calling each of them, so this is only done if the helptext is
+/// actually needed.
+fn help_requested() -> bool {
+    let args = env::args().skip(1).collect::<Vec<String>>();
+    if args.is_empty() {
+        return true;
+    }
+
+    for arg in args {
+        match arg.as_str() {
+            "-h" | "--help" | "help" => return true,
+            a if a.starts_with("-")  => continue,
+            _                        => return false,
+        }
+    }
+
+    false
+}
 
 fn main() {
     // Initialize the Runtime and build the CLI
@@ -149,7 +268,20 @@ fn main() {
     let about    = "imag - the PIM suite for the commandline";
     let mut out  = stdout();
     let commands = get_commands(&mut out);
-    let helptext = help_text(commands.clone());
+    let mut descriptions = load_description_cache();
+    let helptext = {
+        let describe = help_requested();
+        let cmds     = commands
+            .iter()
+            .map(|cmd| {
+                let desc = if describe { describe_command(&mut descriptions, cmd) } else { None };
+                (cmd.clone(), desc)
+            })
+            .collect();
+
+        help_text(cmds, &version)
+    };
+    save_description_cache(&descriptions);
     let mut app  = Runtime::get_default_cli_builder(appname, &version, about)
         .settings(&[AppSettings::AllowExternalSubcommands, AppSettings::ArgRequiredElseHelp])
         .arg(Arg::with_name("version")
@@ -220,6 +352,15 @@ fn main() {
         commands
             .iter()
             .map(|command| {
+                if let Some(desc) = describe_command(&mut descriptions, command) {
+                    return if desc.version == version {
+                        format!("{:15} -> {} {}", command, desc.name, desc.version)
+                    } else {
+                        format!("{:15} -> {} {} (version mismatch, imag is {})",
+                                command, desc.name, desc.version, version)
+                    };
+                }
+
                 match Command::new(format!("imag-{}", command))
                     .stdin(::std::process::Stdio::inherit())
                     .stdout(::std::process::Stdio::piped())
@@ -242,6 +383,7 @@ fn main() {
                     .unwrap_or_exit();
             });
 
+        save_description_cache(&descriptions);
         exit(0);
     }
 
@@ -259,6 +401,16 @@ fn main() {
         }
     };
 
+    for (alias, command) in aliases.iter() {
+        if !commands.contains(command) {
+            warn!("Alias '{}' points to '{}', which is not an installed imag command", alias, command);
+        }
+
+        if commands.contains(alias) {
+            warn!("Alias '{}' shadows the installed command 'imag-{}'", alias, alias);
+        }
+    }
+
     // Matches any subcommand given
     match matches.subcommand() {
         (subcommand, Some(scmd)) => {
@@ -416,10 +568,30 @@ fn forward_commandline_arguments(m: &ArgMatches, scmd: &mut Vec<String>) {
     push(Some("store"),
          Runtime::arg_storepath_name(), m , scmd);
 
+    push(Some("profile"),
+         Runtime::arg_profile_name(), m , scmd);
+
     push(Some("editor"),
          Runtime::arg_editor_name(), m , scmd);
 
     push(None , Runtime::arg_logdest_name()                         , m , scmd);
 
+    push(Some("stream-json"),
+         Runtime::arg_stream_json_name(), m , scmd);
+
+    push(Some("stream-header"),
+         Runtime::arg_stream_header_name(), m , scmd);
+
+    push(Some("error-format"),
+         Runtime::arg_error_format_name(), m , scmd);
+
+    // `push` only forwards one value, but a field can be passed several times
+    if let Some(fields) = m.values_of(Runtime::arg_stream_fields_name()) {
+        for field in fields {
+            scmd.insert(0, String::from(field));
+            scmd.insert(0, String::from("--stream-field"));
+        }
+    }
+
 }
 
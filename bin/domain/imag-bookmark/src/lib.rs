@@ -41,21 +41,27 @@ extern crate toml_query;
 #[macro_use] extern crate failure;
 
 extern crate libimagbookmark;
-#[macro_use] extern crate libimagrt;
+extern crate libimagentrylink;
+extern crate libimagrt;
 extern crate libimagerror;
 extern crate libimagutil;
 
 use std::io::Write;
-use std::process::exit;
+use libimagerror::exit::exit;
+
+use clap::App;
 
 use toml_query::read::TomlValueReadTypeExt;
 use failure::Error;
+use failure::Fallible as Result;
 
 use libimagrt::runtime::Runtime;
-use libimagrt::setup::generate_runtime_setup;
+use libimagrt::application::ImagApplication;
+use libimagrt::completion;
 use libimagbookmark::collection::BookmarkCollection;
 use libimagbookmark::collection::BookmarkCollectionStore;
 use libimagbookmark::link::Link as BookmarkLink;
+use libimagentrylink::external_check::LinkChecker;
 use libimagerror::trace::{MapErrTrace, trace_error};
 use libimagerror::io::ToExitCode;
 use libimagerror::exit::ExitUnwrap;
@@ -65,31 +71,67 @@ mod ui;
 
 use ui::build_ui;
 
-fn main() {
-    let version = make_imag_version!();
-    let rt = generate_runtime_setup("imag-bookmark",
-                                    &version,
-                                    "Bookmark collection tool",
-                                    build_ui);
+/// Marker enum for implementing ImagApplication on
+///
+/// This is used by binaries crates to execute business logic
+/// or to build a CLI completion.
+pub enum ImagBookmark {}
+impl ImagApplication for ImagBookmark {
+    fn run(rt: &Runtime) -> Result<()> {
+        rt.cli()
+            .subcommand_name()
+            .map(|name| {
+                debug!("Call {}", name);
+                match name {
+                    "add"        => add(&rt),
+                    "collection" => collection(&rt),
+                    "list"       => list(&rt),
+                    "check"      => check(&rt),
+                    "remove"     => remove(&rt),
+                    other        => {
+                        debug!("Unknown command");
+                        let _ = rt.handle_unknown_subcommand("imag-bookmark", other, rt.cli())
+                            .map_err_trace_exit_unwrap(1)
+                            .code()
+                            .map(::libimagerror::exit::exit);
+                    },
+                }
+            });
+
+        Ok(())
+    }
 
-    rt.cli()
-        .subcommand_name()
-        .map(|name| {
-            debug!("Call {}", name);
-            match name {
-                "add"        => add(&rt),
-                "collection" => collection(&rt),
-                "list"       => list(&rt),
-                "remove"     => remove(&rt),
-                other        => {
-                    debug!("Unknown command");
-                    let _ = rt.handle_unknown_subcommand("imag-bookmark", other, rt.cli())
-                        .map_err_trace_exit_unwrap(1)
-                        .code()
-                        .map(::std::process::exit);
-                },
-            }
-        });
+    fn build_cli<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
+        build_ui(app)
+    }
+
+    fn name() -> &'static str {
+        env!("CARGO_PKG_NAME")
+    }
+
+    fn description() -> &'static str {
+        "Bookmark collection tool"
+    }
+
+    fn version() -> &'static str {
+        env!("CARGO_PKG_VERSION")
+    }
+
+    fn completions(rt: &Runtime, kind: &str) -> Result<Vec<String>> {
+        match kind {
+            "collections" => rt.store()
+                .entries()?
+                .in_collection("bookmark")
+                .map(|id| {
+                    id?.local()
+                        .strip_prefix("bookmark")
+                        .map(|p| p.display().to_string())
+                        .map_err(Error::from)
+                })
+                .collect(),
+            _ => completion::default_completions(rt, kind),
+        }
+    }
 }
 
 fn add(rt: &Runtime) {
@@ -168,6 +210,58 @@ fn list(rt: &Runtime) {
     debug!("... ready with listing");
 }
 
+fn check(rt: &Runtime) {
+    use std::str::FromStr;
+    use std::time::Duration;
+
+    let scmd = rt.cli().subcommand_matches("check").unwrap();
+    let coll = get_collection_name(rt, "check", "collection");
+
+    // safe by clap default values and validators
+    let timeout = u64::from_str(scmd.value_of("timeout").unwrap()).unwrap();
+    let jobs    = usize::from_str(scmd.value_of("jobs").unwrap()).unwrap();
+    let all     = scmd.is_present("all");
+
+    let collection = BookmarkCollectionStore::get(rt.store(), &coll)
+        .map_err_trace_exit_unwrap(1)
+        .ok_or_else(|| format_err!("No bookmark collection '{}' found", coll))
+        .map_err_trace_exit_unwrap(1);
+
+    let results = LinkChecker::new(Duration::from_secs(timeout), jobs)
+        .and_then(|checker| collection.check_links(rt.store(), &checker))
+        .map_err_trace_exit_unwrap(1);
+
+    let mut broken = 0;
+    for (id, result) in results {
+        if result.is_broken() {
+            broken += 1;
+        } else if !all {
+            continue;
+        }
+
+        let state = match (result.status(), result.error()) {
+            (_, Some(e))    => format!("error: {}", e),
+            (Some(s), None) => format!("{}", s),
+            (None, None)    => String::from("unknown"),
+        };
+
+        let _ = writeln!(rt.stdout(), "{}\t{}", state, result.url())
+            .to_exit_code()
+            .unwrap_or_exit();
+
+        let _ = rt.report_touched(&id).map_err_trace_exit_unwrap(1);
+    }
+
+    let _ = rt
+        .report_touched(collection.get_location())
+        .map_err_trace_exit_unwrap(1);
+
+    if broken > 0 {
+        info!("{} of the bookmarks in '{}' are broken", broken, coll);
+        exit(1);
+    }
+}
+
 fn remove(rt: &Runtime) {
     let scmd = rt.cli().subcommand_matches("remove").unwrap();
     let coll = get_collection_name(rt, "list", "collection");
@@ -101,6 +101,44 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
                         .help("Filter links to contain these tags. When multiple tags are specified, all of them must be set for the link to match."))
                    )
 
+        .subcommand(SubCommand::with_name("check")
+                   .about("Check whether the bookmarks are reachable and print the broken ones")
+                   .version("0.1")
+                   .arg(Arg::with_name("collection")
+                        .long("collection")
+                        .short("c")
+                        .takes_value(true)
+                        .required(false)
+                        .multiple(false)
+                        .value_name("COLLECTION")
+                        .help("Check this collection, if not specified default from config will be used"))
+                   .arg(Arg::with_name("timeout")
+                        .long("timeout")
+                        .takes_value(true)
+                        .required(false)
+                        .multiple(false)
+                        .default_value("10")
+                        .validator(::libimagutil::cli_validators::is_unsigned_integer)
+                        .value_name("SECONDS")
+                        .help("Give up on a bookmark after this many seconds"))
+                   .arg(Arg::with_name("jobs")
+                        .long("jobs")
+                        .short("j")
+                        .takes_value(true)
+                        .required(false)
+                        .multiple(false)
+                        .default_value("4")
+                        .validator(::libimagutil::cli_validators::is_unsigned_integer)
+                        .value_name("N")
+                        .help("Check this many bookmarks at a time"))
+                   .arg(Arg::with_name("all")
+                        .long("all")
+                        .short("a")
+                        .takes_value(false)
+                        .required(false)
+                        .help("Print all checked bookmarks, not only the broken ones"))
+                   )
+
         .subcommand(SubCommand::with_name("collection")
                    .about("Collection commands")
                    .version("0.1")
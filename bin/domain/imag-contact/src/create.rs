@@ -33,7 +33,7 @@
 )]
 
 use std::collections::BTreeMap;
-use std::process::exit;
+use libimagerror::exit::exit;
 use std::io::Read;
 use std::io::Write;
 use std::path::PathBuf;
@@ -47,29 +47,32 @@ extern crate failure;
 
 extern crate libimagcontact;
 extern crate libimagstore;
-#[macro_use] extern crate libimagrt;
+extern crate libimagrt;
 extern crate libimagerror;
 extern crate libimagutil;
 extern crate libimaginteraction;
 extern crate libimagentryedit;
 
-use std::process::exit;
+use libimagerror::exit::exit;
 use std::path::PathBuf;
 use std::io::Write;
 
 use handlebars::Handlebars;
-use clap::ArgMatches;
+use clap::{ArgMatches, App};
 use toml_query::read::TomlValueReadTypeExt;
 use walkdir::WalkDir;
 use failure::Error;
 use failure::err_msg;
+use failure::Fallible as Result;
+use failure::ResultExt;
 
 use libimagrt::runtime::Runtime;
-use libimagrt::setup::generate_runtime_setup;
+use libimagrt::application::ImagApplication;
 use libimagerror::trace::MapErrTrace;
 use libimagerror::io::ToExitCode;
 use libimagerror::exit::ExitUnwrap;
 use libimagerror::iter::TraceIterator;
+use libimagerror::errors::ErrorMsg as EM;
 use libimagcontact::store::ContactStore;
 use libimagcontact::contact::Contact;
 use libimagcontact::deser::DeserVcard;
@@ -83,33 +86,51 @@ use ui::build_ui;
 use util::build_data_object_for_handlebars;
 use create::create;
 
-fn main() {
-    let version = make_imag_version!();
-    let rt = generate_runtime_setup("imag-contact",
-                                    &version,
-                                    "Contact management tool",
-                                    build_ui);
-
-
-    rt.cli()
-        .subcommand_name()
-        .map(|name| {
-            debug!("Call {}", name);
-            match name {
-                "list"   => list(&rt),
-                "import" => import(&rt),
-                "show"   => show(&rt),
-                "find"   => find(&rt),
-                "create" => create(&rt),
-                other    => {
-                    debug!("Unknown command");
-                    let _ = rt.handle_unknown_subcommand("imag-contact", other, rt.cli())
-                        .map_err_trace_exit_unwrap(1)
-                        .code()
-                        .map(::std::process::exit);
-                },
-            }
-        });
+/// Marker enum for implementing ImagApplication on
+///
+/// This is used by binaries crates to execute business logic
+/// or to build a CLI completion.
+pub enum ImagContact {}
+impl ImagApplication for ImagContact {
+    fn run(rt: &Runtime) -> Result<()> {
+        rt.cli()
+            .subcommand_name()
+            .map(|name| {
+                debug!("Call {}", name);
+                match name {
+                    "list"   => list(&rt),
+                    "import" => import(&rt),
+                    "show"   => show(&rt),
+                    "find"   => find(&rt),
+                    "create" => create(&rt),
+                    other    => {
+                        debug!("Unknown command");
+                        let _ = rt.handle_unknown_subcommand("imag-contact", other, rt.cli())
+                            .map_err_trace_exit_unwrap(1)
+                            .code()
+                            .map(::libimagerror::exit::exit);
+                    },
+                }
+            });
+
+        Ok(())
+    }
+
+    fn build_cli<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
+        build_ui(app)
+    }
+
+    fn name() -> &'static str {
+        env!("CARGO_PKG_NAME")
+    }
+
+    fn description() -> &'static str {
+        "Contact management tool"
+    }
+
+    fn version() -> &'static str {
+        env!("CARGO_PKG_VERSION")
+    }
 }
 
 fn list(rt: &Runtime) {
@@ -140,7 +161,7 @@ fn list(rt: &Runtime) {
             Ok(s) => writeln!(rt.stdout(), "{}", s).to_exit_code().unwrap_or_exit(),
             Err(e) => {
                 error!("Error generating JSON: {:?}", e);
-                ::std::process::exit(1)
+                ::libimagerror::exit::exit(1)
             }
         }
     } else {
@@ -161,8 +182,10 @@ fn import(rt: &Runtime) {
     let path = scmd.value_of("path").map(PathBuf::from).unwrap(); // secured by clap
 
     if !path.exists() {
-        error!("Path does not exist");
-        exit(1)
+        let _ = Err::<(), _>(Error::from(EM::FileNotFound))
+            .context(format!("Path does not exist: {}", path.display()))
+            .map_err(Error::from)
+            .map_err_trace_exit_unwrap(1);
     }
 
     if path.is_file() {
@@ -223,7 +246,7 @@ fn show(rt: &Runtime) {
             if deser.uid()
                 .ok_or_else(|| {
                     error!("Could not get StoreId from Store::all_contacts(). This is a BUG!");
-                    ::std::process::exit(1)
+                    ::libimagerror::exit::exit(1)
                 })
                 .unwrap() // exited above
                 .starts_with(&hash)
@@ -269,7 +292,7 @@ fn find(rt: &Runtime) {
             el.map_err_trace_exit_unwrap(1)
                 .ok_or_else(|| {
                     error!("Could not get StoreId from Store::all_contacts(). This is a BUG!");
-                    ::std::process::exit(1)
+                    ::libimagerror::exit::exit(1)
                 })
                 .unwrap() // safed above
         })
@@ -304,7 +327,7 @@ fn find(rt: &Runtime) {
             Ok(s) => writeln!(rt.stdout(), "{}", s).to_exit_code().unwrap_or_exit(),
             Err(e) => {
                 error!("Error generating JSON: {:?}", e);
-                ::std::process::exit(1)
+                ::libimagerror::exit::exit(1)
             }
         }
     } else if scmd.is_present("find-id") {
@@ -130,7 +130,7 @@ fn create_id_from_clispec(create: &ArgMatches, timed_type: Timed) -> NaiveDateTi
             ndt.with_minute(min)
                 .unwrap_or_else(|| {
                     error!("Cannot set {} as minute, would yield invalid time!", min);
-                    ::std::process::exit(1)
+                    ::libimagerror::exit::exit(1)
                 })
                 .with_second(0)
                 .unwrap() // safe because second = 0 is safe
@@ -160,12 +160,12 @@ fn create_id_from_clispec(create: &ArgMatches, timed_type: Timed) -> NaiveDateTi
             ndt.with_minute(min)
                 .unwrap_or_else(|| {
                     error!("Cannot set {} as minute, would yield invalid time!", min);
-                    ::std::process::exit(1)
+                    ::libimagerror::exit::exit(1)
                 })
                 .with_second(sec)
                 .unwrap_or_else(|| {
                     error!("Cannot set {} as second, would yield invalid time!", sec);
-                    ::std::process::exit(1)
+                    ::libimagerror::exit::exit(1)
                 })
         },
     }
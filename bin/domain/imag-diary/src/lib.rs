@@ -41,21 +41,28 @@ extern crate chrono;
 extern crate toml;
 extern crate toml_query;
 extern crate itertools;
+extern crate filters;
 
 extern crate libimagdiary;
 extern crate libimagentryedit;
 extern crate libimagentryview;
+extern crate libimagentryfilter;
 extern crate libimagerror;
 extern crate libimaginteraction;
-#[macro_use] extern crate libimagrt;
+extern crate libimagrt;
 extern crate libimagstore;
 extern crate libimagtimeui;
 extern crate libimagutil;
 
 use std::io::Write;
 
-use libimagrt::setup::generate_runtime_setup;
+use clap::App;
+
+use failure::Fallible as Result;
+
 use libimagrt::runtime::Runtime;
+use libimagrt::application::ImagApplication;
+use libimagrt::completion;
 use libimagerror::trace::MapErrTrace;
 
 use itertools::Itertools;
@@ -72,32 +79,63 @@ use delete::delete;
 use list::list;
 use view::view;
 
-fn main() {
-    let version = make_imag_version!();
-    let rt = generate_runtime_setup("imag-diary",
-                                    &version,
-                                    "Personal Diary/Diaries",
-                                    ui::build_ui);
-
-    rt.cli()
-        .subcommand_name()
-        .map(|name| {
-            debug!("Call {}", name);
-            match name {
-                "diaries" => diaries(&rt),
-                "create" => create(&rt),
-                "delete" => delete(&rt),
-                "list" => list(&rt),
-                "view" => view(&rt),
-                other    => {
-                    debug!("Unknown command");
-                    let _ = rt.handle_unknown_subcommand("imag-diary", other, rt.cli())
-                        .map_err_trace_exit_unwrap(1)
-                        .code()
-                        .map(::std::process::exit);
-                },
-            }
-        });
+/// Marker enum for implementing ImagApplication on
+///
+/// This is used by binaries crates to execute business logic
+/// or to build a CLI completion.
+pub enum ImagDiary {}
+impl ImagApplication for ImagDiary {
+    fn run(rt: &Runtime) -> Result<()> {
+        rt.cli()
+            .subcommand_name()
+            .map(|name| {
+                debug!("Call {}", name);
+                match name {
+                    "diaries" => diaries(&rt),
+                    "create" => create(&rt),
+                    "delete" => delete(&rt),
+                    "list" => list(&rt),
+                    "view" => view(&rt),
+                    other    => {
+                        debug!("Unknown command");
+                        let _ = rt.handle_unknown_subcommand("imag-diary", other, rt.cli())
+                            .map_err_trace_exit_unwrap(1)
+                            .code()
+                            .map(::libimagerror::exit::exit);
+                    },
+                }
+            });
+
+        Ok(())
+    }
+
+    fn build_cli<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
+        ui::build_ui(app)
+    }
+
+    fn name() -> &'static str {
+        env!("CARGO_PKG_NAME")
+    }
+
+    fn description() -> &'static str {
+        "Personal Diary/Diaries"
+    }
+
+    fn version() -> &'static str {
+        env!("CARGO_PKG_VERSION")
+    }
+
+    fn completions(rt: &Runtime, kind: &str) -> Result<Vec<String>> {
+        use libimagdiary::diary::Diary;
+
+        match kind {
+            "diaries" => rt.store()
+                .diary_names()?
+                .collect::<Result<Vec<String>>>()
+                .map(|names| names.into_iter().unique().collect()),
+            _ => completion::default_completions(rt, kind),
+        }
+    }
 }
 
 fn diaries(rt: &Runtime) {
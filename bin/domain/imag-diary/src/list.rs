@@ -30,8 +30,10 @@ use libimagutil::debug_result::*;
 use libimagdiary::diaryid::DiaryId;
 use libimagdiary::diaryid::FromStoreId;
 use libimagstore::storeid::IntoStoreId;
+use libimagentryfilter::tags::query::TagQuery;
 
 use failure::Fallible as Result;
+use filters::filter::Filter;
 
 use util::get_diary_name;
 
@@ -51,9 +53,24 @@ pub fn list(rt: &Runtime) {
         [id.year() as u32, id.month(), id.day(), id.hour(), id.minute(), id.second()]
     });
 
+    let tag_query = rt
+        .cli()
+        .subcommand_matches("list")
+        .and_then(|scmd| scmd.value_of("tag-query"))
+        .map(|query| TagQuery::parse(query).map_err_trace_exit_unwrap(1));
+
     ids.into_iter()
         .map(IntoStoreId::into_storeid)
         .trace_unwrap_exit(1)
+        .filter(|id| match tag_query {
+            None            => true,
+            Some(ref query) => rt
+                .store()
+                .get(id.clone())
+                .map_err_trace_exit_unwrap(1)
+                .map(|entry| query.filter(&*entry))
+                .unwrap_or(false),
+        })
         .for_each(|id| {
             let _ = rt
                 .report_touched(&id)
@@ -38,7 +38,7 @@ pub fn view(rt: &Runtime) {
         .trace_unwrap_exit(1)
         .map(|e| e.unwrap_or_else(|| {
             error!("Failed to fetch entry");
-            ::std::process::exit(1)
+            ::libimagerror::exit::exit(1)
         }));
 
     let entries = entries.map(|e| {
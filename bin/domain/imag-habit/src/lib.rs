@@ -45,21 +45,25 @@ extern crate prettytable;
 
 extern crate libimaghabit;
 extern crate libimagstore;
-#[macro_use] extern crate libimagrt;
+extern crate libimagrt;
 extern crate libimagerror;
 extern crate libimagutil;
 extern crate libimaginteraction;
 
 use std::io::Write;
-use std::process::exit;
+use libimagerror::exit::exit;
+
+use clap::App;
 
 use prettytable::Table;
 use prettytable::Cell;
 use prettytable::Row;
 use failure::Error;
+use failure::Fallible as Result;
 
 use libimagrt::runtime::Runtime;
-use libimagrt::setup::generate_runtime_setup;
+use libimagrt::application::ImagApplication;
+use libimagrt::completion;
 use libimagerror::trace::{MapErrTrace, trace_error};
 use libimagerror::iter::TraceIterator;
 use libimagerror::exit::ExitUnwrap;
@@ -75,37 +79,85 @@ use libimagutil::debug_result::DebugResult;
 
 mod ui;
 
-fn main() {
-    let version = make_imag_version!();
-    let rt = generate_runtime_setup("imag-habit",
-                                    &version,
-                                    "Habit tracking tool",
-                                    ui::build_ui);
+/// Marker enum for implementing ImagApplication on
+///
+/// This is used by binaries crates to execute business logic
+/// or to build a CLI completion.
+pub enum ImagHabit {}
+impl ImagApplication for ImagHabit {
+    fn run(rt: &Runtime) -> Result<()> {
+        let _ = rt
+            .cli()
+            .subcommand_name()
+            .map(|name| {
+                debug!("Call {}", name);
+                match name {
+                    "create" => create(&rt),
+                    "delete" => delete(&rt),
+                    "list"   => list(&rt),
+                    "today"  => today(&rt, false),
+                    "status" => today(&rt, true),
+                    "show"   => show(&rt),
+                    "done"   => done(&rt),
+                    other    => {
+                        debug!("Unknown command");
+                        let _ = rt.handle_unknown_subcommand("imag-habit", other, rt.cli())
+                            .map_err_trace_exit_unwrap(1)
+                            .code()
+                            .map(::libimagerror::exit::exit);
+                    },
+                }
+            })
+            .unwrap_or_else(|| today(&rt, true));
 
+        Ok(())
+    }
 
-    let _ = rt
-        .cli()
-        .subcommand_name()
-        .map(|name| {
-            debug!("Call {}", name);
-            match name {
-                "create" => create(&rt),
-                "delete" => delete(&rt),
-                "list"   => list(&rt),
-                "today"  => today(&rt, false),
-                "status" => today(&rt, true),
-                "show"   => show(&rt),
-                "done"   => done(&rt),
-                other    => {
-                    debug!("Unknown command");
-                    let _ = rt.handle_unknown_subcommand("imag-habit", other, rt.cli())
-                        .map_err_trace_exit_unwrap(1)
-                        .code()
-                        .map(::std::process::exit);
-                },
-            }
-        })
-        .unwrap_or_else(|| today(&rt, true));
+    fn build_cli<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
+        ui::build_ui(app)
+    }
+
+    fn name() -> &'static str {
+        env!("CARGO_PKG_NAME")
+    }
+
+    fn description() -> &'static str {
+        "Habit tracking tool"
+    }
+
+    fn version() -> &'static str {
+        env!("CARGO_PKG_VERSION")
+    }
+
+    fn completions(rt: &Runtime, kind: &str) -> Result<Vec<String>> {
+        match kind {
+            "habits" => {
+                // A broken template should not break the completion of all other habits
+                let names = rt.store()
+                    .all_habit_templates()?
+                    .filter_map(|id| {
+                        let name = id.and_then(|id| {
+                            rt.store()
+                                .get(id.clone())?
+                                .ok_or_else(|| format_err!("No habit found for {:?}", id))?
+                                .habit_name()
+                        });
+
+                        match name {
+                            Ok(name) => Some(name),
+                            Err(e)   => {
+                                warn!("Skipping habit template: {}", e);
+                                None
+                            },
+                        }
+                    })
+                    .collect();
+
+                Ok(names)
+            },
+            _ => completion::default_completions(rt, kind),
+        }
+    }
 }
 
 fn create(rt: &Runtime) {
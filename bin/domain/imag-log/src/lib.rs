@@ -43,18 +43,21 @@ extern crate itertools;
 extern crate failure;
 
 extern crate libimaglog;
-#[macro_use] extern crate libimagrt;
+extern crate libimagrt;
 extern crate libimagstore;
 extern crate libimagerror;
 extern crate libimagdiary;
 
 use std::io::Write;
 
+use clap::App;
+
 use failure::Error;
 use failure::err_msg;
+use failure::Fallible as Result;
 
 use libimagrt::runtime::Runtime;
-use libimagrt::setup::generate_runtime_setup;
+use libimagrt::application::ImagApplication;
 use libimagerror::trace::MapErrTrace;
 use libimagerror::io::ToExitCode;
 use libimagerror::exit::ExitUnwrap;
@@ -70,42 +73,60 @@ use ui::build_ui;
 use toml::Value;
 use itertools::Itertools;
 
-fn main() {
-    let version = make_imag_version!();
-    let rt = generate_runtime_setup("imag-log",
-                                    &version,
-                                    "Overlay to imag-diary to 'log' single lines of text",
-                                    build_ui);
-
-
-    if let Some(scmd) = rt.cli() .subcommand_name() {
-        match scmd {
-            "show" => show(&rt),
-            other    => {
-                debug!("Unknown command");
-                let _ = rt.handle_unknown_subcommand("imag-log", other, rt.cli())
-                    .map_err_trace_exit_unwrap(1)
-                    .code()
-                    .map(::std::process::exit);
-            },
+/// Marker enum for implementing ImagApplication on
+///
+/// This is used by binaries crates to execute business logic
+/// or to build a CLI completion.
+pub enum ImagLog {}
+impl ImagApplication for ImagLog {
+    fn run(rt: &Runtime) -> Result<()> {
+        if let Some(scmd) = rt.cli() .subcommand_name() {
+            match scmd {
+                "show" => show(&rt),
+                other    => {
+                    debug!("Unknown command");
+                    let _ = rt.handle_unknown_subcommand("imag-log", other, rt.cli())
+                        .map_err_trace_exit_unwrap(1)
+                        .code()
+                        .map(::libimagerror::exit::exit);
+                },
+            }
+        } else {
+            let text       = get_log_text(&rt);
+            let diary_name = rt.cli()
+                .value_of("diaryname")
+                .map(String::from)
+                .unwrap_or_else(|| get_diary_name(&rt));
+
+            debug!("Writing to '{}': {}", diary_name, text);
+
+            let _ = rt
+                .store()
+                .new_entry_now(&diary_name)
+                .map(|mut fle| {
+                    let _ = fle.make_log_entry().map_err_trace_exit_unwrap(1);
+                    *fle.get_content_mut() = text;
+                })
+                .map_err_trace_exit_unwrap(1);
         }
-    } else {
-        let text       = get_log_text(&rt);
-        let diary_name = rt.cli()
-            .value_of("diaryname")
-            .map(String::from)
-            .unwrap_or_else(|| get_diary_name(&rt));
-
-        debug!("Writing to '{}': {}", diary_name, text);
-
-        let _ = rt
-            .store()
-            .new_entry_now(&diary_name)
-            .map(|mut fle| {
-                let _ = fle.make_log_entry().map_err_trace_exit_unwrap(1);
-                *fle.get_content_mut() = text;
-            })
-            .map_err_trace_exit_unwrap(1);
+
+        Ok(())
+    }
+
+    fn build_cli<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
+        build_ui(app)
+    }
+
+    fn name() -> &'static str {
+        env!("CARGO_PKG_NAME")
+    }
+
+    fn description() -> &'static str {
+        "Overlay to imag-diary to 'log' single lines of text"
+    }
+
+    fn version() -> &'static str {
+        env!("CARGO_PKG_VERSION")
     }
 }
 
@@ -174,7 +195,7 @@ fn show(rt: &Runtime) {
                 .map_err_trace_exit_unwrap(1);
             Ok(())
         })
-        .collect::<Result<Vec<()>, ExitCode>>()
+        .collect::<::std::result::Result<Vec<()>, ExitCode>>()
         .unwrap_or_exit();
 }
 
@@ -206,7 +227,7 @@ fn get_diary_name(rt: &Runtime) -> String {
         .iter()
         .map(|e| if is_match!(e, &Value::String(_)) {
             error!("Configuration 'log.logs' is not an Array<String>!");
-            ::std::process::exit(1)
+            ::libimagerror::exit::exit(1)
         } else {
             e
         })
@@ -218,7 +239,7 @@ fn get_diary_name(rt: &Runtime) -> String {
         .is_none()
     {
         error!("'log.logs' does not contain 'log.default'");
-        ::std::process::exit(1)
+        ::libimagerror::exit::exit(1)
     } else {
         current_log.into()
     }
@@ -38,60 +38,88 @@ extern crate clap;
 #[macro_use] extern crate log;
 extern crate failure;
 
-#[macro_use] extern crate libimagrt;
+extern crate libimagrt;
 extern crate libimagmail;
+extern crate libimagentryref;
 extern crate libimagerror;
 extern crate libimagutil;
 
 use std::io::Write;
+use std::path::PathBuf;
+
+use clap::{App, ArgMatches};
 
 use failure::Error;
 use failure::err_msg;
+use failure::Fallible as Result;
 
 use libimagerror::trace::{MapErrTrace, trace_error};
 use libimagerror::iter::TraceIterator;
 use libimagerror::exit::ExitUnwrap;
 use libimagerror::io::ToExitCode;
 use libimagmail::mail::Mail;
+use libimagentryref::refind::configured_search_roots;
+use libimagentryref::reference::Config;
+use libimagentryref::refind::files_below;
 use libimagrt::runtime::Runtime;
-use libimagrt::setup::generate_runtime_setup;
+use libimagrt::application::ImagApplication;
 use libimagutil::info_result::*;
+use libimagutil::warn_exit::warn_exit;
 
 mod ui;
 
 use ui::build_ui;
 
-fn main() {
-    let version = make_imag_version!();
-    let rt = generate_runtime_setup("imag-mail",
-                                    &version,
-                                    "Mail collection tool",
-                                    build_ui);
-
-    rt.cli()
-        .subcommand_name()
-        .map(|name| {
-            debug!("Call {}", name);
-            match name {
-                "import-mail" => import_mail(&rt),
-                "list"        => list(&rt),
-                "mail-store"  => mail_store(&rt),
-                other         => {
-                    debug!("Unknown command");
-                    let _ = rt.handle_unknown_subcommand("imag-mail", other, rt.cli())
-                        .map_err_trace_exit_unwrap(1)
-                        .code()
-                        .map(::std::process::exit);
+/// Marker enum for implementing ImagApplication on
+///
+/// This is used by binaries crates to execute business logic
+/// or to build a CLI completion.
+pub enum ImagMail {}
+impl ImagApplication for ImagMail {
+    fn run(rt: &Runtime) -> Result<()> {
+        rt.cli()
+            .subcommand_name()
+            .map(|name| {
+                debug!("Call {}", name);
+                match name {
+                    "import-mail" => import_mail(&rt),
+                    "list"        => list(&rt),
+                    "mail-store"  => mail_store(&rt),
+                    other         => {
+                        debug!("Unknown command");
+                        let _ = rt.handle_unknown_subcommand("imag-mail", other, rt.cli())
+                            .map_err_trace_exit_unwrap(1)
+                            .code()
+                            .map(::libimagerror::exit::exit);
+                    }
                 }
-            }
-        });
+            });
+
+        Ok(())
+    }
+
+    fn build_cli<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
+        build_ui(app)
+    }
+
+    fn name() -> &'static str {
+        env!("CARGO_PKG_NAME")
+    }
+
+    fn description() -> &'static str {
+        "Mail collection tool"
+    }
+
+    fn version() -> &'static str {
+        env!("CARGO_PKG_VERSION")
+    }
 }
 
 fn import_mail(rt: &Runtime) {
     let scmd = rt.cli().subcommand_matches("import-mail").unwrap();
     let path = scmd.value_of("path").unwrap(); // enforced by clap
 
-    let mail = Mail::import_from_path(rt.store(), path)
+    let mail = Mail::import_from_path(rt.store(), path, &ref_config(rt))
         .map_info_str("Ok")
         .map_err_trace_exit_unwrap(1);
 
@@ -101,6 +129,8 @@ fn import_mail(rt: &Runtime) {
 fn list(rt: &Runtime) {
     use failure::ResultExt;
 
+    let config = ref_config(rt);
+
         // TODO: Implement lister type in libimagmail for this
     fn list_mail(rt: &Runtime, m: Mail) {
         let id = match m.get_message_id() {
@@ -161,15 +191,52 @@ fn list(rt: &Runtime) {
                 .context(err_msg("Ref handling error"))
                 .map_err(Error::from)
                 .map_err_trace_exit_unwrap(1)
-                .map(|fle| Mail::from_fle(fle).map_err_trace().ok())
+                .map(|fle| Mail::from_fle(fle, &config).map_err_trace().ok())
         })
         .filter_map(|e| e)
         .for_each(|m| list_mail(&rt, m));
 }
 
 fn mail_store(rt: &Runtime) {
-    let _ = rt.cli().subcommand_matches("mail-store").unwrap();
-    error!("This feature is currently not implemented.");
-    unimplemented!()
+    let scmd = rt.cli().subcommand_matches("mail-store").unwrap();
+
+    match scmd.subcommand() {
+        ("refind", Some(refind_cmd)) => refind(rt, refind_cmd),
+        _ => {
+            error!("This feature is currently not implemented.");
+            unimplemented!()
+        },
+    }
 }
 
+fn refind(rt: &Runtime, scmd: &ArgMatches) {
+    let roots : Vec<PathBuf> = match scmd.values_of("root") {
+        Some(roots) => roots.map(PathBuf::from).collect(),
+        None        => rt.config()
+            .map(|cfg| configured_search_roots(cfg).map_err_trace_exit_unwrap(1))
+            .unwrap_or_else(Vec::new),
+    };
+
+    if roots.is_empty() {
+        warn_exit("No search roots. Pass --root or configure 'ref.search_roots'", 1)
+    }
+
+    let mut out = rt.stdout();
+    let relocated = Mail::refind_dangling(rt.store(), files_below(roots), &ref_config(rt))
+        .map_err_trace_exit_unwrap(1);
+
+    for (id, path) in relocated {
+        let _ = writeln!(out, "{} -> {}", id, path.display())
+            .to_exit_code()
+            .unwrap_or_exit();
+
+        let _ = rt.report_touched(&id).map_err_trace_exit_unwrap(1);
+    }
+}
+
+/// Get the base pathes for refs from the configuration
+fn ref_config(rt: &Runtime) -> Config {
+    rt.config()
+        .map(|cfg| Config::from_config(cfg).map_err_trace_exit_unwrap(1))
+        .unwrap_or_else(Config::default)
+}
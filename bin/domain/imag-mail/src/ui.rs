@@ -68,6 +68,16 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
                     .subcommand(SubCommand::with_name("update-refs")
                                 .about("Create references based on Message-IDs for all loaded mails")
                                 .version("0.1"))
+                    .subcommand(SubCommand::with_name("refind")
+                                .about("Search for the files of mails which moved and update their references")
+                                .version("0.1")
+                                .arg(Arg::with_name("root")
+                                     .long("root")
+                                     .short("r")
+                                     .takes_value(true)
+                                     .multiple(true)
+                                     .help("Search below this directory. Overrides 'ref.search_roots' from the configuration")
+                                     .value_name("DIR")))
                     // TODO: We really should be able to filter here.
                     )
 }
@@ -35,27 +35,37 @@
 )]
 
 extern crate clap;
+extern crate failure;
 #[macro_use] extern crate log;
 extern crate itertools;
+extern crate filters;
 
 extern crate libimagnotes;
-#[macro_use] extern crate libimagrt;
+extern crate libimagrt;
 extern crate libimagentryedit;
+extern crate libimagentryfilter;
 extern crate libimagerror;
 extern crate libimagutil;
 extern crate libimagstore;
 
 use std::io::Write;
-use std::process::exit;
+use libimagerror::exit::exit;
+
+use clap::App;
 
 use itertools::Itertools;
+use filters::filter::Filter;
+
+use failure::Fallible as Result;
 
 use libimagentryedit::edit::Edit;
 use libimagrt::runtime::Runtime;
-use libimagrt::setup::generate_runtime_setup;
+use libimagrt::application::ImagApplication;
+use libimagrt::completion;
 use libimagstore::iter::get::StoreIdGetIteratorExtension;
 use libimagnotes::note::Note;
 use libimagnotes::notestore::*;
+use libimagentryfilter::tags::query::TagQuery;
 use libimagerror::trace::MapErrTrace;
 use libimagerror::exit::ExitUnwrap;
 use libimagerror::io::ToExitCode;
@@ -67,31 +77,65 @@ use libimagutil::warn_result::WarnResult;
 mod ui;
 use ui::build_ui;
 
-fn main() {
-    let version = make_imag_version!();
-    let rt = generate_runtime_setup("imag-notes",
-                                    &version,
-                                    "Note taking helper",
-                                    build_ui);
-
-    rt.cli()
-        .subcommand_name()
-        .map(|name| {
-            debug!("Call: {}", name);
-            match name {
-                "create" => create(&rt),
-                "delete" => delete(&rt),
-                "edit"   => edit(&rt),
-                "list"   => list(&rt),
-                other    => {
-                    debug!("Unknown command");
-                    let _ = rt.handle_unknown_subcommand("imag-notes", other, rt.cli())
-                        .map_err_trace_exit_unwrap(1)
-                        .code()
-                        .map(::std::process::exit);
-                },
-            };
-        });
+/// Marker enum for implementing ImagApplication on
+///
+/// This is used by binaries crates to execute business logic
+/// or to build a CLI completion.
+pub enum ImagNotes {}
+impl ImagApplication for ImagNotes {
+    fn run(rt: &Runtime) -> Result<()> {
+        rt.cli()
+            .subcommand_name()
+            .map(|name| {
+                debug!("Call: {}", name);
+                match name {
+                    "create" => create(&rt),
+                    "delete" => delete(&rt),
+                    "edit"   => edit(&rt),
+                    "list"   => list(&rt),
+                    other    => {
+                        debug!("Unknown command");
+                        let _ = rt.handle_unknown_subcommand("imag-notes", other, rt.cli())
+                            .map_err_trace_exit_unwrap(1)
+                            .code()
+                            .map(::libimagerror::exit::exit);
+                    },
+                };
+            });
+
+        Ok(())
+    }
+
+    fn build_cli<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
+        build_ui(app)
+    }
+
+    fn name() -> &'static str {
+        env!("CARGO_PKG_NAME")
+    }
+
+    fn description() -> &'static str {
+        "Note taking helper"
+    }
+
+    fn version() -> &'static str {
+        env!("CARGO_PKG_VERSION")
+    }
+
+    fn completions(rt: &Runtime, kind: &str) -> Result<Vec<String>> {
+        match kind {
+            "notes" => rt.store()
+                .all_notes()?
+                .into_get_iter(rt.store())
+                .filter_map(|note| match note {
+                    Ok(Some(note)) => Some(note.get_name()),
+                    Ok(None)       => None,
+                    Err(e)         => Some(Err(e)),
+                })
+                .collect(),
+            _ => completion::default_completions(rt, kind),
+        }
+    }
 }
 
 fn name_from_cli(rt: &Runtime, subcmd: &str) -> String {
@@ -148,6 +192,12 @@ fn edit(rt: &Runtime) {
 fn list(rt: &Runtime) {
     use std::cmp::Ordering;
 
+    let tag_query = rt
+        .cli()
+        .subcommand_matches("list")
+        .and_then(|scmd| scmd.value_of("tag-query"))
+        .map(|query| TagQuery::parse(query).map_err_trace_exit_unwrap(1));
+
     let _ = rt
         .store()
         .all_notes()
@@ -158,6 +208,7 @@ fn list(rt: &Runtime) {
             error!("Fatal: Nonexistent entry where entry should exist");
             exit(1)
         }))
+        .filter(|note| tag_query.as_ref().map(|query| query.filter(&**note)).unwrap_or(true))
         .sorted_by(|note_a, note_b| if let (Ok(a), Ok(b)) = (note_a.get_name(), note_b.get_name()) {
             return a.cmp(&b)
         } else {
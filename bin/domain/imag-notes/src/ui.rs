@@ -19,6 +19,8 @@
 
 use clap::{Arg, App, SubCommand};
 
+use libimagentryfilter::tags::query::is_tag_query;
+
 pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
     app
         .subcommand(SubCommand::with_name("create")
@@ -61,6 +63,15 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
 
         .subcommand(SubCommand::with_name("list")
                    .about("List Notes")
-                   .version("0.1"))
+                   .version("0.1")
+                   .arg(Arg::with_name("tag-query")
+                        .long("tag-query")
+                        .short("T")
+                        .takes_value(true)
+                        .required(false)
+                        .multiple(false)
+                        .validator(is_tag_query)
+                        .value_name("QUERY")
+                        .help("Only list the notes whose tags match a query like '(work and urgent) or (home and not done)'")))
 
 }
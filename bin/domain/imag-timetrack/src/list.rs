@@ -49,7 +49,7 @@ pub fn list(rt: &Runtime) -> i32 {
                 Ok(tt) => {
                     let dt = tt.get_moment().unwrap_or_else(|| {
                         error!("Failed to get date from '{}'", cmd.value_of(name).unwrap());
-                        ::std::process::exit(1)
+                        ::libimagerror::exit::exit(1)
                     });
 
                     Some(dt.clone())
@@ -57,17 +57,17 @@ pub fn list(rt: &Runtime) -> i32 {
                 Err(e) => {
                     error!("Failed to calculate date from '{}': {:?}",
                            cmd.value_of(name).unwrap(), e);
-                    ::std::process::exit(1)
+                    ::libimagerror::exit::exit(1)
                 },
             },
             Some(Ok(Parsed::Iterator(_))) => {
                 error!("Expected single point in time, got '{}', which yields a list of dates", cmd.value_of(name).unwrap());
-                ::std::process::exit(1)
+                ::libimagerror::exit::exit(1)
             },
             Some(Err(e)) => {
                 let e = Error::from(e);
                 trace_error(&e);
-                ::std::process::exit(1)
+                ::libimagerror::exit::exit(1)
             }
             None => None,
         }
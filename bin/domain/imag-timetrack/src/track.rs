@@ -17,7 +17,7 @@
 // Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
 //
 
-use std::process::exit;
+use libimagerror::exit::exit;
 
 use clap::ArgMatches;
 use chrono::naive::NaiveDate;
@@ -41,17 +41,20 @@ extern crate toml_query;
 #[macro_use] extern crate is_match;
 extern crate failure;
 
-#[macro_use] extern crate libimagrt;
+extern crate libimagrt;
 extern crate libimagerror;
 extern crate libimagtodo;
 
 use std::process::{Command, Stdio};
 use std::io::stdin;
 use std::io::Write;
+
+use clap::App;
 use failure::Error;
+use failure::Fallible as Result;
 
 use libimagrt::runtime::Runtime;
-use libimagrt::setup::generate_runtime_setup;
+use libimagrt::application::ImagApplication;
 use libimagtodo::taskstore::TaskStore;
 use libimagerror::trace::{MapErrTrace, trace_error};
 use libimagerror::iter::TraceIterator;
@@ -61,28 +64,47 @@ use libimagerror::io::ToExitCode;
 mod ui;
 
 use ui::build_ui;
-fn main() {
-    let version = make_imag_version!();
-    let rt = generate_runtime_setup("imag-todo",
-                                    &version,
-                                    "Interface with taskwarrior",
-                                    build_ui);
-
-    match rt.cli().subcommand_name() {
-        Some("tw-hook") => tw_hook(&rt),
-        Some("list") => list(&rt),
-        Some(other) => {
-            debug!("Unknown command");
-            let _ = rt.handle_unknown_subcommand("imag-todo", other, rt.cli())
-                .map_err_trace_exit_unwrap(1)
-                .code()
-                .map(::std::process::exit);
-        }
-        None => {
-            warn!("No command");
-        },
-    } // end match scmd
-} // end main
+/// Marker enum for implementing ImagApplication on
+///
+/// This is used by binaries crates to execute business logic
+/// or to build a CLI completion.
+pub enum ImagTodo {}
+impl ImagApplication for ImagTodo {
+    fn run(rt: &Runtime) -> Result<()> {
+        match rt.cli().subcommand_name() {
+            Some("tw-hook") => tw_hook(&rt),
+            Some("list") => list(&rt),
+            Some(other) => {
+                debug!("Unknown command");
+                let _ = rt.handle_unknown_subcommand("imag-todo", other, rt.cli())
+                    .map_err_trace_exit_unwrap(1)
+                    .code()
+                    .map(::libimagerror::exit::exit);
+            }
+            None => {
+                warn!("No command");
+            },
+        } // end match scmd
+
+        Ok(())
+    } // end main
+
+    fn build_cli<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
+        build_ui(app)
+    }
+
+    fn name() -> &'static str {
+        env!("CARGO_PKG_NAME")
+    }
+
+    fn description() -> &'static str {
+        "Interface with taskwarrior"
+    }
+
+    fn version() -> &'static str {
+        env!("CARGO_PKG_VERSION")
+    }
+}
 
 fn tw_hook(rt: &Runtime) {
     let subcmd = rt.cli().subcommand_matches("tw-hook").unwrap();
@@ -162,7 +184,7 @@ fn list(rt: &Runtime) {
                     .spawn()
                     .unwrap_or_else(|e| {
                         error!("Failed to execute `task` on the commandline: {:?}. I'm dying now.", e);
-                        ::std::process::exit(1)
+                        ::libimagerror::exit::exit(1)
                     })
                     .wait_with_output()
                     .unwrap_or_else(|e| panic!("failed to unwrap output: {}", e));
@@ -20,60 +20,113 @@
 #![forbid(unsafe_code)]
 
 extern crate clap;
+extern crate failure;
 extern crate regex;
 extern crate filters;
 #[macro_use] extern crate log;
 
-#[macro_use] extern crate libimagrt;
+extern crate libimagrt;
 extern crate libimagerror;
 extern crate libimagstore;
 extern crate libimagwiki;
 extern crate libimagentryedit;
 extern crate libimagentrylink;
+extern crate libimagentryfilter;
+extern crate libimagentryref;
 extern crate libimagutil;
 
 use std::io::Write;
 
+use clap::App;
+
+use failure::Fallible as Result;
+use failure::Error;
+
 use libimagrt::runtime::Runtime;
-use libimagrt::setup::generate_runtime_setup;
+use libimagrt::application::ImagApplication;
+use libimagrt::completion;
 use libimagerror::iter::TraceIterator;
 use libimagerror::trace::MapErrTrace;
 use libimagerror::exit::ExitUnwrap;
 use libimagerror::io::ToExitCode;
 use libimagwiki::store::WikiStore;
 use libimagentryedit::edit::{Edit, EditHeader};
+use libimagentryfilter::tags::query::TagQuery;
+use libimagentryref::reference::Config;
 
 mod ui;
 use ui::build_ui;
 
-fn main() {
-    let version = make_imag_version!();
-    let rt = generate_runtime_setup("imag-wiki",
-                                    &version,
-                                    "Personal wiki",
-                                    build_ui);
-
-    let wiki_name = rt.cli().value_of("wikiname").unwrap_or("default");
-
-    match rt.cli().subcommand_name() {
-        Some("ids")         => ids(&rt, wiki_name),
-        Some("idof")        => idof(&rt, wiki_name),
-        Some("create")      => create(&rt, wiki_name),
-        Some("create-wiki") => create_wiki(&rt),
-        Some("show")        => show(&rt, wiki_name),
-        Some("delete")      => delete(&rt, wiki_name),
-        Some(other)         => {
-            debug!("Unknown command");
-            let _ = rt.handle_unknown_subcommand("imag-wiki", other, rt.cli())
-                .map_err_trace_exit_unwrap(1)
-                .code()
-                .map(std::process::exit);
+/// Marker enum for implementing ImagApplication on
+///
+/// This is used by binaries crates to execute business logic
+/// or to build a CLI completion.
+pub enum ImagWiki {}
+impl ImagApplication for ImagWiki {
+    fn run(rt: &Runtime) -> Result<()> {
+        let wiki_name = rt.cli().value_of("wikiname").unwrap_or("default");
+
+        match rt.cli().subcommand_name() {
+            Some("ids")         => ids(&rt, wiki_name),
+            Some("idof")        => idof(&rt, wiki_name),
+            Some("create")      => create(&rt, wiki_name),
+            Some("create-wiki") => create_wiki(&rt),
+            Some("show")        => show(&rt, wiki_name),
+            Some("delete")      => delete(&rt, wiki_name),
+            Some(other)         => {
+                debug!("Unknown command");
+                let _ = rt.handle_unknown_subcommand("imag-wiki", other, rt.cli())
+                    .map_err_trace_exit_unwrap(1)
+                    .code()
+                    .map(::libimagerror::exit::exit);
+            }
+            None => warn!("No command"),
+        } // end match scmd
+
+        Ok(())
+    } // end main
+
+    fn build_cli<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
+        build_ui(app)
+    }
+
+    fn name() -> &'static str {
+        env!("CARGO_PKG_NAME")
+    }
+
+    fn description() -> &'static str {
+        "Personal wiki"
+    }
+
+    fn version() -> &'static str {
+        env!("CARGO_PKG_VERSION")
+    }
+
+    fn completions(rt: &Runtime, kind: &str) -> Result<Vec<String>> {
+        match kind {
+            "pages" => {
+                let wiki = match rt.store().get_wiki("default")? {
+                    Some(wiki) => wiki,
+                    None       => return Ok(vec![]),
+                };
+
+                wiki.all_ids()?
+                    .map(|id| {
+                        id?.local()
+                            .strip_prefix("wiki/default")
+                            .map(|p| p.display().to_string())
+                            .map_err(Error::from)
+                    })
+                    .collect()
+            },
+            _ => completion::default_completions(rt, kind),
         }
-        None => warn!("No command"),
-    } // end match scmd
-} // end main
+    }
+}
 
 fn ids(rt: &Runtime, wiki_name: &str) {
+    use filters::filter::Filter;
+
     let scmd   = rt.cli().subcommand_matches("ids").unwrap(); // safed by clap
     let prefix = if scmd.is_present("ids-full") {
         format!("{}/", rt.store().path().display())
@@ -81,6 +134,10 @@ fn ids(rt: &Runtime, wiki_name: &str) {
         String::from("")
     };
 
+    let tag_query = scmd
+        .value_of("ids-tag-query")
+        .map(|query| TagQuery::parse(query).map_err_trace_exit_unwrap(1));
+
     let out         = rt.stdout();
     let mut outlock = out.lock();
 
@@ -89,11 +146,20 @@ fn ids(rt: &Runtime, wiki_name: &str) {
         .map_err_trace_exit_unwrap(1)
         .unwrap_or_else(|| {
             error!("No wiki '{}' found", wiki_name);
-            ::std::process::exit(1)
+            ::libimagerror::exit::exit(1)
         })
         .all_ids()
         .map_err_trace_exit_unwrap(1)
         .trace_unwrap_exit(1)
+        .filter(|id| match tag_query {
+            None            => true,
+            Some(ref query) => rt
+                .store()
+                .get(id.clone())
+                .map_err_trace_exit_unwrap(1)
+                .map(|entry| query.filter(&*entry))
+                .unwrap_or(false),
+        })
         .for_each(|id| {
             let _ = writeln!(outlock, "{}{}", prefix, id)
                 .to_exit_code()
@@ -117,7 +183,7 @@ fn idof(rt: &Runtime, wiki_name: &str) {
         .map_err_trace_exit_unwrap(1)
         .unwrap_or_else(|| {
             error!("No wiki '{}' found", wiki_name);
-            ::std::process::exit(1)
+            ::libimagerror::exit::exit(1)
         })
         .get_entry(&entryname)
         .map_err_trace_exit_unwrap(1)
@@ -133,7 +199,7 @@ fn idof(rt: &Runtime, wiki_name: &str) {
         })
         .unwrap_or_else(|| {
             error!("Entry '{}' in wiki '{}' not found!", entryname, wiki_name);
-            ::std::process::exit(1)
+            ::libimagerror::exit::exit(1)
         });
 }
 
@@ -150,7 +216,7 @@ fn create(rt: &Runtime, wiki_name: &str) {
         .map_err_trace_exit_unwrap(1)
         .unwrap_or_else(|| {
             error!("No wiki '{}' found", wiki_name);
-            ::std::process::exit(1)
+            ::libimagerror::exit::exit(1)
         });
 
     let mut entry = wiki.create_entry(name).map_err_trace_exit_unwrap(1);
@@ -163,7 +229,7 @@ fn create(rt: &Runtime, wiki_name: &str) {
         }
     }
 
-    let _ = entry.autolink(rt.store())
+    let _ = entry.autolink(rt.store(), ref_config(rt))
         .map_warn_err_str("Linking has failed. Trying to safe the entry now. Please investigate by hand if this succeeds.")
         .map_err(|e| {
             let _ = rt.store().update(&mut entry).map_err_trace_exit_unwrap(1);
@@ -228,7 +294,7 @@ fn show(rt: &Runtime, wiki_name: &str) {
         .map_err_trace_exit_unwrap(1)
         .unwrap_or_else(|| {
             error!("No wiki '{}' found", wiki_name);
-            ::std::process::exit(1)
+            ::libimagerror::exit::exit(1)
         });
 
     let out         = rt.stdout();
@@ -240,7 +306,7 @@ fn show(rt: &Runtime, wiki_name: &str) {
             .map_err_trace_exit_unwrap(1)
             .unwrap_or_else(|| {
                 error!("No wiki entry '{}' found in wiki '{}'", name, wiki_name);
-                ::std::process::exit(1)
+                ::libimagerror::exit::exit(1)
             });
 
         writeln!(outlock, "{}", entry.get_location())
@@ -270,7 +336,7 @@ fn delete(rt: &Runtime, wiki_name: &str) {
             .map_err_trace_exit_unwrap(1)
             .unwrap_or_else(|| {
                 error!("No wiki '{}' found", wiki_name);
-                ::std::process::exit(1)
+                ::libimagerror::exit::exit(1)
             });
 
     if unlink {
@@ -278,7 +344,7 @@ fn delete(rt: &Runtime, wiki_name: &str) {
             .map_err_trace_exit_unwrap(1)
             .unwrap_or_else(|| {
                 error!("No wiki entry '{}' in '{}' found", name, wiki_name);
-                ::std::process::exit(1)
+                ::libimagerror::exit::exit(1)
             })
             .unlink(rt.store())
             .map_err_trace_exit_unwrap(1);
@@ -289,3 +355,9 @@ fn delete(rt: &Runtime, wiki_name: &str) {
         .map_err_trace_exit_unwrap(1);
 }
 
+/// Get the base pathes for refs from the configuration
+fn ref_config(rt: &Runtime) -> Config {
+    rt.config()
+        .map(|cfg| Config::from_config(cfg).map_err_trace_exit_unwrap(1))
+        .unwrap_or_else(Config::default)
+}
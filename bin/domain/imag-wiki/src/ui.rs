@@ -19,6 +19,8 @@
 
 use clap::{Arg, App, SubCommand};
 
+use libimagentryfilter::tags::query::is_tag_query;
+
 pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
     app
         .arg(Arg::with_name("wikiname")
@@ -39,7 +41,17 @@ pub fn build_ui<'a>(app: App<'a, 'a>) -> App<'a, 'a> {
                         .takes_value(false)
                         .required(false)
                         .multiple(false)
-                        .help("Print full filepath")))
+                        .help("Print full filepath"))
+
+                   .arg(Arg::with_name("ids-tag-query")
+                        .long("tag-query")
+                        .short("T")
+                        .takes_value(true)
+                        .required(false)
+                        .multiple(false)
+                        .validator(is_tag_query)
+                        .value_name("QUERY")
+                        .help("Only list the ids of entries whose tags match a query like '(work and urgent) or (home and not done)'")))
 
         .subcommand(SubCommand::with_name("idof")
                    .about("List id of an entry in this wiki, if it exists")
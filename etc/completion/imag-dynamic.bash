@@ -0,0 +1,105 @@
+# Dynamic completion for imag in bash
+#
+# The completion scripts which are generated when building imag (target/imag.bash
+# and target/imag-*.bash) only complete subcommands and flags. This script adds
+# completion of values which are stored in the imag store, like IDs, tags or
+# names of diaries, by calling 'imag-<command> --imag-complete <kind>'.
+#
+# Source this file after the generated completion scripts.
+
+# <command> <subcommand> <option> <kind>
+#
+# <subcommand> is '*' for "any subcommand" and '-' for "no subcommand",
+# <option> is '@' for positional arguments. The first matching line wins.
+_imag_dynamic_table="
+diary      *                --diary       diaries
+wiki       show             @             pages
+wiki       delete           @             pages
+wiki       idof             @             pages
+habit      delete           @             habits
+habit      show             @             habits
+habit      done             @             habits
+category   delete-category  @             categories
+category   list-category    @             categories
+category   set              @             categories
+tag        add              @             tags
+tag        remove           @             tags
+tag        -                @             ids
+bookmark   *                --collection  collections
+bookmark   collection       --remove      collections
+timetrack  *                --tags        tags
+timetrack  start            @             tags
+timetrack  stop             @             tags
+timetrack  track            @             tags
+notes      delete           @             notes
+notes      edit             @             notes
+view       *                @             ids
+edit       *                @             ids
+"
+
+# Find the kind of completion for a commandline
+#
+# Arguments: <command> <previous word> <non-option words after the command...>
+_imag_dynamic_kind() {
+    local cmd="$1" prev="$2"
+    shift 2
+
+    local c s o k arg found
+    while read -r c s o k; do
+        [ "$c" = "$cmd" ] || continue
+
+        case "$s" in
+            '*') ;;
+            '-') [ $# -eq 0 ] || continue ;;
+            *)
+                found=0
+                for arg in "$@"; do
+                    [ "$arg" = "$s" ] && found=1
+                done
+                [ $found -eq 1 ] || continue
+                ;;
+        esac
+
+        if [ "$o" = "$prev" ] || { [ "$o" = "@" ] && [ "${prev#-}" = "$prev" ]; }; then
+            echo "$k"
+            return 0
+        fi
+    done <<< "$_imag_dynamic_table"
+
+    return 1
+}
+
+_imag_dynamic() {
+    local cur prev base cmd kind i
+    local -a args=()
+
+    cur="${COMP_WORDS[COMP_CWORD]}"
+    prev="${COMP_WORDS[COMP_CWORD-1]}"
+    base="${COMP_WORDS[0]##*/}"
+    cmd="${base#imag}"
+    cmd="${cmd#-}"
+
+    for (( i=1; i < COMP_CWORD; i++ )); do
+        case "${COMP_WORDS[i]}" in
+            -*) ;;
+            *)
+                if [ -z "$cmd" ]; then
+                    cmd="${COMP_WORDS[i]}"
+                else
+                    args+=("${COMP_WORDS[i]}")
+                fi
+                ;;
+        esac
+    done
+
+    if [ -n "$cmd" ] && kind="$(_imag_dynamic_kind "$cmd" "$prev" "${args[@]}")"; then
+        COMPREPLY=( $(compgen -W "$(imag-"$cmd" --imag-complete "$kind" 2>/dev/null)" -- "$cur") )
+        [ ${#COMPREPLY[@]} -gt 0 ] && return 0
+    fi
+
+    # Fall back to the generated completion
+    declare -F "_${base}" >/dev/null && "_${base}" "$@"
+}
+
+complete -F _imag_dynamic -o bashdefault -o default \
+    imag imag-bookmark imag-category imag-diary imag-edit imag-habit imag-notes imag-tag imag-timetrack imag-view imag-wiki
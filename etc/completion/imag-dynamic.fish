@@ -0,0 +1,74 @@
+# Dynamic completion for imag in fish
+#
+# The completion scripts which are generated when building imag (target/imag.fish
+# and target/imag-*.fish) only complete subcommands and flags. This script adds
+# completion of values which are stored in the imag store, like IDs, tags or
+# names of diaries, by calling 'imag-<command> --imag-complete <kind>'.
+#
+# Source this file after the generated completion scripts.
+
+function __imag_dynamic_candidates -a cmd kind
+    command imag-$cmd --imag-complete $kind 2>/dev/null
+end
+
+# Check whether the commandline calls <command> (as 'imag <command>' or as
+# 'imag-<command>') with <subcommand>. '*' stands for "any subcommand" and '-'
+# for "no subcommand".
+function __imag_dynamic_using -a cmd sub
+    set -l tokens (commandline -opc)
+    set -l base (basename -- $tokens[1])
+    set -l rest $tokens
+    set -e rest[1]
+
+    if test "$base" != "imag-$cmd"
+        test "$base" = imag; and contains -- $cmd $rest; or return 1
+    end
+
+    if test "$sub" = '*'
+        return 0
+    else if test "$sub" = '-'
+        for token in $rest
+            string match -q -- '-*' $token; or test "$token" = $cmd; or return 1
+        end
+        return 0
+    end
+
+    contains -- $sub $rest
+end
+
+function __imag_dynamic_complete -a cmd sub opt kind
+    set -l condition "__imag_dynamic_using $cmd '$sub'"
+    set -l candidates "(__imag_dynamic_candidates $cmd $kind)"
+
+    for command in imag imag-$cmd
+        if test "$opt" = '@'
+            complete -c $command -n $condition -f -a $candidates
+        else
+            complete -c $command -n $condition -l (string replace -r -- '^--' '' $opt) -x -a $candidates
+        end
+    end
+end
+
+__imag_dynamic_complete diary      '*'              --diary       diaries
+__imag_dynamic_complete wiki       show             @             pages
+__imag_dynamic_complete wiki       delete           @             pages
+__imag_dynamic_complete wiki       idof             @             pages
+__imag_dynamic_complete habit      delete           @             habits
+__imag_dynamic_complete habit      show             @             habits
+__imag_dynamic_complete habit      done             @             habits
+__imag_dynamic_complete category   delete-category  @             categories
+__imag_dynamic_complete category   list-category    @             categories
+__imag_dynamic_complete category   set              @             categories
+__imag_dynamic_complete tag        add              @             tags
+__imag_dynamic_complete tag        remove           @             tags
+__imag_dynamic_complete tag        -                @             ids
+__imag_dynamic_complete bookmark   '*'              --collection  collections
+__imag_dynamic_complete bookmark   collection       --remove      collections
+__imag_dynamic_complete timetrack  '*'              --tags        tags
+__imag_dynamic_complete timetrack  start            @             tags
+__imag_dynamic_complete timetrack  stop             @             tags
+__imag_dynamic_complete timetrack  track            @             tags
+__imag_dynamic_complete notes      delete           @             notes
+__imag_dynamic_complete notes      edit             @             notes
+__imag_dynamic_complete view       '*'              @             ids
+__imag_dynamic_complete edit       '*'              @             ids
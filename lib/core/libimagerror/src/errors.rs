@@ -95,6 +95,10 @@ pub enum ErrorMsg {
     EntryHeaderFieldMissing(&'static str),
 
 
+    #[fail(display = "No configuration file found")]
+    ConfigMissing,
+
+
     #[fail(display = "Toml deserialization error")]
     TomlDeserError,
 
@@ -17,6 +17,12 @@
 // Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
 //
 
+use std::cell::Cell;
+use std::panic::AssertUnwindSafe;
+use std::panic::catch_unwind;
+use std::panic::resume_unwind;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct ExitCode(i32);
 
 impl From<i32> for ExitCode {
@@ -37,7 +43,67 @@ pub trait ExitUnwrap<T> {
 
 impl<T, E: Into<ExitCode>> ExitUnwrap<T> for Result<T, E> {
     fn unwrap_or_exit(self) -> T {
-        self.map_err(Into::into).unwrap_or_else(|e| ::std::process::exit(e.0))
+        self.map_err(Into::into).unwrap_or_else(|e| exit(e.0))
     }
 }
 
+thread_local! {
+    static CATCH_EXIT: Cell<bool> = Cell::new(false);
+}
+
+/// Exit the process with `code`
+///
+/// Inside of `catch_exit()`, the stack is unwound up to the `catch_exit()` call instead. This way
+/// a program which runs imag commands in-process, like `imag-shell`, keeps running if one of
+/// the commands fails.
+pub fn exit(code: i32) -> ! {
+    if CATCH_EXIT.with(Cell::get) {
+        resume_unwind(Box::new(ExitCode(code)))
+    }
+
+    ::std::process::exit(code)
+}
+
+/// Run `f`, turning calls of `exit()` into an `Err` with the exit code
+///
+/// Panics are not caught, they are passed on.
+pub fn catch_exit<T, F>(f: F) -> Result<T, ExitCode>
+    where F: FnOnce() -> T
+{
+    let catching = CATCH_EXIT.with(|c| c.replace(true));
+    let result   = catch_unwind(AssertUnwindSafe(f));
+    CATCH_EXIT.with(|c| c.set(catching));
+
+    result.or_else(|payload| match payload.downcast::<ExitCode>() {
+        Ok(code)     => Err(*code),
+        Err(payload) => resume_unwind(payload),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_catch_exit() {
+        assert_eq!(Ok(1), catch_exit(|| 1));
+        assert_eq!(Err(ExitCode::from(66)), catch_exit(|| -> i32 { exit(66) }));
+        assert_eq!(Err(ExitCode::from(0)), catch_exit(|| Err::<i32, _>(ExitCode::from(0)).unwrap_or_exit()));
+    }
+
+    #[test]
+    fn test_catch_exit_nested() {
+        let outer = catch_exit(|| {
+            assert_eq!(Err(ExitCode::from(2)), catch_exit(|| -> i32 { exit(2) }));
+            exit(3)
+        });
+
+        assert_eq!(Err(ExitCode::from(3)), outer);
+    }
+
+    #[test]
+    #[should_panic(expected = "not an exit")]
+    fn test_catch_exit_passes_panics_on() {
+        let _ = catch_exit(|| panic!("not an exit"));
+    }
+}
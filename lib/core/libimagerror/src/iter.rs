@@ -95,9 +95,14 @@ pub trait TraceIterator<T> : Iterator<Item = Result<T, Error>> + Sized {
     fn trace_unwrap(self) -> UnwrapWith<Self, fn(Error)> {
         #[inline]
         fn trace_error(err: Error) {
-            err.iter_chain().for_each(|cause| {
-                eprintln!("{}", cause);
-            });
+            use trace::{error_format, error_to_json, ErrorFormat};
+
+            match error_format() {
+                ErrorFormat::Text => err.iter_chain().for_each(|cause| {
+                    eprintln!("{}", cause);
+                }),
+                ErrorFormat::Json => eprintln!("{}", error_to_json(&err)),
+            }
         }
 
         self.unwrap_with(trace_error)
@@ -0,0 +1,170 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Stable kinds of errors
+//!
+//! Errors in imag are `failure::Error` chains. To let other programs tell the reason of an error
+//! apart, each error is classified as one of the kinds below, each of which has its own exit
+//! code. The exit codes are taken from `sysexits.h`.
+//!
+//! | Kind             | Exit code |
+//! | ---------------- | --------- |
+//! | `not-found`      | 66        |
+//! | `already-exists` | 73        |
+//! | `locked`         | 75        |
+//! | `invalid-header` | 65        |
+//! | `config-missing` | 78        |
+//! | `io`             | 74        |
+//! | `other`          | 1         |
+
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::fmt::Result as FmtResult;
+use std::io::Error as IoError;
+use std::io::ErrorKind as IoErrorKind;
+
+use failure::Context;
+use failure::Error;
+use failure::Fail;
+
+use errors::ErrorMsg;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// An entry or a file does not exist
+    NotFound,
+
+    /// An entry or a file exists already
+    AlreadyExists,
+
+    /// An entry is in use and cannot be accessed
+    Locked,
+
+    /// The header of an entry is malformed or misses data
+    InvalidHeader,
+
+    /// There is no configuration file
+    ConfigMissing,
+
+    /// Reading or writing a file failed
+    Io,
+
+    /// Everything else
+    Other,
+}
+
+impl ErrorKind {
+
+    /// Classify an error
+    ///
+    /// The cause chain of the error is searched for known errors. The innermost known error,
+    /// which is the most specific one, decides the kind.
+    pub fn of(e: &Error) -> ErrorKind {
+        e.iter_chain()
+            .filter_map(kind_of_fail)
+            .last()
+            .unwrap_or(ErrorKind::Other)
+    }
+
+    /// The exit code imag commands exit with on an error of this kind
+    pub fn exit_code(&self) -> i32 {
+        match *self {
+            ErrorKind::NotFound      => 66,
+            ErrorKind::AlreadyExists => 73,
+            ErrorKind::Locked        => 75,
+            ErrorKind::InvalidHeader => 65,
+            ErrorKind::ConfigMissing => 78,
+            ErrorKind::Io            => 74,
+            ErrorKind::Other         => 1,
+        }
+    }
+
+    /// The name of the kind, as used in the JSON error output
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            ErrorKind::NotFound      => "not-found",
+            ErrorKind::AlreadyExists => "already-exists",
+            ErrorKind::Locked        => "locked",
+            ErrorKind::InvalidHeader => "invalid-header",
+            ErrorKind::ConfigMissing => "config-missing",
+            ErrorKind::Io            => "io",
+            ErrorKind::Other         => "other",
+        }
+    }
+
+}
+
+impl Display for ErrorKind {
+    fn fmt(&self, fmt: &mut Formatter) -> FmtResult {
+        write!(fmt, "{}", self.as_str())
+    }
+}
+
+fn kind_of_fail(f: &Fail) -> Option<ErrorKind> {
+    if let Some(msg) = f.downcast_ref::<ErrorMsg>() {
+        kind_of_msg(msg)
+    } else if let Some(ctx) = f.downcast_ref::<Context<ErrorMsg>>() {
+        kind_of_msg(ctx.get_context())
+    } else if let Some(e) = f.downcast_ref::<IoError>() {
+        Some(kind_of_io_error(e))
+    } else {
+        None
+    }
+}
+
+fn kind_of_msg(msg: &ErrorMsg) -> Option<ErrorKind> {
+    match *msg {
+        ErrorMsg::EntryNotFound(_)
+            | ErrorMsg::FileNotFound => Some(ErrorKind::NotFound),
+
+        ErrorMsg::EntryAlreadyExists(_) => Some(ErrorKind::AlreadyExists),
+
+        ErrorMsg::LockError
+            | ErrorMsg::IdLocked => Some(ErrorKind::Locked),
+
+        ErrorMsg::EntryHeaderError
+            | ErrorMsg::EntryHeaderTypeError
+            | ErrorMsg::EntryHeaderTypeError2(_, _)
+            | ErrorMsg::EntryHeaderReadError
+            | ErrorMsg::EntryHeaderWriteError
+            | ErrorMsg::EntryHeaderFieldMissing(_) => Some(ErrorKind::InvalidHeader),
+
+        ErrorMsg::ConfigMissing => Some(ErrorKind::ConfigMissing),
+
+        ErrorMsg::IO
+            | ErrorMsg::FileError
+            | ErrorMsg::FileNotCopied
+            | ErrorMsg::FileNotCreated
+            | ErrorMsg::FileNotRemoved
+            | ErrorMsg::FileNotRenamed
+            | ErrorMsg::FileNotSeeked
+            | ErrorMsg::FileNotWritten
+            | ErrorMsg::DirNotCreated => Some(ErrorKind::Io),
+
+        _ => None,
+    }
+}
+
+fn kind_of_io_error(e: &IoError) -> ErrorKind {
+    match e.kind() {
+        IoErrorKind::NotFound      => ErrorKind::NotFound,
+        IoErrorKind::AlreadyExists => ErrorKind::AlreadyExists,
+        _                          => ErrorKind::Io,
+    }
+}
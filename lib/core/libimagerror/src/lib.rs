@@ -36,6 +36,9 @@
 )]
 
 #[macro_use] extern crate log;
+#[macro_use] extern crate serde_derive;
+extern crate serde;
+extern crate serde_json;
 extern crate ansi_term;
 extern crate failure;
 #[macro_use] extern crate failure_derive;
@@ -44,6 +47,7 @@ pub mod errors;
 pub mod exit;
 pub mod io;
 pub mod iter;
+pub mod kind;
 pub mod str;
 pub mod trace;
 
@@ -17,13 +17,42 @@
 // Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
 //
 
-use std::process::exit;
 use std::fmt::Display;
 use std::fmt::Formatter;
 use std::fmt::Result as FmtResult;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
 use failure::Error;
 use ansi_term::Colour::Red;
 
+use exit::exit;
+use kind::ErrorKind;
+
+static JSON_OUTPUT: AtomicBool = AtomicBool::new(false);
+
+/// The format errors are printed in by `trace_error()`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorFormat {
+    /// Human readable, with the cause chain and a backtrace
+    Text,
+
+    /// One line with a JSON object per error
+    Json,
+}
+
+/// Set the format in which errors are printed for the whole program
+pub fn set_error_format(format: ErrorFormat) {
+    JSON_OUTPUT.store(format == ErrorFormat::Json, Ordering::SeqCst);
+}
+
+pub fn error_format() -> ErrorFormat {
+    if JSON_OUTPUT.load(Ordering::SeqCst) {
+        ErrorFormat::Json
+    } else {
+        ErrorFormat::Text
+    }
+}
+
 struct ImagTrace<'a, T: 'a + ?Sized>(&'a T);
 
 impl<'a, T: 'a + ?Sized> ImagTrace<'a, T> {
@@ -54,8 +83,46 @@ impl<'a> Display for ImagTrace<'a, Error>
 }
 
 
+#[derive(Serialize)]
+struct JsonError<'a> {
+    error: JsonErrorBody<'a>,
+}
+
+#[derive(Serialize)]
+struct JsonErrorBody<'a> {
+    kind: &'a str,
+    exit_code: i32,
+    message: String,
+    causes: Vec<String>,
+}
+
+/// Render an error as JSON object
+///
+/// The object looks like this:
+///
+/// ```json
+/// {"error":{"kind":"not-found","exit_code":66,"message":"...","causes":["..."]}}
+/// ```
+pub fn error_to_json(e: &Error) -> String {
+    let kind = ErrorKind::of(e);
+    let json = JsonError {
+        error: JsonErrorBody {
+            kind:      kind.as_str(),
+            exit_code: kind.exit_code(),
+            message:   format!("{}", e),
+            causes:    e.iter_causes().map(|c| format!("{}", c)).collect(),
+        }
+    };
+
+    ::serde_json::to_string(&json)
+        .unwrap_or_else(|_| String::from("{\"error\":{\"kind\":\"other\"}}"))
+}
+
 pub fn trace_error(e: &Error) {
-    eprintln!("{}", ImagTrace::new(e));
+    match error_format() {
+        ErrorFormat::Text => eprintln!("{}", ImagTrace::new(e)),
+        ErrorFormat::Json => eprintln!("{}", error_to_json(e)),
+    }
 }
 
 pub fn trace_error_dbg(e: &Error) {
@@ -87,9 +154,69 @@ impl<U> MapErrTrace for Result<U, Error> {
     }
 
     /// Trace the error and exit or unwrap the Ok(_).
+    ///
+    /// If the error is of a known `ErrorKind`, the program exits with the exit code of that kind
+    /// instead of `code`.
     fn map_err_trace_exit_unwrap(self, code: i32) -> Self::Output {
-        self.map_err(|e| { trace_error(&e); exit(code) }).unwrap()
+        self.map_err(|e| {
+            trace_error(&e);
+            match ErrorKind::of(&e) {
+                ErrorKind::Other => exit(code),
+                kind             => exit(kind.exit_code()),
+            }
+        }).unwrap()
     }
 
 }
 
+
+#[cfg(test)]
+mod tests {
+    use failure::Error;
+    use failure::ResultExt;
+    use failure::err_msg;
+    use serde_json::Value;
+
+    use errors::ErrorMsg as EM;
+    use kind::ErrorKind;
+    use super::*;
+
+    fn not_found() -> Error {
+        Err::<(), _>(Error::from(EM::EntryNotFound(String::from("notes/foo"))))
+            .context(err_msg("GetCallError: notes/foo"))
+            .map_err(Error::from)
+            .unwrap_err()
+    }
+
+    #[test]
+    fn test_error_kind_of_chain() {
+        assert_eq!(ErrorKind::of(&not_found()), ErrorKind::NotFound);
+        assert_eq!(ErrorKind::of(&not_found()).exit_code(), 66);
+        assert_eq!(ErrorKind::of(&err_msg("something")), ErrorKind::Other);
+
+        let io = Error::from(::std::io::Error::new(::std::io::ErrorKind::AlreadyExists, "exists"));
+        assert_eq!(ErrorKind::of(&io), ErrorKind::AlreadyExists);
+    }
+
+    #[test]
+    fn test_error_to_json() {
+        let json  = error_to_json(&not_found());
+        assert!(!json.contains('\n'));
+
+        let value = ::serde_json::from_str::<Value>(&json).unwrap();
+        let error = &value["error"];
+        assert_eq!(error["kind"], Value::from("not-found"));
+        assert_eq!(error["exit_code"], Value::from(66));
+        assert_eq!(error["message"], Value::from("GetCallError: notes/foo"));
+        assert_eq!(error["causes"].as_array().map(|c| c.len()), Some(1));
+    }
+
+    #[test]
+    fn test_error_format() {
+        assert_eq!(error_format(), ErrorFormat::Text);
+        set_error_format(ErrorFormat::Json);
+        assert_eq!(error_format(), ErrorFormat::Json);
+        set_error_format(ErrorFormat::Text);
+        assert_eq!(error_format(), ErrorFormat::Text);
+    }
+}
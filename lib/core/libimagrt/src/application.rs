@@ -0,0 +1,103 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+use clap::App;
+use failure::Fallible as Result;
+
+use runtime::Runtime;
+use completion;
+
+/// An abstraction over an imag application
+///
+/// Each imag-* command implements this trait on a marker type in its library crate. The binary
+/// then only builds the `Runtime` and calls `ImagApplication::run()`, which makes it possible to
+/// run the application from within another process, for example the `imag-shell`, where one
+/// `Runtime` (and thus one `Store`) is used for several invocations.
+pub trait ImagApplication {
+    /// Run the application with the passed `Runtime`
+    fn run(rt: &Runtime) -> Result<()>;
+
+    /// Build the commandline interface of the application, on top of `app`
+    fn build_cli<'a>(app: App<'a, 'a>) -> App<'a, 'a>;
+
+    /// The name of the application, e.g. "imag-tag"
+    fn name() -> &'static str;
+
+    /// A short description of the application
+    fn description() -> &'static str;
+
+    /// The version of the application
+    fn version() -> &'static str;
+
+    /// Candidates for the dynamic shell completion of values of `kind`
+    ///
+    /// Called if the application is invoked with `--imag-complete <kind>`, see `completion`.
+    /// Implementations should fall back to `completion::default_completions()` for kinds they do
+    /// not know themselves.
+    fn completions(rt: &Runtime, kind: &str) -> Result<Vec<String>> {
+        completion::default_completions(rt, kind)
+    }
+}
+
+/// Generate a `main()` function for an application which implements `ImagApplication`
+///
+/// # Example
+///
+/// ```ignore
+/// #[macro_use] extern crate libimagrt;
+///
+/// simple_imag_application_binary!(libimagtagfrontend, ImagTag);
+/// ```
+#[macro_export]
+macro_rules! simple_imag_application_binary {
+    ($application_library:ident, $application_implementor:ident) => {
+        extern crate libimagerror;
+        extern crate $application_library;
+
+        fn main() {
+            use libimagerror::trace::MapErrTrace;
+            use libimagrt::application::ImagApplication;
+            use libimagrt::completion;
+            use libimagrt::setup::generate_runtime_setup;
+            use $application_library::$application_implementor;
+
+            let version = make_imag_version!();
+
+            if let Some(kind) = completion::completion_request() {
+                let rt = completion::generate_completion_runtime($application_implementor::name(),
+                                                                 &version,
+                                                                 $application_implementor::description())
+                    .map_err_trace_exit_unwrap(1);
+
+                let candidates = $application_implementor::completions(&rt, &kind)
+                    .map_err_trace_exit_unwrap(1);
+
+                completion::print_candidates(candidates).map_err_trace_exit_unwrap(1);
+                ::std::process::exit(0);
+            }
+
+            let rt = generate_runtime_setup($application_implementor::name(),
+                                            &version,
+                                            $application_implementor::description(),
+                                            $application_implementor::build_cli);
+
+            $application_implementor::run(&rt).map_err_trace_exit_unwrap(1);
+        }
+    };
+}
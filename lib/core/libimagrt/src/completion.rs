@@ -0,0 +1,162 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Dynamic shell completion
+//!
+//! The completion scripts which are generated from the commandline definitions only know the
+//! static parts of a commandline, like subcommands and flags. Values which live in the store, like
+//! IDs, tags or the names of diaries, are fetched at completion time by calling the command with
+//! `--imag-complete <kind>`. The command then prints the candidates, one per line, and exits.
+//!
+//! Which kinds of values a command can complete is up to the command, see
+//! `ImagApplication::completions()`. Every command knows the kind "ids".
+
+use std::io::Write;
+
+use clap::Arg;
+use failure::Fallible as Result;
+
+use runtime::Runtime;
+
+/// The flag which makes an imag command print completion candidates
+pub const COMPLETE_FLAG: &'static str = "--imag-complete";
+
+/// The kind of completion every command provides: all IDs in the store
+pub const KIND_IDS: &'static str = "ids";
+
+/// Get the requested kind of completion, if the command was called with `--imag-complete <kind>`
+///
+/// The flag is only honored as first argument, so a value which happens to be spelled like the
+/// flag does not trigger the completion.
+pub fn completion_request() -> Option<String> {
+    completion_request_from(::std::env::args())
+}
+
+fn completion_request_from<I: IntoIterator<Item = String>>(args: I) -> Option<String> {
+    let mut args = args.into_iter().skip(1);
+    match args.next() {
+        Some(ref flag) if flag == COMPLETE_FLAG => args.next(),
+        _ => None,
+    }
+}
+
+/// Build the `Runtime` for answering a completion request
+///
+/// Only the arguments from `Runtime::get_default_cli_builder()` are known to this `Runtime`, so
+/// that the configuration and the store can be set on the commandline as usual.
+pub fn generate_completion_runtime<'a>(name: &'a str, version: &'a str, about: &'a str)
+    -> Result<Runtime<'a>>
+{
+    let app = Runtime::get_default_cli_builder(name, version, about)
+        .arg(Arg::with_name("imag-complete")
+             .long("imag-complete")
+             .takes_value(true)
+             .required(true)
+             .hidden(true));
+
+    Runtime::new(app)
+}
+
+/// The completions every command provides
+///
+/// Returns all IDs in the store for the kind "ids" and no candidates for unknown kinds.
+pub fn default_completions(rt: &Runtime, kind: &str) -> Result<Vec<String>> {
+    if kind == KIND_IDS {
+        rt.store()
+            .entries()?
+            .map(|id| id.map(|id| id.local_display_string()))
+            .collect()
+    } else {
+        debug!("Unknown completion kind: {}", kind);
+        Ok(vec![])
+    }
+}
+
+/// Print completion candidates to stdout, one per line
+pub fn print_candidates<I>(candidates: I) -> Result<()>
+    where I: IntoIterator<Item = String>
+{
+    let out         = ::std::io::stdout();
+    let mut outlock = out.lock();
+
+    for candidate in candidates {
+        writeln!(outlock, "{}", candidate)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    use libimagstore::store::Store;
+    use libimagstore::file_abstraction::InMemoryFileAbstraction;
+
+    use runtime::Runtime;
+    use super::*;
+
+    fn args(v: &[&str]) -> Vec<String> {
+        v.iter().map(|s| String::from(*s)).collect()
+    }
+
+    fn test_runtime<'a>() -> Runtime<'a> {
+        let backend = Arc::new(InMemoryFileAbstraction::default());
+        let store   = Store::new_with_backend(PathBuf::from("/"), &None, backend).unwrap();
+        let matches = Runtime::get_default_cli_builder("imag-test", "0.10.0", "test")
+            .get_matches_from(vec!["imag-test"]);
+
+        Runtime::new_with_store(matches, None, PathBuf::from("/"), store)
+    }
+
+    #[test]
+    fn test_completion_request() {
+        let req = completion_request_from(args(&["imag-tag", "--imag-complete", "tags"]));
+        assert_eq!(req, Some(String::from("tags")));
+
+        let req = completion_request_from(args(&["imag-tag", "--imag-complete", "ids", "--store", "/tmp"]));
+        assert_eq!(req, Some(String::from("ids")));
+    }
+
+    #[test]
+    fn test_no_completion_request() {
+        assert_eq!(completion_request_from(args(&["imag-tag"])), None);
+        assert_eq!(completion_request_from(args(&["imag-tag", "--imag-complete"])), None);
+        assert_eq!(completion_request_from(args(&["imag-tag", "add", "foo"])), None);
+
+        // A value spelled like the flag is not a completion request
+        let req = completion_request_from(args(&["imag-tag", "add", "--imag-complete", "tags"]));
+        assert_eq!(req, None);
+    }
+
+    #[test]
+    fn test_default_completions() {
+        let rt = test_runtime();
+        let _  = rt.store().create(PathBuf::from("test/a")).unwrap();
+        let _  = rt.store().create(PathBuf::from("test/b")).unwrap();
+
+        let mut ids = default_completions(&rt, KIND_IDS).unwrap();
+        ids.sort();
+        assert_eq!(ids, vec!["test/a", "test/b"]);
+
+        assert!(default_completions(&rt, "unknown").unwrap().is_empty());
+    }
+
+}
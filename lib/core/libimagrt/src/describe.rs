@@ -0,0 +1,424 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! The imag command description protocol
+//!
+//! Every imag command can be asked to describe itself by calling it with `--imag-describe` as the
+//! only argument. The command then prints a JSON document to stdout and exits, without touching
+//! the configuration or the store. The `imag` binary uses this protocol to build its helptext, to
+//! validate aliases and to detect version mismatches between itself and the installed commands.
+//!
+//! Commands which are not built with libimagrt can implement the protocol by printing a document
+//! which deserializes into `CommandDescription`.
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::UNIX_EPOCH;
+
+use clap::App;
+use clap::ArgSettings;
+use failure::Fallible as Result;
+use failure::Error;
+use serde_json;
+
+use runtime::Runtime;
+
+/// The version of the description protocol implemented by this library
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// The flag which makes an imag command print its description
+pub const DESCRIBE_FLAG: &'static str = "--imag-describe";
+
+/// Description of an imag command, as printed with `--imag-describe`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CommandDescription {
+    pub protocol: u32,
+    pub name: String,
+    pub version: String,
+
+    #[serde(default)]
+    pub description: Option<String>,
+
+    #[serde(default)]
+    pub args: Vec<ArgDescription>,
+
+    #[serde(default)]
+    pub subcommands: Vec<SubcommandDescription>,
+}
+
+/// Description of a subcommand of an imag command
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SubcommandDescription {
+    pub name: String,
+
+    #[serde(default)]
+    pub description: Option<String>,
+
+    #[serde(default)]
+    pub args: Vec<ArgDescription>,
+
+    #[serde(default)]
+    pub subcommands: Vec<SubcommandDescription>,
+}
+
+/// Description of a single argument of an imag command or subcommand
+///
+/// Positional arguments have an `index` and neither `short` nor `long`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ArgDescription {
+    pub name: String,
+
+    #[serde(default)]
+    pub short: Option<char>,
+
+    #[serde(default)]
+    pub long: Option<String>,
+
+    #[serde(default)]
+    pub index: Option<u64>,
+
+    #[serde(default)]
+    pub help: Option<String>,
+
+    #[serde(default)]
+    pub takes_value: bool,
+
+    #[serde(default)]
+    pub required: bool,
+
+    #[serde(default)]
+    pub multiple: bool,
+
+    #[serde(default)]
+    pub possible_values: Vec<String>,
+}
+
+impl CommandDescription {
+
+    /// Build the description of a command from its CLI definition
+    ///
+    /// The arguments every imag command gets from `Runtime::get_default_cli_builder()` are not
+    /// part of the description, as they are the same for all commands.
+    pub fn from_app<'a, 'b>(app: &App<'a, 'b>, version: &str, about: Option<&str>) -> Self {
+        CommandDescription {
+            protocol:    PROTOCOL_VERSION,
+            name:        String::from(app.get_name()),
+            version:     String::from(version),
+            description: about.or(app.p.meta.about).map(String::from),
+            args:        describe_args(app, true),
+            subcommands: describe_subcommands(app),
+        }
+    }
+
+    /// Parse a description as printed by an imag command
+    pub fn from_json(s: &str) -> Result<Self> {
+        serde_json::from_str(s).map_err(From::from)
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string(self).map_err(From::from)
+    }
+
+}
+
+/// A cache for the descriptions of commands, stored as JSON file
+///
+/// Describing a command means calling it, so the descriptions are cached by the path of the
+/// executable. A cached description is only used as long as the modification time and the size of
+/// the executable did not change, so updated commands are described again.
+/// Commands which do not implement the protocol are cached as well, without a description.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct DescriptionCache {
+    entries: BTreeMap<PathBuf, CachedDescription>,
+
+    #[serde(skip)]
+    changed: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+struct CachedDescription {
+    modified: u64,
+    size: u64,
+    description: Option<CommandDescription>,
+}
+
+impl DescriptionCache {
+
+    /// The default location of the cache, `imag/descriptions.json` in the XDG cache directory
+    #[cfg(unix)]
+    pub fn default_path() -> Option<PathBuf> {
+        ::xdg_basedir::get_cache_home()
+            .map(|dir| dir.join("imag").join("descriptions.json"))
+            .ok()
+    }
+
+    #[cfg(not(unix))]
+    pub fn default_path() -> Option<PathBuf> {
+        None
+    }
+
+    /// Load the cache from `path`
+    ///
+    /// A missing or unreadable cache file results in an empty cache, as the cache can always be
+    /// rebuilt.
+    pub fn load<P: AsRef<Path>>(path: P) -> DescriptionCache {
+        File::open(path.as_ref())
+            .map_err(Error::from)
+            .and_then(|file| serde_json::from_reader(file).map_err(Error::from))
+            .unwrap_or_else(|e| {
+                debug!("Not using description cache '{}': {:?}", path.as_ref().display(), e);
+                DescriptionCache::default()
+            })
+    }
+
+    /// Write the cache to `path`, if it changed since it was loaded
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        if !self.changed {
+            return Ok(())
+        }
+
+        let path = path.as_ref();
+        if let Some(dir) = path.parent() {
+            let _ = ::std::fs::create_dir_all(dir)?;
+        }
+
+        let file = OpenOptions::new().write(true).create(true).truncate(true).open(path)?;
+        serde_json::to_writer(file, self).map_err(Error::from)
+    }
+
+    /// Get the cached description of the command at `executable`
+    ///
+    /// Returns `None` if nothing is cached for the executable or if the executable changed since,
+    /// and `Some(None)` if the command is known to not describe itself.
+    pub fn get<P: AsRef<Path>>(&self, executable: P) -> Option<Option<&CommandDescription>> {
+        let (modified, size) = file_stamp(executable.as_ref())?;
+
+        self.entries
+            .get(executable.as_ref())
+            .filter(|cached| cached.modified == modified && cached.size == size)
+            .map(|cached| cached.description.as_ref())
+    }
+
+    /// Cache the description of the command at `executable`
+    ///
+    /// Nothing is cached if the executable cannot be inspected.
+    pub fn insert<P: AsRef<Path>>(&mut self, executable: P, description: Option<CommandDescription>) {
+        if let Some((modified, size)) = file_stamp(executable.as_ref()) {
+            let cached = CachedDescription { modified, size, description };
+            let _      = self.entries.insert(executable.as_ref().to_path_buf(), cached);
+            self.changed = true;
+        }
+    }
+
+}
+
+/// The modification time, in seconds since the epoch, and the size of a file
+fn file_stamp(path: &Path) -> Option<(u64, u64)> {
+    let meta     = path.metadata().ok()?;
+    let modified = meta.modified().ok()?.duration_since(UNIX_EPOCH).ok()?.as_secs();
+    Some((modified, meta.len()))
+}
+
+/// Check whether the command was called with `--imag-describe` as only argument
+pub fn is_describe_request() -> bool {
+    let mut args = ::std::env::args().skip(1);
+    match (args.next(), args.next()) {
+        (Some(ref arg), None) => arg == DESCRIBE_FLAG,
+        _                     => false,
+    }
+}
+
+/// Print the description of a command to stdout
+pub fn print_description<'a, 'b>(app: &App<'a, 'b>, version: &str, about: Option<&str>) -> Result<()> {
+    let json        = CommandDescription::from_app(app, version, about).to_json()?;
+    let out         = ::std::io::stdout();
+    let mut outlock = out.lock();
+    writeln!(outlock, "{}", json).map_err(From::from)
+}
+
+// clap 2 does not offer a public API to inspect the arguments of an `App`, so the (hidden but
+// public) parser fields are used here, the same way clap uses them for generating shell
+// completions. The tests below describe an `App` with all kinds of arguments, so a clap update
+// which changes these fields is caught there.
+
+fn describe_subcommands<'a, 'b>(app: &App<'a, 'b>) -> Vec<SubcommandDescription> {
+    app.p
+        .subcommands
+        .iter()
+        .map(|sc| SubcommandDescription {
+            name:        String::from(sc.get_name()),
+            description: sc.p.meta.about.map(String::from),
+            args:        describe_args(sc, false),
+            subcommands: describe_subcommands(sc),
+        })
+        .collect()
+}
+
+fn describe_args<'a, 'b>(app: &App<'a, 'b>, skip_runtime_args: bool) -> Vec<ArgDescription> {
+    let mut runtime_args = Runtime::arg_names();
+    runtime_args.push(Runtime::arg_logdest_name());
+    let is_described = |name: &str, hidden: bool| {
+        !hidden && !(skip_runtime_args && runtime_args.contains(&name))
+    };
+
+    let flags = app.p
+        .flags
+        .iter()
+        .filter(|f| is_described(f.b.name, f.b.is_set(ArgSettings::Hidden)))
+        .map(|f| ArgDescription {
+            name:            String::from(f.b.name),
+            short:           f.s.short,
+            long:            f.s.long.map(String::from),
+            index:           None,
+            help:            f.b.help.map(String::from),
+            takes_value:     false,
+            required:        f.b.is_set(ArgSettings::Required),
+            multiple:        f.b.is_set(ArgSettings::Multiple),
+            possible_values: vec![],
+        });
+
+    let opts = app.p
+        .opts
+        .iter()
+        .filter(|o| is_described(o.b.name, o.b.is_set(ArgSettings::Hidden)))
+        .map(|o| ArgDescription {
+            name:            String::from(o.b.name),
+            short:           o.s.short,
+            long:            o.s.long.map(String::from),
+            index:           None,
+            help:            o.b.help.map(String::from),
+            takes_value:     true,
+            required:        o.b.is_set(ArgSettings::Required),
+            multiple:        o.b.is_set(ArgSettings::Multiple),
+            possible_values: possible_values(o.v.possible_vals.as_ref()),
+        });
+
+    let positionals = app.p
+        .positionals
+        .values()
+        .filter(|p| is_described(p.b.name, p.b.is_set(ArgSettings::Hidden)))
+        .map(|p| ArgDescription {
+            name:            String::from(p.b.name),
+            short:           None,
+            long:            None,
+            index:           Some(p.index),
+            help:            p.b.help.map(String::from),
+            takes_value:     true,
+            required:        p.b.is_set(ArgSettings::Required),
+            multiple:        p.b.is_set(ArgSettings::Multiple),
+            possible_values: possible_values(p.v.possible_vals.as_ref()),
+        });
+
+    flags.chain(opts).chain(positionals).collect()
+}
+
+fn possible_values(pv: Option<&Vec<&str>>) -> Vec<String> {
+    pv.map(|vals| vals.iter().map(|s| String::from(*s)).collect())
+        .unwrap_or_else(Vec::new)
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::{App, Arg, SubCommand};
+
+    use runtime::Runtime;
+    use super::*;
+
+    fn app() -> App<'static, 'static> {
+        Runtime::get_default_cli_builder("imag-test", "0.10.0", "a test command")
+            .arg(Arg::with_name("loud")
+                 .long("loud")
+                 .short("l")
+                 .help("Be loud"))
+            .arg(Arg::with_name("output")
+                 .long("output")
+                 .takes_value(true)
+                 .possible_values(&["json", "text"])
+                 .help("The output format"))
+            .arg(Arg::with_name("secret")
+                 .long("secret")
+                 .hidden(true))
+            .subcommand(SubCommand::with_name("add")
+                        .about("Add things")
+                        .arg(Arg::with_name("ids")
+                             .index(1)
+                             .required(true)
+                             .multiple(true)
+                             .help("The ids")))
+    }
+
+    #[test]
+    fn test_describe_app() {
+        let desc = CommandDescription::from_app(&app(), "0.10.0", None);
+
+        assert_eq!(desc.protocol, PROTOCOL_VERSION);
+        assert_eq!(desc.name, "imag-test");
+        assert_eq!(desc.version, "0.10.0");
+        assert_eq!(desc.description, Some(String::from("a test command")));
+
+        // The runtime arguments and hidden arguments are not described
+        let names = desc.args.iter().map(|a| a.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(names, vec!["loud", "output"]);
+
+        let loud = &desc.args[0];
+        assert_eq!(loud.short, Some('l'));
+        assert_eq!(loud.long, Some(String::from("loud")));
+        assert!(!loud.takes_value);
+
+        let output = &desc.args[1];
+        assert!(output.takes_value);
+        assert_eq!(output.possible_values, vec!["json", "text"]);
+
+        assert_eq!(desc.subcommands.len(), 1);
+        let add = &desc.subcommands[0];
+        assert_eq!(add.name, "add");
+        assert_eq!(add.description, Some(String::from("Add things")));
+        assert_eq!(add.args.len(), 1);
+        assert_eq!(add.args[0].index, Some(1));
+        assert!(add.args[0].required);
+        assert!(add.args[0].multiple);
+    }
+
+    #[test]
+    fn test_json_roundtrip() {
+        let desc = CommandDescription::from_app(&app(), "0.10.0", Some("overridden"));
+        let json = desc.to_json().unwrap();
+
+        assert_eq!(CommandDescription::from_json(&json).unwrap(), desc);
+        assert_eq!(desc.description, Some(String::from("overridden")));
+    }
+
+    #[test]
+    fn test_minimal_json() {
+        let desc = CommandDescription::from_json(r#"{"protocol":1,"name":"foo","version":"1.0"}"#)
+            .unwrap();
+
+        assert_eq!(desc.name, "foo");
+        assert_eq!(desc.description, None);
+        assert!(desc.args.is_empty());
+        assert!(desc.subcommands.is_empty());
+
+        assert!(CommandDescription::from_json(r#"{"name":"foo"}"#).is_err());
+    }
+
+}
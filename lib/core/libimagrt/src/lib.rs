@@ -49,17 +49,27 @@ extern crate clap;
 extern crate toml;
 extern crate toml_query;
 extern crate atty;
+extern crate chrono;
+extern crate serde;
+#[macro_use] extern crate serde_derive;
+extern crate serde_json;
+#[cfg(test)] extern crate tempdir;
 
 extern crate libimagstore;
 extern crate libimagutil;
 extern crate libimagerror;
 extern crate libimaginteraction;
 
+pub mod application;
+pub mod completion;
 pub mod configuration;
+pub mod describe;
 pub mod logger;
 pub mod io;
+pub mod profile;
 pub mod runtime;
 pub mod setup;
 pub mod spec;
+pub mod stream;
 pub mod version;
 
@@ -23,6 +23,11 @@ use std::collections::BTreeMap;
 use std::sync::Arc;
 use std::sync::Mutex;
 use std::ops::Deref;
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::path::PathBuf;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
 
 use runtime::Runtime;
 
@@ -36,22 +41,175 @@ use toml::Value;
 use toml_query::read::TomlValueReadExt;
 use toml_query::read::TomlValueReadTypeExt;
 use handlebars::Handlebars;
+use chrono::Local;
+use serde_json;
 
 use libimagerror::errors::ErrorMsg as EM;
 
 type ModuleName = String;
 
+/// The format of the lines written to a log destination
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LogFormat {
+    /// Rendered with the templates from `imag.logging.format`
+    Text,
+
+    /// One JSON object per line
+    Json,
+}
+
 enum LogDestination {
-    Stderr,
-    File(Arc<Mutex<::std::fs::File>>),
+    Stderr(LogFormat),
+    File(LogFormat, Arc<Mutex<LogFile>>),
 }
 
 impl Default for LogDestination {
     fn default() -> LogDestination {
-        LogDestination::Stderr
+        LogDestination::Stderr(LogFormat::Text)
     }
 }
 
+/// A log record, as written to destinations with the "json" format
+#[derive(Serialize)]
+struct JsonLogRecord<'a> {
+    timestamp: String,
+    level: String,
+    module: &'a str,
+    file: &'a str,
+    line: u32,
+    target: &'a str,
+    message: String,
+}
+
+/// A log file, which is rotated if it exceeds a size or if a time period passed
+///
+/// On rotation, `<path>` is renamed to `<path>.1`, `<path>.1` to `<path>.2` and so on. Only
+/// `retain` rotated files are kept.
+///
+/// Rotation is not synchronized between processes: each process only knows the size it wrote
+/// itself, and two processes which rotate at the same time may lose a rotated file.
+struct LogFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+
+    max_size: Option<u64>,
+    period: Option<u64>,
+    current_period: u64,
+    retain: usize,
+}
+
+impl LogFile {
+
+    fn open(path: PathBuf, max_size: Option<u64>, period: Option<u64>, retain: usize)
+        -> Result<LogFile>
+    {
+        let file = open_for_append(&path)?;
+        let meta = file.metadata()?;
+
+        // A file which was last written in an earlier period is rotated on the first write
+        let current_period = period
+            .map(|p| {
+                meta.modified()
+                    .ok()
+                    .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                    .map(|d| d.as_secs())
+                    .unwrap_or_else(now_secs) / p
+            })
+            .unwrap_or(0);
+
+        Ok(LogFile {
+            path,
+            file,
+            size: meta.len(),
+            max_size,
+            period,
+            current_period,
+            retain,
+        })
+    }
+
+    fn write_line(&mut self, line: &str) -> ::std::io::Result<()> {
+        let len = line.len() as u64 + 1;
+
+        if self.needs_rotation(len) {
+            self.rotate()?;
+        }
+
+        writeln!(self.file, "{}", line)?;
+        self.size += len;
+        Ok(())
+    }
+
+    fn needs_rotation(&self, additional: u64) -> bool {
+        let too_big = self.max_size
+            .map(|max| self.size > 0 && self.size + additional > max)
+            .unwrap_or(false);
+
+        let too_old = self.period
+            .map(|p| now_secs() / p != self.current_period)
+            .unwrap_or(false);
+
+        too_big || too_old
+    }
+
+    fn rotate(&mut self) -> ::std::io::Result<()> {
+        use std::fs::{remove_file, rename};
+
+        if self.retain == 0 {
+            remove_file(&self.path)?;
+        } else {
+            let oldest = self.rotated_path(self.retain);
+            if oldest.exists() {
+                remove_file(oldest)?;
+            }
+
+            for n in (1..self.retain).rev() {
+                let from = self.rotated_path(n);
+                if from.exists() {
+                    rename(from, self.rotated_path(n + 1))?;
+                }
+            }
+
+            rename(&self.path, self.rotated_path(1))?;
+        }
+
+        self.file = open_for_append(&self.path)
+            .map_err(|_| ::std::io::Error::new(::std::io::ErrorKind::Other, "Cannot reopen log file"))?;
+        self.size = 0;
+
+        if let Some(p) = self.period {
+            self.current_period = now_secs() / p;
+        }
+
+        Ok(())
+    }
+
+    fn rotated_path(&self, n: usize) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".{}", n));
+        PathBuf::from(path)
+    }
+
+}
+
+fn open_for_append(path: &PathBuf) -> Result<File> {
+    OpenOptions::new()
+        .append(true)
+        .create(true)
+        .open(path)
+        .map_err(Error::from)
+        .context(EM::IO)
+        .map_err(Error::from)
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
 struct ModuleSettings {
     enabled:        bool,
     level:          Option<Level>,
@@ -137,18 +295,36 @@ impl Log for ImagLogger {
             .render(&format!("{}", record.level()), &data)
             .unwrap_or_else(|e| format!("Failed rendering logging data: {:?}\n", e));
 
+        let jsontext = || {
+            let json_record = JsonLogRecord {
+                timestamp: Local::now().to_rfc3339(),
+                level:     format!("{}", record.level()),
+                module:    record.module_path().unwrap_or("<modulepath unknown>"),
+                file:      record.file().unwrap_or("<file unknown>"),
+                line:      record.line().unwrap_or(0),
+                target:    record.target(),
+                message:   format!("{}", record.args()),
+            };
+
+            serde_json::to_string(&json_record)
+                .unwrap_or_else(|e| format!("Failed serializing logging data: {:?}", e))
+        };
+
+        let render = |format: &LogFormat| match *format {
+            LogFormat::Text => logtext.clone(),
+            LogFormat::Json => jsontext(),
+        };
+
         let log_to_destination = |d: &LogDestination| match d {
-            &LogDestination::Stderr => {
-                let _ = write!(stderr(), "{}\n", logtext);
+            &LogDestination::Stderr(ref format) => {
+                let _ = write!(stderr(), "{}\n", render(format));
             },
-            &LogDestination::File(ref arc_mutex_logdest) => {
+            &LogDestination::File(ref format, ref arc_mutex_logdest) => {
                 // if there is an error in the lock, we cannot do anything. So we ignore it here.
                 let _ = arc_mutex_logdest
                     .deref()
                     .lock()
-                    .map(|mut logdest| {
-                        write!(logdest, "{}\n", logtext)
-                    });
+                    .map(|mut logdest| logdest.write_line(&render(format)));
             }
         };
 
@@ -241,36 +417,96 @@ fn aggregate_global_loglevel(matches: &ArgMatches, config: Option<&Value>) -> Re
 }
 
 fn translate_destination(raw: &str) -> Result<LogDestination> {
-    use std::fs::OpenOptions;
-
     match raw {
-        "-" => Ok(LogDestination::Stderr),
-        other => {
-            OpenOptions::new()
-                .append(true)
-                .create(true)
-                .open(other)
-                .map(Mutex::new)
-                .map(Arc::new)
-                .map(LogDestination::File)
-                .map_err(Error::from)
-                .context(EM::IO)
-                .map_err(Error::from)
-        }
+        "-"   => Ok(LogDestination::Stderr(LogFormat::Text)),
+        other => LogFile::open(PathBuf::from(other), None, None, 0)
+            .map(Mutex::new)
+            .map(Arc::new)
+            .map(|file| LogDestination::File(LogFormat::Text, file)),
     }
 }
 
+/// Translate a destination table from the configuration
+///
+/// ```toml
+/// { path = "/tmp/imag.log", format = "json", max_size = 1048576, rotate = "daily", retain = 5 }
+/// ```
+///
+/// Only `path` is required. A path of "-" means stderr, which is never rotated.
+fn translate_destination_table(table: &Value) -> Result<LogDestination> {
+    let path = table
+        .read_string("path")
+        .map_err(Error::from)
+        .context(EM::TomlQueryError)?
+        .ok_or_else(|| err_msg("Missing 'path' in logging destination"))?;
+
+    let format = match table
+        .read_string("format")
+        .map_err(Error::from)
+        .context(EM::TomlQueryError)?
+    {
+        None                       => LogFormat::Text,
+        Some(ref s) if s == "text" => LogFormat::Text,
+        Some(ref s) if s == "json" => LogFormat::Json,
+        Some(other)                => return Err(format_err!("Invalid logging format: {}", other)),
+    };
+
+    if path == "-" {
+        return Ok(LogDestination::Stderr(format));
+    }
+
+    let max_size = table
+        .read_int("max_size")
+        .map_err(Error::from)
+        .context(EM::TomlQueryError)?
+        .map(|i| if i > 0 {
+            Ok(i as u64)
+        } else {
+            Err(format_err!("Invalid 'max_size' in logging destination, must be positive: {}", i))
+        })
+        .map_or(Ok(None), |r| r.map(Some))?;
+
+    let period = match table
+        .read_string("rotate")
+        .map_err(Error::from)
+        .context(EM::TomlQueryError)?
+    {
+        None                         => None,
+        Some(ref s) if s == "hourly" => Some(60 * 60),
+        Some(ref s) if s == "daily"  => Some(60 * 60 * 24),
+        Some(ref s) if s == "weekly" => Some(60 * 60 * 24 * 7),
+        Some(other)                  => return Err(format_err!("Invalid log rotation: {}", other)),
+    };
+
+    let retain = table
+        .read_int("retain")
+        .map_err(Error::from)
+        .context(EM::TomlQueryError)?
+        .map(|i| if i >= 0 {
+            Ok(i as usize)
+        } else {
+            Err(format_err!("Invalid 'retain' in logging destination, must not be negative: {}", i))
+        })
+        .unwrap_or(Ok(5))?;
+
+    LogFile::open(PathBuf::from(path), max_size, period, retain)
+        .map(Mutex::new)
+        .map(Arc::new)
+        .map(|file| LogDestination::File(format, file))
+}
 
 fn translate_destinations(raw: &Vec<Value>) -> Result<Vec<LogDestination>> {
     raw.iter()
         .fold(Ok(vec![]), |acc, val| {
             acc.and_then(|mut v| {
-                let dest = val.as_str()
-                    .ok_or_else(|| {
-                        let msg = "Type error at 'imag.logging.modules.<mod>.destinations', expected Array<String>";
-                        Error::from(err_msg(msg))
-                    })
-                    .and_then(translate_destination)?;
+                let dest = match *val {
+                    Value::String(ref s) => translate_destination(s)?,
+                    Value::Table(_)      => translate_destination_table(val)?,
+                    _ => {
+                        let msg = "Type error at 'imag.logging.modules.<mod>.destinations', expected Array<String or Table>";
+                        return Err(Error::from(err_msg(msg)))
+                    },
+                };
                 v.push(dest);
                 Ok(v)
             })
@@ -446,3 +682,122 @@ fn aggregate_module_settings(_matches: &ArgMatches, config: Option<&Value>)
     }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use std::fs::read_to_string;
+
+    use tempdir::TempDir;
+    use toml::Value;
+
+    use super::*;
+
+    fn destination(table: &str) -> Result<LogDestination> {
+        let table = ::toml::de::from_str::<Value>(table).unwrap();
+        translate_destination_table(&table)
+    }
+
+    #[test]
+    fn test_rotate_by_size() {
+        let dir  = TempDir::new("imag-logger-size").unwrap();
+        let path = dir.path().join("imag.log");
+        let mut file = LogFile::open(path.clone(), Some(10), None, 2).unwrap();
+
+        for line in &["first", "second", "third", "fourth"] {
+            file.write_line(line).unwrap();
+        }
+
+        assert_eq!(read_to_string(&path).unwrap(), "fourth\n");
+        assert_eq!(read_to_string(file.rotated_path(1)).unwrap(), "third\n");
+        assert_eq!(read_to_string(file.rotated_path(2)).unwrap(), "second\n");
+        assert!(!file.rotated_path(3).exists());
+    }
+
+    #[test]
+    fn test_rotate_keeps_small_lines_together() {
+        let dir  = TempDir::new("imag-logger-small").unwrap();
+        let path = dir.path().join("imag.log");
+        let mut file = LogFile::open(path.clone(), Some(100), None, 2).unwrap();
+
+        file.write_line("a").unwrap();
+        file.write_line("b").unwrap();
+
+        assert_eq!(read_to_string(&path).unwrap(), "a\nb\n");
+        assert!(!file.rotated_path(1).exists());
+    }
+
+    #[test]
+    fn test_rotate_without_retain() {
+        let dir  = TempDir::new("imag-logger-noretain").unwrap();
+        let path = dir.path().join("imag.log");
+        let mut file = LogFile::open(path.clone(), Some(8), None, 0).unwrap();
+
+        file.write_line("first").unwrap();
+        file.write_line("second").unwrap();
+
+        assert_eq!(read_to_string(&path).unwrap(), "second\n");
+        assert!(!file.rotated_path(1).exists());
+    }
+
+    #[test]
+    fn test_existing_file_size_counts() {
+        let dir  = TempDir::new("imag-logger-existing").unwrap();
+        let path = dir.path().join("imag.log");
+
+        LogFile::open(path.clone(), None, None, 1).unwrap().write_line("0123456789").unwrap();
+
+        let mut file = LogFile::open(path.clone(), Some(15), None, 1).unwrap();
+        file.write_line("abcdef").unwrap();
+
+        assert_eq!(read_to_string(&path).unwrap(), "abcdef\n");
+        assert_eq!(read_to_string(file.rotated_path(1)).unwrap(), "0123456789\n");
+    }
+
+    #[test]
+    fn test_destination_table() {
+        let dir  = TempDir::new("imag-logger-table").unwrap();
+        let path = dir.path().join("imag.log");
+        let table = format!(r#"path = "{}"
+                               format = "json"
+                               max_size = 1024
+                               rotate = "daily"
+                               retain = 3"#, path.display());
+
+        match destination(&table).unwrap() {
+            LogDestination::File(format, file) => {
+                let file = file.lock().unwrap();
+                assert_eq!(format, LogFormat::Json);
+                assert_eq!(file.max_size, Some(1024));
+                assert_eq!(file.period, Some(60 * 60 * 24));
+                assert_eq!(file.retain, 3);
+            },
+            LogDestination::Stderr(_) => panic!("Expected file destination"),
+        }
+
+        match destination(r#"path = "-""#).unwrap() {
+            LogDestination::Stderr(format) => assert_eq!(format, LogFormat::Text),
+            LogDestination::File(_, _)     => panic!("Expected stderr destination"),
+        }
+    }
+
+    #[test]
+    fn test_destination_table_invalid() {
+        let dir  = TempDir::new("imag-logger-invalid").unwrap();
+        let path = dir.path().join("imag.log");
+        let path = path.display();
+
+        assert!(destination(r#"format = "json""#).is_err());
+        assert!(destination(&format!(r#"path = "{}"
+                                        format = "xml""#, path)).is_err());
+        assert!(destination(&format!(r#"path = "{}"
+                                        rotate = "monthly""#, path)).is_err());
+        assert!(destination(&format!(r#"path = "{}"
+                                        max_size = -1"#, path)).is_err());
+        assert!(destination(&format!(r#"path = "{}"
+                                        max_size = 0"#, path)).is_err());
+        assert!(destination(&format!(r#"path = "{}"
+                                        retain = -1"#, path)).is_err());
+        assert!(destination(&format!(r#"path = "{}"
+                                        retain = 0"#, path)).is_ok());
+    }
+}
@@ -0,0 +1,265 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Named store profiles
+//!
+//! The configuration file can define several stores, which can be selected with `--profile
+//! <name>` (or the `IMAG_PROFILE` environment variable):
+//!
+//! ```toml
+//! [stores.work]
+//! path    = "/home/user/work/imag-store"
+//! backend = "fs"
+//!
+//! [stores.work.config.imag.logging]
+//! level = "info"
+//! ```
+//!
+//! `path` is the path of the store, relative pathes are relative to the runtimepath. `backend` is
+//! either "fs" (default) or "inmemory". The `config` table is merged into the configuration when
+//! the profile is used.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use toml::Value;
+use toml_query::read::TomlValueReadExt;
+use toml_query::read::TomlValueReadTypeExt;
+use failure::Fallible as Result;
+use failure::ResultExt;
+use failure::Error;
+
+use libimagerror::errors::ErrorMsg as EM;
+use libimagstore::store::Store;
+use libimagstore::file_abstraction::InMemoryFileAbstraction;
+
+/// The backend of the store of a profile
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StoreBackend {
+    FileSystem,
+    InMemory,
+}
+
+/// A store profile, as defined in the `stores` section of the configuration
+#[derive(Debug, Clone)]
+pub struct StoreProfile {
+    name: String,
+    path: PathBuf,
+    backend: StoreBackend,
+    config: Option<Value>,
+}
+
+impl StoreProfile {
+
+    /// Read the profile `name` from the configuration
+    ///
+    /// Fails if there is no such profile.
+    pub fn from_config(config: &Value, name: &str, rtp: &PathBuf) -> Result<StoreProfile> {
+        let profile = config
+            .read(&format!("stores.{}", name))
+            .map_err(Error::from)
+            .context(EM::TomlQueryError)?
+            .ok_or_else(|| format_err!("No such store profile: {}", name))?;
+
+        let path = profile
+            .read_string("path")
+            .map_err(Error::from)
+            .context(EM::TomlQueryError)?
+            .map(|p| rtp.join(p)) // join() keeps absolute pathes as they are
+            .ok_or_else(|| format_err!("Store profile '{}' has no 'path'", name))?;
+
+        let backend = match profile
+            .read_string("backend")
+            .map_err(Error::from)
+            .context(EM::TomlQueryError)?
+        {
+            None                             => StoreBackend::FileSystem,
+            Some(ref s) if s == "fs"         => StoreBackend::FileSystem,
+            Some(ref s) if s == "inmemory"   => StoreBackend::InMemory,
+            Some(other) => return Err(format_err!("Unknown store backend in profile '{}': {}", name, other)),
+        };
+
+        let config = match profile.read("config").map_err(Error::from).context(EM::TomlQueryError)? {
+            None                        => None,
+            Some(t @ &Value::Table(_))  => Some(t.clone()),
+            Some(_) => return Err(format_err!("Type error at 'stores.{}.config', expected 'Table'", name)),
+        };
+
+        Ok(StoreProfile { name: String::from(name), path, backend, config })
+    }
+
+    /// Get the names of all profiles in the configuration
+    pub fn names(config: &Value) -> Result<Vec<String>> {
+        match config.read("stores").map_err(Error::from).context(EM::TomlQueryError)? {
+            None                        => Ok(vec![]),
+            Some(&Value::Table(ref t))  => Ok(t.keys().cloned().collect()),
+            Some(_)                     => Err(format_err!("Type error at 'stores', expected 'Table'")),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn path(&self) -> &PathBuf {
+        &self.path
+    }
+
+    pub fn backend(&self) -> StoreBackend {
+        self.backend
+    }
+
+    /// Merge the configuration of the profile into `config`
+    ///
+    /// Tables are merged recursively, all other values of the profile replace the ones in
+    /// `config`.
+    pub fn apply_config(&self, config: &mut Value) {
+        if let Some(ref overrides) = self.config {
+            merge(config, overrides)
+        }
+    }
+
+    /// Open the store of the profile
+    ///
+    /// `config` is the configuration the store is opened with, the configuration of the profile
+    /// is expected to be applied already.
+    pub fn open_store(&self, config: &Option<Value>) -> Result<Store> {
+        match self.backend {
+            StoreBackend::FileSystem => Store::new(self.path.clone(), config),
+            StoreBackend::InMemory   => {
+                let backend = Arc::new(InMemoryFileAbstraction::default());
+                Store::new_with_backend(self.path.clone(), config, backend)
+            },
+        }
+    }
+
+}
+
+fn merge(base: &mut Value, overrides: &Value) {
+    match (base, overrides) {
+        (&mut Value::Table(ref mut base), &Value::Table(ref overrides)) => {
+            for (key, value) in overrides {
+                let merged = match base.get_mut(key) {
+                    Some(existing) => {
+                        merge(existing, value);
+                        true
+                    },
+                    None => false,
+                };
+
+                if !merged {
+                    let _ = base.insert(key.clone(), value.clone());
+                }
+            }
+        },
+        (base, overrides) => *base = overrides.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use toml::Value;
+    use toml_query::read::TomlValueReadTypeExt;
+
+    use super::*;
+
+    fn config() -> Value {
+        ::toml::de::from_str(r#"
+            [imag.logging]
+            level = "debug"
+            destinations = [ "-" ]
+
+            [stores.work]
+            path = "work-store"
+
+            [stores.work.config.imag.logging]
+            level = "info"
+
+            [stores.scratch]
+            path    = "/tmp"
+            backend = "inmemory"
+
+            [stores.broken]
+            backend = "fs"
+
+            [stores.unknown]
+            path    = "/tmp/unknown"
+            backend = "sql"
+        "#).unwrap()
+    }
+
+    #[test]
+    fn test_from_config() {
+        let rtp     = PathBuf::from("/home/user/.imag");
+        let work    = StoreProfile::from_config(&config(), "work", &rtp).unwrap();
+        let scratch = StoreProfile::from_config(&config(), "scratch", &rtp).unwrap();
+
+        assert_eq!(work.name(), "work");
+        assert_eq!(work.path(), &PathBuf::from("/home/user/.imag/work-store"));
+        assert_eq!(work.backend(), StoreBackend::FileSystem);
+
+        assert_eq!(scratch.path(), &PathBuf::from("/tmp"));
+        assert_eq!(scratch.backend(), StoreBackend::InMemory);
+    }
+
+    #[test]
+    fn test_from_config_invalid() {
+        let rtp = PathBuf::from("/home/user/.imag");
+
+        assert!(StoreProfile::from_config(&config(), "missing", &rtp).is_err());
+        assert!(StoreProfile::from_config(&config(), "broken", &rtp).is_err());
+        assert!(StoreProfile::from_config(&config(), "unknown", &rtp).is_err());
+    }
+
+    #[test]
+    fn test_names() {
+        let mut names = StoreProfile::names(&config()).unwrap();
+        names.sort();
+        assert_eq!(names, vec!["broken", "scratch", "unknown", "work"]);
+
+        let empty = ::toml::de::from_str::<Value>("[imag]").unwrap();
+        assert!(StoreProfile::names(&empty).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_apply_config() {
+        let rtp        = PathBuf::from("/home/user/.imag");
+        let mut config = config();
+        let work       = StoreProfile::from_config(&config, "work", &rtp).unwrap();
+
+        work.apply_config(&mut config);
+
+        // Tables are merged, values are replaced
+        assert_eq!(config.read_string("imag.logging.level").unwrap(), Some(String::from("info")));
+        assert!(config.read("imag.logging.destinations").unwrap().is_some());
+    }
+
+    #[test]
+    fn test_open_inmemory_store() {
+        let rtp     = PathBuf::from("/home/user/.imag");
+        let scratch = StoreProfile::from_config(&config(), "scratch", &rtp).unwrap();
+        let store   = scratch.open_store(&None).unwrap();
+
+        assert_eq!(store.path(), &PathBuf::from("/tmp"));
+        let _ = store.create(PathBuf::from("test")).unwrap();
+        assert!(store.get(PathBuf::from("test")).unwrap().is_some());
+    }
+}
@@ -25,11 +25,13 @@ use std::io::Stdin;
 use std::sync::Arc;
 use std::io::StdoutLock;
 use std::borrow::Borrow;
+use std::cell::RefCell;
 
 pub use clap::App;
 use clap::AppSettings;
 use toml::Value;
 use toml_query::read::TomlValueReadExt;
+use toml_query::read::TomlValueReadTypeExt;
 
 use clap::{Arg, ArgMatches};
 use failure::ResultExt;
@@ -40,10 +42,16 @@ use failure::err_msg;
 use configuration::{fetch_config, override_config, InternalConfiguration};
 use logger::ImagLogger;
 use io::OutputProxy;
+use stream::StreamEntry;
+use stream::StreamFormat;
+use profile::StoreProfile;
+use profile::StoreBackend;
 
 use libimagerror::errors::ErrorMsg as EM;
 use libimagerror::trace::*;
 use libimagstore::store::Store;
+use libimagstore::store::Entry;
+use libimagstore::oplog::OperationLog;
 use libimagstore::storeid::StoreId;
 use libimagstore::file_abstraction::InMemoryFileAbstraction;
 use libimagutil::debug_result::DebugResult;
@@ -59,9 +67,13 @@ pub struct Runtime<'a> {
     configuration: Option<Value>,
     cli_matches: ArgMatches<'a>,
     store: Store,
+    profile: Option<StoreProfile>,
 
     has_output_pipe: bool,
     has_input_pipe: bool,
+    stream_format: StreamFormat,
+
+    touched_ids: Option<RefCell<Vec<StoreId>>>,
 }
 
 impl<'a> Runtime<'a> {
@@ -77,6 +89,7 @@ impl<'a> Runtime<'a> {
         use libimagerror::trace::trace_error;
 
         let matches = cli_app.clone().matches();
+        set_error_format_from_matches(&matches);
 
         let rtp = get_rtp_match(&matches);
 
@@ -87,7 +100,7 @@ impl<'a> Runtime<'a> {
 
         let config = match fetch_config(&configpath)? {
             None => {
-                return Err(err_msg("No configuration file found"))
+                return Err(EM::ConfigMissing)
                     .context(err_msg("Maybe try to use 'imag-init' to initialize imag?"))
                     .context(err_msg("Continuing without configuration file"))
                     .context(err_msg("Cannot instantiate runtime"))
@@ -113,35 +126,67 @@ impl<'a> Runtime<'a> {
         where C: Clone + CliSpec<'a> + InternalConfiguration
     {
         let matches = cli_app.clone().matches();
+        set_error_format_from_matches(&matches);
         Runtime::_new(cli_app, matches, config)
     }
 
-    fn _new<C>(cli_app: C, matches: ArgMatches<'a>, config: Option<Value>) -> Result<Runtime<'a>>
+    fn _new<C>(cli_app: C, matches: ArgMatches<'a>, mut config: Option<Value>) -> Result<Runtime<'a>>
     where C: Clone + CliSpec<'a> + InternalConfiguration
     {
+        let rtp = get_rtp_match(&matches);
+
+        // The profile is applied before anything else, as it may alter the configuration
+        let profile = match get_profile_name(&matches) {
+            None       => None,
+            Some(name) => {
+                let profile = config
+                    .as_ref()
+                    .ok_or_else(|| format_err!("Cannot use store profile '{}' without configuration", name))
+                    .and_then(|cfg| StoreProfile::from_config(cfg, &name, &rtp))?;
+
+                if let Some(ref mut cfg) = config {
+                    profile.apply_config(cfg);
+                }
+
+                Some(profile)
+            },
+        };
+
         if cli_app.enable_logging() {
             Runtime::init_logger(&matches, config.as_ref())
         }
 
-        let rtp = get_rtp_match(&matches);
-
         let storepath = matches.value_of(Runtime::arg_storepath_name())
-                                .map_or_else(|| {
+                                .map(PathBuf::from)
+                                .or_else(|| profile.as_ref().map(|p| p.path().clone()))
+                                .unwrap_or_else(|| {
                                     let mut spath = rtp.clone();
                                     spath.push("store");
                                     spath
-                                }, PathBuf::from);
+                                });
 
         debug!("RTP path    = {:?}", rtp);
+        debug!("Profile     = {:?}", profile);
         debug!("Store path  = {:?}", storepath);
         debug!("CLI         = {:?}", matches);
 
-        let store_result = if cli_app.use_inmemory_fs() {
+        let use_inmemory_fs = cli_app.use_inmemory_fs() || profile
+            .as_ref()
+            .map(|p| p.backend() == StoreBackend::InMemory)
+            .unwrap_or(false);
+
+        let store_result = if use_inmemory_fs {
             Store::new_with_backend(storepath,
                                     &config,
                                     Arc::new(InMemoryFileAbstraction::default()))
         } else {
-            Store::new(storepath, &config)
+            Store::new(storepath, &config).and_then(|mut store| {
+                let command = env::args().collect();
+                let spath   = store.path().clone();
+                let log     = Runtime::build_operation_log(&rtp, config.as_ref(), spath, command)?;
+                store.set_operation_log(log);
+                Ok(store)
+            })
         };
 
         let has_output_pipe = !atty::is(atty::Stream::Stdout);
@@ -150,19 +195,77 @@ impl<'a> Runtime<'a> {
         debug!("has output pipe = {}", has_output_pipe);
         debug!("has input pipe  = {}", has_input_pipe);
 
+        let stream_format = StreamFormat::from_matches(&matches);
+        debug!("stream format   = {:?}", stream_format);
+
         store_result.map(|store| Runtime {
             cli_matches: matches,
             configuration: config,
             rtp: rtp,
             store: store,
+            profile,
 
             has_output_pipe,
             has_input_pipe,
+            stream_format,
+
+            touched_ids: None,
         })
         .context(err_msg("Cannot instantiate runtime"))
         .map_err(Error::from)
     }
 
+    /// Builds a Runtime object from already parsed commandline arguments and an existing `Store`
+    ///
+    /// This is used for running several imag applications in one process (for example in the
+    /// `imag-shell`), so that the store is only opened once and its cache can be re-used.
+    ///
+    /// The logger is _not_ initialized by this function, as it is expected to be initialized
+    /// already.
+    ///
+    /// IDs reported via `Runtime::report_touched()` are not printed by a Runtime object built with
+    /// this function, but captured. They can be fetched with `Runtime::take_touched_ids()`.
+    ///
+    /// Stdin is never treated as a source of IDs, as it belongs to the caller.
+    ///
+    /// The store profile is not read from the commandline, as the store is given.
+    pub fn new_with_store(matches: ArgMatches<'a>,
+                          config: Option<Value>,
+                          rtp: PathBuf,
+                          store: Store)
+        -> Runtime<'a>
+    {
+        set_error_format_from_matches(&matches);
+
+        let has_output_pipe = !atty::is(atty::Stream::Stdout);
+        let has_input_pipe  = false;
+        let stream_format   = StreamFormat::from_matches(&matches);
+
+        debug!("RTP path    = {:?}", rtp);
+        debug!("CLI         = {:?}", matches);
+
+        Runtime {
+            cli_matches: matches,
+            configuration: config,
+            rtp: rtp,
+            store: store,
+            profile: None,
+
+            has_output_pipe,
+            has_input_pipe,
+            stream_format,
+
+            touched_ids: Some(RefCell::new(vec![])),
+        }
+    }
+
+    /// Destroy the Runtime object and get the `Store` object
+    ///
+    /// Counterpart of `Runtime::new_with_store()`.
+    pub fn into_store(self) -> Store {
+        self.store
+    }
+
     ///
     /// Get a commandline-interface builder object from `clap`
     ///
@@ -234,6 +337,13 @@ impl<'a> Runtime<'a> {
                 .validator(::libimagutil::cli_validators::is_directory)
                 .takes_value(true))
 
+            .arg(Arg::with_name(Runtime::arg_profile_name())
+                .long("profile")
+                .help("Use the store profile NAME from the 'stores' section of the configuration. Defaults to $IMAG_PROFILE")
+                .required(false)
+                .takes_value(true)
+                .value_name("NAME"))
+
             .arg(Arg::with_name(Runtime::arg_editor_name())
                 .long("editor")
                 .help("Set editor")
@@ -247,6 +357,35 @@ impl<'a> Runtime<'a> {
                 .takes_value(true)
                 .value_name("LOGDESTS"))
 
+            .arg(Arg::with_name(Runtime::arg_stream_json_name())
+                .long("stream-json")
+                .help("Report touched entries as JSON objects, one per line, instead of bare IDs")
+                .required(false)
+                .takes_value(false))
+
+            .arg(Arg::with_name(Runtime::arg_stream_header_name())
+                .long("stream-header")
+                .help("Include the header of touched entries in the JSON stream. Implies --stream-json")
+                .required(false)
+                .takes_value(false))
+
+            .arg(Arg::with_name(Runtime::arg_stream_fields_name())
+                .long("stream-field")
+                .help("Include the header field at PATH of touched entries in the JSON stream. Implies --stream-json")
+                .required(false)
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .value_name("PATH"))
+
+            .arg(Arg::with_name(Runtime::arg_error_format_name())
+                .long("error-format")
+                .help("Print errors as text or as one JSON object per error")
+                .required(false)
+                .takes_value(true)
+                .possible_values(&["text", "json"])
+                .value_name("FORMAT"))
+
     }
 
     /// Get the argument names of the Runtime which are available
@@ -259,7 +398,12 @@ impl<'a> Runtime<'a> {
             Runtime::arg_config_override_name(),
             Runtime::arg_runtimepath_name(),
             Runtime::arg_storepath_name(),
+            Runtime::arg_profile_name(),
             Runtime::arg_editor_name(),
+            Runtime::arg_stream_json_name(),
+            Runtime::arg_stream_header_name(),
+            Runtime::arg_stream_fields_name(),
+            Runtime::arg_error_format_name(),
         ]
     }
 
@@ -298,6 +442,11 @@ impl<'a> Runtime<'a> {
         "storepath"
     }
 
+    /// Get the store profile argument name for the Runtime
+    pub fn arg_profile_name() -> &'static str {
+        "profile"
+    }
+
     /// Get the editor argument name for the Runtime
     pub fn arg_editor_name() -> &'static str {
         "editor"
@@ -326,6 +475,26 @@ impl<'a> Runtime<'a> {
         self
     }
 
+    /// Get the argument name for reporting touched entries as JSON stream
+    pub fn arg_stream_json_name() -> &'static str {
+        "stream-json"
+    }
+
+    /// Get the argument name for including the header in the JSON stream
+    pub fn arg_stream_header_name() -> &'static str {
+        "stream-header"
+    }
+
+    /// Get the argument name for including header fields in the JSON stream
+    pub fn arg_stream_fields_name() -> &'static str {
+        "stream-fields"
+    }
+
+    /// Get the argument name for the format of error output
+    pub fn arg_error_format_name() -> &'static str {
+        "error-format"
+    }
+
     /// Get the argument name for the logging destination
     pub fn arg_logdest_name() -> &'static str {
         "logging-destinations"
@@ -384,6 +553,81 @@ impl<'a> Runtime<'a> {
         &self.rtp
     }
 
+    /// Get the directory the operation logs of the store are written to
+    pub fn operation_log_dir(rtp: &PathBuf) -> PathBuf {
+        rtp.join("undo")
+    }
+
+    /// Build the log the store at `storepath` records the mutations of an invocation with the
+    /// commandline `command` in
+    ///
+    /// Returns `None` unless recording is enabled with `imag.undo.enabled = true`. When the first
+    /// operation is recorded, logs of older invocations are removed, so that only the last
+    /// `imag.undo.retain` (default: 50) invocations are kept.
+    pub fn build_operation_log(rtp: &PathBuf,
+                               config: Option<&Value>,
+                               storepath: PathBuf,
+                               command: Vec<String>)
+        -> Result<Option<OperationLog>>
+    {
+        let (enabled, retain) = match config {
+            None      => (false, 50),
+            Some(cfg) => {
+                let enabled = cfg.read_bool("imag.undo.enabled")
+                    .map_err(Error::from)
+                    .context(EM::TomlQueryError)?
+                    .unwrap_or(false);
+
+                let retain = cfg.read_int("imag.undo.retain")
+                    .map_err(Error::from)
+                    .context(EM::TomlQueryError)?
+                    .unwrap_or(50);
+
+                (enabled, retain)
+            },
+        };
+
+        if !enabled {
+            debug!("Operation log disabled");
+            return Ok(None)
+        }
+
+        let dir = Runtime::operation_log_dir(rtp);
+        Ok(Some(OperationLog::new(dir, storepath, command).with_retain(::std::cmp::max(retain, 1) as usize)))
+    }
+
+    /// Get the store profile which is used, if any
+    pub fn profile(&self) -> Option<&StoreProfile> {
+        self.profile.as_ref()
+    }
+
+    /// Open the store of the profile `name`
+    ///
+    /// The store is opened with the configuration of the runtime, with the configuration of the
+    /// profile applied. Its mutations are recorded in the operation log as well.
+    pub fn profile_store(&self, name: &str) -> Result<Store> {
+        let mut config = self.configuration.clone();
+        let profile    = config
+            .as_ref()
+            .ok_or_else(|| format_err!("Cannot use store profile '{}' without configuration", name))
+            .and_then(|cfg| StoreProfile::from_config(cfg, name, &self.rtp))?;
+
+        if let Some(ref mut cfg) = config {
+            profile.apply_config(cfg);
+        }
+
+        let mut store = profile.open_store(&config)?;
+
+        if profile.backend() == StoreBackend::FileSystem {
+            let command = env::args().collect();
+            let spath   = store.path().clone();
+            let log     = Runtime::build_operation_log(&self.rtp, config.as_ref(), spath, command)?;
+            store.set_operation_log(log);
+        }
+
+        Ok(store)
+    }
+
     /// Get the commandline interface matches
     pub fn cli(&self) -> &ArgMatches {
         &self.cli_matches
@@ -394,6 +638,22 @@ impl<'a> Runtime<'a> {
     }
 
     pub fn ids<T: IdPathProvider>(&self) -> Result<Vec<StoreId>> {
+        if self.has_input_pipe {
+            self.stream_entries::<T>()?
+                .iter()
+                .map(StreamEntry::store_id)
+                .collect()
+        } else {
+            Ok(T::get_ids(self.cli()))
+        }
+    }
+
+    /// Get the entries to operate on, including the data which was streamed with them
+    ///
+    /// Like `Runtime::ids()`, but if the entries are piped in as JSON stream (see `stream`), the
+    /// streamed header data is kept, so it can be used without reading the entries from the store.
+    /// IDs which come from the commandline never carry data.
+    pub fn stream_entries<T: IdPathProvider>(&self) -> Result<Vec<StreamEntry>> {
         use std::io::Read;
 
         if self.has_input_pipe {
@@ -407,15 +667,20 @@ impl<'a> Runtime<'a> {
                 .and_then(|_| {
                     trace!("Got IDs = {}", buf);
                     buf.lines()
-                        .map(PathBuf::from)
-                        .map(|id| StoreId::new_baseless(id).map_err(Error::from))
+                        .filter(|line| !line.trim().is_empty())
+                        .map(StreamEntry::parse_line)
                         .collect()
                 })
         } else {
-            Ok(T::get_ids(self.cli()))
+            Ok(T::get_ids(self.cli()).iter().map(StreamEntry::from_id).collect())
         }
     }
 
+    /// The format touched entries are reported in
+    pub fn stream_format(&self) -> &StreamFormat {
+        &self.stream_format
+    }
+
     /// Get the configuration object
     pub fn config(&self) -> Option<&Value> {
         self.configuration.as_ref()
@@ -508,6 +773,7 @@ impl<'a> Runtime<'a> {
     /// # Details
     ///
     /// The `IMAG_RTP` variable is set for the child process. It is set to the current runtime path.
+    /// If a store profile is used, the `IMAG_PROFILE` variable is set to its name.
     ///
     /// Stdin, stdout and stderr are inherited to the child process.
     ///
@@ -533,13 +799,18 @@ impl<'a> Runtime<'a> {
             .map(|sx| sx.map(String::from).collect())
             .unwrap_or_else(|| vec![]);
 
-        Command::new(&command)
-            .stdin(::std::process::Stdio::inherit())
+        let mut cmd = Command::new(&command);
+        cmd.stdin(::std::process::Stdio::inherit())
             .stdout(::std::process::Stdio::inherit())
             .stderr(::std::process::Stdio::inherit())
             .args(&subcommand_args[..])
-            .env("IMAG_RTP", rtp_str)
-            .spawn()
+            .env("IMAG_RTP", rtp_str);
+
+        if let Some(profile) = self.profile() {
+            cmd.env("IMAG_PROFILE", profile.name());
+        }
+
+        cmd.spawn()
             .and_then(|mut c| c.wait())
             .map_err(|e| match e.kind() {
                 ErrorKind::NotFound => {
@@ -552,7 +823,7 @@ impl<'a> Runtime<'a> {
                         return e;
                     }
 
-                    ::std::process::exit(1)
+                    ::libimagerror::exit::exit(1)
                 },
                 _ => e,
             })
@@ -581,13 +852,69 @@ impl<'a> Runtime<'a> {
         Ok(())
     }
 
+    /// Report an entry as touched
+    ///
+    /// Like `Runtime::report_touched()`, but the entry does not have to be fetched from the store
+    /// if header data is requested for the JSON stream. Use this if the entry is at hand anyways,
+    /// as fetching an entry which is borrowed already does not work.
+    pub fn report_touched_entry(&self, entry: &Entry) -> Result<()> {
+        use std::io::Write;
+
+        if self.touched_ids.is_some() || !self.output_is_pipe() || !self.stream_format.needs_entry() {
+            return self.report_touched(entry.get_location());
+        }
+
+        let line     = StreamEntry::from_entry(entry, &self.stream_format)?.to_json_line()?;
+        let out      = ::std::io::stdout();
+        let mut lock = out.lock();
+
+        trace!("Reporting: {} to {:?}", line, lock);
+        writeln!(lock, "{}", line).map_err(Error::from)
+    }
+
+    /// Get the IDs which were reported as touched since the last call of this function
+    ///
+    /// Returns an empty list if the Runtime object does not capture the touched IDs, which is only
+    /// done for Runtime objects built with `Runtime::new_with_store()`.
+    pub fn take_touched_ids(&self) -> Vec<StoreId> {
+        self.touched_ids
+            .as_ref()
+            .map(|ids| ids.replace(vec![]))
+            .unwrap_or_else(|| vec![])
+    }
+
     #[inline]
     fn report_touched_id(&self, id: &StoreId, output: &mut StdoutLock) -> Result<()> {
         use std::io::Write;
 
-        if self.output_is_pipe() {
-            trace!("Reporting: {} to {:?}", id, output);
-            writeln!(output, "{}", id)?;
+        if let Some(ref touched) = self.touched_ids {
+            trace!("Capturing: {}", id);
+            touched.borrow_mut().push(id.clone());
+        } else if self.output_is_pipe() {
+            match self.stream_format {
+                StreamFormat::Ids => {
+                    trace!("Reporting: {} to {:?}", id, output);
+                    writeln!(output, "{}", id)?;
+                },
+                ref format => {
+                    let se = if format.needs_entry() {
+                        match self.store.get(id.clone()) {
+                            Ok(Some(entry)) => StreamEntry::from_entry(&entry, format)?,
+                            Ok(None)        => StreamEntry::from_id(id),
+                            Err(e)          => {
+                                debug!("Cannot get {} for streaming its header: {:?}", id, e);
+                                StreamEntry::from_id(id)
+                            },
+                        }
+                    } else {
+                        StreamEntry::from_id(id)
+                    };
+
+                    let line = se.to_json_line()?;
+                    trace!("Reporting: {} to {:?}", line, output);
+                    writeln!(output, "{}", line)?;
+                },
+            }
         }
 
         Ok(())
@@ -649,6 +976,22 @@ pub fn get_rtp_match<'a>(matches: &ArgMatches<'a>) -> PathBuf {
         }, PathBuf::from)
 }
 
+/// Get the name of the store profile from the commandline or the `IMAG_PROFILE` environment
+/// variable
+fn get_profile_name(matches: &ArgMatches) -> Option<String> {
+    matches
+        .value_of(Runtime::arg_profile_name())
+        .map(String::from)
+        .or_else(|| env::var("IMAG_PROFILE").ok())
+}
+
+fn set_error_format_from_matches(matches: &ArgMatches) {
+    match matches.value_of(Runtime::arg_error_format_name()) {
+        Some("json") => set_error_format(ErrorFormat::Json),
+        _            => set_error_format(ErrorFormat::Text),
+    }
+}
+
 fn get_override_specs(matches: &ArgMatches) -> Vec<String> {
     matches
         .values_of("config-override")
@@ -28,19 +28,46 @@ pub type About         = &'static str;
 /// Helper to generate the Runtime object
 ///
 /// exit()s the program if the runtime couldn't be build, prints error with println!() before
-/// exiting
+/// exiting. The exit code is the one of the `ErrorKind` of the error.
+///
+/// If the program was called with `--imag-describe`, the description of the command is printed
+/// and the program exits (see `describe`).
 pub fn generate_runtime_setup<'a, B>(name: Name, version: Version<'a>, about: About, builder: B)
     -> Runtime<'a>
     where B: FnOnce(App<'a, 'a>) -> App<'a, 'a>
 {
     use std::process::exit;
+    use libimagerror::trace::trace_error;
     use libimagerror::trace::trace_error_dbg;
+    use libimagerror::trace::error_format;
+    use libimagerror::trace::ErrorFormat;
+    use libimagerror::kind::ErrorKind;
+    use describe;
+
+    let app = builder(Runtime::get_default_cli_builder(name, version, about));
+
+    if describe::is_describe_request() {
+        let code = match describe::print_description(&app, version, Some(about)) {
+            Ok(()) => 0,
+            Err(e) => {
+                eprintln!("Could not describe command");
+                trace_error_dbg(&e);
+                1
+            },
+        };
+        exit(code);
+    }
 
-    Runtime::new(builder(Runtime::get_default_cli_builder(name, version, about)))
+    Runtime::new(app)
         .unwrap_or_else(|e| {
-            eprintln!("Could not set up Runtime");
-            eprintln!("{:?}", e);
-            trace_error_dbg(&e);
-            exit(1);
+            match error_format() {
+                ErrorFormat::Json => trace_error(&e),
+                ErrorFormat::Text => {
+                    eprintln!("Could not set up Runtime");
+                    eprintln!("{:?}", e);
+                    trace_error_dbg(&e);
+                },
+            }
+            exit(ErrorKind::of(&e).exit_code());
         })
 }
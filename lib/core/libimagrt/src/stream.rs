@@ -0,0 +1,284 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! The entry stream between piped imag commands
+//!
+//! By default, imag commands pass bare IDs, one per line, to the next command in a pipe. With
+//! `--stream-json`, each line is a JSON object instead, which contains the ID and, if requested
+//! with `--stream-header` or `--stream-field <path>`, the header or some fields of the header of
+//! the entry:
+//!
+//! ```json
+//! {"id":"notes/foo","fields":{"tag.values":["a","b"]}}
+//! ```
+//!
+//! Consumers detect the format of each line automatically, so both formats can be mixed and tools
+//! like `jq` can be used in the middle of a pipe.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use clap::ArgMatches;
+use failure::Fallible as Result;
+use failure::Error;
+use serde_json;
+use toml::Value;
+use toml_query::read::TomlValueReadExt;
+use toml_query::insert::TomlValueInsertExt;
+
+use libimagstore::store::Entry;
+use libimagstore::storeid::StoreId;
+
+use runtime::Runtime;
+
+/// The format touched entries are reported in
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StreamFormat {
+    /// Bare IDs, one per line
+    Ids,
+
+    /// JSON objects, one per line, including the header if `header` is true and the header fields
+    /// at the `fields` paths
+    Json {
+        header: bool,
+        fields: Vec<String>,
+    },
+}
+
+impl StreamFormat {
+
+    /// Get the stream format from the commandline arguments of the `Runtime`
+    pub fn from_matches(matches: &ArgMatches) -> StreamFormat {
+        let header = matches.is_present(Runtime::arg_stream_header_name());
+        let fields = matches
+            .values_of(Runtime::arg_stream_fields_name())
+            .map(|vals| vals.map(String::from).collect::<Vec<String>>())
+            .unwrap_or_else(Vec::new);
+
+        if matches.is_present(Runtime::arg_stream_json_name()) || header || !fields.is_empty() {
+            StreamFormat::Json { header, fields }
+        } else {
+            StreamFormat::Ids
+        }
+    }
+
+    /// Whether the entry is needed to produce a line in this format
+    pub fn needs_entry(&self) -> bool {
+        match *self {
+            StreamFormat::Ids                             => false,
+            StreamFormat::Json { ref header, ref fields } => *header || !fields.is_empty(),
+        }
+    }
+}
+
+/// One line of the entry stream
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct StreamEntry {
+    pub id: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub header: Option<Value>,
+
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub fields: BTreeMap<String, Value>,
+}
+
+impl StreamEntry {
+
+    /// A stream entry which carries nothing but the ID
+    pub fn from_id(id: &StoreId) -> StreamEntry {
+        StreamEntry {
+            id:     id.local_display_string(),
+            header: None,
+            fields: BTreeMap::new(),
+        }
+    }
+
+    /// Build the stream entry for `entry` in the format `format`
+    pub fn from_entry(entry: &Entry, format: &StreamFormat) -> Result<StreamEntry> {
+        let mut se = StreamEntry::from_id(entry.get_location());
+
+        if let StreamFormat::Json { ref header, ref fields } = *format {
+            if *header {
+                se.header = Some(entry.get_header().clone());
+            }
+
+            for field in fields {
+                if let Some(value) = entry.get_header().read(field)? {
+                    se.fields.insert(field.clone(), value.clone());
+                }
+            }
+        }
+
+        Ok(se)
+    }
+
+    /// Parse one line of the stream, which is either a JSON object or a bare ID
+    pub fn parse_line(line: &str) -> Result<StreamEntry> {
+        let line = line.trim();
+        if line.starts_with('{') {
+            serde_json::from_str(line).map_err(Error::from)
+        } else {
+            Ok(StreamEntry {
+                id:     String::from(line),
+                header: None,
+                fields: BTreeMap::new(),
+            })
+        }
+    }
+
+    /// Serialize the stream entry to one line of JSON (without newline)
+    pub fn to_json_line(&self) -> Result<String> {
+        serde_json::to_string(self).map_err(Error::from)
+    }
+
+    pub fn store_id(&self) -> Result<StoreId> {
+        StoreId::new_baseless(PathBuf::from(&self.id))
+    }
+
+    /// Whether the stream entry carries any data besides the ID
+    pub fn has_data(&self) -> bool {
+        self.header.is_some() || !self.fields.is_empty()
+    }
+
+    /// Get the value at `path` from the streamed fields or the streamed header
+    pub fn read(&self, path: &str) -> Result<Option<&Value>> {
+        if let Some(value) = self.fields.get(path) {
+            return Ok(Some(value));
+        }
+
+        match self.header {
+            Some(ref header) => header.read(path).map_err(Error::from),
+            None             => Ok(None),
+        }
+    }
+
+    /// Get the streamed data as header
+    ///
+    /// If the full header was streamed, this is the header. Otherwise, the header is built from
+    /// the streamed fields, so it only contains these. Returns None if no data was streamed.
+    pub fn header_value(&self) -> Result<Option<Value>> {
+        if let Some(ref header) = self.header {
+            return Ok(Some(header.clone()));
+        }
+
+        if self.fields.is_empty() {
+            return Ok(None);
+        }
+
+        let mut header = Value::Table(BTreeMap::new());
+        for (path, value) in self.fields.iter() {
+            let _ = header.insert(path, value.clone())?;
+        }
+
+        Ok(Some(header))
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use toml::Value;
+    use toml_query::read::TomlValueReadExt;
+
+    use runtime::Runtime;
+    use super::*;
+
+    #[test]
+    fn test_parse_bare_id() {
+        let se = StreamEntry::parse_line("  notes/foo \n").unwrap();
+        assert_eq!(se.id, "notes/foo");
+        assert!(!se.has_data());
+        assert!(se.header_value().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_parse_json_line() {
+        let se = StreamEntry::parse_line(r#"{"id":"notes/foo","fields":{"tag.values":["a","b"]}}"#).unwrap();
+        assert_eq!(se.id, "notes/foo");
+        assert!(se.header.is_none());
+        assert!(se.has_data());
+
+        let tags = Value::Array(vec![Value::String(String::from("a")), Value::String(String::from("b"))]);
+        assert_eq!(se.read("tag.values").unwrap(), Some(&tags));
+        assert_eq!(se.read("tag.other").unwrap(), None);
+    }
+
+    #[test]
+    fn test_parse_invalid_json_line() {
+        assert!(StreamEntry::parse_line(r#"{"id":"#).is_err());
+        assert!(StreamEntry::parse_line(r#"{"fields":{}}"#).is_err());
+    }
+
+    #[test]
+    fn test_json_line_roundtrip() {
+        let mut se = StreamEntry::parse_line("notes/foo").unwrap();
+        se.fields.insert(String::from("imag.version"), Value::String(String::from("0.10.0")));
+
+        let line = se.to_json_line().unwrap();
+        assert!(!line.contains('\n'));
+        assert!(!line.contains("header"));
+        assert_eq!(StreamEntry::parse_line(&line).unwrap(), se);
+    }
+
+    #[test]
+    fn test_header_value_from_fields() {
+        let se = StreamEntry::parse_line(r#"{"id":"foo","fields":{"tag.values":["a"],"imag.version":"0.10.0"}}"#)
+            .unwrap();
+
+        let header = se.header_value().unwrap().unwrap();
+        assert_eq!(header.read("imag.version").unwrap(), Some(&Value::String(String::from("0.10.0"))));
+        assert_eq!(header.read("tag.values").unwrap(),
+                   Some(&Value::Array(vec![Value::String(String::from("a"))])));
+        assert_eq!(header.read("imag.links").unwrap(), None);
+    }
+
+    #[test]
+    fn test_header_value_prefers_header() {
+        let se = StreamEntry::parse_line(r#"{"id":"foo","header":{"a":1},"fields":{"b":2}}"#).unwrap();
+
+        let header = se.header_value().unwrap().unwrap();
+        assert_eq!(header.read("a").unwrap(), Some(&Value::Integer(1)));
+        assert_eq!(header.read("b").unwrap(), None);
+
+        // Streamed fields are still readable
+        assert_eq!(se.read("b").unwrap(), Some(&Value::Integer(2)));
+        assert_eq!(se.read("a").unwrap(), Some(&Value::Integer(1)));
+    }
+
+    #[test]
+    fn test_stream_format_from_matches() {
+        let format = |args: Vec<&str>| {
+            let matches = Runtime::get_default_cli_builder("imag-test", "0.10.0", "test")
+                .get_matches_from(args);
+            StreamFormat::from_matches(&matches)
+        };
+
+        assert_eq!(format(vec!["imag-test"]), StreamFormat::Ids);
+        assert_eq!(format(vec!["imag-test", "--stream-json"]),
+                   StreamFormat::Json { header: false, fields: vec![] });
+        assert_eq!(format(vec!["imag-test", "--stream-header"]),
+                   StreamFormat::Json { header: true, fields: vec![] });
+        assert_eq!(format(vec!["imag-test", "--stream-field", "a", "--stream-field", "b.c"]),
+                   StreamFormat::Json { header: false, fields: vec![String::from("a"), String::from("b.c")] });
+
+        assert!(!format(vec!["imag-test", "--stream-json"]).needs_entry());
+        assert!(format(vec!["imag-test", "--stream-field", "a"]).needs_entry());
+    }
+}
@@ -199,11 +199,13 @@ impl FileAbstraction for InMemoryFileAbstraction {
             .map(Ok)
             .collect::<Result<_>>()?; // we have to collect() because of the lock() above.
 
-        Ok(PathIterator::new(Box::new(InMemPathIterBuilder(keys)), storepath, backend))
+        let builder = InMemPathIterBuilder(keys, storepath.clone());
+        Ok(PathIterator::new(Box::new(builder), storepath, backend))
     }
 }
 
-pub(crate) struct InMemPathIterBuilder(Vec<PathBuf>);
+/// The pathes of the entries and the path of the store, which the pathes are relative to
+pub(crate) struct InMemPathIterBuilder(Vec<PathBuf>, PathBuf);
 
 impl PathIterBuilder for InMemPathIterBuilder {
     fn build_iter(&self) -> Box<Iterator<Item = Result<PathBuf>>> {
@@ -211,7 +213,8 @@ impl PathIterBuilder for InMemPathIterBuilder {
     }
 
     fn in_collection(&mut self, c: &str) {
-        self.0.retain(|p| p.starts_with(c));
+        let collection = self.1.join(c);
+        self.0.retain(|p| p.starts_with(&collection));
     }
 }
 
@@ -45,6 +45,8 @@ extern crate toml;
 extern crate semver;
 extern crate walkdir;
 #[macro_use] extern crate is_match;
+extern crate serde;
+#[macro_use] extern crate serde_derive;
 extern crate serde_json;
 #[macro_use] extern crate failure;
 extern crate toml_query;
@@ -57,6 +59,7 @@ extern crate libimagutil;
 pub mod storeid;
 pub mod iter;
 pub mod store;
+pub mod oplog;
 mod configuration;
 pub mod file_abstraction;
 
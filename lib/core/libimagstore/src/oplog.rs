@@ -0,0 +1,343 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Log of the mutations done to a `Store`
+//!
+//! If a `Store` has an `OperationLog`, each create, update, delete and move operation is written
+//! to the log before it is done, together with the contents of the entry before and after the
+//! operation. There is one log file per invocation of an imag command, which makes it possible to
+//! revert the changes of an invocation later on.
+//!
+//! A log file contains one JSON object per line. The first line is the `InvocationHeader`, each
+//! following line is an `Operation`.
+
+use std::path::PathBuf;
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::sync::Mutex;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use failure::Fallible as Result;
+use failure::ResultExt;
+use failure::Error;
+use failure::err_msg;
+use serde_json;
+
+use libimagerror::errors::ErrorMsg as EM;
+
+/// A mutation of the store
+///
+/// IDs are local IDs, the entry contents are stored as they are written to disk.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum Operation {
+    /// The entry `id` was created with the contents `after`
+    Create { id: String, after: String },
+
+    /// The entry `id` was changed from `before` to `after`
+    Update { id: String, before: String, after: String },
+
+    /// The entry `id` with the contents `before` was deleted
+    Delete { id: String, before: String },
+
+    /// The entry `from` was moved to `to`
+    Move { from: String, to: String },
+}
+
+/// The first line of each log file, describing the invocation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InvocationHeader {
+    /// Seconds since the UNIX epoch
+    pub timestamp: u64,
+
+    /// The path of the store the operations were done in
+    pub store: PathBuf,
+
+    /// The commandline of the invocation
+    pub command: Vec<String>,
+}
+
+/// Counter to make the names of log files created within one process unique
+static LOG_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// The log the store writes its operations to
+///
+/// The log file is created when the first operation is recorded, so invocations which do not
+/// alter the store do not leave a log file behind. Old log files are pruned at that point, too.
+#[derive(Debug)]
+pub struct OperationLog {
+    path: PathBuf,
+    header: InvocationHeader,
+    file: Mutex<Option<File>>,
+    enabled: AtomicBool,
+    retain: Option<usize>,
+}
+
+impl OperationLog {
+
+    /// Create a new log in the directory `dir`, for an invocation with the commandline `command`
+    /// on the store at `store`
+    pub fn new(dir: PathBuf, store: PathBuf, command: Vec<String>) -> OperationLog {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+
+        let filename = format!("{:020}-{:09}-{}-{}.log",
+                               now.as_secs(),
+                               now.subsec_nanos(),
+                               ::std::process::id(),
+                               LOG_COUNTER.fetch_add(1, Ordering::SeqCst));
+
+        OperationLog {
+            path: dir.join(filename),
+            header: InvocationHeader { timestamp: now.as_secs(), store, command },
+            file: Mutex::new(None),
+            enabled: AtomicBool::new(true),
+            retain: None,
+        }
+    }
+
+    /// Keep only the log files of the last `retain` invocations (including this one)
+    ///
+    /// Older log files are removed when this log file is created.
+    pub fn with_retain(mut self, retain: usize) -> OperationLog {
+        self.retain = Some(retain);
+        self
+    }
+
+    /// Whether operations are currently recorded
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::SeqCst)
+    }
+
+    /// Enable or disable recording operations
+    ///
+    /// This is used for operations which should not be undoable, for example undoing itself.
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::SeqCst)
+    }
+
+    /// Append an operation to the log
+    pub fn record(&self, op: &Operation) -> Result<()> {
+        let mut file = self.file.lock().map_err(|_| Error::from(EM::LockError))?;
+
+        if file.is_none() {
+            *file = Some(self.create_file()?);
+        }
+
+        let f = file.as_mut().ok_or_else(|| err_msg("Operation log not opened"))?;
+        writeln!(f, "{}", serde_json::to_string(op)?)
+            .context(EM::IO)
+            .map_err(Error::from)
+    }
+
+    fn create_file(&self) -> Result<File> {
+        debug!("Creating operation log: {}", self.path.display());
+
+        if let Some(dir) = self.path.parent() {
+            let _ = ::std::fs::create_dir_all(dir).context(EM::IO)?;
+
+            if let Some(retain) = self.retain {
+                let _ = prune(&dir.to_path_buf(), retain.saturating_sub(1))?;
+            }
+        }
+
+        let mut file = OpenOptions::new()
+            .append(true)
+            .create(true)
+            .open(&self.path)
+            .context(EM::IO)?;
+
+        let _ = writeln!(file, "{}", serde_json::to_string(&self.header)?).context(EM::IO)?;
+        Ok(file)
+    }
+
+}
+
+/// An invocation, as read from a log file
+#[derive(Debug)]
+pub struct Invocation {
+    path: PathBuf,
+    header: InvocationHeader,
+    operations: Vec<Operation>,
+}
+
+impl Invocation {
+
+    /// Read an invocation from a log file
+    pub fn read(path: PathBuf) -> Result<Invocation> {
+        let file      = File::open(&path).context(EM::IO)?;
+        let mut lines = BufReader::new(file).lines();
+
+        let header = match lines.next() {
+            Some(line) => serde_json::from_str(&line.context(EM::IO)?)?,
+            None       => return Err(format_err!("Empty operation log: {}", path.display())),
+        };
+
+        let operations = lines
+            .map(|line| {
+                line.context(EM::IO)
+                    .map_err(Error::from)
+                    .and_then(|l| serde_json::from_str(&l).map_err(Error::from))
+            })
+            .collect::<Result<Vec<Operation>>>()
+            .context(format_err!("Failed to parse operation log: {}", path.display()))?;
+
+        Ok(Invocation { path, header, operations })
+    }
+
+    pub fn path(&self) -> &PathBuf {
+        &self.path
+    }
+
+    pub fn header(&self) -> &InvocationHeader {
+        &self.header
+    }
+
+    /// The operations of the invocation, in the order they were done
+    pub fn operations(&self) -> &Vec<Operation> {
+        &self.operations
+    }
+
+    /// Remove the log file of the invocation
+    pub fn remove(self) -> Result<()> {
+        ::std::fs::remove_file(&self.path)
+            .context(EM::FileNotRemoved)
+            .map_err(Error::from)
+    }
+
+}
+
+/// Get the pathes of all log files in `dir`, oldest first
+fn log_files(dir: &PathBuf) -> Result<Vec<PathBuf>> {
+    if !dir.exists() {
+        return Ok(vec![])
+    }
+
+    let mut files = ::std::fs::read_dir(dir)
+        .context(EM::IO)?
+        .map(|entry| entry.map(|e| e.path()).context(EM::IO).map_err(Error::from))
+        .collect::<Result<Vec<PathBuf>>>()?
+        .into_iter()
+        .filter(|p| p.extension().map(|e| e == "log").unwrap_or(false))
+        .collect::<Vec<PathBuf>>();
+
+    // The file names start with the timestamp, so sorting them sorts them by time
+    files.sort();
+    Ok(files)
+}
+
+/// Read all invocations from the log files in `dir`, oldest first
+pub fn invocations(dir: &PathBuf) -> Result<Vec<Invocation>> {
+    log_files(dir)?
+        .into_iter()
+        .map(Invocation::read)
+        .collect()
+}
+
+/// Remove all but the `retain` newest log files in `dir`
+pub fn prune(dir: &PathBuf, retain: usize) -> Result<()> {
+    let files = log_files(dir)?;
+    let n     = files.len().saturating_sub(retain);
+
+    for file in files.into_iter().take(n) {
+        debug!("Removing old operation log: {}", file.display());
+        let _ = ::std::fs::remove_file(file)
+            .context(err_msg("Failed to remove old operation log"))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use tempdir::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn test_record_and_read() {
+        let dir = TempDir::new("imag-oplog").unwrap();
+        let log = OperationLog::new(dir.path().to_path_buf(),
+                                    PathBuf::from("/store"),
+                                    vec![String::from("imag-test")]);
+
+        let ops = vec![
+            Operation::Create { id: String::from("a"), after: String::from("a") },
+            Operation::Move { from: String::from("a"), to: String::from("b") },
+        ];
+
+        for op in ops.iter() {
+            log.record(op).unwrap();
+        }
+
+        let invocations = invocations(&dir.path().to_path_buf()).unwrap();
+        assert_eq!(1, invocations.len());
+        assert_eq!(vec![String::from("imag-test")], invocations[0].header().command);
+        assert_eq!(&ops, invocations[0].operations());
+    }
+
+    #[test]
+    fn test_no_file_without_operations() {
+        let dir = TempDir::new("imag-oplog").unwrap();
+        let _   = OperationLog::new(dir.path().to_path_buf(), PathBuf::from("/store"), vec![]);
+
+        assert!(invocations(&dir.path().to_path_buf()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_prune() {
+        let dir = TempDir::new("imag-oplog").unwrap();
+        let op  = Operation::Delete { id: String::from("a"), before: String::from("a") };
+
+        for _ in 0..3 {
+            OperationLog::new(dir.path().to_path_buf(), PathBuf::from("/store"), vec![])
+                .record(&op)
+                .unwrap();
+            ::std::thread::sleep(::std::time::Duration::from_millis(2));
+        }
+
+        prune(&dir.path().to_path_buf(), 1).unwrap();
+        assert_eq!(1, invocations(&dir.path().to_path_buf()).unwrap().len());
+    }
+
+    #[test]
+    fn test_prune_on_first_record() {
+        let dir = TempDir::new("imag-oplog").unwrap();
+        let op  = Operation::Delete { id: String::from("a"), before: String::from("a") };
+        let new = || OperationLog::new(dir.path().to_path_buf(), PathBuf::from("/store"), vec![]);
+
+        for _ in 0..3 {
+            new().with_retain(2).record(&op).unwrap();
+            ::std::thread::sleep(::std::time::Duration::from_millis(2));
+        }
+        assert_eq!(2, invocations(&dir.path().to_path_buf()).unwrap().len());
+
+        // A log which records nothing does not prune
+        let _ = new().with_retain(1);
+        assert_eq!(2, invocations(&dir.path().to_path_buf()).unwrap().len());
+    }
+}
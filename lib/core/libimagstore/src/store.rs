@@ -44,6 +44,8 @@ use failure::Error;
 use storeid::{IntoStoreId, StoreId};
 use iter::Entries;
 use file_abstraction::FileAbstractionInstance;
+use oplog::Operation;
+use oplog::OperationLog;
 
 // We re-export the following things so tests can use them
 pub use file_abstraction::FileAbstraction;
@@ -66,6 +68,12 @@ struct StoreEntry {
     id: StoreId,
     file: Box<FileAbstractionInstance>,
     status: StoreEntryStatus,
+
+    /// The content of the file when the entry was borrowed, `Some(None)` if there was no file
+    ///
+    /// This is only known if the store records its operations, so that updates can be recorded
+    /// without reading the file again.
+    on_disk: Option<Option<String>>,
 }
 
 impl StoreEntry {
@@ -84,6 +92,7 @@ impl StoreEntry {
             id,
             file: backend.new_instance(pb),
             status: StoreEntryStatus::Present,
+            on_disk: None,
         })
     }
 
@@ -100,7 +109,27 @@ impl StoreEntry {
                 None       => Ok(Entry::new(self.id.clone()))
             }
         } else {
-            Err(format_err!("EntryAlreadyBorrowed: {}", self.id))
+            Err(EM::IdLocked)
+                .context(format_err!("EntryAlreadyBorrowed: {}", self.id))
+                .map_err(Error::from)
+        }
+    }
+
+    /// Like `StoreEntry::get_entry()`, but remember the content of the file for recording updates
+    fn get_recorded_entry(&mut self) -> Result<Entry> {
+        if self.is_borrowed() {
+            return self.get_entry()
+        }
+
+        match self.file.get_file_content(self.id.clone())? {
+            Some(file) => {
+                self.on_disk = Some(Some(file.to_str()?));
+                Ok(file)
+            },
+            None => {
+                self.on_disk = Some(None);
+                Ok(Entry::new(self.id.clone()))
+            },
         }
     }
 
@@ -146,6 +175,9 @@ pub struct Store {
     ///
     /// This provides the filesystem-operation functions (or pretends to)
     backend: Arc<FileAbstraction>,
+
+    /// The log the mutations of the store are recorded in, if any
+    oplog: Option<OperationLog>,
 }
 
 impl Store {
@@ -201,6 +233,7 @@ impl Store {
             location: location.clone(),
             entries: Arc::new(RwLock::new(HashMap::new())),
             backend: backend,
+            oplog: None,
         };
 
         debug!("Store building succeeded");
@@ -226,7 +259,9 @@ impl Store {
 
         if exists {
             debug!("Entry exists: {:?}", id);
-            return Err(format_err!("EntryAlreadyExists: {}", id));
+            return Err(EM::EntryAlreadyExists(id.local_display_string()))
+                .context(format_err!("CreateCallError: {}", id))
+                .map_err(Error::from);
         }
 
         {
@@ -238,7 +273,7 @@ impl Store {
 
             if hsmap.contains_key(&id) {
                 debug!("Cannot create, internal cache already contains: '{}'", id);
-                return Err(format_err!("EntryAlreadyExists: {}", id))
+                return Err(EM::EntryAlreadyExists(id.local_display_string()))
                            .context(format_err!("CreateCallError: {}", id))
                            .map_err(Error::from)
             }
@@ -246,6 +281,7 @@ impl Store {
                 debug!("Creating: '{}'", id);
                 let mut se = StoreEntry::new(id.clone(), &self.backend)?;
                 se.status = StoreEntryStatus::Borrowed;
+                se.on_disk = Some(None);
                 se
             });
         }
@@ -275,7 +311,11 @@ impl Store {
             .and_then(|mut es| {
                 let new_se = StoreEntry::new(id.clone(), &self.backend)?;
                 let se = es.entry(id.clone()).or_insert(new_se);
-                let entry = se.get_entry();
+                let entry = if self.is_recording() {
+                    se.get_recorded_entry()
+                } else {
+                    se.get_entry()
+                };
                 se.status = StoreEntryStatus::Borrowed;
                 entry
             })
@@ -346,9 +386,35 @@ impl Store {
         debug!("Verifying Entry");
         entry.entry.verify()?;
 
+        // The content of the file before the update is known from borrowing the entry, it is
+        // only read from the backend if recording was enabled after the entry was borrowed
+        let written = if self.is_recording() {
+            let id     = entry.get_location().local_display_string();
+            let after  = entry.entry.to_str()?;
+            let before = match se.on_disk.take() {
+                Some(before) => before,
+                None         => match self.get_from_backend(entry.get_location())? {
+                    Some(before) => Some(before.to_str()?),
+                    None         => None,
+                },
+            };
+
+            let ops = match before {
+                None                                 => vec![Operation::Create { id, after: after.clone() }],
+                Some(ref before) if *before == after => vec![],
+                Some(before)                         => vec![Operation::Update { id, before, after: after.clone() }],
+            };
+
+            self.log_operation(|| Ok(ops))?;
+            Some(after)
+        } else {
+            None
+        };
+
         debug!("Writing Entry");
         se.write_entry(&entry.entry)?;
         trace!("Entry written");
+        se.on_disk = written.map(Some);
         if modify_presence {
             debug!("Modifying presence of {} -> Present", entry.get_location());
             se.status = StoreEntryStatus::Present;
@@ -481,6 +547,15 @@ impl Store {
         }
 
         debug!("Seems like {:?} is on the FS", pb);
+
+        self.log_operation(|| match self.get_from_backend(&id)? {
+            None         => Ok(vec![]),
+            Some(before) => Ok(vec![Operation::Delete {
+                id:     id.local_display_string(),
+                before: before.to_str()?,
+            }]),
+        })?;
+
         let _ = self
             .backend
             .remove_file(&pb)
@@ -515,7 +590,7 @@ impl Store {
             .context(format_err!("MoveCallError: {} -> {}", entry.get_location(), new_id))?;
 
         if hsmap.contains_key(&new_id) {
-            return Err(format_err!("Entry exists already: {}", new_id.clone()))
+            return Err(EM::EntryAlreadyExists(new_id.local_display_string()))
                 .context(format_err!("MoveCallError: {} -> {}", entry.get_location(), new_id))
                 .map_err(Error::from)
         }
@@ -524,6 +599,30 @@ impl Store {
 
         let old_id_as_path = old_id.clone().with_base(self.path().clone()).into_pathbuf()?;
         let new_id_as_path = new_id.clone().with_base(self.path().clone()).into_pathbuf()?;
+
+        // Copying is recorded as creating the new entry (and deleting the old one, if it is
+        // removed), as the copy is an independent entry
+        self.log_operation(|| {
+            let content = match self.get_from_backend(&old_id)? {
+                None        => return Ok(vec![]),
+                Some(entry) => entry.to_str()?,
+            };
+
+            let mut ops = vec![Operation::Create {
+                id:    new_id.local_display_string(),
+                after: content.clone(),
+            }];
+
+            if remove_old {
+                ops.push(Operation::Delete {
+                    id:     old_id.local_display_string(),
+                    before: content,
+                });
+            }
+
+            Ok(ops)
+        })?;
+
         self.backend
             .copy(&old_id_as_path, &new_id_as_path)
             .and_then(|_| if remove_old {
@@ -581,7 +680,7 @@ impl Store {
                 .map_err(|_| Error::from(EM::LockError))?;
 
             if hsmap.contains_key(&new_id) {
-                return Err(format_err!("Entry already exists: {}", new_id));
+                return Err(Error::from(EM::EntryAlreadyExists(new_id.local_display_string())));
             }
             debug!("New id does not exist in cache");
 
@@ -589,7 +688,9 @@ impl Store {
             // if we have one, but it is borrowed, we really should not rename it, as this might
             // lead to strange errors
             if hsmap.get(&old_id).map(|e| e.is_borrowed()).unwrap_or(false) {
-                return Err(format_err!("Entry already borrowed: {}", old_id));
+                return Err(EM::IdLocked)
+                    .context(format_err!("Entry already borrowed: {}", old_id))
+                    .map_err(Error::from);
             }
 
             debug!("Old id is not yet borrowed");
@@ -598,10 +699,15 @@ impl Store {
             let new_id_pb = new_id.clone().with_base(self.path().clone()).into_pathbuf()?;
 
             if self.backend.exists(&new_id_pb)? {
-                return Err(format_err!("Entry already exists: {}", new_id));
+                return Err(Error::from(EM::EntryAlreadyExists(new_id.local_display_string())));
             }
             debug!("New entry does not yet exist on filesystem. Good.");
 
+            self.log_operation(|| Ok(vec![Operation::Move {
+                from: old_id.local_display_string(),
+                to:   new_id.local_display_string(),
+            }]))?;
+
             let _ = self
                 .backend
                 .rename(&old_id_pb, &new_id_pb)
@@ -655,6 +761,48 @@ impl Store {
         &self.location
     }
 
+    /// Set the log the mutations of the store are recorded in
+    ///
+    /// Pass `None` to stop recording mutations.
+    pub fn set_operation_log(&mut self, oplog: Option<OperationLog>) {
+        self.oplog = oplog;
+    }
+
+    /// Get the log the mutations of the store are recorded in, if any
+    pub fn operation_log(&self) -> Option<&OperationLog> {
+        self.oplog.as_ref()
+    }
+
+    /// Whether the store records its operations currently
+    fn is_recording(&self) -> bool {
+        self.oplog.as_ref().map(|log| log.is_enabled()).unwrap_or(false)
+    }
+
+    /// Record an operation in the operation log, if there is one and it is enabled
+    ///
+    /// The operations are only computed if they are recorded, as this involves reading the entry
+    /// from the backend.
+    fn log_operation<F>(&self, f: F) -> Result<()>
+        where F: FnOnce() -> Result<Vec<Operation>>
+    {
+        match self.oplog {
+            Some(ref log) if log.is_enabled() => {
+                for op in f()? {
+                    let _ = log.record(&op).context(err_msg("Failed to record operation"))?;
+                }
+                Ok(())
+            },
+            _ => Ok(()),
+        }
+    }
+
+    /// Read an entry from the backend, bypassing the internal cache
+    fn get_from_backend(&self, id: &StoreId) -> Result<Option<Entry>> {
+        let id = id.clone().with_base(self.path().clone());
+        let pb = id.clone().into_pathbuf()?;
+        self.backend.new_instance(pb).get_file_content(id)
+    }
+
 }
 
 impl Debug for Store {
@@ -808,7 +956,8 @@ impl Entry {
     pub fn from_str<S: IntoStoreId>(loc: S, s: &str) -> Result<Entry> {
         use util::entry_buffer_to_header_content;
 
-        let (header, content) = entry_buffer_to_header_content(s)?;
+        let (header, content) = entry_buffer_to_header_content(s)
+            .context(EM::EntryHeaderError)?;
 
         Ok(Entry {
             location: loc.into_storeid()?,
@@ -868,16 +1017,9 @@ impl Entry {
     ///
     /// Currently, this only verifies the header. This might change in the future.
     pub fn verify(&self) -> Result<()> {
-        if !has_main_section(&self.header)? {
-            Err(format_err!("MissingMainSection"))
-        } else if !has_imag_version_in_main_section(&self.header)? {
-            Err(format_err!("MissingVersionInfo"))
-        } else if !has_only_tables(&self.header)? {
-            debug!("Could not verify that it only has tables in its base table");
-            Err(format_err!("NonTableInBaseTable"))
-        } else {
-            Ok(())
-        }
+        verify_header(&self.header)
+            .context(EM::EntryHeaderError)
+            .map_err(Error::from)
     }
 
 }
@@ -892,6 +1034,19 @@ impl PartialEq for Entry {
 
 }
 
+fn verify_header(t: &Value) -> Result<()> {
+    if !has_main_section(t)? {
+        Err(format_err!("MissingMainSection"))
+    } else if !has_imag_version_in_main_section(t)? {
+        Err(format_err!("MissingVersionInfo"))
+    } else if !has_only_tables(t)? {
+        debug!("Could not verify that it only has tables in its base table");
+        Err(format_err!("NonTableInBaseTable"))
+    } else {
+        Ok(())
+    }
+}
+
 fn has_only_tables(t: &Value) -> Result<bool> {
     debug!("Verifying that table has only tables");
     match *t {
@@ -1132,6 +1287,17 @@ mod store_tests {
         }
     }
 
+    #[test]
+    fn test_store_create_twice_error_kind() {
+        use libimagerror::kind::ErrorKind;
+
+        let store = get_store();
+        let _ = store.create(PathBuf::from("test-twice")).unwrap();
+
+        let err = store.create(PathBuf::from("test-twice")).err().unwrap();
+        assert_eq!(ErrorKind::AlreadyExists, ErrorKind::of(&err));
+    }
+
     #[test]
     fn test_store_create_in_hm() {
         use storeid::StoreId;
@@ -1234,5 +1400,25 @@ mod store_tests {
         }
     }
 
+    #[test]
+    fn test_entries_in_collection() {
+        setup_logging();
+        let store = get_store();
+
+        for name in &["foo/a", "foo/b", "bar/a", "foobar"] {
+            let _ = store.create(PathBuf::from(name)).unwrap();
+        }
+
+        let mut ids = store
+            .entries()
+            .unwrap()
+            .in_collection("foo")
+            .map(|id| id.unwrap().local_display_string())
+            .collect::<Vec<_>>();
+        ids.sort();
+
+        assert_eq!(ids, vec!["foo/a", "foo/b"]);
+    }
+
 }
 
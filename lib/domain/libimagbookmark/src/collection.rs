@@ -39,6 +39,10 @@ use libimagentrylink::external::ExternalLinker;
 use libimagentrylink::external::iter::UrlIter;
 use libimagentrylink::internal::InternalLinker;
 use libimagentrylink::internal::Link as StoreLink;
+#[cfg(feature = "external_check")]
+use libimagentrylink::external_check::LinkChecker;
+#[cfg(feature = "external_check")]
+use libimagentrylink::external_check::CheckResult;
 
 use link::Link;
 
@@ -81,6 +85,9 @@ pub trait BookmarkCollection : Sized + InternalLinker + ExternalLinker {
     fn add_link(&mut self, store: &Store, l: Link)               -> Result<Vec<StoreId>>;
     fn get_links_matching<'a>(&self, store: &'a Store, r: Regex) -> Result<LinksMatchingRegexIter<'a>>;
     fn remove_link(&mut self, store: &Store, l: Link)            -> Result<Vec<StoreId>>;
+
+    #[cfg(feature = "external_check")]
+    fn check_links(&self, store: &Store, checker: &LinkChecker)  -> Result<Vec<(StoreId, CheckResult)>>;
 }
 
 impl BookmarkCollection for Entry {
@@ -109,6 +116,16 @@ impl BookmarkCollection for Entry {
         l.into_url().and_then(|url| self.remove_external_link(store, url))
     }
 
+    /// Check the links of the collection and record the results in the store
+    #[cfg(feature = "external_check")]
+    fn check_links(&self, store: &Store, checker: &LinkChecker) -> Result<Vec<(StoreId, CheckResult)>> {
+        let ids = self.link_entries()?
+            .into_iter()
+            .map(|link| link.get_store_id().clone());
+
+        checker.check_entries(store, ids)
+    }
+
 }
 
 pub mod iter {
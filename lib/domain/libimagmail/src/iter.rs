@@ -28,18 +28,20 @@ use mail::Mail;
 use failure::Fallible as Result;
 
 use libimagstore::store::FileLockEntry;
+use libimagentryref::reference::Config;
 
 use std::marker::PhantomData;
 
 pub struct MailIter<'a, I: Iterator<Item = FileLockEntry<'a>>> {
     _marker: PhantomData<I>,
     i: I,
+    config: Config,
 }
 
 impl<'a, I: Iterator<Item = FileLockEntry<'a>>> MailIter<'a, I> {
 
-    pub fn new(i: I) -> MailIter<'a, I> {
-        MailIter { _marker: PhantomData, i: i }
+    pub fn new(i: I, config: Config) -> MailIter<'a, I> {
+        MailIter { _marker: PhantomData, i: i, config: config }
     }
 
 }
@@ -48,7 +50,8 @@ impl<'a, I: Iterator<Item = FileLockEntry<'a>>> Iterator for MailIter<'a, I> {
     type Item = Result<Mail<'a>>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.i.next().map(Mail::from_fle)
+        let config = &self.config;
+        self.i.next().map(|fle| Mail::from_fle(fle, config))
     }
 
 }
@@ -18,6 +18,7 @@
 //
 
 use std::path::Path;
+use std::path::PathBuf;
 use std::fs::File;
 use std::io::Read;
 use std::fs::OpenOptions;
@@ -26,6 +27,7 @@ use libimagstore::store::Store;
 use libimagstore::storeid::StoreId;
 use libimagstore::store::FileLockEntry;
 use libimagentryref::reference::Ref;
+use libimagentryref::reference::Config;
 use libimagentryref::refstore::RefStore;
 use libimagentryref::refstore::UniqueRefPathGenerator;
 use libimagerror::errors::ErrorMsg as EM;
@@ -108,12 +110,12 @@ pub struct Mail<'a>(FileLockEntry<'a>, Buffer);
 impl<'a> Mail<'a> {
 
     /// Imports a mail from the Path passed
-    pub fn import_from_path<P: AsRef<Path>>(store: &Store, p: P) -> Result<Mail> {
+    pub fn import_from_path<P: AsRef<Path>>(store: &'a Store, p: P, config: &Config) -> Result<Mail<'a>> {
         debug!("Importing Mail from path");
-        store.retrieve_ref::<UniqueMailRefGenerator, P>(p)
+        store.retrieve_ref::<UniqueMailRefGenerator, P>(p, config)
             .and_then(|reference| {
                 debug!("Build reference file: {:?}", reference);
-                reference.get_path()
+                reference.get_path(config)
                     .context(err_msg("Ref handling error"))
                     .map_err(Error::from)
                     .and_then(|path| File::open(path).context(EM::IO).map_err(Error::from))
@@ -129,22 +131,32 @@ impl<'a> Mail<'a> {
             })
     }
 
+    /// Re-finds all mails whose referenced file moved, searching the `candidates`
+    ///
+    /// Returns the ids of the relocated mails together with their new path.
+    /// See `RefStore::refind_dangling()` for details.
+    pub fn refind_dangling<I>(store: &Store, candidates: I, config: &Config) -> Result<Vec<(StoreId, PathBuf)>>
+        where I: IntoIterator<Item = PathBuf>
+    {
+        store.refind_dangling::<UniqueMailRefGenerator, I>(candidates, config)
+    }
+
     /// Opens a mail by the passed hash
-    pub fn open<S: AsRef<str>>(store: &Store, hash: S) -> Result<Option<Mail>> {
+    pub fn open<S: AsRef<str>>(store: &'a Store, hash: S, config: &Config) -> Result<Option<Mail<'a>>> {
         debug!("Opening Mail by Hash");
         store.get_ref::<UniqueMailRefGenerator, S>(hash)
             .context(err_msg("Fetch by hash error"))
             .context(err_msg("Fetch error"))
             .map_err(Error::from)
             .and_then(|o| match o {
-                Some(r) => Mail::from_fle(r).map(Some),
+                Some(r) => Mail::from_fle(r, config).map(Some),
                 None => Ok(None),
             })
     }
 
     /// Implement me as TryFrom as soon as it is stable
-    pub fn from_fle(fle: FileLockEntry<'a>) -> Result<Mail<'a>> {
-        fle.get_path()
+    pub fn from_fle(fle: FileLockEntry<'a>, config: &Config) -> Result<Mail<'a>> {
+        fle.get_path(config)
             .context(err_msg("Ref handling error"))
             .map_err(Error::from)
             .and_then(|path| File::open(path).context(EM::IO).map_err(Error::from))
@@ -20,13 +20,14 @@
 use libimagstore::store::Store;
 use libimagstore::store::Entry;
 use libimagentrymarkdown::processor::LinkProcessor;
+use libimagentryref::reference::Config;
 
 use failure::Fallible as Result;
 use failure::ResultExt;
 use failure::Error;
 
 pub trait WikiEntry {
-    fn autolink(&mut self, store: &Store) -> Result<()>;
+    fn autolink(&mut self, store: &Store, ref_config: Config) -> Result<()>;
     fn autolink_with_processor(&mut self, store: &Store, processor: LinkProcessor) -> Result<()>;
 }
 
@@ -41,6 +42,9 @@ impl WikiEntry for Entry {
     /// * External link processing  = true
     /// * Processing of Refs        = true
     ///
+    /// Refs are created relative to the base pathes from `ref_config`, see
+    /// `::libimagentryref::reference::Config`.
+    ///
     /// This is a convenience function for `WikiEntry::autolink_with_processor()`.
     ///
     /// # Warning
@@ -53,12 +57,13 @@ impl WikiEntry for Entry {
     /// * The documentation of `WikiEntry::autolink_with_processor()`.
     /// * The documentation of `::libimagentrymarkdown::processor::LinkProcessor`.
     ///
-    fn autolink(&mut self, store: &Store) -> Result<()> {
+    fn autolink(&mut self, store: &Store, ref_config: Config) -> Result<()> {
         let processor = LinkProcessor::default()
             .process_internal_links(true)
             .create_internal_targets(true)
             .process_external_links(true)
-            .process_refs(true);
+            .process_refs(true)
+            .ref_config(ref_config);
 
         self.autolink_with_processor(store, processor)
     }
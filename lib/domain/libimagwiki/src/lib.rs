@@ -47,6 +47,7 @@ extern crate toml_query;
 extern crate libimagerror;
 extern crate libimagentrylink;
 extern crate libimagentrymarkdown;
+extern crate libimagentryref;
 
 module_entry_path_mod!("wiki");
 
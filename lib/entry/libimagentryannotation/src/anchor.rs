@@ -0,0 +1,246 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Anchors of annotations
+//!
+//! An annotation can be anchored to a passage of the content of the annotated entry, either by
+//! line numbers or by quoting a snippet of the text. The anchor is stored in the
+//! `annotation.anchor` section of the header of the annotation.
+
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+use toml::Value;
+use toml_query::read::TomlValueReadExt;
+use toml_query::read::TomlValueReadTypeExt;
+use toml_query::insert::TomlValueInsertExt;
+
+use failure::Fallible as Result;
+use failure::Error;
+
+use libimagerror::errors::ErrorMsg as EM;
+
+const ANCHOR_LINES_PATH   : &'static str = "annotation.anchor.lines";
+const ANCHOR_SNIPPET_PATH : &'static str = "annotation.anchor.snippet";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Anchor {
+    /// Lines `start` to `end`, counting from 1, both inclusive
+    Lines(usize, usize),
+
+    /// A snippet of text quoted from the content
+    Snippet(String),
+}
+
+impl Anchor {
+
+    pub fn lines(start: usize, end: usize) -> Result<Anchor> {
+        if start == 0 || end < start {
+            return Err(format_err!("Invalid line range: {}-{}", start, end))
+        }
+
+        Ok(Anchor::Lines(start, end))
+    }
+
+    /// Parse a line range like "12-14", or a single line like "12"
+    pub fn parse_lines(s: &str) -> Result<Anchor> {
+        let parse = |n: &str| n.trim().parse::<usize>().map_err(|_| format_err!("Not a line number: '{}'", n));
+
+        match s.find('-') {
+            Some(idx) => Anchor::lines(parse(&s[..idx])?, parse(&s[idx + 1..])?),
+            None      => parse(s).and_then(|n| Anchor::lines(n, n)),
+        }
+    }
+
+    pub fn snippet<S: Into<String>>(s: S) -> Result<Anchor> {
+        let s = s.into();
+        if s.is_empty() {
+            return Err(format_err!("Empty snippet cannot be used as anchor"))
+        }
+
+        Ok(Anchor::Snippet(s))
+    }
+
+    pub fn from_header(hdr: &Value) -> Result<Option<Anchor>> {
+        if let Some(snippet) = hdr.read_string(ANCHOR_SNIPPET_PATH)? {
+            return Ok(Some(Anchor::Snippet(snippet)))
+        }
+
+        match hdr.read(ANCHOR_LINES_PATH)? {
+            Some(&Value::Array(ref a)) => match (a.get(0), a.get(1)) {
+                (Some(&Value::Integer(start)), Some(&Value::Integer(end))) if start > 0 && end >= start => {
+                    Ok(Some(Anchor::Lines(start as usize, end as usize)))
+                },
+                _ => Err(Error::from(EM::EntryHeaderTypeError2(ANCHOR_LINES_PATH, "array of two line numbers"))),
+            },
+            Some(_) => Err(Error::from(EM::EntryHeaderTypeError2(ANCHOR_LINES_PATH, "array"))),
+            None    => Ok(None),
+        }
+    }
+
+    /// Write the anchor to `hdr`, replacing an anchor which is already there
+    pub fn insert_into(&self, hdr: &mut Value) -> Result<()> {
+        Anchor::remove_from(hdr);
+
+        let _ = match *self {
+            Anchor::Lines(start, end) => {
+                let lines = vec![Value::Integer(start as i64), Value::Integer(end as i64)];
+                hdr.insert(ANCHOR_LINES_PATH, Value::Array(lines))?
+            },
+            Anchor::Snippet(ref s) => hdr.insert(ANCHOR_SNIPPET_PATH, Value::String(s.clone()))?,
+        };
+
+        Ok(())
+    }
+
+    /// Remove the anchor from `hdr`, if there is one
+    pub fn remove_from(hdr: &mut Value) {
+        if let Some(annotation) = hdr.as_table_mut().and_then(|t| t.get_mut("annotation")).and_then(Value::as_table_mut) {
+            let _ = annotation.remove("anchor");
+        }
+    }
+
+    /// Find the anchored passage in `content`
+    ///
+    /// Returns the first and the last line of the passage, counting from 1. `None` is returned if
+    /// the passage does not exist (anymore), for example because the annotated entry was edited.
+    pub fn locate(&self, content: &str) -> Option<(usize, usize)> {
+        match *self {
+            Anchor::Lines(start, end) => {
+                let count = content.lines().count();
+                if start > count {
+                    None
+                } else {
+                    Some((start, ::std::cmp::min(end, count)))
+                }
+            },
+
+            Anchor::Snippet(ref s) => content.find(s.as_str()).map(|idx| {
+                let start = content[..idx].matches('\n').count() + 1;
+                let end   = start + s.trim_end_matches('\n').matches('\n').count();
+                (start, end)
+            }),
+        }
+    }
+
+}
+
+impl Display for Anchor {
+    fn fmt(&self, fmt: &mut Formatter) -> FmtResult {
+        match *self {
+            Anchor::Lines(start, end) if start == end => write!(fmt, "line {}", start),
+            Anchor::Lines(start, end)                 => write!(fmt, "lines {}-{}", start, end),
+            Anchor::Snippet(ref s)                    => write!(fmt, "\"{}\"", s),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use toml::Value;
+    use toml_query::insert::TomlValueInsertExt;
+
+    use super::Anchor;
+
+    fn header() -> Value {
+        Value::Table(::toml::value::Table::new())
+    }
+
+    #[test]
+    fn test_parse_lines() {
+        assert_eq!(Anchor::Lines(12, 14), Anchor::parse_lines("12-14").unwrap());
+        assert_eq!(Anchor::Lines(12, 14), Anchor::parse_lines(" 12 - 14 ").unwrap());
+        assert_eq!(Anchor::Lines(3, 3), Anchor::parse_lines("3").unwrap());
+    }
+
+    #[test]
+    fn test_parse_lines_invalid() {
+        assert!(Anchor::parse_lines("").is_err());
+        assert!(Anchor::parse_lines("a").is_err());
+        assert!(Anchor::parse_lines("0").is_err());
+        assert!(Anchor::parse_lines("-3").is_err());
+        assert!(Anchor::parse_lines("3-").is_err());
+        assert!(Anchor::parse_lines("5-3").is_err());
+        assert!(Anchor::parse_lines("1-2-3").is_err());
+    }
+
+    #[test]
+    fn test_locate_lines() {
+        let content = "one\ntwo\nthree\n";
+
+        assert_eq!(Some((1, 1)), Anchor::Lines(1, 1).locate(content));
+        assert_eq!(Some((2, 3)), Anchor::Lines(2, 3).locate(content));
+        assert_eq!(Some((2, 3)), Anchor::Lines(2, 10).locate(content));
+        assert_eq!(Some((3, 3)), Anchor::Lines(3, 4).locate(content));
+        assert_eq!(None, Anchor::Lines(4, 5).locate(content));
+        assert_eq!(None, Anchor::Lines(1, 1).locate(""));
+    }
+
+    #[test]
+    fn test_locate_snippet() {
+        let content = "one\ntwo\nthree\nfour\n";
+        let locate  = |s: &str| Anchor::Snippet(String::from(s)).locate(content);
+
+        assert_eq!(Some((1, 1)), locate("one"));
+        assert_eq!(Some((2, 2)), locate("wo"));
+        assert_eq!(Some((2, 3)), locate("two\nthree"));
+        assert_eq!(Some((2, 3)), locate("two\nthree\n"));
+        assert_eq!(Some((1, 2)), locate("\ntwo"));
+        assert_eq!(Some((4, 4)), locate("four\n"));
+        assert_eq!(None, locate("five"));
+    }
+
+    #[test]
+    fn test_header_roundtrip() {
+        let mut hdr = header();
+        assert_eq!(None, Anchor::from_header(&hdr).unwrap());
+
+        Anchor::Lines(2, 4).insert_into(&mut hdr).unwrap();
+        assert_eq!(Some(Anchor::Lines(2, 4)), Anchor::from_header(&hdr).unwrap());
+
+        // Inserting replaces the existing anchor, even if it is of another kind
+        Anchor::Snippet(String::from("text")).insert_into(&mut hdr).unwrap();
+        assert_eq!(Some(Anchor::Snippet(String::from("text"))), Anchor::from_header(&hdr).unwrap());
+
+        Anchor::Lines(1, 1).insert_into(&mut hdr).unwrap();
+        assert_eq!(Some(Anchor::Lines(1, 1)), Anchor::from_header(&hdr).unwrap());
+
+        Anchor::remove_from(&mut hdr);
+        assert_eq!(None, Anchor::from_header(&hdr).unwrap());
+    }
+
+    #[test]
+    fn test_from_header_invalid_lines() {
+        let invalid = vec![
+            Value::Integer(1),
+            Value::Array(vec![]),
+            Value::Array(vec![Value::Integer(1)]),
+            Value::Array(vec![Value::Integer(0), Value::Integer(1)]),
+            Value::Array(vec![Value::Integer(3), Value::Integer(2)]),
+            Value::Array(vec![Value::Integer(-1), Value::Integer(2)]),
+            Value::Array(vec![Value::String(String::from("1")), Value::Integer(2)]),
+        ];
+
+        for lines in invalid {
+            let mut hdr = header();
+            let _ = hdr.insert("annotation.anchor.lines", lines.clone()).unwrap();
+            assert!(Anchor::from_header(&hdr).is_err(), "Expected error for {:?}", lines);
+        }
+    }
+
+}
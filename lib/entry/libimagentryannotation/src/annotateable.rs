@@ -17,6 +17,7 @@
 // Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
 //
 
+use chrono::Local;
 use toml::Value;
 
 use libimagstore::store::Entry;
@@ -27,6 +28,7 @@ use libimagstore::storeid::StoreIdIterator;
 use libimagentrylink::internal::InternalLinker;
 use libimagentryutil::isa::Is;
 use libimagentryutil::isa::IsKindHeaderPathProvider;
+use libimagutil::date::datetime_to_string;
 
 use toml_query::read::TomlValueReadTypeExt;
 use toml_query::insert::TomlValueInsertExt;
@@ -40,6 +42,7 @@ use iter::*;
 
 pub trait Annotateable {
     fn annotate<'a>(&mut self, store: &'a Store, ann_name: &str) -> Result<FileLockEntry<'a>>;
+    fn reply<'a>(&mut self, store: &'a Store, ann_name: &str) -> Result<FileLockEntry<'a>>;
     fn denotate<'a>(&mut self, store: &'a Store, ann_name: &str) -> Result<Option<FileLockEntry<'a>>>;
     fn annotations<'a>(&self, store: &'a Store) -> Result<AnnotationIter<'a>>;
     fn is_annotation(&self) -> Result<bool>;
@@ -50,6 +53,8 @@ provide_kindflag_path!(IsAnnotation, "annotation.is_annotation");
 impl Annotateable for Entry {
 
     /// Annotate an entry, returns the new entry which is used to annotate
+    ///
+    /// The time the annotation is created is recorded in its header.
     fn annotate<'a>(&mut self, store: &'a Store, ann_name: &str) -> Result<FileLockEntry<'a>> {
         use module_path::ModuleEntryPath;
         store.retrieve(ModuleEntryPath::new(ann_name).into_storeid()?)
@@ -59,6 +64,13 @@ impl Annotateable for Entry {
                     let _ = anno
                         .get_header_mut()
                         .insert("annotation.name", Value::String(String::from(ann_name)))?;
+
+                    if anno.get_header().read_string("annotation.created")?.is_none() {
+                        let now = datetime_to_string(&Local::now().naive_local());
+                        let _   = anno
+                            .get_header_mut()
+                            .insert("annotation.created", Value::String(now))?;
+                    }
                 }
                 Ok(anno)
             })
@@ -70,6 +82,23 @@ impl Annotateable for Entry {
             })
     }
 
+    /// Reply to an annotation, returns the new annotation which is the reply
+    ///
+    /// Fails if the current entry is not an annotation.
+    fn reply<'a>(&mut self, store: &'a Store, ann_name: &str) -> Result<FileLockEntry<'a>> {
+        if !self.is_annotation()? {
+            return Err(format_err!("Cannot reply to '{}', it is not an annotation", self.get_location()))
+        }
+
+        let reply_to = self.get_location().local_display_string();
+        let mut anno = self.annotate(store, ann_name)?;
+        let _        = anno
+            .get_header_mut()
+            .insert("annotation.reply_to", Value::String(reply_to))?;
+
+        Ok(anno)
+    }
+
     /// Checks the current entry for all annotations and removes the one where the name is
     /// `ann_name`, which is then returned
     fn denotate<'a>(&mut self, store: &'a Store, ann_name: &str) -> Result<Option<FileLockEntry<'a>>> {
@@ -0,0 +1,221 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+use std::path::PathBuf;
+
+use chrono::NaiveDateTime;
+use toml_query::read::TomlValueReadTypeExt;
+
+use libimagstore::store::Entry;
+use libimagstore::store::FileLockEntry;
+use libimagstore::store::Store;
+use libimagstore::storeid::StoreId;
+use libimagentrylink::internal::InternalLinker;
+use libimagutil::date::datetime_from_string;
+
+use failure::Fallible as Result;
+use failure::Error;
+
+use anchor::Anchor;
+
+/// Functionality of annotation entries
+///
+/// See `Annotateable` for creating annotations.
+pub trait Annotation {
+    fn get_annotation_name(&self) -> Result<Option<String>>;
+
+    /// Get the time the annotation was created
+    fn get_created(&self) -> Result<Option<NaiveDateTime>>;
+
+    fn get_anchor(&self) -> Result<Option<Anchor>>;
+    fn set_anchor(&mut self, anchor: &Anchor) -> Result<()>;
+    fn remove_anchor(&mut self);
+
+    /// Get the id of the annotation this annotation replies to, if it is a reply
+    fn get_reply_to(&self) -> Result<Option<StoreId>>;
+
+    /// Get the replies to this annotation, oldest first
+    fn replies<'a>(&self, store: &'a Store) -> Result<Vec<FileLockEntry<'a>>>;
+}
+
+impl Annotation for Entry {
+
+    fn get_annotation_name(&self) -> Result<Option<String>> {
+        self.get_header().read_string("annotation.name").map_err(Error::from)
+    }
+
+    fn get_created(&self) -> Result<Option<NaiveDateTime>> {
+        match self.get_header().read_string("annotation.created")? {
+            Some(s) => datetime_from_string(&s).map(Some).map_err(Error::from),
+            None    => Ok(None),
+        }
+    }
+
+    fn get_anchor(&self) -> Result<Option<Anchor>> {
+        Anchor::from_header(self.get_header())
+    }
+
+    fn set_anchor(&mut self, anchor: &Anchor) -> Result<()> {
+        anchor.insert_into(self.get_header_mut())
+    }
+
+    fn remove_anchor(&mut self) {
+        Anchor::remove_from(self.get_header_mut())
+    }
+
+    fn get_reply_to(&self) -> Result<Option<StoreId>> {
+        match self.get_header().read_string("annotation.reply_to")? {
+            Some(s) => StoreId::new_baseless(PathBuf::from(s)).map(Some),
+            None    => Ok(None),
+        }
+    }
+
+    fn replies<'a>(&self, store: &'a Store) -> Result<Vec<FileLockEntry<'a>>> {
+        let own_id   = self.get_location().local().clone();
+        let reply_to = self.get_reply_to()?.map(|id| id.local().clone());
+        let mut replies = vec![];
+
+        // Only annotations can be replies, so the other linked entries are not even loaded. They
+        // might be held by the caller, as the annotated entry or the annotation this one replies
+        // to usually are.
+        for link in self.get_internal_links()? {
+            let id = link.get_store_id();
+            if !id.is_in_collection(&["annotations"]) || Some(id.local()) == reply_to.as_ref() {
+                continue
+            }
+
+            if let Some(entry) = store.get(id.clone())? {
+                let is_reply = entry.get_reply_to()?.map(|r| *r.local() == own_id).unwrap_or(false);
+                if is_reply {
+                    replies.push(entry);
+                }
+            }
+        }
+
+        replies.sort_by_key(|e| e.get_created().ok().and_then(|c| c));
+        Ok(replies)
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    use toml::Value;
+    use toml_query::insert::TomlValueInsertExt;
+
+    use libimagstore::store::Entry;
+    use libimagstore::store::FileLockEntry;
+    use libimagstore::store::Store;
+
+    use annotateable::Annotateable;
+    use super::Annotation;
+
+    fn get_store() -> Store {
+        use libimagstore::file_abstraction::InMemoryFileAbstraction;
+        let backend = Arc::new(InMemoryFileAbstraction::default());
+        Store::new_with_backend(PathBuf::from("/"), &None, backend).unwrap()
+    }
+
+    fn set_created(entry: &mut Entry, created: &str) {
+        let _ = entry
+            .get_header_mut()
+            .insert("annotation.created", Value::String(String::from(created)))
+            .unwrap();
+    }
+
+    fn names(entries: &[FileLockEntry]) -> Vec<String> {
+        entries.iter().map(|e| e.get_annotation_name().unwrap().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_reply_links_to_annotation() {
+        let store     = get_store();
+        let mut entry = store.create(PathBuf::from("test/entry")).unwrap();
+        let mut anno  = entry.annotate(&store, "note").unwrap();
+        let reply     = anno.reply(&store, "reply").unwrap();
+
+        assert_eq!(None, anno.get_reply_to().unwrap());
+        assert_eq!(Some(anno.get_location().local().clone()),
+                   reply.get_reply_to().unwrap().map(|id| id.local().clone()));
+        assert!(reply.get_created().unwrap().is_some());
+    }
+
+    #[test]
+    fn test_reply_to_non_annotation_fails() {
+        let store     = get_store();
+        let mut entry = store.create(PathBuf::from("test/entry")).unwrap();
+
+        assert!(entry.reply(&store, "reply").is_err());
+    }
+
+    #[test]
+    fn test_replies_oldest_first() {
+        let store     = get_store();
+        let mut entry = store.create(PathBuf::from("test/entry")).unwrap();
+        let mut anno  = entry.annotate(&store, "note").unwrap();
+
+        {
+            let mut late = anno.reply(&store, "late").unwrap();
+            set_created(&mut late, "2018-01-02 00:00:00");
+        }
+        {
+            let mut early = anno.reply(&store, "early").unwrap();
+            set_created(&mut early, "2018-01-01 00:00:00");
+        }
+
+        let replies = anno.replies(&store).unwrap();
+        assert_eq!(vec![String::from("early"), String::from("late")], names(&replies));
+    }
+
+    #[test]
+    fn test_replies_thread() {
+        let store     = get_store();
+        let mut entry = store.create(PathBuf::from("test/entry")).unwrap();
+        let mut anno  = entry.annotate(&store, "note").unwrap();
+
+        {
+            let mut reply = anno.reply(&store, "reply").unwrap();
+            let _         = reply.reply(&store, "nested").unwrap();
+
+            // The annotation replied to is held above, so it must not be loaded again
+            let replies = reply.replies(&store).unwrap();
+            assert_eq!(vec![String::from("nested")], names(&replies));
+        }
+
+        // Replies to replies are not direct replies, and the annotated entry is no reply at all
+        let replies = anno.replies(&store).unwrap();
+        assert_eq!(vec![String::from("reply")], names(&replies));
+
+        let nested = replies[0].replies(&store).unwrap();
+        assert_eq!(vec![String::from("nested")], names(&nested));
+    }
+
+    #[test]
+    fn test_no_replies() {
+        let store     = get_store();
+        let mut entry = store.create(PathBuf::from("test/entry")).unwrap();
+        let anno      = entry.annotate(&store, "note").unwrap();
+
+        assert!(anno.replies(&store).unwrap().is_empty());
+    }
+
+}
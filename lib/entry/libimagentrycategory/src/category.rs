@@ -24,12 +24,20 @@ use libimagstore::store::Store;
 use libimagstore::storeid::StoreIdIterator;
 use libimagentrylink::internal::InternalLinker;
 
+use toml::Value;
+use toml_query::read::TomlValueReadExt;
 use toml_query::read::TomlValueReadTypeExt;
+use toml_query::insert::TomlValueInsertExt;
 
 use failure::Fallible as Result;
 use failure::Error;
 use failure::err_msg;
+use libimagerror::errors::ErrorMsg as EM;
 use store::CATEGORY_REGISTER_NAME_FIELD_PATH;
+use store::CATEGORY_REGISTER_PARENT_FIELD_PATH;
+use store::CATEGORY_REGISTER_DESCRIPTION_FIELD_PATH;
+use store::CATEGORY_REGISTER_REQUIRED_FIELDS_PATH;
+use store::CATEGORY_REGISTER_ALLOWED_COLLECTIONS_PATH;
 use iter::CategoryEntryIterator;
 
 provide_kindflag_path!(pub IsCategory, "category.is_category");
@@ -38,6 +46,18 @@ pub trait Category {
     fn is_category(&self) -> Result<bool>;
     fn get_name(&self)    -> Result<String>;
     fn get_entries<'a>(&self, store: &'a Store) -> Result<CategoryEntryIterator<'a>>;
+
+    /// Get the name of the parent category, if this is a subcategory
+    ///
+    /// Use `CategoryStore::set_category_parent()` to change the parent.
+    fn get_parent(&self) -> Result<Option<String>>;
+
+    fn get_description(&self) -> Result<Option<String>>;
+    fn set_description(&mut self, description: &str) -> Result<()>;
+
+    /// Get the constraints of this category, without the ones inherited from parent categories
+    fn get_constraints(&self) -> Result<Constraints>;
+    fn set_constraints(&mut self, constraints: &Constraints) -> Result<()>;
 }
 
 impl Category for Entry {
@@ -58,7 +78,115 @@ impl Category for Entry {
         let sit  = self.get_internal_links()?.map(|l| l.get_store_id().clone()).map(Ok);
         let sit  = StoreIdIterator::new(Box::new(sit));
         let name = self.get_name()?;
-        Ok(CategoryEntryIterator::new(store, sit, name))
+        Ok(CategoryEntryIterator::new(store, sit, vec![name]))
+    }
+
+    fn get_parent(&self) -> Result<Option<String>> {
+        self.get_header()
+            .read_string(CATEGORY_REGISTER_PARENT_FIELD_PATH)
+            .map_err(Error::from)
+    }
+
+    fn get_description(&self) -> Result<Option<String>> {
+        self.get_header()
+            .read_string(CATEGORY_REGISTER_DESCRIPTION_FIELD_PATH)
+            .map_err(Error::from)
+    }
+
+    fn set_description(&mut self, description: &str) -> Result<()> {
+        self.get_header_mut()
+            .insert(CATEGORY_REGISTER_DESCRIPTION_FIELD_PATH, Value::String(String::from(description)))
+            .map_err(Error::from)
+            .map(|_| ())
+    }
+
+    fn get_constraints(&self) -> Result<Constraints> {
+        let hdr = self.get_header();
+        Ok(Constraints {
+            required_fields: read_string_array(hdr, CATEGORY_REGISTER_REQUIRED_FIELDS_PATH)?,
+            allowed_collections: read_string_array(hdr, CATEGORY_REGISTER_ALLOWED_COLLECTIONS_PATH)?,
+        })
+    }
+
+    fn set_constraints(&mut self, constraints: &Constraints) -> Result<()> {
+        let to_array = |v: &[String]| Value::Array(v.iter().cloned().map(Value::String).collect());
+        let hdr      = self.get_header_mut();
+
+        let _ = hdr.insert(CATEGORY_REGISTER_REQUIRED_FIELDS_PATH, to_array(&constraints.required_fields))?;
+        let _ = hdr.insert(CATEGORY_REGISTER_ALLOWED_COLLECTIONS_PATH, to_array(&constraints.allowed_collections))?;
+        Ok(())
+    }
+}
+
+/// Constraints an entry has to fulfill to be put into a category
+///
+/// Constraints are inherited: an entry put into a subcategory has to fulfill the constraints of
+/// all parent categories as well, see `CategoryStore::check_category_constraints()`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Constraints {
+    required_fields: Vec<String>,
+    allowed_collections: Vec<String>,
+}
+
+impl Constraints {
+
+    /// Create constraints
+    ///
+    /// * `required_fields` are header fields (like `"todo.due"`) the entry must have
+    /// * `allowed_collections` are the collections (like `"notes"` or `"diary/work"`) the entry has
+    ///   to be in. If empty, entries from all collections are allowed.
+    pub fn new(required_fields: Vec<String>, allowed_collections: Vec<String>) -> Constraints {
+        Constraints { required_fields, allowed_collections }
+    }
+
+    pub fn required_fields(&self) -> &[String] {
+        &self.required_fields
+    }
+
+    pub fn allowed_collections(&self) -> &[String] {
+        &self.allowed_collections
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.required_fields.is_empty() && self.allowed_collections.is_empty()
+    }
+
+    /// Check `entry` against the constraints
+    ///
+    /// Returns a description of each violated constraint, so an empty list means the entry is fine.
+    pub fn violations(&self, entry: &Entry) -> Result<Vec<String>> {
+        let mut violations = vec![];
+
+        for field in self.required_fields.iter() {
+            if entry.get_header().read(field).map_err(Error::from)?.is_none() {
+                violations.push(format!("Header field '{}' is missing", field));
+            }
+        }
+
+        let in_allowed_collection = self.allowed_collections.is_empty() ||
+            self.allowed_collections.iter().any(|coll| {
+                let coll : Vec<&str> = coll.split('/').collect();
+                entry.get_location().is_in_collection(&coll)
+            });
+
+        if !in_allowed_collection {
+            violations.push(format!("Entry is not in one of the collections {}",
+                                    self.allowed_collections.join(", ")));
+        }
+
+        Ok(violations)
+    }
+
+}
+
+fn read_string_array(hdr: &Value, path: &'static str) -> Result<Vec<String>> {
+    match hdr.read(path).map_err(Error::from)? {
+        Some(&Value::Array(ref values)) => values
+            .iter()
+            .map(|v| v.as_str().map(String::from).ok_or_else(|| Error::from(EM::EntryHeaderTypeError2(path, "array of strings"))))
+            .collect(),
+        Some(_) => Err(Error::from(EM::EntryHeaderTypeError2(path, "array"))),
+        None    => Ok(vec![]),
     }
 }
 
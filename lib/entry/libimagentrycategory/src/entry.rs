@@ -61,8 +61,17 @@ impl EntryCategory for Entry {
     /// Check whether a category exists before setting it.
     ///
     /// This function should be used by default over EntryCategory::set_category()!
+    ///
+    /// Also fails if the entry violates the constraints of the category or one of its parent
+    /// categories.
     fn set_category_checked(&mut self, register: &CategoryStore, s: &str) -> Result<()> {
         trace!("Setting category '{}' checked", s);
+        let violations = register.check_category_constraints(s, self)?;
+        if !violations.is_empty() {
+            return Err(format_err!("Entry '{}' cannot be put into category '{}': {}",
+                                   self.get_location(), s, violations.join("; ")))
+        }
+
         let mut category = register
             .get_category_by_name(s)?
             .ok_or_else(|| Error::from(err_msg("Category does not exist")))?;
@@ -86,11 +86,15 @@ impl<'a> Iterator for CategoryNameIter<'a> {
     }
 }
 
-pub struct CategoryEntryIterator<'a>(&'a Store, StoreIdIterator, String);
+/// Iterator over the entries of a category
+///
+/// Yields the entries whose category is one of the names the iterator was built for, which are
+/// the category and possibly its subcategories, see `CategoryStore::get_category_entries()`.
+pub struct CategoryEntryIterator<'a>(&'a Store, StoreIdIterator, Vec<String>);
 
 impl<'a> CategoryEntryIterator<'a> {
-    pub(crate) fn new(store: &'a Store, sit: StoreIdIterator, name: String) -> Self {
-        CategoryEntryIterator(store, sit, name)
+    pub(crate) fn new(store: &'a Store, sit: StoreIdIterator, names: Vec<String>) -> Self {
+        CategoryEntryIterator(store, sit, names)
     }
 }
 
@@ -112,7 +116,7 @@ impl<'a> Iterator for CategoryEntryIterator<'a> {
                     match getter(next) {
                         Err(e)     => return Some(Err(e)),
                         Ok((c, e)) => {
-                            if c == self.2 {
+                            if self.2.contains(&c) {
                                 return Some(Ok(e))
                             // } else {
                             // continue
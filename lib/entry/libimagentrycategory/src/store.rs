@@ -17,6 +17,7 @@
 // Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
 //
 
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 
 use toml_query::insert::TomlValueInsertExt;
@@ -24,8 +25,10 @@ use toml_query::read::TomlValueReadTypeExt;
 use toml::Value;
 
 use libimagstore::store::Store;
+use libimagstore::store::Entry;
 use libimagstore::store::FileLockEntry;
 use libimagstore::storeid::StoreId;
+use libimagstore::storeid::StoreIdIterator;
 use libimagentryutil::isa::Is;
 use libimagerror::errors::ErrorMsg as EM;
 
@@ -34,9 +37,15 @@ use failure::ResultExt;
 use failure::Error;
 use failure::err_msg;
 use iter::CategoryNameIter;
+use iter::CategoryEntryIterator;
 use category::IsCategory;
+use category::Category;
 
 pub const CATEGORY_REGISTER_NAME_FIELD_PATH : &'static str = "category.register.name";
+pub const CATEGORY_REGISTER_PARENT_FIELD_PATH : &'static str = "category.register.parent";
+pub const CATEGORY_REGISTER_DESCRIPTION_FIELD_PATH : &'static str = "category.register.description";
+pub const CATEGORY_REGISTER_REQUIRED_FIELDS_PATH : &'static str = "category.register.constraints.required_fields";
+pub const CATEGORY_REGISTER_ALLOWED_COLLECTIONS_PATH : &'static str = "category.register.constraints.allowed_collections";
 
 /// Extension on the Store to make it a register for categories
 ///
@@ -53,6 +62,25 @@ pub trait CategoryStore {
 
     fn get_category_by_name(&self, name: &str) -> Result<Option<FileLockEntry>>;
 
+    fn create_subcategory<'a>(&'a self, name: &str, parent: &str) -> Result<FileLockEntry<'a>>;
+
+    fn set_category_parent(&self, name: &str, parent: Option<&str>) -> Result<()>;
+
+    fn get_category_parent(&self, name: &str) -> Result<Option<String>>;
+
+    fn get_category_ancestors(&self, name: &str) -> Result<Vec<String>>;
+
+    fn get_subcategories(&self, name: &str) -> Result<Vec<String>>;
+
+    fn get_category_tree(&self) -> Result<BTreeMap<Option<String>, Vec<String>>>;
+
+    fn get_root_categories(&self) -> Result<Vec<String>>;
+
+    fn get_category_entries<'a>(&'a self, name: &str, include_subcategories: bool)
+        -> Result<CategoryEntryIterator<'a>>;
+
+    fn check_category_constraints(&self, name: &str, entry: &Entry) -> Result<Vec<String>>;
+
 }
 
 impl CategoryStore for Store {
@@ -85,11 +113,15 @@ impl CategoryStore for Store {
     /// Delete a category
     ///
     /// Automatically removes all category settings from entries which are linked to this category.
+    /// Fails if the category has subcategories.
     fn delete_category(&self, name: &str) -> Result<()> {
         use libimagentrylink::internal::InternalLinker;
-        use category::Category;
 
         trace!("Deleting category: '{}'", name);
+        if !self.get_subcategories(name)?.is_empty() {
+            return Err(format_err!("Category '{}' has subcategories, delete or move them first", name))
+        }
+
         let sid = mk_category_storeid(self.path().clone(), name)?;
 
         {
@@ -124,6 +156,162 @@ impl CategoryStore for Store {
             .context(err_msg("Store write error"))
             .map_err(Error::from)
     }
+
+    /// Create a category as a subcategory of `parent`
+    fn create_subcategory<'a>(&'a self, name: &str, parent: &str) -> Result<FileLockEntry<'a>> {
+        trace!("Creating category '{}' below '{}'", name, parent);
+        if self.get_category_by_name(parent)?.is_none() {
+            return Err(format_err!("Category '{}' does not exist", parent))
+        }
+
+        let mut entry = self.create_category(name)?;
+        let _ = entry
+            .get_header_mut()
+            .insert(CATEGORY_REGISTER_PARENT_FIELD_PATH, Value::String(String::from(parent)))?;
+
+        Ok(entry)
+    }
+
+    /// Move a category below another one, or make it a top-level category by passing `None`
+    ///
+    /// Fails if the parent does not exist or if the category would become its own ancestor.
+    fn set_category_parent(&self, name: &str, parent: Option<&str>) -> Result<()> {
+        use toml_query::delete::TomlValueDeleteExt;
+
+        trace!("Setting parent of category '{}' to {:?}", name, parent);
+        if let Some(parent) = parent {
+            if self.get_category_by_name(parent)?.is_none() {
+                return Err(format_err!("Category '{}' does not exist", parent))
+            }
+
+            if parent == name || self.get_category_ancestors(parent)?.iter().any(|a| a == name) {
+                return Err(format_err!("Category '{}' cannot be moved below its own subcategory '{}'", name, parent))
+            }
+        }
+
+        let mut category = self.get_category_by_name(name)?
+            .ok_or_else(|| format_err!("Category '{}' does not exist", name))?;
+        let hdr = category.get_header_mut();
+
+        match parent {
+            Some(parent) => {
+                let _ = hdr.insert(CATEGORY_REGISTER_PARENT_FIELD_PATH, Value::String(String::from(parent)))?;
+            },
+            None => if hdr.read_string(CATEGORY_REGISTER_PARENT_FIELD_PATH)?.is_some() {
+                let _ = hdr.delete(CATEGORY_REGISTER_PARENT_FIELD_PATH)?;
+            },
+        }
+
+        Ok(())
+    }
+
+    fn get_category_parent(&self, name: &str) -> Result<Option<String>> {
+        self.get_category_by_name(name)?
+            .ok_or_else(|| format_err!("Category '{}' does not exist", name))?
+            .get_parent()
+    }
+
+    /// Get the parent, grandparent, ... of a category, nearest first
+    fn get_category_ancestors(&self, name: &str) -> Result<Vec<String>> {
+        let mut ancestors = vec![];
+        let mut current   = String::from(name);
+
+        while let Some(parent) = self.get_category_parent(&current)? {
+            if parent == name || ancestors.contains(&parent) {
+                return Err(format_err!("Categories '{}' and '{}' are each others ancestors", name, parent))
+            }
+
+            ancestors.push(parent.clone());
+            current = parent;
+        }
+
+        Ok(ancestors)
+    }
+
+    /// Get the names of the direct subcategories of a category
+    fn get_subcategories(&self, name: &str) -> Result<Vec<String>> {
+        self.get_category_tree()
+            .map(|mut tree| tree.remove(&Some(String::from(name))).unwrap_or_else(Vec::new))
+    }
+
+    /// Get the names of the direct subcategories of all categories
+    ///
+    /// The names of the categories which are not a subcategory are listed under `None`. Each list
+    /// is sorted. Prefer this over calling `get_subcategories()` for each category, as this reads
+    /// every category only once.
+    fn get_category_tree(&self) -> Result<BTreeMap<Option<String>, Vec<String>>> {
+        let mut tree = BTreeMap::new();
+
+        for name in self.all_category_names()? {
+            let name   = name?;
+            let parent = self.get_category_parent(&name)?;
+            tree.entry(parent).or_insert_with(Vec::new).push(name);
+        }
+
+        for children in tree.values_mut() {
+            children.sort();
+        }
+
+        Ok(tree)
+    }
+
+    /// Get the names of the categories which are not a subcategory
+    fn get_root_categories(&self) -> Result<Vec<String>> {
+        self.get_category_tree()
+            .map(|mut tree| tree.remove(&None).unwrap_or_else(Vec::new))
+    }
+
+    /// Get the entries of a category, optionally including the entries of all its subcategories
+    fn get_category_entries<'a>(&'a self, name: &str, include_subcategories: bool)
+        -> Result<CategoryEntryIterator<'a>>
+    {
+        use libimagentrylink::internal::InternalLinker;
+
+        let mut names = vec![String::from(name)];
+
+        if include_subcategories {
+            let mut tree = self.get_category_tree()?;
+            let mut i    = 0;
+
+            while i < names.len() {
+                let children = tree.remove(&Some(names[i].clone())).unwrap_or_else(Vec::new);
+                names.extend(children);
+                i += 1;
+            }
+        }
+
+        let mut ids = vec![];
+        for name in names.iter() {
+            let category = self.get_category_by_name(name)?
+                .ok_or_else(|| format_err!("Category '{}' does not exist", name))?;
+
+            for link in category.get_internal_links()? {
+                ids.push(Ok(link.get_store_id().clone()));
+            }
+        }
+
+        let sit = StoreIdIterator::new(Box::new(ids.into_iter()));
+        Ok(CategoryEntryIterator::new(self, sit, names))
+    }
+
+    /// Check `entry` against the constraints of a category and all its parent categories
+    ///
+    /// Returns a description of each violated constraint, so an empty list means the entry may be
+    /// put into the category.
+    fn check_category_constraints(&self, name: &str, entry: &Entry) -> Result<Vec<String>> {
+        let mut violations = vec![];
+
+        for category_name in Some(String::from(name)).into_iter().chain(self.get_category_ancestors(name)?) {
+            let category = self.get_category_by_name(&category_name)?
+                .ok_or_else(|| format_err!("Category '{}' does not exist", category_name))?;
+
+            for violation in category.get_constraints()?.violations(entry)? {
+                violations.push(format!("{} (required by category '{}')", violation, category_name));
+            }
+        }
+
+        Ok(violations)
+    }
 }
 
 #[cfg(test)]
@@ -209,6 +397,91 @@ mod tests {
             None        => assert!(false, "Header field not present"),
         }
     }
+
+    #[test]
+    fn test_creating_subcategory() {
+        let store = get_store();
+        let _ = store.create_category("parent").unwrap();
+        let _ = store.create_subcategory("child", "parent").unwrap();
+
+        assert_eq!(Some(String::from("parent")), store.get_category_parent("child").unwrap());
+        assert_eq!(vec![String::from("child")], store.get_subcategories("parent").unwrap());
+        assert_eq!(vec![String::from("parent")], store.get_root_categories().unwrap());
+        assert!(store.create_subcategory("orphan", "nonexistent").is_err());
+    }
+
+    #[test]
+    fn test_category_tree() {
+        let store = get_store();
+        let _ = store.create_category("b").unwrap();
+        let _ = store.create_category("a").unwrap();
+        let _ = store.create_subcategory("a2", "a").unwrap();
+        let _ = store.create_subcategory("a1", "a").unwrap();
+        let _ = store.create_subcategory("a11", "a1").unwrap();
+
+        let tree = store.get_category_tree().unwrap();
+        assert_eq!(3, tree.len());
+        assert_eq!(Some(&vec![String::from("a"), String::from("b")]), tree.get(&None));
+        assert_eq!(Some(&vec![String::from("a1"), String::from("a2")]), tree.get(&Some(String::from("a"))));
+        assert_eq!(Some(&vec![String::from("a11")]), tree.get(&Some(String::from("a1"))));
+        assert!(store.get_subcategories("b").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_category_parent_cycle_is_rejected() {
+        let store = get_store();
+        let _ = store.create_category("a").unwrap();
+        let _ = store.create_subcategory("b", "a").unwrap();
+        let _ = store.create_subcategory("c", "b").unwrap();
+
+        assert!(store.set_category_parent("a", Some("c")).is_err());
+        assert!(store.set_category_parent("a", Some("a")).is_err());
+        assert!(store.set_category_parent("c", None).is_ok());
+        assert_eq!(None, store.get_category_parent("c").unwrap());
+    }
+
+    #[test]
+    fn test_category_constraints_are_inherited() {
+        use category::Constraints;
+        use entry::EntryCategory;
+
+        let store = get_store();
+        {
+            let mut parent = store.create_category("parent").unwrap();
+            let constraints = Constraints::new(vec![String::from("todo.due")], vec![]);
+            let _ = parent.set_constraints(&constraints).unwrap();
+        }
+        let _ = store.create_subcategory("child", "parent").unwrap();
+
+        let mut entry = store.create(PathBuf::from("notes/example")).unwrap();
+        assert!(entry.set_category_checked(&store, "child").is_err());
+        assert!(!entry.has_category().unwrap());
+
+        let _ = entry.get_header_mut().insert("todo.due", Value::String(String::from("2018-01-01"))).unwrap();
+        assert!(entry.set_category_checked(&store, "child").is_ok());
+    }
+
+    #[test]
+    fn test_category_entries_with_subcategories() {
+        use entry::EntryCategory;
+
+        let store = get_store();
+        let _ = store.create_category("parent").unwrap();
+        let _ = store.create_subcategory("child", "parent").unwrap();
+
+        {
+            let mut a = store.create(PathBuf::from("notes/a")).unwrap();
+            let _ = a.set_category_checked(&store, "parent").unwrap();
+            let mut b = store.create(PathBuf::from("notes/b")).unwrap();
+            let _ = b.set_category_checked(&store, "child").unwrap();
+        }
+
+        let direct = store.get_category_entries("parent", false).unwrap().count();
+        let all    = store.get_category_entries("parent", true).unwrap().count();
+
+        assert_eq!(1, direct);
+        assert_eq!(2, all);
+    }
 }
 
 #[inline]
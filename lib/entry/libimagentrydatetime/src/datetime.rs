@@ -42,6 +42,11 @@ pub trait EntryDate {
     fn read_date_range(&self) -> Result<DateTimeRange>;
     fn set_date_range(&mut self, start: NaiveDateTime, end: NaiveDateTime) -> Result<Option<Result<DateTimeRange>>>;
 
+    /// Read the date or, if the entry has none, the date range
+    ///
+    /// Returns `Ok(None)` if the entry has neither.
+    fn read_date_or_range(&self) -> Result<Option<DateOrRange>>;
+
 }
 
 lazy_static! {
@@ -136,7 +141,7 @@ impl EntryDate for Entry {
 
         let end = self
             .get_header()
-            .read_string(&DATE_RANGE_START_HEADER_LOCATION)
+            .read_string(&DATE_RANGE_END_HEADER_LOCATION)
             .context("Error reading Datetime range")?
             .ok_or_else(|| err_msg("Error reading date"))
             .and_then(str_to_ndt)?;
@@ -188,6 +193,61 @@ impl EntryDate for Entry {
         }
     }
 
+    fn read_date_or_range(&self) -> Result<Option<DateOrRange>> {
+        DateOrRange::from_header(self.get_header())
+    }
+
+}
+
+/// The date or the date range of an entry
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum DateOrRange {
+    Date(NaiveDateTime),
+    Range(DateTimeRange),
+}
+
+impl DateOrRange {
+
+    /// Read the date or, if there is none, the date range from the header of an entry
+    pub fn from_header(hdr: &Value) -> Result<Option<DateOrRange>> {
+        if let Some(date) = hdr.read_string(&DATE_HEADER_LOCATION).context("Error while reading date")? {
+            return str_to_ndt(date).map(DateOrRange::Date).map(Some)
+        }
+
+        let start = hdr.read_string(&DATE_RANGE_START_HEADER_LOCATION).context("Error reading Datetime range")?;
+        let end   = hdr.read_string(&DATE_RANGE_END_HEADER_LOCATION).context("Error reading Datetime range")?;
+
+        match (start, end) {
+            (Some(start), Some(end)) => DateTimeRange::new(str_to_ndt(start)?, str_to_ndt(end)?)
+                .map(DateOrRange::Range)
+                .map(Some),
+            (None, None) => Ok(None),
+            _            => Err(err_msg("Datetime range is missing its start or its end")),
+        }
+    }
+
+    pub fn start(&self) -> &NaiveDateTime {
+        match *self {
+            DateOrRange::Date(ref d)  => d,
+            DateOrRange::Range(ref r) => r.start(),
+        }
+    }
+
+    pub fn end(&self) -> &NaiveDateTime {
+        match *self {
+            DateOrRange::Date(ref d)  => d,
+            DateOrRange::Range(ref r) => r.end(),
+        }
+    }
+
+    /// Check whether the date lies in `window` or the range overlaps it, the borders included
+    pub fn overlaps(&self, window: &DateTimeRange) -> bool {
+        match *self {
+            DateOrRange::Date(ref d)  => window.contains(d),
+            DateOrRange::Range(ref r) => r.overlaps(window),
+        }
+    }
+
 }
 
 #[inline]
@@ -325,5 +385,23 @@ mod tests {
 
         assert!(hdr_field.is_none());
     }
+
+    #[test]
+    fn test_read_date_or_range() {
+        let store = get_store();
+        let start = NaiveDateTime::new(NaiveDate::from_ymd(2000, 01, 02), NaiveTime::from_hms(03, 04, 05));
+        let end   = NaiveDateTime::new(NaiveDate::from_ymd(2000, 01, 03), NaiveTime::from_hms(03, 04, 05));
+
+        let mut entry = store.create(PathBuf::from("test")).unwrap();
+        assert_eq!(None, entry.read_date_or_range().unwrap());
+
+        let _ = entry.set_date_range(start, end).unwrap();
+        let range = DateTimeRange::new(start, end).unwrap();
+        assert_eq!(range, entry.read_date_range().unwrap());
+        assert_eq!(Some(DateOrRange::Range(range)), entry.read_date_or_range().unwrap());
+
+        let _ = entry.set_date(start).unwrap();
+        assert_eq!(Some(DateOrRange::Date(start)), entry.read_date_or_range().unwrap());
+    }
 }
 
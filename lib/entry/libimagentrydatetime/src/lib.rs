@@ -38,15 +38,17 @@
 )]
 
 #[macro_use] extern crate lazy_static;
+#[macro_use] extern crate log;
 extern crate chrono;
 extern crate toml_query;
 extern crate toml;
-extern crate failure;
+#[macro_use] extern crate failure;
 
 extern crate libimagerror;
 extern crate libimagstore;
 
 pub mod datepath;
 pub mod datetime;
+pub mod query;
 pub mod range;
 
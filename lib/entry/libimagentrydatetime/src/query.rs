@@ -0,0 +1,131 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+use libimagstore::store::Store;
+use libimagstore::storeid::StoreId;
+
+use failure::Fallible as Result;
+
+use datetime::DateOrRange;
+use datetime::EntryDate;
+use range::DateTimeRange;
+
+/// Queries for entries by their date or date range
+///
+/// All entries in the store are inspected, so none of them may be held by the caller.
+pub trait DateQuery {
+
+    /// Get the entries whose date lies in `window` or whose date range overlaps it
+    ///
+    /// The entries are ordered chronologically, by the start of their date range. The date or
+    /// range is returned along with the id of each entry. Entries with a malformed date or range
+    /// are skipped with a warning.
+    fn entries_dated_between(&self, window: &DateTimeRange) -> Result<Vec<(StoreId, DateOrRange)>>;
+
+}
+
+impl DateQuery for Store {
+
+    fn entries_dated_between(&self, window: &DateTimeRange) -> Result<Vec<(StoreId, DateOrRange)>> {
+        let mut found = vec![];
+
+        for id in self.entries()?.without_store() {
+            let id = id?;
+            if let Some(entry) = self.get(id.clone())? {
+                match entry.read_date_or_range() {
+                    Ok(Some(ref d)) if d.overlaps(window) => found.push((id, d.clone())),
+                    Ok(_)                                 => continue,
+                    Err(e)                                => warn!("Ignoring malformed date of {}: {}", id, e),
+                }
+            }
+        }
+
+        found.sort_by(|a, b| a.1.start().cmp(b.1.start()).then_with(|| a.1.end().cmp(b.1.end())));
+        Ok(found)
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    use chrono::naive::NaiveDate;
+
+    use libimagstore::store::Store;
+
+    use super::*;
+
+    pub fn get_store() -> Store {
+        use libimagstore::store::InMemoryFileAbstraction;
+        let backend = Arc::new(InMemoryFileAbstraction::default());
+        Store::new_with_backend(PathBuf::from("/"), &None, backend).unwrap()
+    }
+
+    #[test]
+    fn test_entries_dated_between() {
+        let store = get_store();
+
+        {
+            let mut note = store.create(PathBuf::from("note")).unwrap();
+            let _ = note.set_date(NaiveDate::from_ymd(2019, 05, 08).and_hms(10, 0, 0)).unwrap();
+
+            let mut trip = store.create(PathBuf::from("trip")).unwrap();
+            let _ = trip.set_date_range(NaiveDate::from_ymd(2019, 05, 01).and_hms(8, 0, 0),
+                                        NaiveDate::from_ymd(2019, 05, 07).and_hms(18, 0, 0)).unwrap();
+
+            let mut old = store.create(PathBuf::from("old")).unwrap();
+            let _ = old.set_date(NaiveDate::from_ymd(2019, 04, 01).and_hms(10, 0, 0)).unwrap();
+
+            let _ = store.create(PathBuf::from("undated")).unwrap();
+        }
+
+        let week  = DateTimeRange::parse("2019-05-06", "2019-05-12").unwrap();
+        let found = store.entries_dated_between(&week).unwrap();
+        let ids   = found.iter().map(|&(ref id, _)| id.local().clone()).collect::<Vec<_>>();
+
+        assert_eq!(vec![PathBuf::from("trip"), PathBuf::from("note")], ids);
+    }
+
+    #[test]
+    fn test_entries_dated_between_skips_malformed_dates() {
+        use toml::Value;
+        use toml_query::insert::TomlValueInsertExt;
+
+        let store = get_store();
+
+        {
+            let mut note = store.create(PathBuf::from("note")).unwrap();
+            let _ = note.set_date(NaiveDate::from_ymd(2019, 05, 08).and_hms(10, 0, 0)).unwrap();
+
+            let mut broken = store.create(PathBuf::from("broken")).unwrap();
+            let _ = broken
+                .get_header_mut()
+                .insert("datetime.value", Value::String(String::from("yesterday")))
+                .unwrap();
+        }
+
+        let week  = DateTimeRange::parse("2019-05-06", "2019-05-12").unwrap();
+        let found = store.entries_dated_between(&week).unwrap();
+
+        assert_eq!(1, found.len());
+        assert_eq!(PathBuf::from("note"), *found[0].0.local());
+    }
+}
@@ -17,6 +17,7 @@
 // Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
 //
 
+use chrono::naive::NaiveDate;
 use chrono::naive::NaiveDateTime;
 
 use failure::Fallible as Result;
@@ -43,6 +44,45 @@ impl DateTimeRange {
         }
     }
 
+    /// Parse a range from two dates, each either "YYYY-MM-DD" or "YYYY-MM-DDTHH:MM:SS"
+    ///
+    /// A date without time covers the whole day, so "2019-05-06" to "2019-05-12" is the week from
+    /// monday morning until the end of sunday.
+    pub fn parse(start: &str, end: &str) -> Result<DateTimeRange> {
+        let parse = |s: &str, end_of_day: bool| {
+            s.parse::<NaiveDateTime>()
+                .or_else(|_| {
+                    s.parse::<NaiveDate>()
+                        .map(|d| if end_of_day { d.and_hms(23, 59, 59) } else { d.and_hms(0, 0, 0) })
+                })
+                .map_err(|_| format_err!("Cannot parse date: '{}'", s))
+        };
+
+        DateTimeRange::new(parse(start, false)?, parse(end, true)?)
+    }
+
+    pub fn start(&self) -> &NaiveDateTime {
+        &self.0
+    }
+
+    pub fn end(&self) -> &NaiveDateTime {
+        &self.1
+    }
+
+    /// Check whether two ranges overlap, the borders included
+    pub fn overlaps(&self, other: &DateTimeRange) -> bool {
+        self.overlaps_between(&other.0, &other.1)
+    }
+
+    /// Check whether `date` lies in the range, the borders included
+    pub fn contains(&self, date: &NaiveDateTime) -> bool {
+        self.overlaps_between(date, date)
+    }
+
+    fn overlaps_between(&self, start: &NaiveDateTime, end: &NaiveDateTime) -> bool {
+        self.0 <= *end && *start <= self.1
+    }
+
 }
 
 #[cfg(test)]
@@ -87,4 +127,38 @@ mod tests {
 
         assert!(res.is_ok());
     }
+
+    #[test]
+    fn test_parse_covers_whole_days() {
+        let range = DateTimeRange::parse("2019-05-06", "2019-05-12").unwrap();
+
+        assert_eq!(NaiveDate::from_ymd(2019, 05, 06).and_hms(0, 0, 0), *range.start());
+        assert_eq!(NaiveDate::from_ymd(2019, 05, 12).and_hms(23, 59, 59), *range.end());
+        assert!(DateTimeRange::parse("2019-05-06T12:00:00", "2019-05-06T11:00:00").is_err());
+        assert!(DateTimeRange::parse("last week", "2019-05-12").is_err());
+    }
+
+    #[test]
+    fn test_overlaps() {
+        let week = DateTimeRange::parse("2019-05-06", "2019-05-12").unwrap();
+        let inside = DateTimeRange::parse("2019-05-07", "2019-05-08").unwrap();
+        let around = DateTimeRange::parse("2019-05-01", "2019-05-31").unwrap();
+        let before = DateTimeRange::parse("2019-04-01", "2019-05-05").unwrap();
+        let border = DateTimeRange::parse("2019-05-12T23:59:59", "2019-05-13").unwrap();
+
+        assert!(week.overlaps(&inside));
+        assert!(week.overlaps(&around));
+        assert!(week.overlaps(&border));
+        assert!(!week.overlaps(&before));
+    }
+
+    #[test]
+    fn test_contains() {
+        let week = DateTimeRange::parse("2019-05-06", "2019-05-12").unwrap();
+
+        assert!(week.contains(&NaiveDate::from_ymd(2019, 05, 06).and_hms(0, 0, 0)));
+        assert!(week.contains(&NaiveDate::from_ymd(2019, 05, 12).and_hms(23, 59, 59)));
+        assert!(!week.contains(&NaiveDate::from_ymd(2019, 05, 05).and_hms(23, 59, 59)));
+        assert!(!week.contains(&NaiveDate::from_ymd(2019, 05, 13).and_hms(0, 0, 0)));
+    }
 }
@@ -0,0 +1,65 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Filters for the date or date range of an entry, see `libimagentrydatetime`
+
+use toml::Value;
+use filters::filter::Filter;
+
+use libimagstore::store::Entry;
+use libimagentrydatetime::datetime::DateOrRange;
+use libimagentrydatetime::range::DateTimeRange;
+
+/// Check whether the date of an Entry lies in a window, or its date range overlaps the window
+///
+/// Entries without date or date range never pass. The filter can also be applied to the header of
+/// an entry.
+pub struct DatedBetween {
+    window: DateTimeRange,
+}
+
+impl DatedBetween {
+
+    pub fn new(window: DateTimeRange) -> DatedBetween {
+        DatedBetween {
+            window: window,
+        }
+    }
+
+}
+
+impl Filter<Value> for DatedBetween {
+
+    fn filter(&self, header: &Value) -> bool {
+        DateOrRange::from_header(header)
+            .ok()
+            .and_then(|d| d)
+            .map(|d| d.overlaps(&self.window))
+            .unwrap_or(false)
+    }
+
+}
+
+impl Filter<Entry> for DatedBetween {
+
+    fn filter(&self, e: &Entry) -> bool {
+        <Self as Filter<Value>>::filter(self, e.get_header())
+    }
+
+}
@@ -40,10 +40,11 @@ extern crate regex;
 extern crate semver;
 extern crate toml;
 extern crate toml_query;
-extern crate failure;
+#[macro_use] extern crate failure;
 
 extern crate libimagstore;
 extern crate libimagentrytag;
+extern crate libimagentrydatetime;
 extern crate libimagerror;
 
 // core functionality modules of the crate,
@@ -55,4 +56,5 @@ pub mod builtin;
 // these depend on other internal libraries than libimagstore and use the upper core modules for
 // their functionality
 
+pub mod datetime;
 pub mod tags;
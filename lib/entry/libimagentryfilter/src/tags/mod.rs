@@ -17,13 +17,18 @@
 // Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
 //
 
-use libimagstore::store::Entry;
+pub mod query;
+
 use libimagentrytag::tagable::Tagable;
 use libimagentrytag::tag::Tag;
 
 use filters::filter::Filter;
 
 /// Check whether an Entry has a certain tag
+///
+/// The tags of the filters in this module are patterns, so `HasTag::new("project/**")` matches
+/// all entries with a tag in the "project" subtree (see `libimagentrytag::tag::tag_matches()`).
+/// The filters can also be applied to the header of an entry.
 pub struct HasTag {
     tag: Tag,
 }
@@ -38,10 +43,10 @@ impl HasTag {
 
 }
 
-impl Filter<Entry> for HasTag {
+impl<T: Tagable> Filter<T> for HasTag {
 
-    fn filter(&self, e: &Entry) -> bool {
-        e.has_tag(&self.tag).ok().unwrap_or(false)
+    fn filter(&self, e: &T) -> bool {
+        e.has_tag_matching(&self.tag).ok().unwrap_or(false)
     }
 
 }
@@ -62,10 +67,10 @@ impl HasAllTags {
 
 }
 
-impl Filter<Entry> for HasAllTags {
+impl<T: Tagable> Filter<T> for HasAllTags {
 
-    fn filter(&self, e: &Entry) -> bool {
-        e.has_tags(&self.tags).ok().unwrap_or(false)
+    fn filter(&self, e: &T) -> bool {
+        self.tags.iter().all(|tag| e.has_tag_matching(tag).ok().unwrap_or(false))
     }
 
 }
@@ -86,10 +91,10 @@ impl HasAnyTags {
 
 }
 
-impl Filter<Entry> for HasAnyTags {
+impl<T: Tagable> Filter<T> for HasAnyTags {
 
-    fn filter(&self, e: &Entry) -> bool {
-        self.tags.iter().any(|tag| e.has_tag(tag).ok().unwrap_or(false))
+    fn filter(&self, e: &T) -> bool {
+        self.tags.iter().any(|tag| e.has_tag_matching(tag).ok().unwrap_or(false))
     }
 
 }
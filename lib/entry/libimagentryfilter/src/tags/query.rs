@@ -0,0 +1,373 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+
+//! A query language for tags
+//!
+//! A query is a boolean expression over tag patterns, for example
+//! `(work and urgent) or (home and not done)`. `not` binds stronger than `and`, which binds
+//! stronger than `or`. The tags in a query are patterns, so `project/**` matches all tags in the
+//! "project" subtree (see `libimagentrytag::tag::tag_matches()`).
+
+use std::str::FromStr;
+use std::iter::Peekable;
+use std::str::Chars;
+
+use libimagentrytag::tagable::Tagable;
+use libimagentrytag::tag::Tag;
+use libimagentrytag::tag::is_tag_pattern;
+use libimagentrytag::tag::tag_matches;
+
+use filters::filter::Filter;
+use failure::Fallible as Result;
+use failure::Error;
+
+/// A parsed tag query
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TagQuery {
+    /// Matches if one of the tags matches the pattern
+    Tag(String),
+    Not(Box<TagQuery>),
+    And(Box<TagQuery>, Box<TagQuery>),
+    Or(Box<TagQuery>, Box<TagQuery>),
+}
+
+impl TagQuery {
+
+    /// Parse a query like `(work and urgent) or (home and not done)`
+    pub fn parse(s: &str) -> Result<TagQuery> {
+        let tokens = tokenize(s)?;
+        let mut parser = Parser { tokens: tokens, pos: 0, depth: 0 };
+        let query = parser.parse_or()?;
+
+        match parser.tokens.get(parser.pos) {
+            None        => Ok(query),
+            Some(token) => Err(format_err!("Unexpected {} in tag query '{}'", token, s)),
+        }
+    }
+
+    /// Check whether the query matches a set of tags
+    pub fn matches_tags(&self, tags: &[Tag]) -> bool {
+        match *self {
+            TagQuery::Tag(ref pattern) => tags.iter().any(|t| tag_matches(t, pattern)),
+            TagQuery::Not(ref q)       => !q.matches_tags(tags),
+            TagQuery::And(ref a, ref b) => a.matches_tags(tags) && b.matches_tags(tags),
+            TagQuery::Or(ref a, ref b)  => a.matches_tags(tags) || b.matches_tags(tags),
+        }
+    }
+
+}
+
+impl FromStr for TagQuery {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<TagQuery> {
+        TagQuery::parse(s)
+    }
+}
+
+/// An entry (or its header) matches if its tags match the query
+///
+/// Objects with invalid tags do not match.
+impl<T: Tagable> Filter<T> for TagQuery {
+    fn filter(&self, t: &T) -> bool {
+        t.get_tags().map(|tags| self.matches_tags(&tags)).unwrap_or(false)
+    }
+}
+
+/// validator which can be used by clap to validate that a string is a valid tag query
+pub fn is_tag_query(s: String) -> ::std::result::Result<(), String> {
+    TagQuery::parse(&s).map(|_| ()).map_err(|e| e.to_string())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Open,
+    Close,
+    And,
+    Or,
+    Not,
+    Tag(String),
+}
+
+impl ::std::fmt::Display for Token {
+    fn fmt(&self, fmt: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        match *self {
+            Token::Open       => write!(fmt, "'('"),
+            Token::Close      => write!(fmt, "')'"),
+            Token::And        => write!(fmt, "'and'"),
+            Token::Or         => write!(fmt, "'or'"),
+            Token::Not        => write!(fmt, "'not'"),
+            Token::Tag(ref t) => write!(fmt, "tag '{}'", t),
+        }
+    }
+}
+
+fn tokenize(s: &str) -> Result<Vec<Token>> {
+    let mut chars  = s.chars().peekable();
+    let mut tokens = vec![];
+
+    while let Some(c) = chars.next() {
+        match c {
+            '(' => tokens.push(Token::Open),
+            ')' => tokens.push(Token::Close),
+            c if c.is_whitespace() => continue,
+            c => {
+                let word = read_word(c, &mut chars);
+                let token = match word.as_str() {
+                    "and" => Token::And,
+                    "or"  => Token::Or,
+                    "not" => Token::Not,
+                    _     => {
+                        let _ = is_tag_pattern(word.clone()).map_err(|e| format_err!("{}", e))?;
+                        Token::Tag(word)
+                    },
+                };
+                tokens.push(token);
+            },
+        }
+    }
+
+    Ok(tokens)
+}
+
+fn read_word(first: char, chars: &mut Peekable<Chars>) -> String {
+    let mut word = first.to_string();
+
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() || c == '(' || c == ')' {
+            break;
+        }
+        word.push(c);
+        let _ = chars.next();
+    }
+
+    word
+}
+
+/// The maximum nesting depth of a query
+///
+/// The parser and the evaluation of a query are recursive, so this keeps queries like
+/// `not not not ...` from overflowing the stack.
+const MAX_DEPTH : usize = 128;
+
+/// A recursive descent parser for the grammar
+///
+/// ```text
+/// or    := and ("or" and)*
+/// and   := unary ("and" unary)*
+/// unary := "not" unary | "(" or ")" | tag
+/// ```
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+
+    /// The nesting depth of the query node which is currently parsed
+    depth: usize,
+}
+
+impl Parser {
+
+    fn enter(&mut self) -> Result<()> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            Err(format_err!("Tag query is nested too deeply, at most {} levels are allowed", MAX_DEPTH))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn next_token(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn next_is(&self, token: &Token) -> bool {
+        self.tokens.get(self.pos) == Some(token)
+    }
+
+    // Each operator in a chain like `a or b or c` nests the query one level deeper, so the chains
+    // count towards the depth as well.
+    fn parse_or(&mut self) -> Result<TagQuery> {
+        let depth     = self.depth;
+        let mut query = self.parse_and()?;
+        while self.next_is(&Token::Or) {
+            self.pos += 1;
+            self.enter()?;
+            query = TagQuery::Or(Box::new(query), Box::new(self.parse_and()?));
+        }
+        self.depth = depth;
+        Ok(query)
+    }
+
+    fn parse_and(&mut self) -> Result<TagQuery> {
+        let depth     = self.depth;
+        let mut query = self.parse_unary()?;
+        while self.next_is(&Token::And) {
+            self.pos += 1;
+            self.enter()?;
+            query = TagQuery::And(Box::new(query), Box::new(self.parse_unary()?));
+        }
+        self.depth = depth;
+        Ok(query)
+    }
+
+    fn parse_unary(&mut self) -> Result<TagQuery> {
+        match self.next_token() {
+            Some(Token::Not)    => {
+                self.enter()?;
+                let query = TagQuery::Not(Box::new(self.parse_unary()?));
+                self.depth -= 1;
+                Ok(query)
+            },
+            Some(Token::Tag(t)) => Ok(TagQuery::Tag(t)),
+            Some(Token::Open)   => {
+                self.enter()?;
+                let query = self.parse_or()?;
+                self.depth -= 1;
+                match self.next_token() {
+                    Some(Token::Close) => Ok(query),
+                    Some(other)        => Err(format_err!("Expected ')', found {}", other)),
+                    None               => Err(format_err!("Expected ')', found end of query")),
+                }
+            },
+            Some(other) => Err(format_err!("Expected a tag, 'not' or '(', found {}", other)),
+            None        => Err(format_err!("Expected a tag, 'not' or '(', found end of query")),
+        }
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TagQuery;
+
+    fn tag(t: &str) -> Box<TagQuery> {
+        Box::new(TagQuery::Tag(String::from(t)))
+    }
+
+    fn tags(v: &[&str]) -> Vec<String> {
+        v.iter().map(|s| String::from(*s)).collect()
+    }
+
+    #[test]
+    fn test_single_tag() {
+        assert_eq!(TagQuery::parse("work").unwrap(), *tag("work"));
+        assert_eq!(TagQuery::parse("  work ").unwrap(), *tag("work"));
+    }
+
+    #[test]
+    fn test_and_binds_stronger_than_or() {
+        assert_eq!(TagQuery::parse("a and b or c").unwrap(),
+                   TagQuery::Or(Box::new(TagQuery::And(tag("a"), tag("b"))), tag("c")));
+
+        assert_eq!(TagQuery::parse("a or b and c").unwrap(),
+                   TagQuery::Or(tag("a"), Box::new(TagQuery::And(tag("b"), tag("c")))));
+    }
+
+    #[test]
+    fn test_not_binds_strongest() {
+        assert_eq!(TagQuery::parse("not a and b").unwrap(),
+                   TagQuery::And(Box::new(TagQuery::Not(tag("a"))), tag("b")));
+
+        assert_eq!(TagQuery::parse("not not a").unwrap(),
+                   TagQuery::Not(Box::new(TagQuery::Not(tag("a")))));
+    }
+
+    #[test]
+    fn test_nested_parentheses() {
+        assert_eq!(TagQuery::parse("((a or b)) and c").unwrap(),
+                   TagQuery::And(Box::new(TagQuery::Or(tag("a"), tag("b"))), tag("c")));
+
+        assert_eq!(TagQuery::parse("a and (b or (c and not d))").unwrap(),
+                   TagQuery::And(tag("a"),
+                                 Box::new(TagQuery::Or(tag("b"),
+                                                       Box::new(TagQuery::And(tag("c"),
+                                                                              Box::new(TagQuery::Not(tag("d")))))))));
+    }
+
+    #[test]
+    fn test_unbalanced_parentheses() {
+        assert!(TagQuery::parse("(a and b").is_err());
+        assert!(TagQuery::parse("a and b)").is_err());
+        assert!(TagQuery::parse("((a)").is_err());
+        assert!(TagQuery::parse("()").is_err());
+    }
+
+    #[test]
+    fn test_trailing_and_leading_operators() {
+        assert!(TagQuery::parse("a and").is_err());
+        assert!(TagQuery::parse("a or").is_err());
+        assert!(TagQuery::parse("a not").is_err());
+        assert!(TagQuery::parse("not").is_err());
+        assert!(TagQuery::parse("and a").is_err());
+        assert!(TagQuery::parse("a b").is_err());
+    }
+
+    #[test]
+    fn test_empty_query() {
+        assert!(TagQuery::parse("").is_err());
+        assert!(TagQuery::parse("   ").is_err());
+    }
+
+    #[test]
+    fn test_invalid_tag() {
+        assert!(TagQuery::parse("a and B").is_err());
+    }
+
+    #[test]
+    fn test_depth_limit() {
+        let nots = format!("{}a", "not ".repeat(10_000));
+        assert!(TagQuery::parse(&nots).is_err());
+
+        let parens = format!("{}a{}", "(".repeat(10_000), ")".repeat(10_000));
+        assert!(TagQuery::parse(&parens).is_err());
+
+        let chain = vec!["a"; 10_000].join(" or ");
+        assert!(TagQuery::parse(&chain).is_err());
+
+        let ok = format!("{}a{}", "(".repeat(50), ")".repeat(50));
+        assert_eq!(TagQuery::parse(&ok).unwrap(), *tag("a"));
+
+        let ok = format!("{}a", "not ".repeat(50));
+        assert!(TagQuery::parse(&ok).is_ok());
+    }
+
+    #[test]
+    fn test_matches_tags() {
+        let q = TagQuery::parse("(work and urgent) or (home and not done)").unwrap();
+
+        assert!(q.matches_tags(&tags(&["work", "urgent"])));
+        assert!(q.matches_tags(&tags(&["home"])));
+        assert!(!q.matches_tags(&tags(&["home", "done"])));
+        assert!(!q.matches_tags(&tags(&["work"])));
+        assert!(!q.matches_tags(&[]));
+    }
+
+    #[test]
+    fn test_matches_tag_patterns() {
+        let q = TagQuery::parse("project/** and not project/old/**").unwrap();
+
+        assert!(q.matches_tags(&tags(&["project/imag"])));
+        assert!(!q.matches_tags(&tags(&["project/old/foo"])));
+        assert!(!q.matches_tags(&tags(&["projects"])));
+    }
+
+}
@@ -21,10 +21,9 @@ use types::*;
 
 use libimagstore::store::Entry;
 
+use toml::Value;
 use toml_query::read::TomlValueReadExt;
 use toml_query::insert::TomlValueInsertExt;
-use toml_query::delete::TomlValueDeleteExt;
-use failure::ResultExt;
 use failure::Fallible as Result;
 use failure::Error;
 
@@ -73,24 +72,20 @@ impl GPSEntry for Entry {
     fn remove_coordinates(&mut self) -> Result<Option<Result<Coordinates>>> {
         let coordinates = self.get_coordinates();
 
-        let patterns = [
-            "gps.coordinates.latitude.degree",
-            "gps.coordinates.latitude.minutes",
-            "gps.coordinates.latitude.seconds",
-            "gps.coordinates.longitude.degree",
-            "gps.coordinates.longitude.minutes",
-            "gps.coordinates.longitude.seconds",
-            "gps.coordinates.latitude",
-            "gps.coordinates.longitude",
-            "gps.coordinates",
-            "gps",
-        ];
-
-        let hdr = self.get_header_mut();
-        for pattern in patterns.iter() {
-            let _ = hdr.delete(pattern)
-                .map_err(Error::from)
-                .context("Error writing header")?;
+        // The coordinates have optional fields, so they are removed as a whole instead of deleting
+        // field by field
+        if let Some(table) = self.get_header_mut().as_table_mut() {
+            let gps_is_empty = match table.get_mut("gps").and_then(Value::as_table_mut) {
+                Some(gps) => {
+                    let _ = gps.remove("coordinates");
+                    gps.is_empty()
+                },
+                None => false,
+            };
+
+            if gps_is_empty {
+                let _ = table.remove("gps");
+            }
         }
 
         match coordinates {
@@ -131,6 +126,7 @@ mod tests {
         let coordinates = Coordinates {
             latitude: GPSValue::new(0, 0, 0),
             longitude: GPSValue::new(0, 0, 0),
+            altitude: None,
         };
 
         let res = entry.set_coordinates(coordinates);
@@ -149,6 +145,7 @@ mod tests {
         let coordinates = Coordinates {
             latitude: GPSValue::new(0, 0, 0),
             longitude: GPSValue::new(0, 0, 0),
+            altitude: None,
         };
 
         let res = entry.set_coordinates(coordinates);
@@ -164,10 +161,10 @@ mod tests {
 
         assert_eq!(0, coordinates.longitude.degree);
         assert_eq!(0, coordinates.longitude.minutes);
-        assert_eq!(0, coordinates.longitude.seconds);
+        assert_eq!(0.0, coordinates.longitude.seconds);
         assert_eq!(0, coordinates.latitude.degree);
         assert_eq!(0, coordinates.latitude.minutes);
-        assert_eq!(0, coordinates.latitude.seconds);
+        assert_eq!(0.0, coordinates.latitude.seconds);
     }
 }
 
@@ -0,0 +1,119 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Writing GeoJSON
+//!
+//! Each point becomes a GeoJSON "Feature" with a "Point" geometry. The name and the time of the
+//! point are written as properties of the feature, the time as RFC 3339 timestamp in UTC.
+
+use serde_json::Value;
+
+use failure::Fallible as Result;
+
+use gpx::Waypoint;
+use gpx::format_time;
+
+/// Build a GeoJSON "FeatureCollection" from `points`
+///
+/// Fails if the time of a point does not exist in the local timezone.
+pub fn to_geojson(points: &[Waypoint]) -> Result<Value> {
+    let features = points
+        .iter()
+        .map(|p| -> Result<Value> {
+            let time = match p.time {
+                Some(ref t) => Some(format_time(t)?),
+                None        => None,
+            };
+
+            let c = &p.coordinates;
+
+            // GeoJSON positions are longitude first
+            let mut position = vec![json!(c.longitude().to_decimal()), json!(c.latitude().to_decimal())];
+            if let Some(altitude) = c.altitude() {
+                position.push(json!(altitude));
+            }
+
+            Ok(json!({
+                "type": "Feature",
+                "geometry": {
+                    "type": "Point",
+                    "coordinates": position,
+                },
+                "properties": {
+                    "name": p.name,
+                    "time": time,
+                },
+            }))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(json!({
+        "type": "FeatureCollection",
+        "features": features,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::DateTime;
+    use chrono::Local;
+    use chrono::NaiveDate;
+
+    use super::*;
+    use types::Coordinates;
+
+    #[test]
+    fn test_to_geojson() {
+        let time  = NaiveDate::from_ymd(2019, 5, 1).and_hms(8, 0, 0);
+        let point = Waypoint {
+            name:        Some(String::from("munich")),
+            coordinates: Coordinates::from_decimal(48.1372, 11.5756).unwrap().with_altitude(520.0),
+            time:        Some(time),
+        };
+
+        let geojson = to_geojson(&[point]).unwrap();
+        assert_eq!(Some("FeatureCollection"), geojson["type"].as_str());
+
+        let feature  = &geojson["features"][0];
+        let position = feature["geometry"]["coordinates"].as_array().unwrap();
+        assert_eq!(Some("munich"), feature["properties"]["name"].as_str());
+        assert_eq!(3, position.len());
+        assert!((position[0].as_f64().unwrap() - 11.5756).abs() < 1e-6);
+        assert!((position[1].as_f64().unwrap() - 48.1372).abs() < 1e-6);
+        assert_eq!(Some(520.0), position[2].as_f64());
+
+        // The timestamp has an offset, and is the same point in time
+        let timestamp = feature["properties"]["time"].as_str().unwrap();
+        let parsed    = DateTime::parse_from_rfc3339(timestamp).unwrap();
+        assert_eq!(time, parsed.with_timezone(&Local).naive_local());
+    }
+
+    #[test]
+    fn test_to_geojson_without_time() {
+        let point = Waypoint {
+            name:        None,
+            coordinates: Coordinates::from_decimal(48.1372, 11.5756).unwrap(),
+            time:        None,
+        };
+
+        let geojson = to_geojson(&[point]).unwrap();
+        assert!(geojson["features"][0]["properties"]["time"].is_null());
+        assert_eq!(2, geojson["features"][0]["geometry"]["coordinates"].as_array().unwrap().len());
+    }
+}
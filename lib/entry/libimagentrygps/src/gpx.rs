@@ -0,0 +1,252 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Reading and writing GPX files
+//!
+//! Only the parts of GPX imag cares about are supported: waypoints, tracks and routes, each point
+//! with its coordinates, elevation, time and name. Routes are treated like tracks, the segments
+//! of a track are joined.
+
+use std::io::Read;
+use std::io::Write;
+
+use chrono::DateTime;
+use chrono::Local;
+use chrono::NaiveDateTime;
+use chrono::TimeZone;
+use chrono::Utc;
+use xml::reader::EventReader;
+use xml::reader::XmlEvent;
+use xml::attribute::OwnedAttribute;
+
+use failure::Fallible as Result;
+use failure::Error;
+
+use types::Coordinates;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Waypoint {
+    pub name:        Option<String>,
+    pub coordinates: Coordinates,
+
+    /// The time the point was recorded, in local time
+    pub time:        Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Track {
+    pub name:   Option<String>,
+    pub points: Vec<Waypoint>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Gpx {
+    pub waypoints: Vec<Waypoint>,
+    pub tracks:    Vec<Track>,
+}
+
+impl Gpx {
+
+    pub fn parse<R: Read>(r: R) -> Result<Gpx> {
+        let mut gpx   = Gpx::default();
+        let mut track = None;
+        let mut point = None;
+        let mut text  = String::new();
+
+        for event in EventReader::new(r) {
+            match event? {
+                XmlEvent::StartElement { name, attributes, .. } => {
+                    text.clear();
+                    match name.local_name.as_str() {
+                        "wpt" | "trkpt" | "rtept" => point = Some(parse_point(&attributes)?),
+                        "trk" | "rte"             => track = Some(Track::default()),
+                        _                         => {},
+                    }
+                },
+
+                XmlEvent::Characters(s) | XmlEvent::CData(s) => text.push_str(&s),
+
+                XmlEvent::EndElement { name } => {
+                    let text = text.trim();
+                    match (name.local_name.as_str(), point.as_mut(), track.as_mut()) {
+                        ("ele", Some(p), _) => {
+                            let altitude = text.parse::<f64>()
+                                .map_err(|_| format_err!("Invalid elevation: '{}'", text))?;
+                            p.coordinates.altitude = Some(altitude);
+                        },
+                        ("time", Some(p), _)  => p.time = Some(parse_time(text)?),
+                        ("name", Some(p), _)  => p.name = Some(String::from(text)),
+                        ("name", None, Some(t)) => t.name = Some(String::from(text)),
+                        _                     => {},
+                    }
+
+                    match name.local_name.as_str() {
+                        "wpt" => gpx.waypoints.extend(point.take()),
+                        "trkpt" | "rtept" => match (point.take(), track.as_mut()) {
+                            (Some(p), Some(t)) => t.points.push(p),
+                            _                  => return Err(format_err!("Track point outside of a track")),
+                        },
+                        "trk" | "rte" => gpx.tracks.extend(track.take()),
+                        _             => {},
+                    }
+                },
+
+                _ => {},
+            }
+        }
+
+        Ok(gpx)
+    }
+
+    pub fn write<W: Write>(&self, w: &mut W) -> Result<()> {
+        writeln!(w, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(w, r#"<gpx version="1.1" creator="imag" xmlns="http://www.topografix.com/GPX/1/1">"#)?;
+
+        for waypoint in self.waypoints.iter() {
+            write_point(w, "wpt", waypoint, 1)?;
+        }
+
+        for track in self.tracks.iter() {
+            writeln!(w, "  <trk>")?;
+            if let Some(ref name) = track.name {
+                writeln!(w, "    <name>{}</name>", escape(name))?;
+            }
+            writeln!(w, "    <trkseg>")?;
+            for point in track.points.iter() {
+                write_point(w, "trkpt", point, 3)?;
+            }
+            writeln!(w, "    </trkseg>")?;
+            writeln!(w, "  </trk>")?;
+        }
+
+        writeln!(w, "</gpx>").map_err(Error::from)
+    }
+
+}
+
+fn parse_point(attributes: &[OwnedAttribute]) -> Result<Waypoint> {
+    let attr = |what: &str| -> Result<f64> {
+        let value = attributes
+            .iter()
+            .find(|a| a.name.local_name == what)
+            .map(|a| a.value.as_str())
+            .ok_or_else(|| format_err!("Point without '{}' attribute", what))?;
+
+        value.parse::<f64>().map_err(|_| format_err!("Invalid '{}' attribute: '{}'", what, value))
+    };
+
+    Ok(Waypoint {
+        name:        None,
+        coordinates: Coordinates::from_decimal(attr("lat")?, attr("lon")?)?,
+        time:        None,
+    })
+}
+
+/// Parse a GPX time, which is in UTC, into local time
+fn parse_time(s: &str) -> Result<NaiveDateTime> {
+    DateTime::parse_from_rfc3339(s)
+        .map(|dt| dt.with_timezone(&Local).naive_local())
+        .map_err(|_| format_err!("Invalid time: '{}'", s))
+}
+
+/// Format a local time as RFC 3339 timestamp in UTC, as GPX and GeoJSON use it
+pub(crate) fn format_time(time: &NaiveDateTime) -> Result<String> {
+    Local.from_local_datetime(time)
+        .earliest()
+        .map(|dt| dt.with_timezone(&Utc).format("%Y-%m-%dT%H:%M:%SZ").to_string())
+        .ok_or_else(|| format_err!("Invalid local time: {}", time))
+}
+
+fn write_point<W: Write>(w: &mut W, element: &str, p: &Waypoint, depth: usize) -> Result<()> {
+    let indent = "  ".repeat(depth);
+    writeln!(w, r#"{}<{} lat="{}" lon="{}">"#,
+             indent,
+             element,
+             p.coordinates.latitude().to_decimal(),
+             p.coordinates.longitude().to_decimal())?;
+
+    if let Some(altitude) = p.coordinates.altitude() {
+        writeln!(w, "{}  <ele>{}</ele>", indent, altitude)?;
+    }
+
+    if let Some(ref time) = p.time {
+        writeln!(w, "{}  <time>{}</time>", indent, format_time(time)?)?;
+    }
+
+    if let Some(ref name) = p.name {
+        writeln!(w, "{}  <name>{}</name>", indent, escape(name))?;
+    }
+
+    writeln!(w, "{}</{}>", indent, element).map_err(Error::from)
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE : &'static str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<gpx version="1.1" creator="test" xmlns="http://www.topografix.com/GPX/1/1">
+  <metadata><name>Not a track name</name></metadata>
+  <wpt lat="47.4210" lon="10.9863">
+    <ele>2962</ele>
+    <name>Zugspitze</name>
+  </wpt>
+  <trk>
+    <name>Hike</name>
+    <trkseg>
+      <trkpt lat="47.4920" lon="11.0955"><time>2019-05-01T08:00:00Z</time></trkpt>
+      <trkpt lat="47.4800" lon="11.0600"><time>2019-05-01T09:00:00Z</time></trkpt>
+    </trkseg>
+    <trkseg>
+      <trkpt lat="47.4700" lon="11.0300"/>
+    </trkseg>
+  </trk>
+</gpx>"#;
+
+    #[test]
+    fn test_parse() {
+        let gpx = Gpx::parse(EXAMPLE.as_bytes()).unwrap();
+
+        assert_eq!(1, gpx.waypoints.len());
+        assert_eq!(Some(String::from("Zugspitze")), gpx.waypoints[0].name);
+        assert_eq!(Some(2962.0), gpx.waypoints[0].coordinates.altitude());
+
+        assert_eq!(1, gpx.tracks.len());
+        assert_eq!(Some(String::from("Hike")), gpx.tracks[0].name);
+        assert_eq!(3, gpx.tracks[0].points.len());
+        assert!(gpx.tracks[0].points[0].time.is_some());
+        assert!(gpx.tracks[0].points[2].time.is_none());
+    }
+
+    #[test]
+    fn test_write_roundtrip() {
+        let gpx     = Gpx::parse(EXAMPLE.as_bytes()).unwrap();
+        let mut out = vec![];
+        let _       = gpx.write(&mut out).unwrap();
+
+        assert_eq!(gpx, Gpx::parse(&out[..]).unwrap());
+    }
+}
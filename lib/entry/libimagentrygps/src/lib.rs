@@ -35,17 +35,28 @@
     while_true,
 )]
 
+extern crate chrono;
+#[macro_use] extern crate log;
 extern crate toml;
 extern crate toml_query;
 #[macro_use] extern crate serde_derive;
-extern crate failure;
+#[macro_use] extern crate serde_json;
+#[macro_use] extern crate failure;
+extern crate xml;
 
-extern crate libimagstore;
+#[macro_use] extern crate libimagstore;
 extern crate libimagerror;
+extern crate libimagentrydatetime;
+extern crate libimagentrylink;
 
 #[cfg(test)]
 extern crate env_logger;
 
+module_entry_path_mod!("gps");
+
 pub mod entry;
+pub mod geojson;
+pub mod gpx;
+pub mod store;
 pub mod types;
 
@@ -0,0 +1,295 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+use std::cmp::Ordering;
+
+use toml::Value;
+use toml_query::read::TomlValueReadExt;
+use toml_query::insert::TomlValueInsertExt;
+
+use libimagstore::store::Entry;
+use libimagstore::store::FileLockEntry;
+use libimagstore::store::Store;
+use libimagstore::storeid::IntoStoreId;
+use libimagstore::storeid::StoreId;
+use libimagentrydatetime::datetime::EntryDate;
+use libimagentrylink::internal::InternalLinker;
+
+use failure::Fallible as Result;
+
+use entry::GPSEntry;
+use gpx::Gpx;
+use gpx::Waypoint;
+use module_path::ModuleEntryPath;
+use types::BoundingBox;
+use types::Coordinates;
+
+/// Queries for entries by their coordinates
+///
+/// All entries in the store are inspected, so none of them may be held by the caller.
+pub trait GPSStore {
+
+    /// Get the entries within `radius_km` kilometers of `center`, nearest first
+    ///
+    /// The distance in kilometers is returned along with the id of each entry.
+    fn entries_near(&self, center: &Coordinates, radius_km: f64) -> Result<Vec<(StoreId, f64)>>;
+
+    /// Get the entries inside of `bbox`
+    fn entries_within(&self, bbox: &BoundingBox) -> Result<Vec<StoreId>>;
+
+    /// Get all entries with coordinates for which `filter` returns true, as points for exporting
+    ///
+    /// The name of each point is the id of the entry, its time is the date of the entry (see
+    /// `libimagentrydatetime`), if it has one. Entries with malformed coordinates are skipped with a
+    /// warning.
+    fn geotagged<F>(&self, filter: F) -> Result<Vec<Waypoint>>
+        where F: Fn(&Entry) -> Result<bool>;
+
+    /// Import the waypoints and tracks from `gpx`
+    ///
+    /// A track entry `gps/tracks/<name>` is created, with the time span of the points as date
+    /// range. Each point becomes an entry in `gps/points/<name>/`, with its coordinates and time,
+    /// and is linked to the track entry.
+    fn import_gpx<'a>(&'a self, gpx: &Gpx, name: &str) -> Result<FileLockEntry<'a>>;
+
+}
+
+impl GPSStore for Store {
+
+    fn entries_near(&self, center: &Coordinates, radius_km: f64) -> Result<Vec<(StoreId, f64)>> {
+        let mut found = vec![];
+
+        for (id, coordinates) in with_coordinates(self)? {
+            let distance = center.distance_to(&coordinates);
+            if distance <= radius_km {
+                found.push((id, distance));
+            }
+        }
+
+        found.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(Ordering::Equal));
+        Ok(found)
+    }
+
+    fn entries_within(&self, bbox: &BoundingBox) -> Result<Vec<StoreId>> {
+        with_coordinates(self).map(|v| {
+            v.into_iter()
+                .filter(|&(_, ref coordinates)| bbox.contains(coordinates))
+                .map(|(id, _)| id)
+                .collect()
+        })
+    }
+
+    fn geotagged<F>(&self, filter: F) -> Result<Vec<Waypoint>>
+        where F: Fn(&Entry) -> Result<bool>
+    {
+        let mut points = vec![];
+
+        for id in self.entries()?.without_store() {
+            let id = id?;
+            let entry = match self.get(id.clone())? {
+                Some(entry) => entry,
+                None        => continue,
+            };
+
+            let coordinates = match entry.get_coordinates() {
+                Ok(c)  => c,
+                Err(e) => {
+                    warn!("Ignoring malformed coordinates of {}: {}", id, e);
+                    continue
+                },
+            };
+
+            if let Some(coordinates) = coordinates {
+                if !filter(&entry)? {
+                    continue
+                }
+
+                let time = if entry.get_header().read("datetime.value")?.is_some() {
+                    Some(entry.read_date()?)
+                } else {
+                    None
+                };
+
+                points.push(Waypoint {
+                    name:        Some(id.local_display_string()),
+                    coordinates: coordinates,
+                    time:        time,
+                });
+            }
+        }
+
+        Ok(points)
+    }
+
+    fn import_gpx<'a>(&'a self, gpx: &Gpx, name: &str) -> Result<FileLockEntry<'a>> {
+        let mut track = self.create(ModuleEntryPath::new(format!("tracks/{}", name)).into_storeid()?)?;
+        let _ = track.get_header_mut().insert("gps.track.name", Value::String(String::from(name)))?;
+
+        let points = gpx.waypoints
+            .iter()
+            .map(|p| ("waypoint", None, p))
+            .chain(gpx.tracks.iter().flat_map(|t| {
+                t.points.iter().map(move |p| ("trackpoint", t.name.as_ref(), p))
+            }));
+
+        let mut times = vec![];
+        for (i, (kind, track_name, point)) in points.enumerate() {
+            let id        = ModuleEntryPath::new(format!("points/{}/{:06}", name, i)).into_storeid()?;
+            let mut entry = self.create(id)?;
+            let _         = entry.set_coordinates(point.coordinates.clone())?;
+
+            {
+                let hdr = entry.get_header_mut();
+                let _   = hdr.insert("gps.point.kind", Value::String(String::from(kind)))?;
+
+                if let Some(ref point_name) = point.name {
+                    let _ = hdr.insert("gps.point.name", Value::String(point_name.clone()))?;
+                }
+
+                if let Some(track_name) = track_name {
+                    let _ = hdr.insert("gps.point.track", Value::String(track_name.clone()))?;
+                }
+            }
+
+            if let Some(time) = point.time {
+                let _ = entry.set_date(time)?;
+                times.push(time);
+            }
+
+            let _ = track.add_internal_link(&mut entry)?;
+        }
+
+        match (times.iter().min(), times.iter().max()) {
+            (Some(start), Some(end)) if start < end => { let _ = track.set_date_range(*start, *end)?; },
+            (Some(start), _)                        => { let _ = track.set_date(*start)?; },
+            _                                       => {},
+        }
+
+        Ok(track)
+    }
+
+}
+
+/// Get the ids and coordinates of all entries which have coordinates
+///
+/// Entries with malformed coordinates are skipped with a warning.
+fn with_coordinates(store: &Store) -> Result<Vec<(StoreId, Coordinates)>> {
+    let mut v = vec![];
+
+    for id in store.entries()?.without_store() {
+        let id = id?;
+        if let Some(entry) = store.get(id.clone())? {
+            match entry.get_coordinates() {
+                Ok(Some(coordinates)) => v.push((id, coordinates)),
+                Ok(None)              => {},
+                Err(e)                => warn!("Ignoring malformed coordinates of {}: {}", id, e),
+            }
+        }
+    }
+
+    Ok(v)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    use libimagstore::store::Store;
+
+    use super::*;
+
+    fn get_store() -> Store {
+        use libimagstore::file_abstraction::InMemoryFileAbstraction;
+        let backend = Arc::new(InMemoryFileAbstraction::default());
+        Store::new_with_backend(PathBuf::from("/"), &None, backend).unwrap()
+    }
+
+    #[test]
+    fn test_entries_near_and_within() {
+        let store = get_store();
+
+        for &(name, lat, long) in [("munich", 48.1372, 11.5756), ("berlin", 52.5200, 13.4050)].iter() {
+            let mut entry = store.create(PathBuf::from(name)).unwrap();
+            let _ = entry.set_coordinates(Coordinates::from_decimal(lat, long).unwrap()).unwrap();
+        }
+        let _ = store.create(PathBuf::from("nowhere")).unwrap();
+
+        let augsburg = Coordinates::from_decimal(48.3705, 10.8978).unwrap();
+        let near     = store.entries_near(&augsburg, 100.0).unwrap();
+        assert_eq!(1, near.len());
+        assert_eq!(PathBuf::from("munich"), *near[0].0.local());
+
+        let all = store.entries_near(&augsburg, 1000.0).unwrap();
+        assert_eq!(2, all.len());
+        assert!(all[0].1 < all[1].1);
+
+        let bbox   = "52,13,53,14".parse::<BoundingBox>().unwrap();
+        let within = store.entries_within(&bbox).unwrap();
+        assert_eq!(1, within.len());
+        assert_eq!(PathBuf::from("berlin"), *within[0].local());
+    }
+
+    #[test]
+    fn test_malformed_coordinates_are_skipped() {
+        let store = get_store();
+
+        {
+            let mut entry = store.create(PathBuf::from("munich")).unwrap();
+            let _ = entry.set_coordinates(Coordinates::from_decimal(48.1372, 11.5756).unwrap()).unwrap();
+        }
+        {
+            let mut entry = store.create(PathBuf::from("broken")).unwrap();
+            let _ = entry
+                .get_header_mut()
+                .insert("gps.coordinates", Value::String(String::from("somewhere")))
+                .unwrap();
+        }
+
+        let center = Coordinates::from_decimal(48.1372, 11.5756).unwrap();
+        let near   = store.entries_near(&center, 1.0).unwrap();
+        assert_eq!(1, near.len());
+        assert_eq!(PathBuf::from("munich"), *near[0].0.local());
+    }
+
+    #[test]
+    fn test_import_gpx() {
+        use gpx::Track;
+
+        let store = get_store();
+        let point = |lat, long| Waypoint {
+            name:        None,
+            coordinates: Coordinates::from_decimal(lat, long).unwrap(),
+            time:        None,
+        };
+
+        let gpx = Gpx {
+            waypoints: vec![point(47.4210, 10.9863)],
+            tracks:    vec![Track { name: None, points: vec![point(47.4920, 11.0955), point(47.4800, 11.0600)] }],
+        };
+
+        {
+            let track = store.import_gpx(&gpx, "hike").unwrap();
+            assert_eq!(3, track.get_internal_links().unwrap().count());
+        }
+
+        assert_eq!(3, store.geotagged(|_| Ok(true)).unwrap().len());
+        assert!(store.import_gpx(&gpx, "hike").is_err());
+    }
+}
@@ -21,6 +21,7 @@ use std::collections::BTreeMap;
 use std::fmt::Display;
 use std::fmt::Formatter;
 use std::fmt::Result as FmtResult;
+use std::str::FromStr;
 
 use toml::Value;
 use failure::Fallible as Result;
@@ -29,27 +30,75 @@ use failure::Error;
 
 use libimagerror::errors::ErrorMsg as EM;
 
+/// Mean radius of the earth in kilometers, as used for great-circle distances
+pub const EARTH_RADIUS_KM : f64 = 6371.0088;
+
 pub trait FromValue : Sized {
     fn from_value(v: &Value) -> Result<Self>;
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+/// An angle in degrees, minutes and seconds
+///
+/// `degree` and `minutes` are never negative, the sign of the value is stored separately. So a
+/// latitude in the southern hemisphere or a longitude west of the prime meridian is `negative`.
+///
+/// `seconds` is a float, so that decimal degrees can be stored without losing precision. Because
+/// of that, `GPSValue` does not implement `Eq` anymore.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub struct GPSValue {
-    pub degree:  i64,
-    pub minutes: i64,
-    pub seconds: i64,
+    pub degree:   i64,
+    pub minutes:  i64,
+    pub seconds:  f64,
+    #[serde(default)]
+    pub negative: bool,
 }
 
 impl GPSValue {
 
+    /// Create a value from degree, minutes and seconds
+    ///
+    /// A negative `d` makes the value negative.
     pub fn new(d: i64, m: i64, s: i64) -> GPSValue {
+        GPSValue::from_dms(d < 0, d.abs(), m, s as f64)
+    }
+
+    pub fn from_dms(negative: bool, d: i64, m: i64, s: f64) -> GPSValue {
         GPSValue {
-            degree:  d,
-            minutes: m,
-            seconds: s
+            degree:   d,
+            minutes:  m,
+            seconds:  s,
+            negative: negative,
         }
     }
 
+    /// Create a value from decimal degrees, like `-48.1372`
+    ///
+    /// Seconds are rounded to four decimal places, which is a few millimeters on the ground.
+    /// Fails if `value` is not a number between -180 and 180.
+    pub fn from_decimal(value: f64) -> Result<GPSValue> {
+        // Also rejects NaN
+        if !(value.abs() <= 180.0) {
+            return Err(format_err!("Not a number of degrees between -180 and 180: {}", value))
+        }
+
+        let abs     = value.abs();
+        let degree  = abs.trunc();
+        let minutes = ((abs - degree) * 60.0).trunc();
+        let seconds = ((abs - degree - minutes / 60.0) * 3600.0 * 10_000.0).round() / 10_000.0;
+
+        // Rounding may carry over into the next minute
+        let (minutes, seconds) = if seconds >= 60.0 { (minutes + 1.0, 0.0) } else { (minutes, seconds) };
+        let (degree, minutes)  = if minutes >= 60.0 { (degree + 1.0, 0.0) } else { (degree, minutes) };
+
+        Ok(GPSValue::from_dms(value < 0.0, degree as i64, minutes as i64, seconds))
+    }
+
+    /// Get the value as decimal degrees, negative for south or west
+    pub fn to_decimal(&self) -> f64 {
+        let abs = self.degree as f64 + self.minutes as f64 / 60.0 + self.seconds / 3600.0;
+        if self.negative { -abs } else { abs }
+    }
+
     pub fn degree(&self) -> i64 {
         self.degree
     }
@@ -58,20 +107,56 @@ impl GPSValue {
         self.minutes
     }
 
-    pub fn seconds(&self) -> i64 {
+    pub fn seconds(&self) -> f64 {
         self.seconds
     }
 
-}
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
 
+    /// Format the absolute value, followed by `positive` or `negative` as hemisphere letter
+    pub fn to_hemisphere_string(&self, positive: char, negative: char) -> String {
+        format!("{}° {}' {}\" {}",
+                self.degree,
+                self.minutes,
+                self.seconds,
+                if self.negative { negative } else { positive })
+    }
+
+    fn check_range(&self, max_degree: f64, what: &str) -> Result<()> {
+        // Comparisons with NaN are always false, so the seconds are checked to be finite first
+        if !self.seconds.is_finite() || self.minutes < 0 || self.minutes >= 60 || self.seconds < 0.0 || self.seconds >= 60.0 {
+            return Err(format_err!("Invalid {}: {}", what, self))
+        }
+
+        if self.to_decimal().abs() > max_degree {
+            return Err(format_err!("Invalid {}: {} is out of range", what, self))
+        }
+
+        Ok(())
+    }
+
+}
 
 impl Into<Value> for GPSValue {
 
     fn into(self) -> Value {
         let mut map = BTreeMap::new();
+
+        // Whole seconds are stored as integer, as they were before fractions were supported
+        let seconds = if self.seconds.fract() == 0.0 {
+            Value::Integer(self.seconds as i64)
+        } else {
+            Value::Float(self.seconds)
+        };
+
         let _ = map.insert("degree".to_owned(),  Value::Integer(self.degree));
         let _ = map.insert("minutes".to_owned(), Value::Integer(self.minutes));
-        let _ = map.insert("seconds".to_owned(), Value::Integer(self.seconds));
+        let _ = map.insert("seconds".to_owned(), seconds);
+        if self.negative {
+            let _ = map.insert("negative".to_owned(), Value::Boolean(true));
+        }
         Value::Table(map)
     }
 
@@ -84,23 +169,36 @@ impl FromValue for GPSValue {
              .ok_or_else(|| Error::from(EM::EntryHeaderTypeError))
         };
 
+        let number = |v: &Value| match *v {
+            Value::Integer(i) => Ok(i as f64),
+            Value::Float(f)   => Ok(f),
+            _                 => Err(Error::from(EM::EntryHeaderTypeError)),
+        };
+
         match *v {
             Value::Table(ref map) => {
-                Ok(GPSValue::new(
-                    map.get("degree")
-                        .ok_or_else(|| Error::from(err_msg("Degree missing")))
-                        .and_then(&int_to_appropriate_width)?,
-
-                    map
-                        .get("minutes")
-                        .ok_or_else(|| Error::from(err_msg("Minutes missing")))
-                        .and_then(&int_to_appropriate_width)?,
-
-                    map
-                        .get("seconds")
-                        .ok_or_else(|| Error::from(err_msg("Seconds missing")))
-                        .and_then(&int_to_appropriate_width)?
-                ))
+                let degree = map.get("degree")
+                    .ok_or_else(|| Error::from(err_msg("Degree missing")))
+                    .and_then(&int_to_appropriate_width)?;
+
+                let minutes = map
+                    .get("minutes")
+                    .ok_or_else(|| Error::from(err_msg("Minutes missing")))
+                    .and_then(&int_to_appropriate_width)?;
+
+                let seconds = map
+                    .get("seconds")
+                    .ok_or_else(|| Error::from(err_msg("Seconds missing")))
+                    .and_then(&number)?;
+
+                // Older entries store the sign in the degree
+                let negative = match map.get("negative") {
+                    Some(&Value::Boolean(b)) => b,
+                    Some(_)                  => return Err(Error::from(EM::EntryHeaderTypeError)),
+                    None                     => degree < 0,
+                };
+
+                Ok(GPSValue::from_dms(negative, degree.abs(), minutes, seconds))
             }
             _ => Err(Error::from(EM::EntryHeaderTypeError))
         }
@@ -110,15 +208,78 @@ impl FromValue for GPSValue {
 
 impl Display for GPSValue {
     fn fmt(&self, f: &mut Formatter) -> FmtResult {
-        write!(f, "{}° {}\" {}'", self.degree, self.minutes, self.seconds)
+        let sign = if self.negative { "-" } else { "" };
+        write!(f, "{}{}° {}' {}\"", sign, self.degree, self.minutes, self.seconds)
+    }
+}
+
+/// Parse a finite decimal number, like a number of degrees or an altitude
+fn parse_decimal(s: &str) -> Result<f64> {
+    f64::from_str(s.trim())
+        .ok()
+        .filter(|f| f.is_finite())
+        .ok_or_else(|| format_err!("Not a number: '{}'", s))
+}
+
+/// Parse a single angle
+///
+/// Understands decimal degrees ("-48.1372", "48.1372 N") and degrees, minutes and seconds
+/// ("48°8'14\"N", "48 8 14.2 S"). Returns the hemisphere letter, if there was one.
+fn parse_angle(s: &str) -> Result<(GPSValue, Option<char>)> {
+    let s = s.trim();
+    let hemisphere = s.chars()
+        .last()
+        .map(|c| c.to_ascii_uppercase())
+        .and_then(|c| if "NSEW".contains(c) { Some(c) } else { None });
+
+    let s = if hemisphere.is_some() { &s[..s.len() - 1] } else { s };
+
+    let numbers = s
+        .split(|c: char| c.is_whitespace() || "°'\"′″".contains(c))
+        .filter(|p| !p.is_empty())
+        .collect::<Vec<_>>();
+
+    let minus = numbers.get(0).map(|n| n.starts_with('-')).unwrap_or(false);
+    if minus && hemisphere.is_some() {
+        return Err(format_err!("Angle has a sign and a hemisphere: '{}'", s))
+    }
+
+    let negative = minus || hemisphere == Some('S') || hemisphere == Some('W');
+    let float    = |n: &str| f64::from_str(n.trim_start_matches('-'))
+        .map_err(|_| format_err!("Not a number: '{}'", n));
+    let integer  = |n: &str| i64::from_str(n.trim_start_matches('-'))
+        .map_err(|_| format_err!("Not a whole number: '{}'", n));
+
+    let value = match numbers.len() {
+        1 => {
+            let v = GPSValue::from_decimal(float(numbers[0])?)?;
+            GPSValue::from_dms(negative, v.degree, v.minutes, v.seconds)
+        },
+        2 => GPSValue::from_dms(negative, integer(numbers[0])?, integer(numbers[1])?, 0.0),
+        3 => GPSValue::from_dms(negative, integer(numbers[0])?, integer(numbers[1])?, float(numbers[2])?),
+        _ => return Err(format_err!("Cannot parse angle: '{}'", s)),
+    };
+
+    Ok((value, hemisphere))
+}
+
+impl FromStr for GPSValue {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        parse_angle(s).map(|(value, _)| value)
     }
 }
 
 /// Data-transfer type for transfering longitude-latitude-pairs
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+///
+/// The altitude is in meters above sea level.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub struct Coordinates {
     pub longitude: GPSValue,
     pub latitude:  GPSValue,
+    #[serde(default)]
+    pub altitude:  Option<f64>,
 }
 
 impl Coordinates {
@@ -126,9 +287,21 @@ impl Coordinates {
         Coordinates {
             longitude: long,
             latitude:  lat,
+            altitude:  None,
         }
     }
 
+    /// Create coordinates from decimal degrees
+    pub fn from_decimal(latitude: f64, longitude: f64) -> Result<Coordinates> {
+        let c = Coordinates::new(GPSValue::from_decimal(longitude)?, GPSValue::from_decimal(latitude)?);
+        c.check().map(|_| c)
+    }
+
+    pub fn with_altitude(mut self, altitude: f64) -> Coordinates {
+        self.altitude = Some(altitude);
+        self
+    }
+
     pub fn longitude(&self) -> &GPSValue {
         &self.longitude
     }
@@ -136,6 +309,36 @@ impl Coordinates {
     pub fn latitude(&self) -> &GPSValue {
         &self.latitude
     }
+
+    pub fn altitude(&self) -> Option<f64> {
+        self.altitude
+    }
+
+    /// Check whether latitude and longitude are in range
+    pub fn check(&self) -> Result<()> {
+        let _ = self.latitude.check_range(90.0, "latitude")?;
+        self.longitude.check_range(180.0, "longitude")
+    }
+
+    /// Get the great-circle distance to `other` in kilometers, ignoring the altitude
+    pub fn distance_to(&self, other: &Coordinates) -> f64 {
+        let lat1  = self.latitude.to_decimal().to_radians();
+        let lat2  = other.latitude.to_decimal().to_radians();
+        let dlat  = lat2 - lat1;
+        let dlong = (other.longitude.to_decimal() - self.longitude.to_decimal()).to_radians();
+
+        // Haversine formula
+        let a = (dlat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (dlong / 2.0).sin().powi(2);
+        2.0 * EARTH_RADIUS_KM * a.sqrt().min(1.0).asin()
+    }
+
+    /// Format the coordinates as `geo:` URI (RFC 5870)
+    pub fn to_geo_uri(&self) -> String {
+        match self.altitude {
+            Some(alt) => format!("geo:{},{},{}", self.latitude.to_decimal(), self.longitude.to_decimal(), alt),
+            None      => format!("geo:{},{}", self.latitude.to_decimal(), self.longitude.to_decimal()),
+        }
+    }
 }
 
 impl Into<Value> for Coordinates {
@@ -144,6 +347,9 @@ impl Into<Value> for Coordinates {
         let mut map = BTreeMap::new();
         let _ = map.insert("longitude".to_owned(), self.longitude.into());
         let _ = map.insert("latitude".to_owned(), self.latitude.into());
+        if let Some(altitude) = self.altitude {
+            let _ = map.insert("altitude".to_owned(), Value::Float(altitude));
+        }
         Value::Table(map)
     }
 
@@ -160,19 +366,251 @@ impl FromValue for Coordinates {
                         .and_then(GPSValue::from_value)
                 };
 
-                Ok(Coordinates::new(
-                    get(t, "longitude", "Longitude missing")?,
-                    get(t, "latitude", "Latitude missing")?
-                ))
+                let altitude = match t.get("altitude") {
+                    Some(&Value::Float(f))   => Some(f),
+                    Some(&Value::Integer(i)) => Some(i as f64),
+                    Some(_)                  => return Err(Error::from(EM::EntryHeaderTypeError)),
+                    None                     => None,
+                };
+
+                Ok(Coordinates {
+                    longitude: get(t, "longitude", "Longitude missing")?,
+                    latitude:  get(t, "latitude", "Latitude missing")?,
+                    altitude:  altitude,
+                })
             })
     }
 
 }
 
+/// Parse coordinates
+///
+/// Understands
+///
+/// * `geo:` URIs (RFC 5870), like "geo:48.1372,11.5756" or "geo:48.1372,11.5756,520"
+/// * Pairs of decimal degrees, latitude first, like "48.1372, 11.5756" or "48.1372 11.5756"
+/// * Pairs of degrees, minutes and seconds with hemisphere, like "48°8'14\"N 11°34'32\"E"
+///
+impl FromStr for Coordinates {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+
+        if s.starts_with("geo:") {
+            let path   = s["geo:".len()..].split(';').next().unwrap_or("");
+            let values = path.split(',').map(parse_decimal).collect::<Result<Vec<f64>>>()?;
+
+            return match values.len() {
+                2 => Coordinates::from_decimal(values[0], values[1]),
+                3 => Coordinates::from_decimal(values[0], values[1]).map(|c| c.with_altitude(values[2])),
+                _ => Err(format_err!("Invalid geo URI: '{}'", s)),
+            }
+        }
+
+        let ends_with_hemisphere = s.chars().last().map(|c| "NSEWnsew".contains(c)).unwrap_or(false);
+
+        let parts : Vec<&str> = if s.contains(',') {
+            s.split(',').collect()
+        } else if ends_with_hemisphere {
+            // Split after the hemisphere letter of the first angle, which is followed by the second
+            match s.char_indices().find(|&(i, c)| "NSEWnsew".contains(c) && !s[i + 1..].trim().is_empty()) {
+                Some((idx, _)) => vec![&s[..idx + 1], &s[idx + 1..]],
+                None           => vec![s],
+            }
+        } else {
+            s.split_whitespace().collect()
+        };
+
+        if parts.len() != 2 {
+            return Err(format_err!("Expected latitude and longitude: '{}'", s))
+        }
+
+        let (first, first_hemisphere)   = parse_angle(parts[0])?;
+        let (second, second_hemisphere) = parse_angle(parts[1])?;
+        let is_latitude = |h: Option<char>| h.map(|c| c == 'N' || c == 'S');
+
+        let c = match (is_latitude(first_hemisphere), is_latitude(second_hemisphere)) {
+            (Some(false), Some(true)) => Coordinates::new(first, second),  // longitude given first
+            (Some(true), Some(true)) | (Some(false), Some(false)) => {
+                return Err(format_err!("Hemispheres do not match: '{}'", s))
+            },
+            _ => Coordinates::new(second, first),
+        };
+
+        c.check().map(|_| c)
+    }
+}
+
 impl Display for Coordinates {
     fn fmt(&self, f: &mut Formatter) -> FmtResult {
-        write!(f, "longitude = {}\nlatitude = {}", self.longitude, self.latitude)
+        write!(f, "longitude = {}\nlatitude = {}",
+               self.longitude.to_hemisphere_string('E', 'W'),
+               self.latitude.to_hemisphere_string('N', 'S'))?;
+
+        if let Some(altitude) = self.altitude {
+            write!(f, "\naltitude = {}", altitude)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// An area between two latitudes and two longitudes
+///
+/// If the western longitude is east of the eastern one, the box spans the antimeridian.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BoundingBox {
+    south: f64,
+    west:  f64,
+    north: f64,
+    east:  f64,
+}
+
+impl BoundingBox {
+
+    /// Create a bounding box from its south-western and its north-eastern corner
+    pub fn new(south_west: &Coordinates, north_east: &Coordinates) -> Result<BoundingBox> {
+        let south = south_west.latitude.to_decimal();
+        let north = north_east.latitude.to_decimal();
+
+        if south > north {
+            return Err(format_err!("Southern border of bounding box is north of the northern border"))
+        }
+
+        Ok(BoundingBox {
+            south: south,
+            west:  south_west.longitude.to_decimal(),
+            north: north,
+            east:  north_east.longitude.to_decimal(),
+        })
+    }
+
+    pub fn contains(&self, c: &Coordinates) -> bool {
+        let lat  = c.latitude.to_decimal();
+        let long = c.longitude.to_decimal();
+
+        let in_longitudes = if self.west <= self.east {
+            self.west <= long && long <= self.east
+        } else {
+            self.west <= long || long <= self.east
+        };
+
+        self.south <= lat && lat <= self.north && in_longitudes
     }
+
 }
 
+/// Parse a bounding box from "SOUTH,WEST,NORTH,EAST" in decimal degrees
+impl FromStr for BoundingBox {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let values = s.split(',').map(parse_decimal).collect::<Result<Vec<f64>>>()?;
 
+        if values.len() != 4 {
+            return Err(format_err!("Expected SOUTH,WEST,NORTH,EAST: '{}'", s))
+        }
+
+        BoundingBox::new(&Coordinates::from_decimal(values[0], values[1])?,
+                         &Coordinates::from_decimal(values[2], values[3])?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(expected: f64, actual: f64, epsilon: f64) {
+        assert!((expected - actual).abs() < epsilon, "Expected {}, got {}", expected, actual);
+    }
+
+    #[test]
+    fn test_decimal_roundtrip() {
+        let v = GPSValue::from_decimal(-48.1372).unwrap();
+        assert!(v.is_negative());
+        assert_eq!(48, v.degree());
+        assert_eq!(8, v.minutes());
+        assert_close(-48.1372, v.to_decimal(), 1e-7);
+    }
+
+    #[test]
+    fn test_legacy_header_value() {
+        let v : Value = ::toml::from_str("degree = -12\nminutes = 30\nseconds = 0").unwrap();
+        let v = GPSValue::from_value(&v).unwrap();
+        assert!(v.is_negative());
+        assert_close(-12.5, v.to_decimal(), 1e-9);
+    }
+
+    #[test]
+    fn test_header_roundtrip() {
+        let c = Coordinates::from_decimal(-33.8568, 151.2153).unwrap().with_altitude(5.0);
+        let v : Value = c.clone().into();
+        assert_eq!(c, Coordinates::from_value(&v).unwrap());
+    }
+
+    #[test]
+    fn test_parse_coordinates() {
+        let expected = Coordinates::from_decimal(48.1372, 11.5756).unwrap();
+        let inputs   = [
+            "48.1372, 11.5756",
+            "48.1372 11.5756",
+            "geo:48.1372,11.5756;u=35",
+            "48°8'13.92\"N 11°34'32.16\"E",
+            "11°34'32.16\"E, 48°8'13.92\"N",
+            "11°34'32.16\"E 48°8'13.92\"N",
+            "48.1372 N 11.5756 E",
+            "11.5756 E 48.1372 N",
+        ];
+
+        for input in inputs.iter() {
+            let c = Coordinates::from_str(input).unwrap();
+            assert_close(expected.latitude.to_decimal(), c.latitude.to_decimal(), 1e-6);
+            assert_close(expected.longitude.to_decimal(), c.longitude.to_decimal(), 1e-6);
+        }
+
+        let c = Coordinates::from_str("geo:-33.8568,151.2153,5").unwrap();
+        assert!(c.latitude.is_negative());
+        assert_eq!(Some(5.0), c.altitude());
+
+        assert!(Coordinates::from_str("91.0, 0.0").is_err());
+        assert!(Coordinates::from_str("48°8'N 11°34'S").is_err());
+    }
+
+    #[test]
+    fn test_non_finite_values_are_rejected() {
+        use std::f64;
+
+        assert!(GPSValue::from_decimal(f64::NAN).is_err());
+        assert!(GPSValue::from_decimal(f64::INFINITY).is_err());
+        assert!(GPSValue::from_decimal(f64::NEG_INFINITY).is_err());
+        assert!(GPSValue::from_decimal(1e300).is_err());
+        assert!(Coordinates::from_decimal(f64::NAN, 0.0).is_err());
+
+        assert!(Coordinates::from_str("geo:NaN,NaN").is_err());
+        assert!(Coordinates::from_str("geo:inf,0").is_err());
+        assert!(Coordinates::from_str("geo:48.1372,11.5756,NaN").is_err());
+        assert!(Coordinates::from_str("NaN, 11.5756").is_err());
+        assert!(Coordinates::from_str("48 8 NaN N, 11 34 32 E").is_err());
+        assert!(Coordinates::from_str("48 8 inf N, 11 34 32 E").is_err());
+
+        assert!(BoundingBox::from_str("NaN,NaN,NaN,NaN").is_err());
+        assert!(BoundingBox::from_str("-inf,0,inf,10").is_err());
+    }
+
+    #[test]
+    fn test_distance() {
+        let munich = Coordinates::from_decimal(48.1372, 11.5756).unwrap();
+        let berlin = Coordinates::from_decimal(52.5200, 13.4050).unwrap();
+        assert_close(504.0, munich.distance_to(&berlin), 2.0);
+        assert_close(0.0, munich.distance_to(&munich), 1e-9);
+    }
+
+    #[test]
+    fn test_bounding_box_across_antimeridian() {
+        let bbox = BoundingBox::from_str("-20,170,-10,-170").unwrap();
+        assert!(bbox.contains(&Coordinates::from_decimal(-15.0, 179.0).unwrap()));
+        assert!(bbox.contains(&Coordinates::from_decimal(-15.0, -175.0).unwrap()));
+        assert!(!bbox.contains(&Coordinates::from_decimal(-15.0, 0.0).unwrap()));
+    }
+}
@@ -0,0 +1,458 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Health checks for external links
+//!
+//! The `LinkChecker` requests the URLs of external links (see `external`) and records the result
+//! of the check in the header of the entry which holds the URL:
+//!
+//! ```toml
+//! [links.external.check]
+//! status   = 301
+//! redirect = "https://example.com/new"
+//! checked  = "2018-10-18 12:00:00"
+//! ```
+//!
+//! If the request failed, there is an `error` instead of a `status`.
+//! As bookmarks are external links, this can be used to check bookmark collections, too.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::mpsc::channel;
+use std::thread;
+use std::time::Duration;
+
+use libimagstore::store::Entry;
+use libimagstore::store::Store;
+use libimagstore::storeid::StoreId;
+use libimagerror::errors::ErrorMsg as EM;
+use libimagutil::date::datetime_to_string;
+use libimagutil::date::datetime_from_string;
+
+use chrono::NaiveDateTime;
+use failure::Fallible as Result;
+use failure::Error;
+use failure::ResultExt;
+use reqwest::Client;
+use reqwest::RedirectPolicy;
+use reqwest::StatusCode;
+use reqwest::header::LOCATION;
+use toml::Value;
+use toml_query::read::TomlValueReadExt;
+use toml_query::insert::TomlValueInsertExt;
+use url::Url;
+
+use external::Link;
+
+/// The result of checking one URL
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckResult {
+    url: Url,
+    status: Option<u16>,
+    redirect: Option<Url>,
+    error: Option<String>,
+    checked: NaiveDateTime,
+}
+
+impl CheckResult {
+
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+
+    /// The HTTP status code, if the request succeeded
+    pub fn status(&self) -> Option<u16> {
+        self.status
+    }
+
+    /// The target of the redirect, if the server answered with one
+    pub fn redirect(&self) -> Option<&Url> {
+        self.redirect.as_ref()
+    }
+
+    /// The reason why the request failed, for example a timeout
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_ref().map(String::as_ref)
+    }
+
+    pub fn checked(&self) -> &NaiveDateTime {
+        &self.checked
+    }
+
+    /// A link is broken if the request failed or the server answered with an error status
+    pub fn is_broken(&self) -> bool {
+        self.error.is_some() || self.status.map(|s| s >= 400).unwrap_or(true)
+    }
+
+    fn to_value(&self) -> Value {
+        let mut tab = BTreeMap::new();
+        if let Some(status) = self.status {
+            tab.insert(String::from("status"), Value::Integer(status as i64));
+        }
+        if let Some(ref redirect) = self.redirect {
+            tab.insert(String::from("redirect"), Value::String(redirect.as_str().to_owned()));
+        }
+        if let Some(ref error) = self.error {
+            tab.insert(String::from("error"), Value::String(error.clone()));
+        }
+        tab.insert(String::from("checked"), Value::String(datetime_to_string(&self.checked)));
+        Value::Table(tab)
+    }
+
+    fn from_value(url: Url, v: &Value) -> Result<CheckResult> {
+        let get_str = |key: &str| -> Result<Option<String>> {
+            match v.read(key)? {
+                Some(&Value::String(ref s)) => Ok(Some(s.clone())),
+                Some(_) => Err(Error::from(EM::EntryHeaderTypeError)),
+                None    => Ok(None),
+            }
+        };
+
+        let status = match v.read("status")? {
+            Some(&Value::Integer(i)) => Some(i as u16),
+            Some(_) => return Err(Error::from(EM::EntryHeaderTypeError)),
+            None    => None,
+        };
+        let redirect = match get_str("redirect")? {
+            Some(s) => Some(Url::parse(&s)?),
+            None    => None,
+        };
+        let error   = get_str("error")?;
+        let checked = get_str("checked")?
+            .ok_or_else(|| Error::from(EM::EntryHeaderFieldMissing("links.external.check.checked")))
+            .and_then(|s| datetime_from_string(s).map_err(Error::from))?;
+
+        Ok(CheckResult { url, status, redirect, error, checked })
+    }
+
+}
+
+/// Reading and writing the result of the last check of an external link entry
+pub trait LinkCheckStatus {
+
+    /// Get the result of the last check, if the link was checked already
+    fn get_link_check_result(&self) -> Result<Option<CheckResult>>;
+
+    /// Record the result of a check
+    fn set_link_check_result(&mut self, result: &CheckResult) -> Result<()>;
+
+}
+
+impl LinkCheckStatus for Entry {
+
+    fn get_link_check_result(&self) -> Result<Option<CheckResult>> {
+        let url = match self.get_link_uri_from_filelockentry()? {
+            Some(url) => url,
+            None      => return Ok(None),
+        };
+
+        match self.get_header().read("links.external.check")? {
+            Some(v) => CheckResult::from_value(url, v)
+                .context(EM::EntryHeaderReadError)
+                .map_err(Error::from)
+                .map(Some),
+            None    => Ok(None),
+        }
+    }
+
+    fn set_link_check_result(&mut self, result: &CheckResult) -> Result<()> {
+        self.get_header_mut()
+            .insert("links.external.check", result.to_value())
+            .map_err(Error::from)
+            .context(EM::EntryHeaderWriteError)
+            .map_err(Error::from)
+            .map(|_| ())
+    }
+
+}
+
+/// Checks URLs with HTTP requests
+///
+/// The checker sends a HEAD request and falls back to GET if the server does not allow HEAD.
+/// Redirects are not followed, but reported.
+pub struct LinkChecker {
+    client: Client,
+    concurrency: usize,
+}
+
+impl LinkChecker {
+
+    /// Create a checker which gives up on a URL after `timeout` and checks at most `concurrency`
+    /// URLs at a time
+    pub fn new(timeout: Duration, concurrency: usize) -> Result<LinkChecker> {
+        let client = Client::builder()
+            .timeout(timeout)
+            .redirect(RedirectPolicy::none())
+            .build()?;
+
+        Ok(LinkChecker { client, concurrency: ::std::cmp::max(1, concurrency) })
+    }
+
+    /// Check a single URL
+    pub fn check_url(&self, url: Url) -> CheckResult {
+        check_url(&self.client, url)
+    }
+
+    /// Check URLs concurrently
+    ///
+    /// The results are returned in the order of the URLs.
+    pub fn check_urls(&self, urls: Vec<Url>) -> Vec<CheckResult> {
+        let count = urls.len();
+        let queue = Arc::new(Mutex::new(urls.into_iter().enumerate().collect::<Vec<_>>()));
+        let (sender, receiver) = channel();
+
+        let workers = (0..::std::cmp::min(self.concurrency, count))
+            .map(|_| {
+                let queue  = queue.clone();
+                let sender = sender.clone();
+                let client = self.client.clone();
+
+                thread::spawn(move || loop {
+                    let next = match queue.lock() {
+                        Ok(mut queue) => queue.pop(),
+                        Err(_)        => None,
+                    };
+
+                    match next {
+                        Some((i, url)) => {
+                            debug!("Checking {}", url);
+                            if sender.send((i, check_url(&client, url))).is_err() {
+                                break;
+                            }
+                        },
+                        None => break,
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        drop(sender);
+
+        let mut results = receiver.iter().collect::<Vec<_>>();
+        for worker in workers {
+            let _ = worker.join();
+        }
+
+        results.sort_by_key(|&(i, _)| i);
+        results.into_iter().map(|(_, r)| r).collect()
+    }
+
+    /// Check the external links with the IDs `ids` and record the results in their headers
+    ///
+    /// IDs which are not external link entries are ignored.
+    pub fn check_entries<I>(&self, store: &Store, ids: I) -> Result<Vec<(StoreId, CheckResult)>>
+        where I: IntoIterator<Item = StoreId>
+    {
+        let mut links = vec![];
+        for id in ids.into_iter().filter(|id| id.is_in_collection(&["links", "external"])) {
+            let url = match store.get(id.clone())? {
+                Some(entry) => entry.get_link_uri_from_filelockentry()?,
+                None        => None,
+            };
+
+            match url {
+                Some(url) => links.push((id, url)),
+                None      => warn!("Not an external link: {}", id),
+            }
+        }
+
+        let (ids, urls) : (Vec<_>, Vec<_>) = links.into_iter().unzip();
+        let results = ids.into_iter().zip(self.check_urls(urls)).collect::<Vec<_>>();
+
+        for &(ref id, ref result) in results.iter() {
+            if let Some(mut entry) = store.get(id.clone())? {
+                let _ = entry.set_link_check_result(result)?;
+            }
+        }
+
+        Ok(results)
+    }
+
+    /// Check all external links in the store and record the results in their headers
+    pub fn check_store(&self, store: &Store) -> Result<Vec<(StoreId, CheckResult)>> {
+        let ids = store
+            .entries()?
+            .in_collection("links/external")
+            .collect::<Result<Vec<_>>>()?;
+
+        self.check_entries(store, ids)
+    }
+
+}
+
+fn check_url(client: &Client, url: Url) -> CheckResult {
+    let response = client
+        .head(url.as_str())
+        .send()
+        .and_then(|response| match response.status() {
+            StatusCode::METHOD_NOT_ALLOWED | StatusCode::NOT_IMPLEMENTED => {
+                debug!("HEAD not allowed for {}, falling back to GET", url);
+                client.get(url.as_str()).send()
+            },
+            _ => Ok(response),
+        });
+
+    let checked = ::chrono::Local::now().naive_local();
+
+    match response {
+        Ok(response) => {
+            let redirect = if response.status().is_redirection() {
+                response.headers()
+                    .get(LOCATION)
+                    .and_then(|location| location.to_str().ok())
+                    .and_then(|location| url.join(location).ok())
+            } else {
+                None
+            };
+
+            CheckResult {
+                status: Some(response.status().as_u16()),
+                redirect,
+                error: None,
+                checked,
+                url,
+            }
+        },
+        Err(e) => CheckResult {
+            status: None,
+            redirect: None,
+            error: Some(format!("{}", e)),
+            checked,
+            url,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+    use std::io::Write;
+    use std::net::TcpListener;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
+    use libimagstore::store::Store;
+    use url::Url;
+
+    use external::ExternalLinker;
+    use internal::InternalLinker;
+    use super::*;
+
+    fn get_store() -> Store {
+        use libimagstore::file_abstraction::InMemoryFileAbstraction;
+        let backend = Arc::new(InMemoryFileAbstraction::default());
+        Store::new_with_backend(PathBuf::from("/"), &None, backend).unwrap()
+    }
+
+    /// Start a minimal HTTP server and return its base URL
+    fn serve() -> Url {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address  = listener.local_addr().unwrap();
+
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream  = stream.unwrap();
+                let mut request = vec![];
+                let mut buf     = [0; 1024];
+
+                while !String::from_utf8_lossy(&request).contains("\r\n\r\n") {
+                    match stream.read(&mut buf) {
+                        Ok(0) | Err(_) => break,
+                        Ok(n)          => request.extend_from_slice(&buf[..n]),
+                    }
+                }
+
+                let request = String::from_utf8_lossy(&request).into_owned();
+                let mut words = request.split_whitespace();
+                let method  = words.next().unwrap_or("");
+                let path    = words.next().unwrap_or("");
+
+                let status = match (method, path) {
+                    (_, "/ok")           => "200 OK",
+                    (_, "/moved")        => "301 Moved Permanently\r\nLocation: /ok",
+                    ("HEAD", "/no-head") => "405 Method Not Allowed",
+                    ("GET", "/no-head")  => "200 OK",
+                    _                    => "404 Not Found",
+                };
+
+                let _ = write!(stream, "HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", status);
+            }
+        });
+
+        Url::parse(&format!("http://{}/", address)).unwrap()
+    }
+
+    #[test]
+    fn test_check_urls() {
+        let base    = serve();
+        let checker = LinkChecker::new(Duration::from_secs(5), 2).unwrap();
+        let urls    = vec!["ok", "moved", "gone", "no-head"]
+            .into_iter()
+            .map(|p| base.join(p).unwrap())
+            .collect();
+
+        let results = checker.check_urls(urls);
+        assert_eq!(results.len(), 4);
+
+        assert_eq!(results[0].status(), Some(200));
+        assert!(!results[0].is_broken());
+
+        assert_eq!(results[1].status(), Some(301));
+        assert_eq!(results[1].redirect(), Some(&base.join("ok").unwrap()));
+        assert!(!results[1].is_broken());
+
+        assert_eq!(results[2].status(), Some(404));
+        assert!(results[2].is_broken());
+
+        assert_eq!(results[3].status(), Some(200));
+    }
+
+    #[test]
+    fn test_unreachable_url_is_broken() {
+        let checker = LinkChecker::new(Duration::from_secs(5), 1).unwrap();
+        let result  = checker.check_url(Url::parse("http://127.0.0.1:1/").unwrap());
+
+        assert!(result.is_broken());
+        assert!(result.error().is_some());
+        assert_eq!(result.status(), None);
+    }
+
+    #[test]
+    fn test_check_store_records_result() {
+        let base      = serve();
+        let store     = get_store();
+        let mut entry = store.retrieve(PathBuf::from("test_check_store")).unwrap();
+
+        let _ = entry.add_external_link(&store, base.join("gone").unwrap()).unwrap();
+        let link_id = entry.get_internal_links().unwrap().next().unwrap().get_store_id().clone();
+        drop(entry);
+
+        let checker = LinkChecker::new(Duration::from_secs(5), 4).unwrap();
+        let results = checker.check_store(&store).unwrap();
+        assert_eq!(results.len(), 1);
+
+        let link_entry = store.get(link_id).unwrap().unwrap();
+        let recorded   = link_entry.get_link_check_result().unwrap().unwrap();
+        assert_eq!(recorded.status(), Some(404));
+        assert_eq!(recorded.url(), &base.join("gone").unwrap());
+        assert!(recorded.is_broken());
+    }
+}
@@ -0,0 +1,457 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! The graph of internal links
+//!
+//! `LinkGraph` loads the internal links of the whole store or of one collection and offers queries
+//! on them, like shortest pathes or connected components, as well as exporting the graph in DOT,
+//! GraphML or JSON format.
+//!
+//! Every internal link is stored in both entries, so the queries treat the graph as undirected:
+//! a link can be followed either way. Typed links have a direction though (for example "parent"
+//! in one entry and "child" in the other), so the type is kept per direction, see
+//! `LinkGraph::link_type()`.
+
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::collections::VecDeque;
+use std::collections::btree_map::Keys;
+
+use libimagstore::store::Store;
+use libimagstore::storeid::StoreId;
+use libimagstore::iter::Entries;
+
+use failure::Fallible as Result;
+use failure::Error;
+
+use internal::InternalLinker;
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct LinkGraph {
+    /// Maps each node onto its neighbours and the type of the link to the neighbour, if any
+    nodes: BTreeMap<StoreId, BTreeMap<StoreId, Option<String>>>,
+}
+
+impl LinkGraph {
+
+    /// Load the link graph of the whole store
+    pub fn from_store(store: &Store) -> Result<LinkGraph> {
+        LinkGraph::load(store.entries()?)
+    }
+
+    /// Load the link graph of the entries in `collection`
+    ///
+    /// Links to entries outside of the collection are not part of the graph.
+    pub fn from_collection(store: &Store, collection: &str) -> Result<LinkGraph> {
+        LinkGraph::load(store.entries()?.in_collection(collection))
+    }
+
+    fn load(entries: Entries) -> Result<LinkGraph> {
+        let mut graph = LinkGraph::default();
+        let mut links = vec![];
+
+        for entry in entries.into_get_iter() {
+            let entry = match entry? {
+                Some(entry) => entry,
+                None        => continue,
+            };
+            let id = entry.get_location().clone().without_base();
+            debug!("Loading links of {}", id);
+
+            for link in entry.get_internal_links()? {
+                let link_type = link.link_type().map(|t| t.name().to_owned());
+                links.push((id.clone(), link.get_store_id().clone().without_base(), link_type));
+            }
+
+            graph.add_node(id);
+        }
+
+        for (from, to, link_type) in links {
+            if graph.contains(&to) {
+                graph.add_edge(from, to, link_type);
+            } else {
+                debug!("Not in graph, ignoring link: {} -> {}", from, to);
+            }
+        }
+
+        Ok(graph)
+    }
+
+    pub fn add_node(&mut self, id: StoreId) {
+        self.nodes.entry(id).or_insert_with(BTreeMap::new);
+    }
+
+    /// Add a link from `from` to `to`, adding the nodes if necessary
+    ///
+    /// The link back is added as well, without a type if it does not exist yet.
+    pub fn add_edge(&mut self, from: StoreId, to: StoreId, link_type: Option<String>) {
+        self.nodes
+            .entry(to.clone())
+            .or_insert_with(BTreeMap::new)
+            .entry(from.clone())
+            .or_insert(None);
+
+        let t = self.nodes
+            .entry(from)
+            .or_insert_with(BTreeMap::new)
+            .entry(to)
+            .or_insert(None);
+
+        if link_type.is_some() {
+            *t = link_type;
+        }
+    }
+
+    pub fn contains(&self, id: &StoreId) -> bool {
+        self.nodes.contains_key(id)
+    }
+
+    pub fn nodes(&self) -> Keys<StoreId, BTreeMap<StoreId, Option<String>>> {
+        self.nodes.keys()
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Get the entries `id` is linked with
+    pub fn neighbours(&self, id: &StoreId) -> Vec<&StoreId> {
+        self.nodes
+            .get(id)
+            .map(|n| n.keys().filter(|n| *n != id).collect())
+            .unwrap_or_else(Vec::new)
+    }
+
+    /// Get the type of the link from `from` to `to`, if there is a typed link
+    pub fn link_type(&self, from: &StoreId, to: &StoreId) -> Option<&str> {
+        self.nodes
+            .get(from)
+            .and_then(|n| n.get(to))
+            .and_then(|t| t.as_ref())
+            .map(String::as_ref)
+    }
+
+    /// Get all links, each one only once
+    ///
+    /// If only one direction of a link has a type, the link is reported in that direction.
+    pub fn edges(&self) -> Vec<(&StoreId, &StoreId, Option<&str>)> {
+        let mut edges = vec![];
+        for (from, neighbours) in self.nodes.iter() {
+            for to in neighbours.keys().filter(|to| from <= *to) {
+                match (self.link_type(from, to), self.link_type(to, from)) {
+                    (None, Some(t)) => edges.push((to, from, Some(t))),
+                    (t, _)          => edges.push((from, to, t)),
+                }
+            }
+        }
+        edges
+    }
+
+    /// Get the subgraph of all entries which are at most `depth` links away from `id`
+    pub fn neighbourhood(&self, id: &StoreId, depth: usize) -> LinkGraph {
+        let mut reached = BTreeSet::new();
+        let mut queue   = VecDeque::new();
+
+        if self.contains(id) {
+            reached.insert(id.clone());
+            queue.push_back((id, 0));
+        }
+
+        while let Some((current, distance)) = queue.pop_front() {
+            if distance == depth {
+                continue;
+            }
+
+            for n in self.neighbours(current) {
+                if reached.insert(n.clone()) {
+                    queue.push_back((n, distance + 1));
+                }
+            }
+        }
+
+        self.subgraph(&reached)
+    }
+
+    /// Get the subgraph which consists of the entries in `ids` and the links between them
+    pub fn subgraph(&self, ids: &BTreeSet<StoreId>) -> LinkGraph {
+        let nodes = self.nodes
+            .iter()
+            .filter(|&(id, _)| ids.contains(id))
+            .map(|(id, neighbours)| {
+                let neighbours = neighbours
+                    .iter()
+                    .filter(|&(n, _)| ids.contains(n))
+                    .map(|(n, t)| (n.clone(), t.clone()))
+                    .collect();
+                (id.clone(), neighbours)
+            })
+            .collect();
+
+        LinkGraph { nodes }
+    }
+
+    /// Get the shortest path from `from` to `to`, including both
+    ///
+    /// Returns `None` if there is no path.
+    pub fn shortest_path(&self, from: &StoreId, to: &StoreId) -> Option<Vec<StoreId>> {
+        if !self.contains(from) || !self.contains(to) {
+            return None
+        }
+
+        let mut predecessors : BTreeMap<&StoreId, &StoreId> = BTreeMap::new();
+        let mut queue = VecDeque::new();
+        queue.push_back(from);
+
+        while let Some(current) = queue.pop_front() {
+            if current == to {
+                let mut path = vec![current.clone()];
+                let mut node = current;
+                while let Some(pred) = predecessors.get(node) {
+                    path.push((*pred).clone());
+                    node = *pred;
+                }
+                path.reverse();
+                return Some(path)
+            }
+
+            for n in self.neighbours(current) {
+                if n != from && !predecessors.contains_key(n) {
+                    predecessors.insert(n, current);
+                    queue.push_back(n);
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Get the sets of entries which are connected with each other
+    pub fn connected_components(&self) -> Vec<BTreeSet<StoreId>> {
+        let mut seen       = BTreeSet::new();
+        let mut components = vec![];
+
+        for id in self.nodes.keys() {
+            if !seen.insert(id) {
+                continue;
+            }
+
+            let mut component = BTreeSet::new();
+            let mut queue     = VecDeque::new();
+            queue.push_back(id);
+
+            while let Some(current) = queue.pop_front() {
+                component.insert(current.clone());
+
+                for n in self.neighbours(current) {
+                    if seen.insert(n) {
+                        queue.push_back(n);
+                    }
+                }
+            }
+
+            components.push(component);
+        }
+
+        components
+    }
+
+    /// Get the entries which are not linked to any other entry
+    pub fn orphans(&self) -> Vec<&StoreId> {
+        self.nodes
+            .keys()
+            .filter(|id| self.neighbours(id).is_empty())
+            .collect()
+    }
+
+    /// Render the graph in the DOT format of graphviz
+    pub fn to_dot(&self) -> String {
+        fn escape(s: &str) -> String {
+            s.replace('\\', "\\\\").replace('"', "\\\"")
+        }
+
+        let mut out = String::from("graph imag {\n");
+        for id in self.nodes.keys() {
+            out.push_str(&format!("  \"{}\";\n", escape(&id.local_display_string())));
+        }
+        for (from, to, link_type) in self.edges() {
+            out.push_str(&format!("  \"{}\" -- \"{}\"",
+                                  escape(&from.local_display_string()),
+                                  escape(&to.local_display_string())));
+            if let Some(t) = link_type {
+                out.push_str(&format!(" [label=\"{}\"]", escape(t)));
+            }
+            out.push_str(";\n");
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    /// Render the graph in the GraphML format
+    pub fn to_graphml(&self) -> String {
+        fn escape(s: &str) -> String {
+            s.replace('&', "&amp;")
+                .replace('<', "&lt;")
+                .replace('>', "&gt;")
+                .replace('"', "&quot;")
+                .replace('\'', "&apos;")
+        }
+
+        let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        out.push_str("<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n");
+        out.push_str("  <key id=\"type\" for=\"edge\" attr.name=\"type\" attr.type=\"string\"/>\n");
+        out.push_str("  <graph id=\"imag\" edgedefault=\"undirected\">\n");
+        for id in self.nodes.keys() {
+            out.push_str(&format!("    <node id=\"{}\"/>\n", escape(&id.local_display_string())));
+        }
+        for (from, to, link_type) in self.edges() {
+            let from = escape(&from.local_display_string());
+            let to   = escape(&to.local_display_string());
+            match link_type {
+                Some(t) => out.push_str(&format!(
+                        "    <edge source=\"{}\" target=\"{}\"><data key=\"type\">{}</data></edge>\n",
+                        from, to, escape(t))),
+                None => out.push_str(&format!("    <edge source=\"{}\" target=\"{}\"/>\n", from, to)),
+            }
+        }
+        out.push_str("  </graph>\n");
+        out.push_str("</graphml>\n");
+        out
+    }
+
+    /// Render the graph as JSON object
+    ///
+    /// The object has the keys "nodes", a list of IDs, and "edges", a list of objects with the
+    /// keys "source", "target" and "type".
+    pub fn to_json(&self) -> Result<String> {
+        let nodes = self.nodes
+            .keys()
+            .map(StoreId::local_display_string)
+            .collect::<Vec<_>>();
+
+        let edges = self.edges()
+            .into_iter()
+            .map(|(from, to, link_type)| json!({
+                "source": from.local_display_string(),
+                "target": to.local_display_string(),
+                "type":   link_type,
+            }))
+            .collect::<Vec<_>>();
+
+        ::serde_json::to_string(&json!({ "nodes": nodes, "edges": edges })).map_err(Error::from)
+    }
+
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    use libimagstore::store::Store;
+    use libimagstore::storeid::StoreId;
+
+    use internal::InternalLinker;
+    use internal::LinkType;
+    use super::LinkGraph;
+
+    fn get_store() -> Store {
+        use libimagstore::file_abstraction::InMemoryFileAbstraction;
+        let backend = Arc::new(InMemoryFileAbstraction::default());
+        Store::new_with_backend(PathBuf::from("/"), &None, backend).unwrap()
+    }
+
+    fn id(s: &str) -> StoreId {
+        StoreId::new_baseless(PathBuf::from(s)).unwrap()
+    }
+
+    // Builds a - b - c, d - e and the orphan f
+    fn get_graph(store: &Store) -> LinkGraph {
+        {
+            let mut a = store.create(PathBuf::from("test/a")).unwrap();
+            let mut b = store.create(PathBuf::from("test/b")).unwrap();
+            let mut c = store.create(PathBuf::from("test/c")).unwrap();
+            let mut d = store.create(PathBuf::from("test/d")).unwrap();
+            let mut e = store.create(PathBuf::from("other/e")).unwrap();
+            let _     = store.create(PathBuf::from("test/f")).unwrap();
+
+            a.add_internal_typed_link(&mut b, LinkType::parent()).unwrap();
+            b.add_internal_link(&mut c).unwrap();
+            d.add_internal_link(&mut e).unwrap();
+        }
+
+        LinkGraph::from_store(store).unwrap()
+    }
+
+    #[test]
+    fn test_load_graph() {
+        let store = get_store();
+        let graph = get_graph(&store);
+
+        assert_eq!(graph.len(), 6);
+        assert_eq!(graph.neighbours(&id("test/b")), vec![&id("test/a"), &id("test/c")]);
+        assert_eq!(graph.link_type(&id("test/a"), &id("test/b")), Some("parent"));
+        assert_eq!(graph.link_type(&id("test/b"), &id("test/a")), Some("child"));
+        assert_eq!(graph.edges().len(), 3);
+    }
+
+    #[test]
+    fn test_collection_graph() {
+        let store = get_store();
+        let _     = get_graph(&store);
+        let graph = LinkGraph::from_collection(&store, "test").unwrap();
+
+        assert_eq!(graph.len(), 5);
+        assert!(graph.neighbours(&id("test/d")).is_empty());
+    }
+
+    #[test]
+    fn test_queries() {
+        let store = get_store();
+        let graph = get_graph(&store);
+
+        let hood = graph.neighbourhood(&id("test/a"), 1);
+        assert_eq!(hood.nodes().collect::<Vec<_>>(), vec![&id("test/a"), &id("test/b")]);
+
+        assert_eq!(graph.shortest_path(&id("test/a"), &id("test/c")),
+                   Some(vec![id("test/a"), id("test/b"), id("test/c")]));
+        assert_eq!(graph.shortest_path(&id("test/a"), &id("test/d")), None);
+
+        let components = graph.connected_components();
+        assert_eq!(components.len(), 3);
+        assert!(components.contains(&vec![id("test/a"), id("test/b"), id("test/c")].into_iter().collect()));
+        assert!(components.contains(&vec![id("other/e"), id("test/d")].into_iter().collect()));
+        assert!(components.contains(&vec![id("test/f")].into_iter().collect()));
+        assert_eq!(graph.orphans(), vec![&id("test/f")]);
+    }
+
+    #[test]
+    fn test_dot_export() {
+        let store = get_store();
+        let graph = get_graph(&store);
+        let dot   = graph.to_dot();
+
+        assert!(dot.starts_with("graph imag {\n"));
+        assert!(dot.contains("  \"test/a\" -- \"test/b\" [label=\"parent\"];\n"));
+        assert!(dot.contains("  \"test/b\" -- \"test/c\";\n"));
+    }
+}
@@ -39,36 +39,116 @@ use self::iter::IntoValues;
 
 use toml::Value;
 
+/// The header location of the plain links, which are stored as strings
+const LINKS_HEADER : &'static str = "links.internal";
+
+/// The header location of the links with a type or an annotation, which are stored as tables
+///
+/// TOML arrays cannot mix strings and tables, so these are kept apart from the plain links.
+const DETAILED_LINKS_HEADER : &'static str = "links.internal_detailed";
+
+/// The type of a link
+///
+/// A typed link has a direction: If A links to B with type "parent", B links to A with the
+/// inverse type "child". Both names are stored in the header of the linking entry, so custom
+/// pairs of types do not need to be configured anywhere to be read back.
+///
+/// A type which is its own inverse (for example "related") is symmetric.
+#[derive(Eq, PartialEq, PartialOrd, Ord, Hash, Debug, Clone)]
+pub struct LinkType {
+    name: String,
+    inverse: String,
+}
+
+impl LinkType {
+
+    pub fn new<N: Into<String>, I: Into<String>>(name: N, inverse: I) -> LinkType {
+        LinkType { name: name.into(), inverse: inverse.into() }
+    }
+
+    /// A link type which is its own inverse
+    pub fn symmetric<N: Into<String>>(name: N) -> LinkType {
+        let name = name.into();
+        LinkType { inverse: name.clone(), name }
+    }
+
+    pub fn parent() -> LinkType {
+        LinkType::new("parent", "child")
+    }
+
+    pub fn child() -> LinkType {
+        LinkType::parent().inverse()
+    }
+
+    pub fn depends_on() -> LinkType {
+        LinkType::new("depends-on", "blocks")
+    }
+
+    pub fn blocks() -> LinkType {
+        LinkType::depends_on().inverse()
+    }
+
+    pub fn references() -> LinkType {
+        LinkType::new("references", "referenced-by")
+    }
+
+    pub fn referenced_by() -> LinkType {
+        LinkType::references().inverse()
+    }
+
+    /// Get one of the builtin link types by either of its names
+    pub fn builtin(name: &str) -> Option<LinkType> {
+        vec![LinkType::parent(), LinkType::depends_on(), LinkType::references()]
+            .into_iter()
+            .filter_map(|t| if t.name == name {
+                Some(t)
+            } else if t.inverse == name {
+                Some(t.inverse())
+            } else {
+                None
+            })
+            .next()
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn inverse_name(&self) -> &str {
+        &self.inverse
+    }
+
+    /// Get the type of the link in the other direction
+    pub fn inverse(&self) -> LinkType {
+        LinkType::new(self.inverse.clone(), self.name.clone())
+    }
+
+    pub fn is_symmetric(&self) -> bool {
+        self.name == self.inverse
+    }
+
+}
+
 #[derive(Eq, PartialOrd, Ord, Hash, Debug, Clone)]
 pub enum Link {
     Id          { link: StoreId },
     Annotated   { link: StoreId, annotation: String },
+    Typed       { link: StoreId, link_type: LinkType, annotation: Option<String> },
 }
 
 impl Link {
 
     pub fn exists(&self) -> Result<bool> {
-        match *self {
-            Link::Id { ref link }             => link.exists(),
-            Link::Annotated { ref link, .. }  => link.exists(),
-        }
-        .map_err(From::from)
+        self.get_store_id().exists().map_err(From::from)
     }
 
     pub fn to_str(&self) -> Result<String> {
-        match *self {
-            Link::Id { ref link }             => link.to_str(),
-            Link::Annotated { ref link, .. }  => link.to_str(),
-        }
-        .map_err(From::from)
+        self.get_store_id().to_str().map_err(From::from)
     }
 
 
     fn eq_store_id(&self, id: &StoreId) -> bool {
-        match self {
-            &Link::Id { link: ref s }             => s.eq(id),
-            &Link::Annotated { link: ref s, .. }  => s.eq(id),
-        }
+        self.get_store_id().eq(id)
     }
 
     /// Get the StoreId inside the Link, which is always present
@@ -76,6 +156,41 @@ impl Link {
         match self {
             &Link::Id { link: ref s }             => s,
             &Link::Annotated { link: ref s, .. }  => s,
+            &Link::Typed { link: ref s, .. }      => s,
+        }
+    }
+
+    /// Get the type of the link, if it is a typed link
+    pub fn link_type(&self) -> Option<&LinkType> {
+        match self {
+            &Link::Typed { ref link_type, .. } => Some(link_type),
+            _ => None,
+        }
+    }
+
+    /// Check whether the link is of the type named `name`
+    pub fn is_of_type(&self, name: &str) -> bool {
+        self.link_type().map(|t| t.name() == name).unwrap_or(false)
+    }
+
+    /// Get the annotation of the link, if any
+    pub fn annotation(&self) -> Option<&str> {
+        match self {
+            &Link::Annotated { ref annotation, .. } => Some(annotation.as_str()),
+            &Link::Typed { ref annotation, .. }     => annotation.as_ref().map(String::as_ref),
+            &Link::Id { .. }                        => None,
+        }
+    }
+
+    /// Get the same link with the annotation replaced by `annotation`
+    pub fn with_annotation(self, annotation: Option<String>) -> Link {
+        match (self, annotation) {
+            (Link::Id { link }, None) | (Link::Annotated { link, .. }, None) =>
+                Link::Id { link },
+            (Link::Id { link }, Some(annotation)) | (Link::Annotated { link, .. }, Some(annotation)) =>
+                Link::Annotated { link, annotation },
+            (Link::Typed { link, link_type, .. }, annotation) =>
+                Link::Typed { link, link_type, annotation },
         }
     }
 
@@ -85,6 +200,8 @@ impl Link {
             Link::Id { link: s } => Link::Id { link: s.without_base() },
             Link::Annotated { link: s, annotation: ann } =>
                 Link::Annotated { link: s.without_base(), annotation: ann },
+            Link::Typed { link: s, link_type, annotation } =>
+                Link::Typed { link: s.without_base(), link_type, annotation },
         }
     }
 
@@ -95,29 +212,38 @@ impl Link {
             Link::Id { link: s } => Link::Id { link: s.with_base(pb) },
             Link::Annotated { link: s, annotation: ann } =>
                 Link::Annotated { link: s.with_base(pb), annotation: ann },
+            Link::Typed { link: s, link_type, annotation } =>
+                Link::Typed { link: s.with_base(pb), link_type, annotation },
         }
     }
 
     fn to_value(&self) -> Result<Value> {
+        let link = self.get_store_id()
+            .to_str()
+            .map(Value::String)
+            .context(EM::ConversionError)
+            .map_err(Error::from)?;
+
         match self {
-            &Link::Id { link: ref s } =>
-                s.to_str()
-                .map(Value::String)
-                .context(EM::ConversionError)
-                .map_err(Error::from),
-            &Link::Annotated { ref link, annotation: ref anno } => {
-                link.to_str()
-                    .map(Value::String)
-                    .context(EM::ConversionError)
-                    .map_err(Error::from)
-                    .map(|link| {
-                        let mut tab = BTreeMap::new();
-
-                        tab.insert("link".to_owned(),       link);
-                        tab.insert("annotation".to_owned(), Value::String(anno.clone()));
-                        Value::Table(tab)
-                    })
-            }
+            &Link::Id { .. } => Ok(link),
+            &Link::Annotated { annotation: ref anno, .. } => {
+                let mut tab = BTreeMap::new();
+
+                tab.insert("link".to_owned(),       link);
+                tab.insert("annotation".to_owned(), Value::String(anno.clone()));
+                Ok(Value::Table(tab))
+            },
+            &Link::Typed { ref link_type, ref annotation, .. } => {
+                let mut tab = BTreeMap::new();
+
+                tab.insert("link".to_owned(),    link);
+                tab.insert("type".to_owned(),    Value::String(link_type.name().to_owned()));
+                tab.insert("inverse".to_owned(), Value::String(link_type.inverse_name().to_owned()));
+                if let Some(ref anno) = *annotation {
+                    tab.insert("annotation".to_owned(), Value::String(anno.clone()));
+                }
+                Ok(Value::Table(tab))
+            },
         }
     }
 
@@ -130,6 +256,9 @@ impl ::std::cmp::PartialEq for Link {
             (&Link::Annotated { link: ref a, annotation: ref ann1 },
              &Link::Annotated { link: ref b, annotation: ref ann2 }) =>
                 (a, ann1).eq(&(b, ann2)),
+            (&Link::Typed { link: ref a, link_type: ref t1, annotation: ref ann1 },
+             &Link::Typed { link: ref b, link_type: ref t2, annotation: ref ann2 }) =>
+                (a, t1, ann1).eq(&(b, t2, ann2)),
             _ => false,
         }
     }
@@ -147,25 +276,20 @@ impl Into<StoreId> for Link {
         match self {
             Link::Id { link }            => link,
             Link::Annotated { link, .. } => link,
+            Link::Typed { link, .. }     => link,
         }
     }
 }
 
 impl IntoStoreId for Link {
     fn into_storeid(self) -> Result<StoreId> {
-        match self {
-            Link::Id { link }            => Ok(link),
-            Link::Annotated { link, .. } => Ok(link),
-        }
+        Ok(self.into())
     }
 }
 
 impl AsRef<StoreId> for Link {
     fn as_ref(&self) -> &StoreId {
-        match self {
-            &Link::Id { ref link }            => &link,
-            &Link::Annotated { ref link, .. } => &link,
-        }
+        self.get_store_id()
     }
 }
 
@@ -188,6 +312,38 @@ pub trait InternalLinker {
 
     /// Add internal annotated link
     fn add_internal_annotated_link(&mut self, link: &mut Entry, annotation: String) -> Result<()>;
+
+    /// Add an internal link of type `link_type`
+    ///
+    /// The linked entry links back with the inverse type. An existing link between the two
+    /// entries is replaced, so this can also be used to change the type of a link.
+    fn add_internal_typed_link(&mut self, link: &mut Entry, link_type: LinkType) -> Result<()>;
+
+    /// Get the internal links of the type named `name`
+    fn get_internal_links_of_type(&self, name: &str) -> Result<LinkIter>;
+
+    /// Get the annotation of the link to `link`
+    ///
+    /// Returns an error if there is no link to `link`.
+    fn get_internal_link_annotation(&self, link: &StoreId) -> Result<Option<String>>;
+
+    /// Set the annotation of the link to `link`, replacing the existing one
+    ///
+    /// Returns an error if there is no link to `link`.
+    fn set_internal_link_annotation(&mut self, link: &StoreId, annotation: String) -> Result<()>;
+
+    /// Remove the annotation of the link to `link`
+    ///
+    /// Returns an error if there is no link to `link`.
+    fn remove_internal_link_annotation(&mut self, link: &StoreId) -> Result<()>;
+
+    /// Remove the links for which `keep` returns false and return them
+    ///
+    /// Only the links of this entry are changed, the linked entries keep their links back. Use
+    /// this only if the linked entries are not available, for example because this entry is
+    /// copied to another store.
+    fn retain_internal_links<F>(&mut self, keep: F) -> Result<Vec<Link>>
+        where F: Fn(&Link) -> bool;
 }
 
 pub mod iter {
@@ -232,6 +388,7 @@ pub mod iter {
 
     impl<I: Iterator<Item = Link>> IntoValues for I {
         fn into_values(self) -> Vec<Result<Value>> {
+            // Plain links stay plain strings, only annotated and typed links are tables
             self.map(|s| s.without_base())
                 .unique()
                 .sorted()
@@ -394,21 +551,11 @@ impl InternalLinker for Entry {
     fn get_internal_links(&self) -> Result<LinkIter> {
         debug!("Getting internal links");
         trace!("Getting internal links from header of '{}' = {:?}", self.get_location(), self.get_header());
-        let res = self
-            .get_header()
-            .read("links.internal")
-            .map_err(Error::from)
-            .context(EM::EntryHeaderReadError)
-            .context(EM::EntryHeaderError)
-            .map_err(Error::from)
-            .map(|r| r.cloned());
-        process_rw_result(res)
+        read_links(self.get_header())
     }
 
     /// Set the links in a header and return the old links, if any.
     fn set_internal_links(&mut self, links: Vec<&mut Entry>) -> Result<LinkIter> {
-        use internal::iter::IntoValues;
-
         debug!("Setting internal links");
 
         let self_location = self.get_location().clone();
@@ -421,22 +568,7 @@ impl InternalLinker for Entry {
             new_links.push(link.get_location().clone().into());
         }
 
-        let new_links = LinkIter::new(new_links)
-                             .into_values()
-                             .into_iter()
-                             .fold(Ok(vec![]), |acc: Result<Vec<_>>, elem| {
-                                acc.and_then(move |mut v| {
-                                    v.push(elem.context(EM::ConversionError)?);
-                                    Ok(v)
-                                })
-                            })?;
-        let res = self
-            .get_header_mut()
-            .insert("links.internal", Value::Array(new_links))
-            .map_err(Error::from)
-            .context(EM::EntryHeaderReadError)
-            .map_err(Error::from);
-        process_rw_result(res)
+        write_links(self.get_header_mut(), LinkIter::new(new_links))
     }
 
     fn add_internal_link(&mut self, link: &mut Entry) -> Result<()> {
@@ -488,6 +620,57 @@ impl InternalLinker for Entry {
         add_internal_link_with_instance(self, link, new_link)
     }
 
+    fn add_internal_typed_link(&mut self, link: &mut Entry, link_type: LinkType) -> Result<()> {
+        debug!("Adding internal link of type {:?}: {:?}", link_type, link);
+        let backlink = Link::Typed {
+            link: self.get_location().clone(),
+            link_type: link_type.inverse(),
+            annotation: None,
+        };
+        let new_link = Link::Typed {
+            link: link.get_location().clone(),
+            link_type: link_type,
+            annotation: None,
+        };
+
+        replace_link(link, backlink).and_then(|_| replace_link(self, new_link))
+    }
+
+    fn get_internal_links_of_type(&self, name: &str) -> Result<LinkIter> {
+        self.get_internal_links()
+            .map(|links| LinkIter::new(links.filter(|l| l.is_of_type(name)).collect()))
+    }
+
+    fn get_internal_link_annotation(&self, link: &StoreId) -> Result<Option<String>> {
+        let target = link.clone().without_base();
+
+        self.get_internal_links()?
+            .find(|l| l.eq_store_id(&target))
+            .map(|l| l.annotation().map(String::from))
+            .ok_or_else(|| format_err!("No link from {} to {}", self.get_location(), link))
+    }
+
+    fn set_internal_link_annotation(&mut self, link: &StoreId, annotation: String) -> Result<()> {
+        replace_link_annotation(self, link, Some(annotation))
+    }
+
+    fn remove_internal_link_annotation(&mut self, link: &StoreId) -> Result<()> {
+        replace_link_annotation(self, link, None)
+    }
+
+    fn retain_internal_links<F>(&mut self, keep: F) -> Result<Vec<Link>>
+        where F: Fn(&Link) -> bool
+    {
+        let (kept, removed) : (Vec<Link>, Vec<Link>) = self.get_internal_links()?.partition(|l| keep(l));
+
+        if !removed.is_empty() {
+            debug!("Removing links from {}: {:?}", self.get_location(), removed);
+            rewrite_links(self.get_header_mut(), kept.into_iter())?;
+        }
+
+        Ok(removed)
+    }
+
 }
 
 fn add_internal_link_with_instance(this: &mut Entry, link: &mut Entry, instance: Link) -> Result<()> {
@@ -503,52 +686,103 @@ fn add_internal_link_with_instance(this: &mut Entry, link: &mut Entry, instance:
         })
 }
 
+/// Replace the link from `this` to the entry the link `new` points to, if any, with `new`
+fn replace_link(this: &mut Entry, new: Link) -> Result<()> {
+    let target = new.get_store_id().clone().without_base();
+
+    this.get_internal_links()
+        .and_then(|links| {
+            let links = links
+                .filter(|l| !l.eq_store_id(&target))
+                .chain(LinkIter::new(vec![new]));
+            rewrite_links(this.get_header_mut(), links)
+        })
+}
+
+fn replace_link_annotation(this: &mut Entry, link: &StoreId, annotation: Option<String>) -> Result<()> {
+    let target = link.clone().without_base();
+    let links  = this.get_internal_links()?.collect::<Vec<_>>();
+
+    if !links.iter().any(|l| l.eq_store_id(&target)) {
+        return Err(format_err!("No link from {} to {}", this.get_location(), link))
+    }
+
+    let links = links
+        .into_iter()
+        .map(|l| if l.eq_store_id(&target) {
+            l.with_annotation(annotation.clone())
+        } else {
+            l
+        });
+
+    rewrite_links(this.get_header_mut(), links)
+}
+
 fn rewrite_links<I: Iterator<Item = Link>>(header: &mut Value, links: I) -> Result<()> {
-    let links = links.into_values()
-                     .into_iter()
-                     .fold(Ok(vec![]), |acc: Result<Vec<_>>, elem| {
-                        acc.and_then(move |mut v| {
-                            v.push(elem.context(EM::ConversionError)?);
-                            Ok(v)
-                        })
-                     })?;
-
-    debug!("Setting new link array: {:?}", links);
-    let process = header
-        .insert("links.internal", Value::Array(links))
+    write_links(header, links).map(|_| ())
+}
+
+/// Read the plain and the detailed links from `header`
+fn read_links(header: &Value) -> Result<LinkIter> {
+    let read = |key: &str| header
+        .read(key)
         .map_err(Error::from)
         .context(EM::EntryHeaderReadError)
-        .map_err(Error::from);
-    process_rw_result(process).map(|_| ())
+        .context(EM::EntryHeaderError)
+        .map_err(Error::from)
+        .map(|r| r.cloned());
+
+    let plain    = process_rw_result(read(LINKS_HEADER))?;
+    let detailed = process_rw_result(read(DETAILED_LINKS_HEADER))?;
+    Ok(LinkIter::new(plain.chain(detailed).collect()))
+}
+
+/// Write `links` to `header` and return the links which were set before
+///
+/// Plain links are written as strings to `links.internal`, links with a type or an annotation
+/// as tables to `links.internal_detailed`. The latter is removed if there are no such links, so
+/// the header of entries which only have plain links keeps its format.
+fn write_links<I: Iterator<Item = Link>>(header: &mut Value, links: I) -> Result<LinkIter> {
+    let (plain, detailed) : (Vec<Value>, Vec<Value>) = links
+        .into_values()
+        .into_iter()
+        .map(|elem| elem.context(EM::ConversionError).map_err(Error::from))
+        .collect::<Result<Vec<_>>>()?
+        .into_iter()
+        .partition(|v| is_match!(*v, Value::String(_)));
+
+    debug!("Setting new link arrays: {:?}, {:?}", plain, detailed);
+
+    let old = read_links(header)?.collect::<Vec<_>>();
+
+    let _ = header
+        .insert(LINKS_HEADER, Value::Array(plain))
+        .map_err(Error::from)
+        .context(EM::EntryHeaderWriteError)?;
+
+    if !detailed.is_empty() {
+        let _ = header
+            .insert(DETAILED_LINKS_HEADER, Value::Array(detailed))
+            .map_err(Error::from)
+            .context(EM::EntryHeaderWriteError)?;
+    } else if let Some(&mut Value::Table(ref mut links)) = header
+        .read_mut("links")
+        .map_err(Error::from)
+        .context(EM::EntryHeaderWriteError)?
+    {
+        // toml-query refuses to delete non-empty arrays, so remove the key from the table itself
+        let _ = links.remove("internal_detailed");
+    }
+
+    Ok(LinkIter::new(old))
 }
 
 /// When Linking A -> B, the specification wants us to link back B -> A.
 /// This is a helper function which does this.
 fn add_foreign_link(target: &mut Entry, from: StoreId) -> Result<()> {
     debug!("Linking back from {:?} to {:?}", target.get_location(), from);
-    target.get_internal_links()
-        .and_then(|links| {
-            let links = links
-                             .chain(LinkIter::new(vec![from.into()]))
-                             .into_values()
-                             .into_iter()
-                             .fold(Ok(vec![]), |acc: Result<Vec<_>>, elem| {
-                                acc.and_then(move |mut v| {
-                                    v.push(elem.context(EM::ConversionError)?);
-                                    Ok(v)
-                                })
-                             })?;
-            debug!("Setting links in {:?}: {:?}", target.get_location(), links);
-
-            let res = target
-                .get_header_mut()
-                .insert("links.internal", Value::Array(links))
-                .map_err(Error::from)
-                .context(EM::EntryHeaderReadError)
-                .map_err(Error::from);
-
-            process_rw_result(res).map(|_| ())
-        })
+    let links = target.get_internal_links()?.chain(LinkIter::new(vec![from.into()]));
+    rewrite_links(target.get_header_mut(), links)
 }
 
 fn process_rw_result(links: Result<Option<Value>>) -> Result<LinkIter> {
@@ -584,6 +818,15 @@ fn process_rw_result(links: Result<Option<Value>>) -> Result<LinkIter> {
                     .map(|s| Link::Id { link: s })
                     .map_err(From::from)
                     ,
+                Value::Table(ref tab) if tab.contains_key("type") => parse_typed_link(tab),
+                Value::Table(ref tab) if tab.len() == 1 && tab.contains_key("link") => {
+                    match tab.get("link") {
+                        Some(&Value::String(ref link)) => StoreId::new_baseless(PathBuf::from(link))
+                            .map(|s| Link::Id { link: s })
+                            .map_err(From::from),
+                        _ => Err(err_msg("Link parser: Field type error")),
+                    }
+                },
                 Value::Table(mut tab) => {
                     debug!("Destructuring table");
                     if !tab.contains_key("link")
@@ -622,162 +865,268 @@ fn process_rw_result(links: Result<Option<Value>>) -> Result<LinkIter> {
     Ok(LinkIter::new(links))
 }
 
+fn parse_typed_link(tab: &BTreeMap<String, Value>) -> Result<Link> {
+    use std::path::PathBuf;
+
+    let get_str = |key: &str| -> Result<Option<String>> {
+        match tab.get(key) {
+            Some(&Value::String(ref s)) => Ok(Some(s.clone())),
+            Some(_)                     => Err(format_err!("Link parser: Field type error: {}", key)),
+            None                        => Ok(None),
+        }
+    };
+
+    let link      = get_str("link")?.ok_or_else(|| err_msg("Link parser: field missing"))?;
+    let name      = get_str("type")?.ok_or_else(|| err_msg("Link parser: field missing"))?;
+    let link_type = match get_str("inverse")? {
+        Some(inverse) => LinkType::new(name, inverse),
+        None          => LinkType::builtin(&name).unwrap_or_else(|| LinkType::symmetric(name)),
+    };
+    let annotation = get_str("annotation")?;
+
+    StoreId::new_baseless(PathBuf::from(link))
+        .map_err(From::from)
+        .map(|link| Link::Typed { link, link_type, annotation })
+}
+
 pub mod store_check {
+    use std::collections::BTreeMap;
+    use std::fmt::{Display, Formatter, Error as FmtError};
+
     use libimagstore::store::Store;
+    use libimagstore::store::FileLockEntry;
+    use libimagstore::storeid::StoreId;
 
-    use failure::ResultExt;
     use failure::Fallible as Result;
-    use failure::Error;
-    use failure::err_msg;
 
-    pub trait StoreLinkConsistentExt {
-        fn check_link_consistency(&self) -> Result<()>;
+    use internal::InternalLinker;
+    use internal::Link;
+
+    /// A problem with the links in the store
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum LinkProblem {
+        /// `from` links to `to`, but `to` does not link back to `from`
+        MissingBacklink { from: StoreId, to: StoreId },
+
+        /// `from` links to `to`, which does not exist
+        DanglingLink { from: StoreId, to: StoreId },
     }
 
-    impl StoreLinkConsistentExt for Store {
-        fn check_link_consistency(&self) -> Result<()> {
-            use std::collections::HashMap;
+    impl LinkProblem {
+        /// The entry which holds the link
+        pub fn from(&self) -> &StoreId {
+            match *self {
+                LinkProblem::MissingBacklink { ref from, .. } => from,
+                LinkProblem::DanglingLink { ref from, .. }    => from,
+            }
+        }
 
-            use internal::InternalLinker;
+        /// The entry the link points to
+        pub fn to(&self) -> &StoreId {
+            match *self {
+                LinkProblem::MissingBacklink { ref to, .. } => to,
+                LinkProblem::DanglingLink { ref to, .. }    => to,
+            }
+        }
+    }
 
-            use libimagstore::storeid::StoreId;
-            use libimagutil::debug_result::DebugResult;
+    impl Display for LinkProblem {
+        fn fmt(&self, fmt: &mut Formatter) -> ::std::result::Result<(), FmtError> {
+            match *self {
+                LinkProblem::MissingBacklink { ref from, ref to } => write!(fmt,
+                    "Missing backlink: {} -> {}",
+                    from.local_display_string(),
+                    to.local_display_string()),
+
+                LinkProblem::DanglingLink { ref from, ref to } => write!(fmt,
+                    "Dead link: {} -> {}",
+                    from.local_display_string(),
+                    to.local_display_string()),
+            }
+        }
+    }
+
+    /// What to do about a `LinkProblem`
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum RepairAction {
+        /// Add the missing link back from the target to the linking entry
+        AddBacklink,
 
-            // Helper data structure to collect incoming and outgoing links for each StoreId
-            #[derive(Debug, Default)]
-            struct Linking {
-                outgoing: Vec<StoreId>,
-                incoming: Vec<StoreId>,
+        /// Remove the link from the linking entry
+        RemoveLink,
+
+        /// Leave the problem as it is
+        Ignore,
+    }
+
+    impl RepairAction {
+        /// The action which fixes `problem` without losing a link which can be kept
+        pub fn default_for(problem: &LinkProblem) -> RepairAction {
+            match *problem {
+                LinkProblem::MissingBacklink { .. } => RepairAction::AddBacklink,
+                LinkProblem::DanglingLink { .. }    => RepairAction::RemoveLink,
             }
+        }
+    }
 
-            // Helper function to aggregate the Link network
-            //
-            // This function aggregates a HashMap which maps each StoreId object in the store onto
-            // a Linking object, which contains a list of StoreIds which this entry links to and a
-            // list of StoreIds which link to the current one.
-            //
-            // The lambda returns an error if something fails
-            let aggregate_link_network = |store: &Store| -> Result<HashMap<StoreId, Linking>> {
-                store
-                    .entries()?
-                    .into_get_iter()
-                    .fold(Ok(HashMap::new()), |map, element| {
-                        map.and_then(|mut map| {
-                            debug!("Checking element = {:?}", element);
-                            let entry = element?.ok_or_else(|| err_msg("TODO: Not yet handled"))?;
-
-                            debug!("Checking entry = {:?}", entry.get_location());
-
-                            let internal_links = entry
-                                .get_internal_links()?
-                                .into_getter(store); // get the FLEs from the Store
-
-                            let mut linking = Linking::default();
-                            for internal_link in internal_links {
-                                debug!("internal link = {:?}", internal_link);
-
-                                linking.outgoing.push(internal_link?.get_location().clone());
-                                linking.incoming.push(entry.get_location().clone());
-                            }
+    /// All problems found by `StoreLinkConsistentExt::check_link_consistency()`
+    #[derive(Debug, Default)]
+    pub struct ConsistencyReport(Vec<LinkProblem>);
 
-                            map.insert(entry.get_location().clone(), linking);
-                            Ok(map)
-                        })
-                    })
-            };
-
-            // Helper to check whethre all StoreIds in the network actually exists
-            //
-            // Because why not?
-            let all_collected_storeids_exist = |network: &HashMap<StoreId, Linking>| -> Result<()> {
-                for (id, _) in network.iter() {
-                    if is_match!(self.get(id.clone()), Ok(Some(_))) {
-                        debug!("Exists in store: {:?}", id);
-
-                        if !id.exists()? {
-                            warn!("Does exist in store but not on FS: {:?}", id);
-                            return Err(err_msg("Link target does not exist"))
-                        }
-                    } else {
-                        warn!("Does not exist in store: {:?}", id);
-                        return Err(err_msg("Link target does not exist"))
-                    }
-                }
+    impl ConsistencyReport {
+        pub fn is_consistent(&self) -> bool {
+            self.0.is_empty()
+        }
 
-                Ok(())
-            };
-
-            // Helper function to create a SLCECD::OneDirectionalLink error object
-            let mk_one_directional_link_err = |src: StoreId, target: StoreId| -> Error {
-                Error::from(format_err!("Dead link: {} -> {}",
-                                        src.local_display_string(),
-                                        target.local_display_string()))
-            };
-
-            // Helper lambda to check whether the _incoming_ links of each entry actually also
-            // appear in the _outgoing_ list of the linked entry
-            let incoming_links_exists_as_outgoing_links =
-                |src: &StoreId, linking: &Linking, network: &HashMap<StoreId, Linking>| -> Result<()> {
-                    for link in linking.incoming.iter() {
-                        // Check whether the links which are _incoming_ on _src_ are outgoing
-                        // in each of the links in the incoming list.
-                        let incoming_consistent = network.get(link)
-                            .map(|l| l.outgoing.contains(src))
-                            .unwrap_or(false);
-
-                        if !incoming_consistent {
-                            return Err(mk_one_directional_link_err(src.clone(), link.clone()))
-                        }
-                    }
+        pub fn problems(&self) -> &[LinkProblem] {
+            &self.0
+        }
+    }
 
-                    Ok(())
-                };
+    impl IntoIterator for ConsistencyReport {
+        type Item     = LinkProblem;
+        type IntoIter = ::std::vec::IntoIter<LinkProblem>;
 
-            // Helper lambda to check whether the _outgoing links of each entry actually also
-            // appear in the _incoming_ list of the linked entry
-            let outgoing_links_exist_as_incoming_links =
-                |src: &StoreId, linking: &Linking, network: &HashMap<StoreId, Linking>| -> Result<()> {
-                    for link in linking.outgoing.iter() {
-                        // Check whether the links which are _outgoing_ on _src_ are incoming
-                        // in each of the links in the outgoing list.
-                        let outgoing_consistent = network.get(link)
-                            .map(|l| l.incoming.contains(src))
-                            .unwrap_or(false);
-
-                        if !outgoing_consistent {
-                            return Err(mk_one_directional_link_err(link.clone(), src.clone()))
-                        }
-                    }
+        fn into_iter(self) -> Self::IntoIter {
+            self.0.into_iter()
+        }
+    }
+
+    /// What `StoreLinkConsistentExt::repair_link_consistency()` did
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+    pub struct RepairSummary {
+        added_backlinks: usize,
+        removed_links: usize,
+        ignored: usize,
+    }
+
+    impl RepairSummary {
+        pub fn added_backlinks(&self) -> usize {
+            self.added_backlinks
+        }
+
+        pub fn removed_links(&self) -> usize {
+            self.removed_links
+        }
+
+        pub fn ignored(&self) -> usize {
+            self.ignored
+        }
+    }
+
+    pub trait StoreLinkConsistentExt {
+        /// Check the internal links of all entries in the store
+        ///
+        /// Returns all problems found, not only the first one.
+        fn check_link_consistency(&self) -> Result<ConsistencyReport>;
+
+        /// Repair the problems in `report`, deciding what to do about each problem with `decide`
+        fn repair_link_consistency_with<F>(&self, report: &ConsistencyReport, decide: F)
+            -> Result<RepairSummary>
+            where F: FnMut(&LinkProblem) -> Result<RepairAction>;
+
+        /// Repair the problems in `report` with the `RepairAction::default_for()` each problem
+        fn repair_link_consistency(&self, report: &ConsistencyReport) -> Result<RepairSummary> {
+            self.repair_link_consistency_with(report, |problem| Ok(RepairAction::default_for(problem)))
+        }
+    }
 
-                    Ok(())
+    impl StoreLinkConsistentExt for Store {
+        fn check_link_consistency(&self) -> Result<ConsistencyReport> {
+            // Map each entry in the store onto its outgoing links
+            let mut network : BTreeMap<StoreId, Vec<Link>> = BTreeMap::new();
+            for entry in self.entries()?.into_get_iter() {
+                let entry = match entry? {
+                    Some(entry) => entry,
+                    None        => continue,
                 };
+                debug!("Checking entry = {:?}", entry.get_location());
 
-            aggregate_link_network(&self)
-                .map_dbg_str("Aggregated")
-                .map_dbg(|nw| {
-                    let mut s = String::new();
-                    for (k, v) in nw {
-                        s.push_str(&format!("{}\n in: {:?}\n out: {:?}", k, v.incoming, v.outgoing));
-                    }
-                    s
-                })
-                .and_then(|nw| {
-                    all_collected_storeids_exist(&nw)
-                        .map(|_| nw)
-                        .context(err_msg("Link handling error"))
-                        .map_err(Error::from)
-                })
-                .and_then(|nw| {
-                    for (id, linking) in nw.iter() {
-                        incoming_links_exists_as_outgoing_links(id, linking, &nw)?;
-                        outgoing_links_exist_as_incoming_links(id, linking, &nw)?;
+                let links = entry.get_internal_links()?.collect();
+                let _     = network.insert(entry.get_location().clone().without_base(), links);
+            }
+
+            let mut problems = vec![];
+            for (from, links) in network.iter() {
+                for link in links {
+                    let to = link.get_store_id().clone().without_base();
+
+                    match network.get(&to) {
+                        None => {
+                            debug!("Does not exist in store: {:?}", to);
+                            problems.push(LinkProblem::DanglingLink { from: from.clone(), to });
+                        },
+                        Some(backlinks) => if !backlinks.iter().any(|l| l.eq_store_id(from)) {
+                            debug!("No link back from {:?} to {:?}", to, from);
+                            problems.push(LinkProblem::MissingBacklink { from: from.clone(), to });
+                        },
                     }
-                    Ok(())
-                })
-                .map(|_| ())
+                }
+            }
+
+            Ok(ConsistencyReport(problems))
+        }
+
+        fn repair_link_consistency_with<F>(&self, report: &ConsistencyReport, mut decide: F)
+            -> Result<RepairSummary>
+            where F: FnMut(&LinkProblem) -> Result<RepairAction>
+        {
+            use internal::replace_link;
+            use internal::rewrite_links;
+
+            let mut summary = RepairSummary::default();
+            for problem in report.problems() {
+                match (decide(problem)?, problem) {
+                    (RepairAction::AddBacklink, &LinkProblem::MissingBacklink { ref from, ref to }) => {
+                        debug!("Adding backlink from {:?} to {:?}", to, from);
+                        let link = get_entry(self, from)?
+                            .get_internal_links()?
+                            .find(|l| l.eq_store_id(to))
+                            .ok_or_else(|| format_err!("No link from {} to {}", from, to))?;
+
+                        // The annotation belongs to the linking entry only
+                        let backlink = match link.link_type() {
+                            Some(link_type) => Link::Typed {
+                                link: from.clone(),
+                                link_type: link_type.inverse(),
+                                annotation: None,
+                            },
+                            None => Link::Id { link: from.clone() },
+                        };
+
+                        replace_link(&mut *get_entry(self, to)?, backlink)?;
+                        summary.added_backlinks += 1;
+                    },
+
+                    (RepairAction::AddBacklink, &LinkProblem::DanglingLink { ref to, .. }) => {
+                        return Err(format_err!("Cannot link back from {}, it does not exist", to))
+                    },
+
+                    (RepairAction::RemoveLink, problem) => {
+                        debug!("Removing link from {:?} to {:?}", problem.from(), problem.to());
+                        let mut entry = get_entry(self, problem.from())?;
+                        let links     = entry
+                            .get_internal_links()?
+                            .filter(|l| !l.eq_store_id(problem.to()))
+                            .collect::<Vec<_>>();
+
+                        rewrite_links(entry.get_header_mut(), links.into_iter())?;
+                        summary.removed_links += 1;
+                    },
+
+                    (RepairAction::Ignore, _) => summary.ignored += 1,
+                }
+            }
+
+            Ok(summary)
         }
     }
 
+    fn get_entry<'a>(store: &'a Store, id: &StoreId) -> Result<FileLockEntry<'a>> {
+        store.get(id.clone())?
+            .ok_or_else(|| format_err!("Entry vanished: {}", id.local_display_string()))
+    }
+
 }
 
 #[cfg(test)]
@@ -995,6 +1344,141 @@ mod test {
         assert_eq!(e3.get_internal_links().unwrap().collect::<Vec<_>>().len(), 0);
     }
 
+    #[test]
+    fn test_typed_link() {
+        use toml::Value;
+        use toml_query::read::TomlValueReadExt;
+        use super::LinkType;
+
+        setup_logging();
+        let store      = get_store();
+        let mut entry1 = store.create(PathBuf::from("test_typed_link-1")).unwrap();
+        let mut entry2 = store.create(PathBuf::from("test_typed_link-2")).unwrap();
+
+        assert!(entry1.add_internal_typed_link(&mut entry2, LinkType::parent()).is_ok());
+
+        let links1 = entry1.get_internal_links().unwrap().collect::<Vec<_>>();
+        let links2 = entry2.get_internal_links().unwrap().collect::<Vec<_>>();
+        assert_eq!(links1.len(), 1);
+        assert_eq!(links2.len(), 1);
+        assert_eq!(links1[0].link_type(), Some(&LinkType::parent()));
+        assert_eq!(links2[0].link_type(), Some(&LinkType::child()));
+
+        assert_eq!(entry1.get_internal_links_of_type("parent").unwrap().count(), 1);
+        assert_eq!(entry1.get_internal_links_of_type("child").unwrap().count(), 0);
+        assert_eq!(entry2.get_internal_links_of_type("child").unwrap().count(), 1);
+
+        // Untyped links stay plain strings, even when mixed with typed links
+        let mut entry3 = store.create(PathBuf::from("test_typed_link-3")).unwrap();
+        assert!(entry1.add_internal_link(&mut entry3).is_ok());
+        assert_eq!(entry1.get_internal_links().unwrap().count(), 2);
+        {
+            let hdr = entry1.get_header();
+            let plain = hdr.read("links.internal").unwrap().unwrap();
+            assert_eq!(*plain, Value::Array(vec![Value::String(String::from("test_typed_link-3"))]));
+
+            match hdr.read("links.internal_detailed").unwrap() {
+                Some(&Value::Array(ref a)) => {
+                    assert_eq!(a.len(), 1);
+                    assert!(is_match!(a[0], Value::Table(_)));
+                },
+                other => panic!("Expected an array of detailed links, got {:?}", other),
+            }
+        }
+        assert!(entry1.remove_internal_link(&mut entry3).is_ok());
+
+        // Changing the type replaces the link
+        let mentor = LinkType::new("mentor", "mentee");
+        assert!(entry2.add_internal_typed_link(&mut entry1, mentor.clone()).is_ok());
+
+        let links1 = entry1.get_internal_links().unwrap().collect::<Vec<_>>();
+        let links2 = entry2.get_internal_links().unwrap().collect::<Vec<_>>();
+        assert_eq!(links1.len(), 1);
+        assert_eq!(links2.len(), 1);
+        assert_eq!(links1[0].link_type(), Some(&mentor.inverse()));
+        assert_eq!(links2[0].link_type(), Some(&mentor));
+
+        assert!(entry1.remove_internal_link(&mut entry2).is_ok());
+        assert_eq!(entry1.get_internal_links().unwrap().count(), 0);
+        assert_eq!(entry2.get_internal_links().unwrap().count(), 0);
+
+        // Without typed links, the table array is removed again
+        assert!(entry1.get_header().read("links.internal_detailed").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_link_annotation_accessors() {
+        use super::LinkType;
+
+        setup_logging();
+        let store      = get_store();
+        let mut entry1 = store.create(PathBuf::from("test_link_annotation_accessors-1")).unwrap();
+        let mut entry2 = store.create(PathBuf::from("test_link_annotation_accessors-2")).unwrap();
+        let mut entry3 = store.create(PathBuf::from("test_link_annotation_accessors-3")).unwrap();
+        let id2        = entry2.get_location().clone();
+        let id3        = entry3.get_location().clone();
+
+        assert!(entry1.add_internal_link(&mut entry2).is_ok());
+        assert!(entry1.add_internal_typed_link(&mut entry3, LinkType::parent()).is_ok());
+        assert_eq!(entry1.get_internal_link_annotation(&id2).unwrap(), None);
+
+        assert!(entry1.set_internal_link_annotation(&id2, String::from("met at")).is_ok());
+        assert!(entry1.set_internal_link_annotation(&id3, String::from("source for")).is_ok());
+        assert_eq!(entry1.get_internal_link_annotation(&id2).unwrap(), Some(String::from("met at")));
+        assert_eq!(entry1.get_internal_link_annotation(&id3).unwrap(), Some(String::from("source for")));
+        assert!(entry1.get_internal_links_of_type("parent").unwrap().count() == 1);
+
+        assert!(entry1.remove_internal_link_annotation(&id2).is_ok());
+        assert_eq!(entry1.get_internal_link_annotation(&id2).unwrap(), None);
+        assert_eq!(entry1.get_internal_links().unwrap().count(), 2);
+
+        let id1 = entry1.get_location().clone();
+        assert!(entry2.get_internal_link_annotation(&id3).is_err());
+        assert!(entry2.set_internal_link_annotation(&id3, String::from("x")).is_err());
+        assert_eq!(entry2.get_internal_link_annotation(&id1).unwrap(), None);
+    }
+
+    #[test]
+    fn test_retain_internal_links() {
+        use toml_query::read::TomlValueReadExt;
+        use super::LinkType;
+
+        setup_logging();
+        let store      = get_store();
+        let mut entry1 = store.create(PathBuf::from("test_retain_internal_links-1")).unwrap();
+        let mut entry2 = store.create(PathBuf::from("test_retain_internal_links-2")).unwrap();
+        let mut entry3 = store.create(PathBuf::from("test_retain_internal_links-3")).unwrap();
+        let id2        = entry2.get_location().clone().without_base();
+
+        assert!(entry1.add_internal_link(&mut entry2).is_ok());
+        assert!(entry1.add_internal_typed_link(&mut entry3, LinkType::parent()).is_ok());
+
+        let removed = entry1.retain_internal_links(|l| l.get_store_id() == &id2).unwrap();
+        assert_eq!(removed.len(), 1);
+        assert!(removed[0].is_of_type("parent"));
+
+        let links = entry1.get_internal_links().unwrap().collect::<Vec<_>>();
+        assert_eq!(links, vec![Link::Id { link: id2 }]);
+        assert!(entry1.get_header().read("links.internal_detailed").unwrap().is_none());
+
+        // The linked entry is not touched
+        assert_eq!(entry3.get_internal_links().unwrap().count(), 1);
+
+        let removed = entry1.retain_internal_links(|_| true).unwrap();
+        assert!(removed.is_empty());
+    }
+
+    #[test]
+    fn test_builtin_link_types() {
+        use super::LinkType;
+
+        assert_eq!(LinkType::builtin("blocks"), Some(LinkType::blocks()));
+        assert_eq!(LinkType::builtin("depends-on"), Some(LinkType::depends_on()));
+        assert_eq!(LinkType::builtin("referenced-by").map(|t| t.inverse()), Some(LinkType::references()));
+        assert_eq!(LinkType::builtin("mentor"), None);
+        assert!(LinkType::symmetric("related").is_symmetric());
+    }
+
     #[test]
     fn test_link_annotating() {
         setup_logging();
@@ -1019,10 +1503,57 @@ mod test {
                 match link  {
                     Link::Id {..}        => {},
                     Link::Annotated {..} => assert!(false, "Annotated link found"),
+                    Link::Typed {..}     => assert!(false, "Typed link found"),
                 }
             }
         }
     }
 
+    #[test]
+    fn test_link_consistency_repair() {
+        use toml::Value;
+        use toml_query::insert::TomlValueInsertExt;
+        use libimagstore::storeid::StoreId;
+        use super::store_check::StoreLinkConsistentExt;
+        use super::store_check::LinkProblem;
+
+        setup_logging();
+        let store = get_store();
+        let id    = |s: &str| StoreId::new_baseless(PathBuf::from(s)).unwrap();
+
+        {
+            let mut a = store.create(PathBuf::from("test_link_consistency-a")).unwrap();
+            let mut b = store.create(PathBuf::from("test_link_consistency-b")).unwrap();
+            let mut c = store.create(PathBuf::from("test_link_consistency-c")).unwrap();
+            assert!(a.add_internal_link(&mut b).is_ok());
+
+            // Remove the backlink and link to an entry which does not exist
+            let _ = b.get_header_mut().insert("links.internal", Value::Array(vec![])).unwrap();
+            let dead = Value::String(String::from("test_link_consistency-d"));
+            let _ = c.get_header_mut().insert("links.internal", Value::Array(vec![dead])).unwrap();
+        }
+
+        let report = store.check_link_consistency().unwrap();
+        assert!(!report.is_consistent());
+        assert_eq!(report.problems().len(), 2);
+        assert!(report.problems().contains(&LinkProblem::MissingBacklink {
+            from: id("test_link_consistency-a"),
+            to: id("test_link_consistency-b"),
+        }));
+        assert!(report.problems().contains(&LinkProblem::DanglingLink {
+            from: id("test_link_consistency-c"),
+            to: id("test_link_consistency-d"),
+        }));
+
+        let summary = store.repair_link_consistency(&report).unwrap();
+        assert_eq!(summary.added_backlinks(), 1);
+        assert_eq!(summary.removed_links(), 1);
+        assert!(store.check_link_consistency().unwrap().is_consistent());
+
+        let b     = store.get(PathBuf::from("test_link_consistency-b")).unwrap().unwrap();
+        let links = b.get_internal_links().unwrap().collect::<Vec<_>>();
+        assert_eq!(links, vec![Link::Id { link: id("test_link_consistency-a") }]);
+    }
+
 }
 
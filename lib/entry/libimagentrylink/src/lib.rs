@@ -38,14 +38,19 @@
 )]
 
 extern crate itertools;
+#[cfg(feature = "external_check")]
+extern crate chrono;
 #[macro_use] extern crate log;
 extern crate toml;
 extern crate toml_query;
 extern crate url;
 extern crate sha1;
 extern crate hex;
+#[cfg(feature = "external_check")]
+extern crate reqwest;
 #[macro_use] extern crate is_match;
 #[macro_use] extern crate failure;
+#[macro_use] extern crate serde_json;
 
 #[cfg(test)]
 extern crate env_logger;
@@ -57,5 +62,8 @@ extern crate libimagutil;
 module_entry_path_mod!("links");
 
 pub mod external;
+#[cfg(feature = "external_check")]
+pub mod external_check;
+pub mod graph;
 pub mod internal;
 
@@ -26,6 +26,7 @@ use link::extract_links;
 
 use libimagentrylink::external::ExternalLinker;
 use libimagentrylink::internal::InternalLinker;
+use libimagentryref::reference::Config;
 use libimagentryref::refstore::RefStore;
 use libimagentryref::refstore::UniqueRefPathGenerator;
 use libimagentryref::generators::sha512::Sha512;
@@ -75,7 +76,8 @@ pub struct LinkProcessor {
     process_internal_links: bool,
     create_internal_targets: bool,
     process_external_links: bool,
-    process_refs: bool
+    process_refs: bool,
+    ref_config: Config,
 }
 
 impl LinkProcessor {
@@ -115,6 +117,14 @@ impl LinkProcessor {
         self
     }
 
+    /// Set the base pathes refs are created relative to
+    ///
+    /// Without this, refs store the absolute path of the referenced file.
+    pub fn ref_config(mut self, config: Config) -> Self {
+        self.ref_config = config;
+        self
+    }
+
     /// Process an Entry for its links
     ///
     /// # Warning
@@ -161,7 +171,7 @@ impl LinkProcessor {
                     trace!("URL.host_str() = {:?}", url.host_str());
                     let path = url.host_str().unwrap_or_else(|| url.path());
                     let path = PathBuf::from(path);
-                    let mut target = store.create_ref::<UniqueMarkdownRefGenerator, PathBuf>(path)?;
+                    let mut target = store.create_ref::<UniqueMarkdownRefGenerator, PathBuf>(path, &self.ref_config)?;
 
                     entry.add_internal_link(&mut target)?;
                 },
@@ -222,7 +232,8 @@ impl Default for LinkProcessor {
             process_internal_links: true,
             create_internal_targets: false,
             process_external_links: true,
-            process_refs: false
+            process_refs: false,
+            ref_config: Config::default(),
         }
     }
 }
@@ -446,6 +457,46 @@ mod tests {
         debug!("{:?}", entries);
     }
 
+    #[test]
+    fn test_process_ref_relative_to_basepath() {
+        use std::collections::BTreeMap;
+        use libimagentryref::reference::Ref;
+
+        setup_logging();
+        let store = get_store();
+
+        let mut base = store.create(PathBuf::from("test-5.1")).unwrap();
+
+        // As the ref target must exist, we're using /etc/hosts here
+        *base.get_content_mut() = format!("An [example ref](file:///etc/hosts) is here.");
+
+        let update = store.update(&mut base);
+        assert!(update.is_ok());
+
+        let mut basepathes = BTreeMap::new();
+        basepathes.insert(String::from("etc"), PathBuf::from("/etc"));
+        let config = Config::new(basepathes);
+
+        let processor = LinkProcessor::default()
+            .process_internal_links(false)
+            .create_internal_targets(false)
+            .process_external_links(false)
+            .process_refs(true)
+            .ref_config(config.clone());
+
+        let result = processor.process(&mut base, &store);
+        assert!(result.is_ok(), "Should be Ok(()): {:?}", result);
+
+        let ids : Vec<_> = store.entries().unwrap().in_collection("ref").collect();
+        assert_eq!(1, ids.len(), "Expected 1 ref, got: {:?}", ids);
+
+        let reference = store.get(ids[0].as_ref().unwrap().clone()).unwrap().unwrap();
+        assert_eq!(PathBuf::from("/etc/hosts"), reference.get_path(&config).unwrap());
+
+        // Without the base path configured, the ref cannot be resolved
+        assert!(reference.get_path(&Config::default()).is_err());
+    }
+
     #[test]
     fn test_process_two_refs() {
         setup_logging();
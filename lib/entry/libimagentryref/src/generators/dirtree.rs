@@ -0,0 +1,226 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! A generator for refs to directory trees
+//!
+//! `DirTree` hashes files by their content with SHA-256, without interpreting it as text, so
+//! binary files can be referenced as well. Directories are hashed Merkle-style: the hash of a
+//! directory is the hash over the names and hashes of its children, so it changes whenever any file
+//! below it is added, removed or modified, and is independent of the order the filesystem lists
+//! the entries in. Files matching the patterns from the `.imagignore` file of the referenced
+//! directory are left out, see `tree::Ignore`. Empty directories do not contribute to the hash.
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use sha2::{Sha256, Digest};
+use hex;
+use failure::Fallible as Result;
+
+use refstore::UniqueRefPathGenerator;
+use tree::Snapshot;
+
+pub struct DirTree;
+
+impl UniqueRefPathGenerator for DirTree {
+
+    fn unique_hash<A: AsRef<Path>>(path: A) -> Result<String> {
+        let path = path.as_ref();
+
+        if path.is_dir() {
+            debug!("Hashing directory tree '{}'", path.display());
+            Snapshot::of::<DirTree, _>(path).map(|snapshot| merkle_hash(&snapshot))
+        } else {
+            hash_file(path)
+        }
+    }
+
+    fn unique_hash_of_snapshot<A: AsRef<Path>>(_path: A, snapshot: &Snapshot) -> Result<String> {
+        Ok(merkle_hash(snapshot))
+    }
+
+}
+
+fn hash_file(path: &Path) -> Result<String> {
+    let mut file   = File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = [0; 8192];
+
+    loop {
+        let n = file.read(&mut buffer)?;
+        if n == 0 {
+            break;
+        }
+        hasher.input(&buffer[..n]);
+    }
+
+    Ok(hex::encode(hasher.result()))
+}
+
+enum Node {
+    File(String),
+    Dir(BTreeMap<String, Node>),
+}
+
+impl Node {
+
+    fn hash(&self) -> String {
+        match *self {
+            Node::File(ref hash) => hash.clone(),
+            Node::Dir(ref children) => {
+                let mut hasher = Sha256::new();
+
+                for (name, child) in children {
+                    let kind = match *child {
+                        Node::File(_) => "file",
+                        Node::Dir(_)  => "dir",
+                    };
+                    hasher.input(format!("{} {} {}\n", kind, child.hash(), name).as_bytes());
+                }
+
+                hex::encode(hasher.result())
+            },
+        }
+    }
+
+}
+
+/// Compute the hash of the directory from the hashes of the files in a snapshot of it
+fn merkle_hash(snapshot: &Snapshot) -> String {
+    let mut root = BTreeMap::new();
+
+    for (path, hash) in snapshot.files() {
+        let mut parts = path.split('/').peekable();
+        let mut dir   = &mut root;
+
+        while let Some(part) = parts.next() {
+            if parts.peek().is_none() {
+                dir.insert(String::from(part), Node::File(hash.clone()));
+                break;
+            }
+
+            let node = dir
+                .entry(String::from(part))
+                .or_insert_with(|| Node::Dir(BTreeMap::new()));
+
+            dir = match *node {
+                Node::Dir(ref mut children) => children,
+                Node::File(_) => unreachable!("A path in a snapshot cannot be both a file and a directory"),
+            };
+        }
+    }
+
+    Node::Dir(root).hash()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+
+    use tempdir::TempDir;
+
+    use super::*;
+
+    fn write(dir: &Path, name: &str, content: &str) {
+        let path = dir.join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, content).unwrap();
+    }
+
+    fn sha256(s: &[u8]) -> String {
+        hex::encode(Sha256::digest(s))
+    }
+
+    #[test]
+    fn test_hash_is_stable() {
+        // The hash is stored in refs, so the scheme must not change
+        let tmp = TempDir::new("imag-ref-dirtree").unwrap();
+        write(tmp.path(), "a", "x");
+        write(tmp.path(), "d/b", "y");
+
+        let dir      = sha256(format!("file {} b\n", sha256(b"y")).as_bytes());
+        let expected = sha256(format!("file {} a\ndir {} d\n", sha256(b"x"), dir).as_bytes());
+
+        assert_eq!(DirTree::unique_hash(tmp.path()).unwrap(), expected);
+        assert_eq!(DirTree::unique_hash(tmp.path().join("a")).unwrap(), sha256(b"x"));
+    }
+
+    #[test]
+    fn test_hash_is_independent_of_creation_order() {
+        let one = TempDir::new("imag-ref-dirtree").unwrap();
+        write(one.path(), "a", "1");
+        write(one.path(), "z/b", "2");
+        write(one.path(), "m/c", "3");
+
+        let two = TempDir::new("imag-ref-dirtree").unwrap();
+        write(two.path(), "m/c", "3");
+        write(two.path(), "z/b", "2");
+        write(two.path(), "a", "1");
+
+        assert_eq!(DirTree::unique_hash(one.path()).unwrap(), DirTree::unique_hash(two.path()).unwrap());
+    }
+
+    #[test]
+    fn test_hash_changes() {
+        let tmp = TempDir::new("imag-ref-dirtree").unwrap();
+        write(tmp.path(), "a", "1");
+        write(tmp.path(), "d/b", "2");
+        let original = DirTree::unique_hash(tmp.path()).unwrap();
+
+        // Empty directories and ignored files do not count
+        fs::create_dir_all(tmp.path().join("empty")).unwrap();
+        write(tmp.path(), ".imagignore", "*.tmp\n");
+        write(tmp.path(), "d/scratch.tmp", "tmp");
+        let with_ignore = DirTree::unique_hash(tmp.path()).unwrap();
+        fs::remove_file(tmp.path().join(".imagignore")).unwrap();
+        fs::remove_file(tmp.path().join("d/scratch.tmp")).unwrap();
+        assert_eq!(DirTree::unique_hash(tmp.path()).unwrap(), original);
+        assert_ne!(with_ignore, original); // the ignore file itself is part of the tree
+
+        // Modifying, renaming and moving files does
+        write(tmp.path(), "d/b", "changed");
+        assert_ne!(DirTree::unique_hash(tmp.path()).unwrap(), original);
+        write(tmp.path(), "d/b", "2");
+        assert_eq!(DirTree::unique_hash(tmp.path()).unwrap(), original);
+
+        fs::rename(tmp.path().join("a"), tmp.path().join("renamed")).unwrap();
+        assert_ne!(DirTree::unique_hash(tmp.path()).unwrap(), original);
+        fs::rename(tmp.path().join("renamed"), tmp.path().join("a")).unwrap();
+
+        fs::rename(tmp.path().join("a"), tmp.path().join("d/a")).unwrap();
+        assert_ne!(DirTree::unique_hash(tmp.path()).unwrap(), original);
+    }
+
+    #[test]
+    fn test_merkle_hash_of_snapshot() {
+        let tmp = TempDir::new("imag-ref-dirtree").unwrap();
+        write(tmp.path(), "a", "1");
+        write(tmp.path(), "d/e/b", "2");
+
+        let snapshot = Snapshot::of::<DirTree, _>(tmp.path()).unwrap();
+
+        assert_eq!(merkle_hash(&snapshot), DirTree::unique_hash(tmp.path()).unwrap());
+        assert_eq!(DirTree::unique_hash_of_snapshot(tmp.path(), &snapshot).unwrap(), merkle_hash(&snapshot));
+    }
+
+}
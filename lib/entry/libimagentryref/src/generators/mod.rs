@@ -274,3 +274,5 @@ make_sha_mod! {
     }
 }
 
+#[cfg(feature = "generators-dirtree")]
+pub mod dirtree;
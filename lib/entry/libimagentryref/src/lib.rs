@@ -41,16 +41,25 @@
 extern crate itertools;
 extern crate toml;
 extern crate toml_query;
+extern crate chrono;
+extern crate walkdir;
+extern crate glob;
 
 #[macro_use] extern crate libimagstore;
 extern crate libimagerror;
+extern crate libimagutil;
 #[macro_use] extern crate libimagentryutil;
-extern crate failure;
+#[macro_use] extern crate failure;
+
+#[cfg(test)] extern crate tempdir;
 
 module_entry_path_mod!("ref");
 
+pub mod metadata;
 pub mod reference;
+pub mod refind;
 pub mod refstore;
+pub mod tree;
 
 #[cfg(feature  = "generators-sha1")]
 extern crate sha1;
@@ -60,6 +69,7 @@ extern crate sha1;
     feature = "generators-sha256",
     feature = "generators-sha384",
     feature = "generators-sha512",
+    feature = "generators-dirtree",
 ))]
 extern crate sha2;
 
@@ -73,6 +83,7 @@ extern crate sha3;
     feature = "generators-sha384",
     feature = "generators-sha512",
     feature = "generators-sha3",
+    feature = "generators-dirtree",
 ))]
 extern crate hex;
 
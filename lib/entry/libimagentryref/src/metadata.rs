@@ -0,0 +1,890 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Metadata about referenced files
+//!
+//! When a ref is created, some metadata about the referenced file is recorded in the `ref.meta`
+//! section of the header, so refs can be browsed without opening the referenced files:
+//!
+//! * The MIME type, sniffed from the content of the file
+//! * The size of the file in bytes
+//! * The modification time of the file
+//! * For images, the date the image was taken (EXIF `DateTimeOriginal`)
+//! * For audio files, the title (ID3)
+//!
+
+use std::fs::File;
+use std::fs::Metadata as FsMetadata;
+use std::io::Read;
+use std::io::Seek;
+use std::io::SeekFrom;
+use std::path::Path;
+
+use chrono::DateTime;
+use chrono::Local;
+use chrono::NaiveDateTime;
+use chrono::Timelike;
+use toml::Value;
+use toml::value::Table;
+use toml_query::read::TomlValueReadExt;
+use toml_query::insert::TomlValueInsertExt;
+use failure::Fallible as Result;
+use failure::Error;
+
+use libimagerror::errors::ErrorMsg as EM;
+use libimagutil::date::datetime_to_string;
+use libimagutil::date::datetime_from_string;
+
+/// Number of bytes read from the start of a file for sniffing the content
+///
+/// Large enough to contain the EXIF segment of a JPEG file, which is limited to 64 KiB.
+const HEAD_LEN : u64 = 64 * 1024 + 16;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Metadata {
+    mime: String,
+    size: u64,
+    modified: Option<NaiveDateTime>,
+    date: Option<NaiveDateTime>,
+    title: Option<String>,
+}
+
+impl Metadata {
+
+    /// Collect the metadata of the file or directory at `path`
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Metadata> {
+        let path    = path.as_ref();
+        let fs_meta = path.metadata()?;
+
+        if fs_meta.is_dir() {
+            return Ok(Metadata {
+                mime: String::from("inode/directory"),
+                size: fs_meta.len(),
+                modified: modified(&fs_meta),
+                date: None,
+                title: None,
+            })
+        }
+
+        let mut file = File::open(path)?;
+        let mut head = Vec::new();
+        let _        = (&mut file).take(HEAD_LEN).read_to_end(&mut head)?;
+        let mime     = sniff_mime(&head);
+
+        let date = match mime {
+            "image/jpeg" => jpeg_exif_date(&head),
+            "image/tiff" => tiff_date(&head),
+            _            => None,
+        };
+
+        let title = if mime == "audio/mpeg" {
+            match id3v2_title(&head) {
+                Some(title) => Some(title),
+                None        => id3v1_title(&mut file, fs_meta.len())?,
+            }
+        } else {
+            None
+        };
+
+        Ok(Metadata {
+            mime: String::from(mime),
+            size: fs_meta.len(),
+            modified: modified(&fs_meta),
+            date,
+            title,
+        })
+    }
+
+    /// Read the metadata from the `ref.meta` section of a header
+    ///
+    /// Returns `None` if no metadata was recorded.
+    pub fn from_header(hdr: &Value) -> Result<Option<Metadata>> {
+        let meta = match hdr.read("ref.meta").map_err(Error::from)? {
+            Some(&Value::Table(ref meta)) => meta,
+            Some(_) => return Err(Error::from(EM::EntryHeaderTypeError2("ref.meta", "table"))),
+            None    => return Ok(None),
+        };
+
+        let get_str = |key: &'static str, hdr_key: &'static str| -> Result<Option<String>> {
+            match meta.get(key) {
+                Some(&Value::String(ref s)) => Ok(Some(s.clone())),
+                Some(_) => Err(Error::from(EM::EntryHeaderTypeError2(hdr_key, "string"))),
+                None    => Ok(None),
+            }
+        };
+
+        let get_datetime = |key: &'static str, hdr_key: &'static str| -> Result<Option<NaiveDateTime>> {
+            match get_str(key, hdr_key)? {
+                Some(s) => datetime_from_string(s).map(Some).map_err(Error::from),
+                None    => Ok(None),
+            }
+        };
+
+        let mime = get_str("mime", "ref.meta.mime")?
+            .ok_or_else(|| Error::from(EM::EntryHeaderFieldMissing("ref.meta.mime")))?;
+
+        let size = match meta.get("size") {
+            Some(&Value::Integer(i)) if i >= 0 => i as u64,
+            Some(_) => return Err(Error::from(EM::EntryHeaderTypeError2("ref.meta.size", "integer"))),
+            None    => return Err(Error::from(EM::EntryHeaderFieldMissing("ref.meta.size"))),
+        };
+
+        Ok(Some(Metadata {
+            mime,
+            size,
+            modified: get_datetime("modified", "ref.meta.modified")?,
+            date: get_datetime("date", "ref.meta.date")?,
+            title: get_str("title", "ref.meta.title")?,
+        }))
+    }
+
+    /// Write the metadata to the `ref.meta` section of a header, replacing what was there
+    pub fn insert_into(&self, hdr: &mut Value) -> Result<()> {
+        let mut meta = Table::new();
+        meta.insert(String::from("mime"), Value::String(self.mime.clone()));
+        meta.insert(String::from("size"), Value::Integer(self.size as i64));
+
+        if let Some(ref modified) = self.modified {
+            meta.insert(String::from("modified"), Value::String(datetime_to_string(modified)));
+        }
+
+        if let Some(ref date) = self.date {
+            meta.insert(String::from("date"), Value::String(datetime_to_string(date)));
+        }
+
+        if let Some(ref title) = self.title {
+            meta.insert(String::from("title"), Value::String(title.clone()));
+        }
+
+        let _ = hdr.insert("ref.meta", Value::Table(meta))?;
+        Ok(())
+    }
+
+    /// Check whether this metadata still describes the file with the passed filesystem metadata
+    ///
+    /// Only the size and the modification time are compared, the content is not read.
+    pub fn matches(&self, fs_meta: &FsMetadata) -> bool {
+        self.size == fs_meta.len() && self.modified == modified(fs_meta)
+    }
+
+    pub fn mime(&self) -> &str {
+        &self.mime
+    }
+
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    pub fn modified(&self) -> Option<&NaiveDateTime> {
+        self.modified.as_ref()
+    }
+
+    /// The date the content was created, for example the date an image was taken
+    pub fn date(&self) -> Option<&NaiveDateTime> {
+        self.date.as_ref()
+    }
+
+    pub fn title(&self) -> Option<&str> {
+        self.title.as_ref().map(String::as_str)
+    }
+
+}
+
+/// The modification time, truncated to seconds as this is what is stored in the header
+fn modified(fs_meta: &FsMetadata) -> Option<NaiveDateTime> {
+    fs_meta
+        .modified()
+        .ok()
+        .map(|time| DateTime::<Local>::from(time).naive_local())
+        .and_then(|ndt| ndt.with_nanosecond(0))
+}
+
+/// Guess the MIME type from the first bytes of a file
+fn sniff_mime(head: &[u8]) -> &'static str {
+    let at = |offset: usize, magic: &[u8]| head.len() >= offset + magic.len() && &head[offset..offset + magic.len()] == magic;
+
+    if at(0, b"\x89PNG\r\n\x1a\n") {
+        "image/png"
+    } else if at(0, b"\xff\xd8\xff") {
+        "image/jpeg"
+    } else if at(0, b"GIF87a") || at(0, b"GIF89a") {
+        "image/gif"
+    } else if at(0, b"II*\0") || at(0, b"MM\0*") {
+        "image/tiff"
+    } else if at(0, b"RIFF") && at(8, b"WEBP") {
+        "image/webp"
+    } else if at(0, b"RIFF") && at(8, b"WAVE") {
+        "audio/wav"
+    } else if at(0, b"ID3") || (head.len() >= 2 && head[0] == 0xff && head[1] & 0xe0 == 0xe0) {
+        "audio/mpeg"
+    } else if at(0, b"OggS") {
+        "audio/ogg"
+    } else if at(0, b"fLaC") {
+        "audio/flac"
+    } else if at(4, b"ftypM4A") {
+        "audio/mp4"
+    } else if at(4, b"ftyp") {
+        "video/mp4"
+    } else if at(0, b"%PDF-") {
+        "application/pdf"
+    } else if at(0, b"PK\x03\x04") {
+        "application/zip"
+    } else if at(0, b"\x1f\x8b") {
+        "application/gzip"
+    } else if is_text(head) {
+        if is_mail(head) {
+            "message/rfc822"
+        } else {
+            "text/plain"
+        }
+    } else {
+        "application/octet-stream"
+    }
+}
+
+/// Check whether the bytes are UTF-8 text, allowing a multibyte character cut off at the end
+fn is_text(head: &[u8]) -> bool {
+    match ::std::str::from_utf8(head) {
+        Ok(s)  => !s.contains('\0'),
+        Err(e) => e.error_len().is_none() && !head[..e.valid_up_to()].contains(&0),
+    }
+}
+
+fn is_mail(head: &[u8]) -> bool {
+    ["Return-Path:", "Received:", "Delivered-To:", "Message-ID:", "From:", "From "]
+        .iter()
+        .any(|hdr| head.starts_with(hdr.as_bytes()))
+}
+
+/// Find the EXIF segment of a JPEG file and read the date from it
+fn jpeg_exif_date(head: &[u8]) -> Option<NaiveDateTime> {
+    let mut pos = 2; // skip the start of image marker
+
+    while pos + 4 <= head.len() {
+        if head[pos] != 0xff {
+            return None
+        }
+
+        let marker = head[pos + 1];
+        match marker {
+            0x01 | 0xd0..=0xd8 => { // markers without a segment
+                pos += 2;
+                continue;
+            },
+            0xd9 | 0xda => return None, // end of image, start of scan: no more metadata segments
+            _ => {},
+        }
+
+        let len = ((head[pos + 2] as usize) << 8) | head[pos + 3] as usize;
+        let end = pos + 2 + len;
+        if len < 2 || end > head.len() {
+            return None
+        }
+
+        let segment = &head[pos + 4..end];
+        if marker == 0xe1 && segment.starts_with(b"Exif\0\0") {
+            return tiff_date(&segment[6..])
+        }
+
+        pos = end;
+    }
+
+    None
+}
+
+/// Read the date from TIFF structured data, as used by EXIF
+///
+/// Prefers `DateTimeOriginal` from the EXIF IFD, falls back to `DateTime` from the first IFD.
+fn tiff_date(tiff: &[u8]) -> Option<NaiveDateTime> {
+    const TAG_DATETIME          : u16 = 0x0132;
+    const TAG_EXIF_IFD          : u16 = 0x8769;
+    const TAG_DATETIME_ORIGINAL : u16 = 0x9003;
+
+    let tiff = Tiff::new(tiff)?;
+    let ifd0 = tiff.u32(4)? as usize;
+
+    let original = tiff
+        .entry(ifd0, TAG_EXIF_IFD)
+        .and_then(|entry| tiff.u32(entry + 8))
+        .and_then(|exif_ifd| tiff.entry(exif_ifd as usize, TAG_DATETIME_ORIGINAL))
+        .and_then(|entry| tiff.ascii(entry));
+
+    original
+        .or_else(|| tiff.entry(ifd0, TAG_DATETIME).and_then(|entry| tiff.ascii(entry)))
+        .and_then(|s| NaiveDateTime::parse_from_str(s.trim_end_matches('\0').trim(), "%Y:%m:%d %H:%M:%S").ok())
+}
+
+struct Tiff<'a> {
+    data: &'a [u8],
+    little_endian: bool,
+}
+
+impl<'a> Tiff<'a> {
+
+    fn new(data: &'a [u8]) -> Option<Tiff<'a>> {
+        let little_endian = if data.starts_with(b"II") {
+            true
+        } else if data.starts_with(b"MM") {
+            false
+        } else {
+            return None
+        };
+
+        Some(Tiff { data, little_endian })
+    }
+
+    fn u16(&self, at: usize) -> Option<u16> {
+        let b = self.data.get(at..at.checked_add(2)?)?;
+        Some(if self.little_endian {
+            (b[1] as u16) << 8 | b[0] as u16
+        } else {
+            (b[0] as u16) << 8 | b[1] as u16
+        })
+    }
+
+    fn u32(&self, at: usize) -> Option<u32> {
+        let first  = self.u16(at)? as u32;
+        let second = self.u16(at.checked_add(2)?)? as u32;
+        Some(if self.little_endian {
+            second << 16 | first
+        } else {
+            first << 16 | second
+        })
+    }
+
+    /// Find the position of the entry for `tag` in the IFD at `ifd`
+    fn entry(&self, ifd: usize, tag: u16) -> Option<usize> {
+        let count = self.u16(ifd)? as usize;
+        (0..count)
+            .filter_map(|n| ifd.checked_add(2 + n * 12))
+            .find(|&entry| self.u16(entry) == Some(tag))
+    }
+
+    /// Read the value of an entry of type ASCII
+    fn ascii(&self, entry: usize) -> Option<&'a str> {
+        const TYPE_ASCII : u16 = 2;
+
+        if self.u16(entry + 2)? != TYPE_ASCII {
+            return None
+        }
+
+        let count = self.u32(entry + 4)? as usize;
+        let start = if count <= 4 {
+            entry + 8
+        } else {
+            self.u32(entry + 8)? as usize
+        };
+
+        self.data
+            .get(start..start.checked_add(count)?)
+            .and_then(|bytes| ::std::str::from_utf8(bytes).ok())
+    }
+
+}
+
+/// Read the title from an ID3v2 tag at the start of a file
+fn id3v2_title(head: &[u8]) -> Option<String> {
+    if !head.starts_with(b"ID3") || head.len() < 10 {
+        return None
+    }
+
+    let syncsafe = |b: &[u8]| b.iter().fold(0usize, |acc, &byte| acc << 7 | (byte & 0x7f) as usize);
+    let plain    = |b: &[u8]| b.iter().fold(0usize, |acc, &byte| acc << 8 | byte as usize);
+
+    let version  = head[3];
+    let flags    = head[5];
+    let tag_end  = ::std::cmp::min(10 + syncsafe(&head[6..10]), head.len());
+    let mut pos  : usize = 10;
+
+    if flags & 0x40 != 0 { // extended header
+        let ext = head.get(10..14)?;
+        pos = pos.checked_add(match version {
+            3 => 4 + plain(ext),
+            _ => syncsafe(ext),
+        })?;
+    }
+
+    let (id_len, header_len, title_id) : (usize, usize, &[u8]) = match version {
+        2     => (3, 6, &b"TT2"[..]),
+        3 | 4 => (4, 10, &b"TIT2"[..]),
+        _     => return None,
+    };
+
+    while pos <= tag_end && tag_end - pos >= header_len {
+        let id = &head[pos..pos + id_len];
+        if id[0] == 0 { // padding
+            return None
+        }
+
+        let size_bytes = &head[pos + id_len..pos + id_len + if version == 2 { 3 } else { 4 }];
+        let size = if version == 4 { syncsafe(size_bytes) } else { plain(size_bytes) };
+        let data_start = pos + header_len;
+        let data_end   = data_start.checked_add(size)?;
+
+        if id == title_id {
+            return head.get(data_start..data_end).and_then(decode_id3_text)
+        }
+
+        pos = data_end;
+    }
+
+    None
+}
+
+/// Decode the content of an ID3v2 text frame
+fn decode_id3_text(frame: &[u8]) -> Option<String> {
+    let (encoding, text) = frame.split_first()?;
+
+    let decoded = match *encoding {
+        0 => text.iter().map(|&b| b as char).collect(),
+        1 | 2 => {
+            let (big_endian, text) = match (*encoding, text.get(0..2)) {
+                (1, Some(b"\xff\xfe")) => (false, &text[2..]),
+                (1, Some(b"\xfe\xff")) => (true, &text[2..]),
+                (1, _)                 => (false, text),
+                _                      => (true, text),
+            };
+
+            let units : Vec<u16> = text
+                .chunks(2)
+                .filter(|c| c.len() == 2)
+                .map(|c| if big_endian {
+                    (c[0] as u16) << 8 | c[1] as u16
+                } else {
+                    (c[1] as u16) << 8 | c[0] as u16
+                })
+                .collect();
+
+            String::from_utf16_lossy(&units)
+        },
+        3 => String::from_utf8_lossy(text).into_owned(),
+        _ => return None,
+    };
+
+    let decoded = decoded.trim_end_matches('\0').trim().to_string();
+    if decoded.is_empty() { None } else { Some(decoded) }
+}
+
+/// Read the title from an ID3v1 tag at the end of a file
+fn id3v1_title(file: &mut File, len: u64) -> Result<Option<String>> {
+    if len < 128 {
+        return Ok(None)
+    }
+
+    let mut tag = [0; 128];
+    let _ = file.seek(SeekFrom::End(-128))?;
+    let _ = file.read_exact(&mut tag)?;
+
+    if !tag.starts_with(b"TAG") {
+        return Ok(None)
+    }
+
+    let title : String = tag[3..33]
+        .iter()
+        .take_while(|&&b| b != 0)
+        .map(|&b| b as char)
+        .collect();
+
+    let title = title.trim();
+    Ok(if title.is_empty() { None } else { Some(String::from(title)) })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::fs::File;
+
+    use chrono::NaiveDate;
+    use chrono::NaiveDateTime;
+    use tempdir::TempDir;
+
+    use super::*;
+
+    fn datetime(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, "%Y:%m:%d %H:%M:%S").unwrap()
+    }
+
+    fn le16(v: u16) -> Vec<u8> {
+        vec![v as u8, (v >> 8) as u8]
+    }
+
+    fn le32(v: u32) -> Vec<u8> {
+        vec![v as u8, (v >> 8) as u8, (v >> 16) as u8, (v >> 24) as u8]
+    }
+
+    fn ifd_entry(tag: u16, typ: u16, count: u32, value: u32) -> Vec<u8> {
+        let mut entry = le16(tag);
+        entry.extend(le16(typ));
+        entry.extend(le32(count));
+        entry.extend(le32(value));
+        entry
+    }
+
+    /// A little endian TIFF with `DateTime` in the first IFD and, optionally, `DateTimeOriginal`
+    /// in the EXIF IFD. The dates must be 19 characters long, as EXIF dates are.
+    fn tiff(date: &str, original: Option<&str>) -> Vec<u8> {
+        let entries  = if original.is_some() { 2 } else { 1 };
+        let date_pos = 8 + 2 + 12 * entries + 4;
+        let exif_pos = date_pos + 20;
+
+        let mut tiff = b"II*\0".to_vec();
+        tiff.extend(le32(8));
+        tiff.extend(le16(entries as u16));
+        tiff.extend(ifd_entry(0x0132, 2, 20, date_pos as u32));
+        if original.is_some() {
+            tiff.extend(ifd_entry(0x8769, 4, 1, exif_pos as u32));
+        }
+        tiff.extend(le32(0));
+        tiff.extend(date.as_bytes());
+        tiff.push(0);
+
+        if let Some(original) = original {
+            tiff.extend(le16(1));
+            tiff.extend(ifd_entry(0x9003, 2, 20, (exif_pos + 2 + 12 + 4) as u32));
+            tiff.extend(le32(0));
+            tiff.extend(original.as_bytes());
+            tiff.push(0);
+        }
+
+        tiff
+    }
+
+    fn jpeg(exif: &[u8]) -> Vec<u8> {
+        let len = (2 + 6 + exif.len()) as u16;
+
+        let mut jpeg = vec![0xff, 0xd8];
+        jpeg.extend(&[0xff, 0xe0, 0x00, 0x04, b'J', b'F']); // some segment before the EXIF one
+        jpeg.extend(&[0xff, 0xe1, (len >> 8) as u8, len as u8]);
+        jpeg.extend(b"Exif\0\0");
+        jpeg.extend(exif);
+        jpeg.extend(&[0xff, 0xda, 0x00, 0x02]);
+        jpeg
+    }
+
+    /// An ID3v2 tag of `version` with the `frames`
+    fn id3v2(version: u8, frames: &[u8]) -> Vec<u8> {
+        let size = frames.len();
+        let mut tag = vec![b'I', b'D', b'3', version, 0, 0];
+        tag.extend(&[(size >> 21) as u8 & 0x7f, (size >> 14) as u8 & 0x7f, (size >> 7) as u8 & 0x7f, size as u8 & 0x7f]);
+        tag.extend(frames);
+        tag
+    }
+
+    /// An ID3v2.3 frame, the size must be below 128 to be a valid ID3v2.4 frame as well
+    fn frame(id: &[u8], data: &[u8]) -> Vec<u8> {
+        let mut frame = id.to_vec();
+        frame.extend(&[0, 0, 0, data.len() as u8, 0, 0]);
+        frame.extend(data);
+        frame
+    }
+
+    #[test]
+    fn test_sniff_mime() {
+        assert_eq!(sniff_mime(b"\x89PNG\r\n\x1a\n...."), "image/png");
+        assert_eq!(sniff_mime(b"\xff\xd8\xff\xe0"), "image/jpeg");
+        assert_eq!(sniff_mime(b"GIF89a"), "image/gif");
+        assert_eq!(sniff_mime(b"GIF87a"), "image/gif");
+        assert_eq!(sniff_mime(b"II*\0\x08\0\0\0"), "image/tiff");
+        assert_eq!(sniff_mime(b"MM\0*\0\0\0\x08"), "image/tiff");
+        assert_eq!(sniff_mime(b"RIFF\0\0\0\0WEBPVP8 "), "image/webp");
+        assert_eq!(sniff_mime(b"RIFF\0\0\0\0WAVEfmt "), "audio/wav");
+        assert_eq!(sniff_mime(b"ID3\x04\0\0"), "audio/mpeg");
+        assert_eq!(sniff_mime(b"\xff\xfb\x90\x00"), "audio/mpeg");
+        assert_eq!(sniff_mime(b"OggS\0"), "audio/ogg");
+        assert_eq!(sniff_mime(b"fLaC\0"), "audio/flac");
+        assert_eq!(sniff_mime(b"\0\0\0\x20ftypM4A "), "audio/mp4");
+        assert_eq!(sniff_mime(b"\0\0\0\x20ftypisom"), "video/mp4");
+        assert_eq!(sniff_mime(b"%PDF-1.4"), "application/pdf");
+        assert_eq!(sniff_mime(b"PK\x03\x04"), "application/zip");
+        assert_eq!(sniff_mime(b"\x1f\x8b\x08"), "application/gzip");
+        assert_eq!(sniff_mime(b"Return-Path: <a@example.com>\n"), "message/rfc822");
+        assert_eq!(sniff_mime(b"From a@example.com Sat Jan  1 00:00:00 2000\n"), "message/rfc822");
+        assert_eq!(sniff_mime(b"hello world\n"), "text/plain");
+        assert_eq!(sniff_mime(b"gr\xc3\xbc\xc3"), "text/plain"); // multibyte character cut off at the end
+        assert_eq!(sniff_mime(b""), "text/plain");
+        assert_eq!(sniff_mime(b"hello\0world"), "application/octet-stream");
+        assert_eq!(sniff_mime(b"\xc3\x28"), "application/octet-stream");
+        assert_eq!(sniff_mime(b"\xff"), "application/octet-stream");
+    }
+
+    #[test]
+    fn test_sniff_mime_short_input() {
+        // Magic numbers at an offset must not be read past the end of the input
+        assert_eq!(sniff_mime(b"RIFF"), "text/plain");
+        assert_eq!(sniff_mime(b"\0\0\0\x20ftyp"), "video/mp4");
+        assert_eq!(sniff_mime(b"\0\0\0\x20fty"), "application/octet-stream");
+    }
+
+    #[test]
+    fn test_tiff_date() {
+        let date     = "2018:05:06 07:08:09";
+        let original = "2017:01:02 03:04:05";
+
+        assert_eq!(tiff_date(&tiff(date, None)), Some(datetime(date)));
+        assert_eq!(tiff_date(&tiff(date, Some(original))), Some(datetime(original)));
+    }
+
+    #[test]
+    fn test_tiff_date_invalid() {
+        assert_eq!(tiff_date(b""), None);
+        assert_eq!(tiff_date(b"XX*\0\x08\0\0\0"), None);
+        assert_eq!(tiff_date(&tiff("not a date at all!!", None)), None);
+    }
+
+    #[test]
+    fn test_tiff_date_truncated() {
+        let date     = datetime("2018:05:06 07:08:09");
+        let original = datetime("2017:01:02 03:04:05");
+        let full     = tiff("2018:05:06 07:08:09", Some("2017:01:02 03:04:05"));
+
+        for len in 0..full.len() {
+            let found = tiff_date(&full[..len]);
+            assert!(found.is_none() || found == Some(date) || found == Some(original));
+        }
+    }
+
+    #[test]
+    fn test_tiff_hostile_offsets() {
+        // IFD offset far behind the end of the data
+        let mut bytes = b"II*\0".to_vec();
+        bytes.extend(le32(0xffff_ffff));
+        assert_eq!(tiff_date(&bytes), None);
+
+        // DateTime with an offset and count pointing anywhere
+        for &(count, offset) in &[(20, 0xffff_fff0), (0xffff_ffff, 26), (0xffff_ffff, 0xffff_ffff)] {
+            let mut bytes = b"II*\0".to_vec();
+            bytes.extend(le32(8));
+            bytes.extend(le16(1));
+            bytes.extend(ifd_entry(0x0132, 2, count, offset));
+            bytes.extend(le32(0));
+            assert_eq!(tiff_date(&bytes), None);
+        }
+
+        // An IFD claiming more entries than there is data
+        let mut bytes = b"II*\0".to_vec();
+        bytes.extend(le32(8));
+        bytes.extend(le16(0xffff));
+        assert_eq!(tiff_date(&bytes), None);
+
+        // The EXIF IFD pointing outside of the data
+        let mut bytes = b"II*\0".to_vec();
+        bytes.extend(le32(8));
+        bytes.extend(le16(1));
+        bytes.extend(ifd_entry(0x8769, 4, 1, 0xffff_ffff));
+        bytes.extend(le32(0));
+        assert_eq!(tiff_date(&bytes), None);
+    }
+
+    #[test]
+    fn test_tiff_reads() {
+        let le = Tiff::new(b"II*\0\x08\0\0\0").unwrap();
+        assert_eq!(le.u16(2), Some(0x2a));
+        assert_eq!(le.u32(4), Some(8));
+        assert_eq!(le.u32(6), None);
+
+        let be = Tiff::new(b"MM\0*\0\0\0\x08").unwrap();
+        assert_eq!(be.u16(2), Some(0x2a));
+        assert_eq!(be.u32(4), Some(8));
+
+        assert!(Tiff::new(b"I").is_none());
+
+        // Offsets near the end of the address space must not overflow
+        assert_eq!(le.u16(usize::max_value()), None);
+        assert_eq!(le.u32(usize::max_value() - 1), None);
+        assert_eq!(le.entry(usize::max_value(), 0x0132), None);
+    }
+
+    #[test]
+    fn test_jpeg_exif_date() {
+        let jpeg = jpeg(&tiff("2018:05:06 07:08:09", None));
+
+        assert_eq!(sniff_mime(&jpeg), "image/jpeg");
+        assert_eq!(jpeg_exif_date(&jpeg), Some(datetime("2018:05:06 07:08:09")));
+    }
+
+    #[test]
+    fn test_jpeg_exif_date_malformed() {
+        assert_eq!(jpeg_exif_date(b""), None);
+        assert_eq!(jpeg_exif_date(b"\xff\xd8"), None);
+
+        // Not a marker where a segment should start
+        assert_eq!(jpeg_exif_date(b"\xff\xd8\x00\xe1\x00\x10"), None);
+
+        // Segment lengths below the minimum and past the end of the data
+        assert_eq!(jpeg_exif_date(b"\xff\xd8\xff\xe1\x00\x00Exif\0\0"), None);
+        assert_eq!(jpeg_exif_date(b"\xff\xd8\xff\xe1\x00\x01Exif\0\0"), None);
+        assert_eq!(jpeg_exif_date(b"\xff\xd8\xff\xe1\xff\xffExif\0\0"), None);
+
+        // Start of scan before any EXIF segment
+        assert_eq!(jpeg_exif_date(b"\xff\xd8\xff\xda\x00\x02\xff\xe1\x00\x08Exif\0\0"), None);
+
+        // EXIF segment with garbage instead of TIFF data
+        assert_eq!(jpeg_exif_date(&jpeg(b"garbage")), None);
+    }
+
+    #[test]
+    fn test_jpeg_exif_date_truncated() {
+        let full = jpeg(&tiff("2018:05:06 07:08:09", None));
+
+        // Only the start of scan marker follows the EXIF segment, without it the segment is complete
+        for len in 0..full.len() - 4 {
+            // The EXIF segment is incomplete in all of these
+            assert_eq!(jpeg_exif_date(&full[..len]), None, "Truncated to {} bytes", len);
+        }
+    }
+
+    #[test]
+    fn test_id3v2_title() {
+        let v3 = id3v2(3, &[frame(b"TPE1", b"\0Artist"), frame(b"TIT2", b"\0Title")].concat());
+        assert_eq!(sniff_mime(&v3), "audio/mpeg");
+        assert_eq!(id3v2_title(&v3), Some(String::from("Title")));
+
+        let v4 = id3v2(4, &frame(b"TIT2", "\u{3}Grüße".as_bytes()));
+        assert_eq!(id3v2_title(&v4), Some(String::from("Grüße")));
+
+        let v2 = id3v2(2, b"TT2\0\0\x06\0Title");
+        assert_eq!(id3v2_title(&v2), Some(String::from("Title")));
+
+        let untitled = id3v2(3, &frame(b"TPE1", b"\0Artist"));
+        assert_eq!(id3v2_title(&untitled), None);
+    }
+
+    #[test]
+    fn test_id3v2_title_malformed() {
+        assert_eq!(id3v2_title(b""), None);
+        assert_eq!(id3v2_title(b"ID3\x03\0\0\0\0"), None);
+        assert_eq!(id3v2_title(&id3v2(5, &frame(b"TIT2", b"\0Title"))), None);
+
+        // Padding before the title frame
+        let padded = id3v2(3, &[vec![0; 10], frame(b"TIT2", b"\0Title")].concat());
+        assert_eq!(id3v2_title(&padded), None);
+
+        // Frame sizes past the end of the tag, up to the maximum
+        for size in &[[0u8, 0, 0, 0x7f], [0x7f, 0x7f, 0x7f, 0x7f], [0xff, 0xff, 0xff, 0xff]] {
+            let mut frame = b"TIT2".to_vec();
+            frame.extend(size);
+            frame.extend(&[0, 0, 0]);
+            frame.extend(b"Title");
+
+            assert_eq!(id3v2_title(&id3v2(3, &frame)), None);
+            assert_eq!(id3v2_title(&id3v2(4, &frame)), None);
+        }
+
+        // A huge extended header
+        let mut ext = id3v2(3, &[vec![0xff, 0xff, 0xff, 0xff], frame(b"TIT2", b"\0Title")].concat());
+        ext[5] = 0x40;
+        assert_eq!(id3v2_title(&ext), None);
+
+        // The extended header flag without an extended header
+        assert_eq!(id3v2_title(b"ID3\x03\0\x40\0\0\0\0"), None);
+    }
+
+    #[test]
+    fn test_id3v2_title_truncated() {
+        let full = id3v2(3, &[frame(b"TPE1", b"\0Artist"), frame(b"TIT2", b"\0Title")].concat());
+
+        for len in 0..full.len() {
+            let title = id3v2_title(&full[..len]);
+            assert!(title.is_none() || title == Some(String::from("Title")), "Truncated to {} bytes", len);
+        }
+    }
+
+    #[test]
+    fn test_decode_id3_text() {
+        assert_eq!(decode_id3_text(b""), None);
+        assert_eq!(decode_id3_text(b"\0"), None);
+        assert_eq!(decode_id3_text(b"\0  \0"), None);
+        assert_eq!(decode_id3_text(b"\0Caf\xe9\0"), Some(String::from("Café")));
+        assert_eq!(decode_id3_text(b"\x01\xff\xfeH\0i\0"), Some(String::from("Hi")));
+        assert_eq!(decode_id3_text(b"\x01\xfe\xff\0H\0i"), Some(String::from("Hi")));
+        assert_eq!(decode_id3_text(b"\x01H\0i\0"), Some(String::from("Hi")));
+        assert_eq!(decode_id3_text(b"\x02\0H\0i"), Some(String::from("Hi")));
+        assert_eq!(decode_id3_text("\u{3}Grüße\0".as_bytes()), Some(String::from("Grüße")));
+        assert_eq!(decode_id3_text(b"\x04Hi"), None);
+    }
+
+    #[test]
+    fn test_decode_id3_text_malformed() {
+        // A byte order mark only, an odd number of bytes, an unpaired surrogate, invalid UTF-8
+        assert_eq!(decode_id3_text(b"\x01\xff\xfe"), None);
+        assert_eq!(decode_id3_text(b"\x01\xff"), None);
+        assert_eq!(decode_id3_text(b"\x01\xff\xfeH\0i"), Some(String::from("H")));
+        assert_eq!(decode_id3_text(b"\x01\xff\xfe\0\xd8"), Some(String::from("\u{fffd}")));
+        assert_eq!(decode_id3_text(b"\x03\xffHi"), Some(String::from("\u{fffd}Hi")));
+    }
+
+    fn mp3_with_id3v1(title: &[u8]) -> (TempDir, File, u64) {
+        let tmp  = TempDir::new("imag-ref-metadata").unwrap();
+        let path = tmp.path().join("song.mp3");
+
+        let mut content = vec![0xff; 100];
+        content.extend(b"TAG");
+        content.extend(title);
+        content.extend(vec![0; 125 - title.len()]);
+        fs::write(&path, &content).unwrap();
+
+        (tmp, File::open(&path).unwrap(), content.len() as u64)
+    }
+
+    #[test]
+    fn test_id3v1_title() {
+        let (_tmp, mut file, len) = mp3_with_id3v1(b"Title");
+        assert_eq!(id3v1_title(&mut file, len).unwrap(), Some(String::from("Title")));
+
+        let (_tmp, mut file, len) = mp3_with_id3v1(b"Exactly thirty characters long");
+        assert_eq!(id3v1_title(&mut file, len).unwrap(), Some(String::from("Exactly thirty characters long")));
+
+        let (_tmp, mut file, len) = mp3_with_id3v1(b"   ");
+        assert_eq!(id3v1_title(&mut file, len).unwrap(), None);
+    }
+
+    #[test]
+    fn test_id3v1_title_malformed() {
+        let tmp  = TempDir::new("imag-ref-metadata").unwrap();
+        let path = tmp.path().join("short.mp3");
+        fs::write(&path, b"TAG short").unwrap();
+
+        // Too short for a tag
+        assert_eq!(id3v1_title(&mut File::open(&path).unwrap(), 9).unwrap(), None);
+
+        // A length not matching the file must not panic
+        assert!(id3v1_title(&mut File::open(&path).unwrap(), 200).is_err());
+
+        // No tag at the end
+        fs::write(&path, &vec![b'x'; 300]).unwrap();
+        assert_eq!(id3v1_title(&mut File::open(&path).unwrap(), 300).unwrap(), None);
+    }
+
+    #[test]
+    fn test_from_path() {
+        let tmp  = TempDir::new("imag-ref-metadata").unwrap();
+        let path = tmp.path().join("photo.jpg");
+        fs::write(&path, jpeg(&tiff("2018:05:06 07:08:09", None))).unwrap();
+
+        let meta = Metadata::from_path(&path).unwrap();
+        assert_eq!(meta.mime(), "image/jpeg");
+        assert_eq!(meta.date(), Some(&NaiveDate::from_ymd(2018, 5, 6).and_hms(7, 8, 9)));
+        assert!(meta.matches(&path.metadata().unwrap()));
+
+        let mut hdr = Value::Table(Table::new());
+        meta.insert_into(&mut hdr).unwrap();
+        assert_eq!(Metadata::from_header(&hdr).unwrap(), Some(meta));
+    }
+
+}
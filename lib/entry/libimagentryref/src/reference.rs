@@ -20,12 +20,15 @@
 //! The Ref object is a helper over the link functionality, so one is able to create references to
 //! files outside of the imag store.
 
+use std::collections::BTreeMap;
+use std::env;
 use std::path::Path;
 use std::path::PathBuf;
 
 use libimagentryutil::isa::Is;
 use libimagentryutil::isa::IsKindHeaderPathProvider;
 use libimagstore::store::Entry;
+use libimagstore::store::Store;
 use libimagerror::errors::ErrorMsg as EM;
 
 use toml::Value;
@@ -34,8 +37,97 @@ use toml_query::delete::TomlValueDeleteExt;
 use toml_query::insert::TomlValueInsertExt;
 use failure::Fallible as Result;
 use failure::Error;
+use failure::err_msg;
 
+use metadata::Metadata;
+use refind;
 use refstore::UniqueRefPathGenerator;
+use tree::Changes;
+use tree::Snapshot;
+
+/// The named base pathes refs can be relative to
+///
+/// A ref to a file below one of the base pathes stores the name of the base path and the path
+/// relative to it instead of the absolute path. The name is resolved through the configuration of
+/// the machine the ref is read on, so refs survive syncing the store between machines with
+/// different filesystem layouts.
+///
+/// The base pathes are read from `ref.basepathes` in the configuration, for example
+/// `music = "~/Music"`. A leading `~` is expanded to `$HOME`.
+#[derive(Debug, Clone, Default)]
+pub struct Config(BTreeMap<String, PathBuf>);
+
+impl Config {
+
+    pub fn new(basepathes: BTreeMap<String, PathBuf>) -> Config {
+        Config(basepathes)
+    }
+
+    /// Read the base pathes from `ref.basepathes` in the configuration
+    ///
+    /// If no base pathes are configured, an empty `Config` is returned.
+    pub fn from_config(config: &Value) -> Result<Config> {
+        let type_error = || err_msg("Type error at 'ref.basepathes', expected 'Table<String>'");
+
+        match config.read("ref.basepathes").map_err(Error::from)? {
+            Some(&Value::Table(ref tab)) => tab
+                .iter()
+                .map(|(name, path)| {
+                    let path = path.as_str().ok_or_else(&type_error)?;
+                    Ok((name.clone(), expand_home(path)?))
+                })
+                .collect::<Result<BTreeMap<_, _>>>()
+                .map(Config),
+            Some(_) => Err(type_error()),
+            None    => Ok(Config::default()),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Get the directory of the base path `name`
+    pub fn get_basepath(&self, name: &str) -> Option<&PathBuf> {
+        self.0.get(name)
+    }
+
+    /// Find the base path `path` is below
+    ///
+    /// Returns the name of the base path and the path relative to it. If base pathes are nested,
+    /// the innermost one wins.
+    /// Both sides are canonicalized if they exist, so a path reached through a symlink or with `..`
+    /// in it is still found to be below its base path.
+    pub fn relativize<P: AsRef<Path>>(&self, path: P) -> Option<(&str, PathBuf)> {
+        let path = canonicalize_if_exists(path.as_ref());
+
+        self.0
+            .iter()
+            .filter_map(|(name, base)| {
+                let base = canonicalize_if_exists(base);
+                path.strip_prefix(&base)
+                    .ok()
+                    .map(|rel| (base.components().count(), name.as_str(), rel.to_path_buf()))
+            })
+            .max_by_key(|&(depth, _, _)| depth)
+            .map(|(_, name, rel)| (name, rel))
+    }
+
+}
+
+fn canonicalize_if_exists(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+fn expand_home(path: &str) -> Result<PathBuf> {
+    if path == "~" {
+        Ok(PathBuf::from(env::var("HOME")?))
+    } else if path.starts_with("~/") {
+        Ok(PathBuf::from(env::var("HOME")?).join(&path[2..]))
+    } else {
+        Ok(PathBuf::from(path))
+    }
+}
 
 pub trait Ref {
 
@@ -48,31 +140,92 @@ pub trait Ref {
     fn get_hash(&self) -> Result<&str>;
 
     /// Make this object a ref
-    fn make_ref<P: AsRef<Path>>(&mut self, hash: String, path: P) -> Result<()>;
+    ///
+    /// If `path` is below one of the base pathes from `config`, the ref is stored relative to it.
+    fn make_ref<P: AsRef<Path>>(&mut self, hash: String, path: P, config: &Config) -> Result<()>;
 
     /// Get the referenced path.
     ///
     /// Does not need a `UniqueRefPathGenerator` as it reads the path stored in the header.
-    fn get_path(&self) -> Result<PathBuf>;
+    /// Refs relative to a base path are resolved through `config`, it is an error if the base path
+    /// is not configured.
+    fn get_path(&self, config: &Config) -> Result<PathBuf>;
 
     /// Check whether the referenced file still matches its hash
-    fn hash_valid<RPG: UniqueRefPathGenerator>(&self) -> Result<bool>;
+    fn hash_valid<RPG: UniqueRefPathGenerator>(&self, config: &Config) -> Result<bool>;
 
+    /// Remove the ref from the header
+    ///
+    /// The snapshot of a ref to a directory is stored in an entry of its own, which has to be
+    /// deleted with `tree::Snapshot::delete()`.
     fn remove_ref(&mut self) -> Result<()>;
 
+    /// Record metadata about the referenced file in the header, see `metadata::Metadata`
+    fn update_metadata(&mut self, config: &Config) -> Result<()>;
+
+    /// Get the metadata recorded in the header, if any
+    fn get_metadata(&self) -> Result<Option<Metadata>>;
+
+    /// Check whether the recorded metadata is missing or does not match the referenced file anymore
+    ///
+    /// Only the size and modification time of the file are compared.
+    fn metadata_stale(&self, config: &Config) -> Result<bool>;
+
+    /// Rewrite a ref to an absolute path into the form relative to a base path from `config`
+    ///
+    /// Returns `false` if the ref is already relative to a base path or if its path is not below
+    /// any of the configured base pathes.
+    fn make_relative(&mut self, config: &Config) -> Result<bool>;
+
+    /// Record `snapshot` as the snapshot of the referenced directory
+    ///
+    /// The snapshot is saved in `store`, the header only gets the number of files in it, see
+    /// `tree::Snapshot`. `RefStore::create_ref()` does this for refs to directories.
+    fn record_tree(&mut self, store: &Store, snapshot: &Snapshot) -> Result<()>;
+
+    /// Check which files were added, removed or modified since the ref was made
+    ///
+    /// For refs to directories, the files are compared to the snapshot recorded in `store` when the
+    /// ref was made, with each file hashed by `RPG`. For refs to files without a recorded snapshot,
+    /// the hash of the file is compared to the hash of the ref.
+    fn has_changed<RPG: UniqueRefPathGenerator>(&self, store: &Store, config: &Config) -> Result<Changes>;
+
+    /// Search the `candidates` for the referenced file and update the path if it is found
+    ///
+    /// Each candidate is hashed with `RPG` and compared to the stored hash, the first match becomes
+    /// the new path of the ref. Candidates which cannot be hashed are skipped.
+    /// See `refind::files_below()` for a way to build the candidates from a number of directories.
+    ///
+    /// Returns the new path, or `None` if no candidate matched. It is an error if the hashes of the
+    /// candidates cannot have been made by the generator the ref was created with.
+    fn refind<RPG, I>(&mut self, candidates: I, config: &Config) -> Result<Option<PathBuf>>
+        where RPG: UniqueRefPathGenerator,
+              I: IntoIterator<Item = PathBuf>
+    {
+        self.refind_with(candidates, config, |path: &Path| RPG::unique_hash(path))
+    }
+
+    /// Same as `Ref::refind()`, but each candidate is hashed with `hasher`
+    ///
+    /// This is for refs which were not created with the plain `UniqueRefPathGenerator::unique_hash()`,
+    /// for example with a generator wrapping `generators::sha1::Sha1::hash_n_bytes()`.
+    fn refind_with<F, I>(&mut self, candidates: I, config: &Config, hasher: F) -> Result<Option<PathBuf>>
+        where F: Fn(&Path) -> Result<String>,
+              I: IntoIterator<Item = PathBuf>;
+
     /// Alias for `r.fs_link_exists() && r.deref().is_file()`
-    fn is_ref_to_file(&self) -> Result<bool> {
-        self.get_path().map(|p| p.is_file())
+    fn is_ref_to_file(&self, config: &Config) -> Result<bool> {
+        self.get_path(config).map(|p| p.is_file())
     }
 
     /// Alias for `r.fs_link_exists() && r.deref().is_dir()`
-    fn is_ref_to_dir(&self) -> Result<bool> {
-        self.get_path().map(|p| p.is_dir())
+    fn is_ref_to_dir(&self, config: &Config) -> Result<bool> {
+        self.get_path(config).map(|p| p.is_dir())
     }
 
     /// Alias for `!Ref::fs_link_exists()`
-    fn is_dangling(&self) -> Result<bool> {
-        self.get_path().map(|p| !p.exists())
+    fn is_dangling(&self, config: &Config) -> Result<bool> {
+        self.get_path(config).map(|p| !p.exists())
     }
 
 }
@@ -96,36 +249,36 @@ impl Ref for Entry {
             })
     }
 
-    fn make_ref<P: AsRef<Path>>(&mut self, hash: String, path: P) -> Result<()> {
-        let path_str : String = path
-            .as_ref()
-            .to_str()
-            .map(String::from)
-            .ok_or_else(|| EM::UTF8Error)?;
-
+    fn make_ref<P: AsRef<Path>>(&mut self, hash: String, path: P, config: &Config) -> Result<()> {
         let _   = self.set_isflag::<IsRef>()?;
         let hdr = self.get_header_mut();
-        hdr.insert("ref.path", Value::String(String::from(path_str)))?;
+        let _   = set_location(hdr, path.as_ref(), config)?;
         hdr.insert("ref.hash", Value::String(hash))?;
 
         Ok(())
     }
 
-    fn get_path(&self) -> Result<PathBuf> {
-        self.get_header()
-            .read("ref.path")
-            .map_err(Error::from)?
-            .ok_or_else(|| Error::from(EM::EntryHeaderFieldMissing("ref.path")))
-            .and_then(|v| {
-                v.as_str()
-                    .ok_or_else(|| EM::EntryHeaderTypeError2("ref.path", "string"))
-                    .map_err(Error::from)
-            })
-            .map(PathBuf::from)
+    fn get_path(&self, config: &Config) -> Result<PathBuf> {
+        let hdr = self.get_header();
+
+        match read_str(hdr, "ref.basepath")? {
+            Some(name) => {
+                let rel  = read_str(hdr, "ref.relpath")?
+                    .ok_or_else(|| Error::from(EM::EntryHeaderFieldMissing("ref.relpath")))?;
+                let base = config
+                    .get_basepath(name)
+                    .ok_or_else(|| format_err!("Base path '{}' is not configured at 'ref.basepathes'", name))?;
+
+                Ok(base.join(rel))
+            },
+            None => read_str(hdr, "ref.path")?
+                .ok_or_else(|| Error::from(EM::EntryHeaderFieldMissing("ref.path")))
+                .map(PathBuf::from),
+        }
     }
 
-    fn hash_valid<RPG: UniqueRefPathGenerator>(&self) -> Result<bool> {
-        self.get_path()
+    fn hash_valid<RPG: UniqueRefPathGenerator>(&self, config: &Config) -> Result<bool> {
+        self.get_path(config)
             .map(PathBuf::from)
             .map_err(Error::from)
             .and_then(|pb| RPG::unique_hash(pb))
@@ -135,10 +288,232 @@ impl Ref for Entry {
     fn remove_ref(&mut self) -> Result<()> {
         let hdr = self.get_header_mut();
         let _   = hdr.delete("ref.hash")?;
-        let _   = hdr.delete("ref.path")?;
+        let _   = delete_location(hdr)?;
+
+        // The metadata and the number of files in the tree are tables, which have to be removed as a whole
+        if let Some(reference) = hdr.as_table_mut().and_then(|t| t.get_mut("ref")).and_then(Value::as_table_mut) {
+            let _ = reference.remove("meta");
+            let _ = reference.remove("tree");
+        }
+
         let _   = hdr.delete("ref")?;
         Ok(())
     }
 
+    fn update_metadata(&mut self, config: &Config) -> Result<()> {
+        let meta = Metadata::from_path(self.get_path(config)?)?;
+        meta.insert_into(self.get_header_mut())
+    }
+
+    fn get_metadata(&self) -> Result<Option<Metadata>> {
+        Metadata::from_header(self.get_header())
+    }
+
+    fn metadata_stale(&self, config: &Config) -> Result<bool> {
+        match self.get_metadata()? {
+            Some(meta) => Ok(!meta.matches(&self.get_path(config)?.metadata()?)),
+            None       => Ok(true),
+        }
+    }
+
+    fn make_relative(&mut self, config: &Config) -> Result<bool> {
+        if read_str(self.get_header(), "ref.basepath")?.is_some() {
+            return Ok(false)
+        }
+
+        let path = self.get_path(config)?;
+        if config.relativize(&path).is_none() {
+            return Ok(false)
+        }
+
+        let _ = set_location(self.get_header_mut(), &path, config)?;
+        Ok(true)
+    }
+
+    fn record_tree(&mut self, store: &Store, snapshot: &Snapshot) -> Result<()> {
+        let _ = snapshot.save(store, self.get_location())?;
+        let _ = self.get_header_mut().insert("ref.tree.files", Value::Integer(snapshot.len() as i64))?;
+        Ok(())
+    }
+
+    fn has_changed<RPG: UniqueRefPathGenerator>(&self, store: &Store, config: &Config) -> Result<Changes> {
+        let path     = self.get_path(config)?;
+        let recorded = if self.get_header().read("ref.tree").map_err(Error::from)?.is_some() {
+            Snapshot::load(store, self.get_location())?
+                .ok_or_else(|| format_err!("The snapshot of '{}' is missing", self.get_location()))
+                .map(Some)?
+        } else {
+            None
+        };
+
+        match recorded {
+            Some(recorded) => Ok(recorded.diff(&Snapshot::of::<RPG, _>(path)?)),
+            None if path.is_file() => {
+                let recorded = Snapshot::of_file_with_hash(&path, String::from(self.get_hash()?))?;
+                Ok(recorded.diff(&Snapshot::of::<RPG, _>(&path)?))
+            },
+            None => Err(format_err!("No snapshot recorded for the directory '{}'", path.display())),
+        }
+    }
+
+    fn refind_with<F, I>(&mut self, candidates: I, config: &Config, hasher: F) -> Result<Option<PathBuf>>
+        where F: Fn(&Path) -> Result<String>,
+              I: IntoIterator<Item = PathBuf>
+    {
+        let hash = String::from(self.get_hash()?);
+
+        for candidate in candidates {
+            let found = match refind::try_hash(&hasher, &candidate) {
+                Some(found) => found,
+                None        => continue,
+            };
+
+            let _ = refind::ensure_same_hasher(self.get_location(), &hash, &found)?;
+
+            if found == hash {
+                debug!("Re-found '{}' at '{}'", hash, candidate.display());
+                let _ = self.make_ref(hash, &candidate, config)?;
+                return Ok(Some(candidate))
+            }
+        }
+
+        Ok(None)
+    }
+
+}
+
+fn read_str<'a>(hdr: &'a Value, key: &'static str) -> Result<Option<&'a str>> {
+    match hdr.read(key).map_err(Error::from)? {
+        Some(v) => v.as_str().map(Some).ok_or_else(|| Error::from(EM::EntryHeaderTypeError2(key, "string"))),
+        None    => Ok(None),
+    }
+}
+
+fn path_to_string(path: &Path) -> Result<String> {
+    path.to_str().map(String::from).ok_or_else(|| Error::from(EM::UTF8Error))
+}
+
+/// Delete whichever form of the referenced path is stored in the header
+fn delete_location(hdr: &mut Value) -> Result<()> {
+    for key in &["ref.path", "ref.basepath", "ref.relpath"] {
+        if hdr.read(key).map_err(Error::from)?.is_some() {
+            let _ = hdr.delete(key)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Store `path` in the header, relative to a base path from `config` if possible
+fn set_location(hdr: &mut Value, path: &Path, config: &Config) -> Result<()> {
+    let _ = delete_location(hdr)?;
+
+    match config.relativize(path) {
+        Some((name, rel)) => {
+            hdr.insert("ref.basepath", Value::String(String::from(name)))?;
+            hdr.insert("ref.relpath", Value::String(path_to_string(&rel)?))?;
+        },
+        None => {
+            hdr.insert("ref.path", Value::String(path_to_string(path)?))?;
+        },
+    }
+
+    Ok(())
 }
 
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::path::PathBuf;
+
+    use tempdir::TempDir;
+    use toml::Value;
+
+    use super::*;
+
+    fn config_of(bases: &[(&str, PathBuf)]) -> Config {
+        Config::new(bases.iter().map(|&(ref name, ref path)| (String::from(*name), path.clone())).collect())
+    }
+
+    #[test]
+    fn test_expand_home() {
+        let home = PathBuf::from(env::var("HOME").unwrap());
+
+        assert_eq!(expand_home("~").unwrap(), home);
+        assert_eq!(expand_home("~/Music").unwrap(), home.join("Music"));
+        assert_eq!(expand_home("/srv/music").unwrap(), PathBuf::from("/srv/music"));
+        assert_eq!(expand_home("~other/Music").unwrap(), PathBuf::from("~other/Music"));
+        assert_eq!(expand_home("music/~/a").unwrap(), PathBuf::from("music/~/a"));
+    }
+
+    #[test]
+    fn test_from_config() {
+        let cfg = "[ref.basepathes]\nmusic = \"/srv/music\"\nhome = \"~\"\n".parse::<Value>().unwrap();
+        let config = Config::from_config(&cfg).unwrap();
+
+        assert_eq!(config.get_basepath("music"), Some(&PathBuf::from("/srv/music")));
+        assert_eq!(config.get_basepath("home"), Some(&PathBuf::from(env::var("HOME").unwrap())));
+        assert_eq!(config.get_basepath("videos"), None);
+
+        assert!(Config::from_config(&"[ref]\n".parse::<Value>().unwrap()).unwrap().is_empty());
+        assert!(Config::from_config(&"[ref]\nbasepathes = 1\n".parse::<Value>().unwrap()).is_err());
+        assert!(Config::from_config(&"[ref.basepathes]\nmusic = 1\n".parse::<Value>().unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_relativize() {
+        let config = config_of(&[("music", PathBuf::from("/srv/music"))]);
+
+        assert_eq!(config.relativize("/srv/music/a/b.mp3"), Some(("music", PathBuf::from("a/b.mp3"))));
+        assert_eq!(config.relativize("/srv/musicals/b.mp3"), None);
+        assert_eq!(config.relativize("/srv/b.mp3"), None);
+    }
+
+    #[test]
+    fn test_relativize_nested_bases() {
+        let config = config_of(&[
+            ("music", PathBuf::from("/srv/music")),
+            ("srv", PathBuf::from("/srv")),
+        ]);
+
+        assert_eq!(config.relativize("/srv/music/a.mp3"), Some(("music", PathBuf::from("a.mp3"))));
+        assert_eq!(config.relativize("/srv/videos/a.mkv"), Some(("srv", PathBuf::from("videos/a.mkv"))));
+    }
+
+    #[test]
+    fn test_relativize_canonicalizes() {
+        let tmp  = TempDir::new("imag-ref-config").unwrap();
+        let base = tmp.path().join("music");
+        fs::create_dir_all(base.join("album")).unwrap();
+        fs::write(base.join("album/a.mp3"), "").unwrap();
+
+        let config = config_of(&[("music", base.join("album/.."))]);
+
+        let dotted = base.join("album/../album/a.mp3");
+        assert_eq!(config.relativize(&dotted), Some(("music", PathBuf::from("album/a.mp3"))));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_relativize_through_symlink() {
+        use std::os::unix::fs::symlink;
+
+        let tmp  = TempDir::new("imag-ref-config").unwrap();
+        let real = tmp.path().join("real");
+        let link = tmp.path().join("link");
+        fs::create_dir_all(&real).unwrap();
+        fs::write(real.join("a.mp3"), "").unwrap();
+        symlink(&real, &link).unwrap();
+
+        // The base path is configured through the symlink, the file is passed by its real path
+        let config = config_of(&[("music", link.clone())]);
+        assert_eq!(config.relativize(real.join("a.mp3")), Some(("music", PathBuf::from("a.mp3"))));
+
+        // and the other way round
+        let config = config_of(&[("music", real.clone())]);
+        assert_eq!(config.relativize(link.join("a.mp3")), Some(("music", PathBuf::from("a.mp3"))));
+    }
+
+}
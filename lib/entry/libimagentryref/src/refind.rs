@@ -0,0 +1,364 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Helpers for re-finding files which were referenced but moved away from their original location
+//!
+//! Re-finding works by hashing candidate files the same way the ref was created, usually with a
+//! `UniqueRefPathGenerator`, and comparing the result to the hash stored in the ref. Which files are
+//! candidates is up to the caller, this module only provides a default way to build them by
+//! walking a number of search roots.
+
+use std::fs::FileType;
+use std::path::Path;
+use std::path::PathBuf;
+
+use libimagstore::storeid::StoreId;
+
+use walkdir::WalkDir;
+use toml::Value;
+use toml_query::read::TomlValueReadExt;
+use failure::Fallible as Result;
+use failure::Error;
+use failure::err_msg;
+
+/// Read the search roots from `ref.search_roots` in the configuration
+///
+/// Returns an empty list if no search roots are configured.
+pub fn configured_search_roots(config: &Value) -> Result<Vec<PathBuf>> {
+    let type_error = || err_msg("Type error at 'ref.search_roots', expected 'Array<String>'");
+
+    match config.read("ref.search_roots").map_err(Error::from)? {
+        Some(&Value::Array(ref roots)) => roots
+            .iter()
+            .map(|root| root.as_str().map(PathBuf::from).ok_or_else(&type_error))
+            .collect(),
+        Some(_) => Err(type_error()),
+        None    => Ok(vec![]),
+    }
+}
+
+/// Get an iterator over all files below the passed search roots
+///
+/// Directories are walked recursively, symlinks are not followed. Entries which cannot be read
+/// are skipped.
+pub fn files_below<I>(roots: I) -> Box<Iterator<Item = PathBuf>>
+    where I: IntoIterator<Item = PathBuf>,
+          I::IntoIter: 'static
+{
+    paths_below(roots, |file_type| file_type.is_file())
+}
+
+/// Get an iterator over all directories below the passed search roots, the roots included
+///
+/// These are the candidates for refs which hash a directory instead of a file, like the ones made
+/// with the `dirtree` generator. Walked like `files_below()`.
+pub fn dirs_below<I>(roots: I) -> Box<Iterator<Item = PathBuf>>
+    where I: IntoIterator<Item = PathBuf>,
+          I::IntoIter: 'static
+{
+    paths_below(roots, |file_type| file_type.is_dir())
+}
+
+fn paths_below<I, F>(roots: I, keep: F) -> Box<Iterator<Item = PathBuf>>
+    where I: IntoIterator<Item = PathBuf>,
+          I::IntoIter: 'static,
+          F: Fn(&FileType) -> bool + 'static
+{
+    let iter = roots
+        .into_iter()
+        .flat_map(|root| WalkDir::new(root).follow_links(false).into_iter())
+        .filter_map(|entry| match entry {
+            Ok(entry) => Some(entry),
+            Err(e)    => {
+                debug!("Skipping unreadable path while searching: {:?}", e);
+                None
+            },
+        })
+        .filter(move |entry| keep(&entry.file_type()))
+        .map(|entry| entry.path().to_path_buf());
+
+    Box::new(iter)
+}
+
+/// Hash `path` with `hasher`, returning `None` if the file cannot be hashed
+///
+/// Search roots usually contain lots of files a generator cannot handle (binary files for the
+/// generators which read the file as text, for example), so failing here is not an error.
+pub(crate) fn try_hash<F>(hasher: &F, path: &Path) -> Option<String>
+    where F: Fn(&Path) -> Result<String>
+{
+    match hasher(path) {
+        Ok(hash) => Some(hash),
+        Err(e)   => {
+            trace!("Cannot hash '{}', skipping: {:?}", path.display(), e);
+            None
+        },
+    }
+}
+
+/// Fail if the hash `found` of a candidate cannot be compared to the hash `stored` in `id`
+///
+/// The hashes of the generators differ in length, so searching with another generator than the
+/// one the ref was made with would silently find nothing.
+pub(crate) fn ensure_same_hasher(id: &StoreId, stored: &str, found: &str) -> Result<()> {
+    if stored.len() == found.len() {
+        Ok(())
+    } else {
+        Err(format_err!("The hash of '{}' was not made by the selected hasher: it has {} characters, the hasher makes {}",
+                        id, stored.len(), found.len()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::fs;
+    use std::path::Path;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    use tempdir::TempDir;
+
+    use libimagstore::store::Store;
+    use libimagstore::file_abstraction::InMemoryFileAbstraction;
+
+    use reference::Config;
+    use reference::Ref;
+    use refstore::RefStore;
+    use refstore::UniqueRefPathGenerator;
+    use super::*;
+
+    /// Uses the content of a file as its hash, so the tests do not depend on a generator feature
+    struct ContentHash;
+
+    impl UniqueRefPathGenerator for ContentHash {
+        fn unique_hash<A: AsRef<Path>>(path: A) -> Result<String> {
+            fs::read_to_string(path).map_err(Error::from)
+        }
+    }
+
+    fn get_store() -> Store {
+        let backend = Arc::new(InMemoryFileAbstraction::default());
+        Store::new_with_backend(PathBuf::from("/"), &None, backend).unwrap()
+    }
+
+    fn write(dir: &Path, name: &str, content: &str) -> PathBuf {
+        let path = dir.join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    fn move_file(from: &Path, to: &Path) {
+        fs::create_dir_all(to.parent().unwrap()).unwrap();
+        fs::rename(from, to).unwrap();
+    }
+
+    #[test]
+    fn test_refind_moved_file() {
+        let tmp    = TempDir::new("imag-refind").unwrap();
+        let store  = get_store();
+        let config = Config::default();
+        let old    = write(tmp.path(), "old/a.txt", "alpha");
+        let new    = tmp.path().join("new/a.txt");
+
+        let mut entry = store.create_ref::<ContentHash, _>(&old, &config).unwrap();
+        move_file(&old, &new);
+        assert!(entry.is_dangling(&config).unwrap());
+
+        let found = entry.refind::<ContentHash, _>(files_below(vec![tmp.path().to_path_buf()]), &config).unwrap();
+
+        assert_eq!(found, Some(new.clone()));
+        assert_eq!(entry.get_path(&config).unwrap(), new);
+        assert_eq!(entry.get_hash().unwrap(), "alpha");
+        assert!(!entry.is_dangling(&config).unwrap());
+    }
+
+    #[test]
+    fn test_refind_nothing_found() {
+        let tmp    = TempDir::new("imag-refind").unwrap();
+        let store  = get_store();
+        let config = Config::default();
+        let old    = write(tmp.path(), "a.txt", "alpha");
+        let _      = write(tmp.path(), "other/b.txt", "gamma");
+
+        let mut entry = store.create_ref::<ContentHash, _>(&old, &config).unwrap();
+        fs::remove_file(&old).unwrap();
+
+        let found = entry.refind::<ContentHash, _>(files_below(vec![tmp.path().to_path_buf()]), &config).unwrap();
+
+        assert_eq!(found, None);
+        assert_eq!(entry.get_path(&config).unwrap(), old);
+    }
+
+    #[test]
+    fn test_refind_with_other_hasher_fails() {
+        let tmp    = TempDir::new("imag-refind").unwrap();
+        let store  = get_store();
+        let config = Config::default();
+        let old    = write(tmp.path(), "a.txt", "alpha");
+
+        let mut entry = store.create_ref::<ContentHash, _>(&old, &config).unwrap();
+        move_file(&old, &tmp.path().join("moved/a.txt"));
+
+        let other = |_: &Path| Ok(String::from("0123456789abcdef"));
+        let res   = entry.refind_with(files_below(vec![tmp.path().to_path_buf()]), &config, other);
+
+        assert!(res.is_err());
+        assert_eq!(entry.get_path(&config).unwrap(), old);
+    }
+
+    #[test]
+    fn test_refind_dangling() {
+        let tmp    = TempDir::new("imag-refind").unwrap();
+        let store  = get_store();
+        let config = Config::default();
+        let a      = write(tmp.path(), "old/a.txt", "alpha");
+        let b      = write(tmp.path(), "old/b.txt", "beta!");
+        let c      = write(tmp.path(), "old/c.txt", "gamma");
+
+        let id_a = store.create_ref::<ContentHash, _>(&a, &config).unwrap().get_location().clone();
+        let id_b = store.create_ref::<ContentHash, _>(&b, &config).unwrap().get_location().clone();
+        let _    = store.create_ref::<ContentHash, _>(&c, &config).unwrap();
+
+        let new_a = tmp.path().join("new/a.txt");
+        let new_b = tmp.path().join("new/deeper/b.txt");
+        move_file(&a, &new_a);
+        move_file(&b, &new_b);
+
+        let roots         = vec![tmp.path().join("new")];
+        let mut relocated = store.refind_dangling::<ContentHash, _>(files_below(roots), &config).unwrap();
+        relocated.sort_by(|x, y| x.1.cmp(&y.1));
+
+        assert_eq!(relocated, vec![(id_a.clone(), new_a.clone()), (id_b.clone(), new_b.clone())]);
+        assert_eq!(store.get(id_a).unwrap().unwrap().get_path(&config).unwrap(), new_a);
+        assert_eq!(store.get(id_b).unwrap().unwrap().get_path(&config).unwrap(), new_b);
+    }
+
+    #[test]
+    fn test_refind_dangling_skips_unresolvable_refs() {
+        let tmp    = TempDir::new("imag-refind").unwrap();
+        let store  = get_store();
+        let config = Config::default();
+        let a      = write(tmp.path(), "old/a.txt", "alpha");
+        let b      = write(tmp.path(), "music/b.txt", "beta!");
+
+        let mut bases = BTreeMap::new();
+        bases.insert(String::from("music"), tmp.path().join("music"));
+        let _ = store.create_ref::<ContentHash, _>(&b, &Config::new(bases)).unwrap();
+        let id_a = store.create_ref::<ContentHash, _>(&a, &config).unwrap().get_location().clone();
+
+        let new_a = tmp.path().join("new/a.txt");
+        move_file(&a, &new_a);
+
+        // The base path of the ref to b is not configured, which must not stop re-finding a
+        let relocated = store
+            .refind_dangling::<ContentHash, _>(files_below(vec![tmp.path().join("new")]), &config)
+            .unwrap();
+
+        assert_eq!(relocated, vec![(id_a, new_a)]);
+    }
+
+    #[test]
+    fn test_refind_dangling_only_in_collection() {
+        let tmp    = TempDir::new("imag-refind").unwrap();
+        let store  = get_store();
+        let config = Config::default();
+        let a      = write(tmp.path(), "old/a.txt", "alpha");
+        let b      = write(tmp.path(), "old/b.txt", "beta!");
+
+        let id_a = store.create_ref::<ContentHash, _>(&a, &config).unwrap().get_location().clone();
+        let id_b = {
+            let mut entry = store.create(PathBuf::from("other/b")).unwrap();
+            entry.make_ref(String::from("beta!"), &b, &config).unwrap();
+            entry.get_location().clone()
+        };
+
+        let new_a = tmp.path().join("new/a.txt");
+        let new_b = tmp.path().join("new/b.txt");
+        move_file(&a, &new_a);
+        move_file(&b, &new_b);
+
+        let relocated = store
+            .refind_dangling::<ContentHash, _>(files_below(vec![tmp.path().join("new")]), &config)
+            .unwrap();
+
+        assert_eq!(relocated, vec![(id_a, new_a)]);
+        assert_eq!(store.get(id_b).unwrap().unwrap().get_path(&config).unwrap(), b);
+    }
+
+    #[cfg(feature = "generators-sha1")]
+    #[test]
+    fn test_refind_with_hash_n_bytes() {
+        use generators::sha1::Sha1;
+
+        let tmp    = TempDir::new("imag-refind").unwrap();
+        let store  = get_store();
+        let config = Config::default();
+        let old    = write(tmp.path(), "mail", "Subject: hello\n\nbody");
+        let hash   = Sha1::hash_n_bytes(&old, 14).unwrap();
+
+        let mut entry = store.create(PathBuf::from("ref/partial")).unwrap();
+        entry.make_ref(hash.clone(), &old, &config).unwrap();
+
+        // Only the first bytes count, so the file is re-found even though the rest changed
+        let new = write(tmp.path(), "moved/mail", "Subject: hello\n\nanother body");
+        fs::remove_file(&old).unwrap();
+
+        let hasher = |path: &Path| Sha1::hash_n_bytes(path, 14);
+        let found  = entry.refind_with(files_below(vec![tmp.path().to_path_buf()]), &config, hasher).unwrap();
+
+        assert_eq!(found, Some(new));
+        assert_eq!(entry.get_hash().unwrap(), hash);
+    }
+
+    #[test]
+    fn test_refind_moved_directory() {
+        let tmp    = TempDir::new("imag-refind").unwrap();
+        let store  = get_store();
+        let config = Config::default();
+        let old    = tmp.path().join("old/photos");
+        let new    = tmp.path().join("new/photos");
+        let _      = write(&old, "a.txt", "alpha");
+
+        // Hashes a directory by the names of the files in it
+        let hasher = |path: &Path| -> Result<String> {
+            use std::collections::hash_map::DefaultHasher;
+            use std::hash::{Hash, Hasher};
+
+            let mut names = fs::read_dir(path)?
+                .map(|e| e.map(|e| e.file_name()))
+                .collect::<::std::io::Result<Vec<_>>>()?;
+            names.sort();
+
+            let mut h = DefaultHasher::new();
+            names.hash(&mut h);
+            Ok(format!("{:016x}", h.finish()))
+        };
+
+        let mut entry = store.create(PathBuf::from("ref/photos")).unwrap();
+        entry.make_ref(hasher(&old).unwrap(), &old, &config).unwrap();
+        move_file(&old, &new);
+
+        let roots = vec![tmp.path().join("new")];
+        assert_eq!(entry.refind_with(files_below(roots.clone()), &config, &hasher).unwrap(), None);
+        assert_eq!(entry.refind_with(dirs_below(roots), &config, &hasher).unwrap(), Some(new));
+    }
+
+}
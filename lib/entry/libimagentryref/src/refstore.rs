@@ -17,6 +17,7 @@
 // Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
 //
 
+use std::collections::HashMap;
 use std::path::Path;
 use std::path::PathBuf;
 
@@ -25,6 +26,9 @@ use libimagstore::store::Store;
 use libimagstore::storeid::StoreId;
 
 use reference::Ref;
+use reference::Config;
+use refind;
+use tree::Snapshot;
 
 use failure::Fallible as Result;
 use failure::Error;
@@ -42,6 +46,15 @@ pub trait UniqueRefPathGenerator {
     /// A function which should generate a unique string for a Path
     fn unique_hash<A: AsRef<Path>>(path: A) -> Result<String>;
 
+    /// Generate the unique string for the directory at `path` from a snapshot of it
+    ///
+    /// `RefStore::create_ref()` takes a snapshot of referenced directories anyway, so generators
+    /// for directories can implement this to not walk the directory a second time. The default
+    /// implementation ignores the snapshot and calls `unique_hash()`.
+    fn unique_hash_of_snapshot<A: AsRef<Path>>(path: A, _snapshot: &Snapshot) -> Result<String> {
+        Self::unique_hash(path)
+    }
+
     /// Postprocess the generated `StoreId` object
     fn postprocess_storeid(sid: StoreId) -> Result<StoreId> {
         Ok(sid)
@@ -67,7 +80,11 @@ pub trait UniqueRefPathGenerator {
 /// The StoreId which is generated is then used to carry out the actual action (reading, creating
 /// ...).
 /// If a entry is created, header information is set (that it is a ref, the hash which was just
-/// generated and the path of the referenced file)
+/// generated and the path of the referenced file). The path is stored relative to a base path
+/// from the passed `Config` if it is below one, see `reference::Config`.
+/// Metadata about the referenced file is recorded as well, see `metadata::Metadata`, and for
+/// directories a snapshot of the files below it, see `tree::Snapshot`. The snapshot is taken once
+/// and passed to `UniqueRefPathGenerator::unique_hash_of_snapshot()` for generating the hash.
 ///
 /// # Details
 ///
@@ -81,8 +98,25 @@ pub trait UniqueRefPathGenerator {
 pub trait RefStore<'a> {
 
     fn get_ref<RPG: UniqueRefPathGenerator, H: AsRef<str>>(&'a self, hash: H) -> Result<Option<FileLockEntry<'a>>>;
-    fn create_ref<RPG: UniqueRefPathGenerator, A: AsRef<Path>>(&'a self, path: A) -> Result<FileLockEntry<'a>>;
-    fn retrieve_ref<RPG: UniqueRefPathGenerator, A: AsRef<Path>>(&'a self, path: A) -> Result<FileLockEntry<'a>>;
+    fn create_ref<RPG: UniqueRefPathGenerator, A: AsRef<Path>>(&'a self, path: A, config: &Config) -> Result<FileLockEntry<'a>>;
+    fn retrieve_ref<RPG: UniqueRefPathGenerator, A: AsRef<Path>>(&'a self, path: A, config: &Config) -> Result<FileLockEntry<'a>>;
+
+    /// Re-find all dangling refs in the collection of `RPG` within the `candidates`
+    ///
+    /// This is the bulk version of `Ref::refind()`: the candidates are walked only once and each
+    /// candidate is hashed at most once, no matter how many refs are dangling.
+    ///
+    /// Returns the ids of the refs which were relocated, together with their new path.
+    fn refind_dangling<RPG, I>(&'a self, candidates: I, config: &Config) -> Result<Vec<(StoreId, PathBuf)>>
+        where RPG: UniqueRefPathGenerator,
+              I: IntoIterator<Item = PathBuf>;
+
+    /// Same as `RefStore::refind_dangling()` for the refs in `collection`, but each candidate is
+    /// hashed with `hasher`, see `Ref::refind_with()`
+    fn refind_dangling_with<F, I>(&'a self, collection: &str, candidates: I, config: &Config, hasher: F)
+        -> Result<Vec<(StoreId, PathBuf)>>
+        where F: Fn(&Path) -> Result<String>,
+              I: IntoIterator<Item = PathBuf>;
 
 }
 
@@ -99,30 +133,130 @@ impl<'a> RefStore<'a> for Store {
             .map_err(Error::from)
     }
 
-    fn create_ref<RPG: UniqueRefPathGenerator, A: AsRef<Path>>(&'a self, path: A)
+    fn create_ref<RPG: UniqueRefPathGenerator, A: AsRef<Path>>(&'a self, path: A, config: &Config)
         -> Result<FileLockEntry<'a>>
     {
-        let hash     = RPG::unique_hash(&path)?;
-        let pathbuf  = PathBuf::from(format!("{}/{}", RPG::collection(), hash));
-        let sid      = StoreId::new_baseless(pathbuf.clone())?;
-
-        debug!("Creating: {:?}", sid);
-        self.create(sid)
-            .map_err(Error::from)
-            .and_then(|mut fle| {
-                fle.make_ref(hash, path)?;
-                Ok(fle)
-            })
+        let (hash, snapshot) = hash_path::<RPG>(path.as_ref())?;
+        create_ref_with_hash::<RPG>(self, hash, snapshot, path.as_ref(), config)
     }
 
-    fn retrieve_ref<RPG: UniqueRefPathGenerator, A: AsRef<Path>>(&'a self, path: A)
+    fn retrieve_ref<RPG: UniqueRefPathGenerator, A: AsRef<Path>>(&'a self, path: A, config: &Config)
         -> Result<FileLockEntry<'a>>
     {
-        match self.get_ref::<RPG, String>(RPG::unique_hash(path.as_ref())?)? {
+        let (hash, snapshot) = hash_path::<RPG>(path.as_ref())?;
+        let existing         = self.get_ref::<RPG, &str>(&hash)?;
+
+        match existing {
             Some(r) => Ok(r),
-            None    => self.create_ref::<RPG, A>(path),
+            None    => create_ref_with_hash::<RPG>(self, hash, snapshot, path.as_ref(), config),
+        }
+    }
+
+    fn refind_dangling<RPG, I>(&'a self, candidates: I, config: &Config) -> Result<Vec<(StoreId, PathBuf)>>
+        where RPG: UniqueRefPathGenerator,
+              I: IntoIterator<Item = PathBuf>
+    {
+        self.refind_dangling_with(RPG::collection(), candidates, config, |path: &Path| RPG::unique_hash(path))
+    }
+
+    fn refind_dangling_with<F, I>(&'a self, collection: &str, candidates: I, config: &Config, hasher: F)
+        -> Result<Vec<(StoreId, PathBuf)>>
+        where F: Fn(&Path) -> Result<String>,
+              I: IntoIterator<Item = PathBuf>
+    {
+        let mut dangling : HashMap<String, Vec<StoreId>> = HashMap::new();
+
+        for id in self.entries()?.in_collection(collection) {
+            let id = id?;
+            if let Some(entry) = self.get(id.clone())? {
+                if !entry.is_ref()? {
+                    continue;
+                }
+
+                // A ref relative to a base path which is not configured here cannot be resolved,
+                // but that should not stop searching for all the others
+                match entry.is_dangling(config) {
+                    Ok(true)  => {
+                        let hash = String::from(entry.get_hash()?);
+                        dangling.entry(hash).or_insert_with(Vec::new).push(id);
+                    },
+                    Ok(false) => {},
+                    Err(e)    => warn!("Cannot resolve the path of '{}', skipping: {}", id, e),
+                }
+            }
+        }
+
+        debug!("{} dangling refs to search for", dangling.len());
+        let mut relocated = vec![];
+        let mut checked   = false;
+
+        for candidate in candidates {
+            if dangling.is_empty() {
+                break;
+            }
+
+            let hash = match refind::try_hash(&hasher, &candidate) {
+                Some(hash) => hash,
+                None       => continue,
+            };
+
+            // The first hash tells whether the hasher can have made the stored hashes at all
+            if !checked {
+                for (stored, ids) in dangling.iter() {
+                    let _ = refind::ensure_same_hasher(&ids[0], stored, &hash)?;
+                }
+                checked = true;
+            }
+
+            let ids = match dangling.remove(&hash) {
+                Some(ids) => ids,
+                None      => continue,
+            };
+
+            for id in ids {
+                if let Some(mut entry) = self.get(id.clone())? {
+                    let hash = String::from(entry.get_hash()?);
+                    let _    = entry.make_ref(hash, &candidate, config)?;
+                    relocated.push((id, candidate.clone()));
+                }
+            }
         }
+
+        Ok(relocated)
     }
 
 }
 
+/// Hash `path` with `RPG`, taking the snapshot of a directory on the way
+fn hash_path<RPG: UniqueRefPathGenerator>(path: &Path) -> Result<(String, Option<Snapshot>)> {
+    if path.is_dir() {
+        let snapshot = Snapshot::of::<RPG, _>(path)?;
+        let hash     = RPG::unique_hash_of_snapshot(path, &snapshot)?;
+        Ok((hash, Some(snapshot)))
+    } else {
+        RPG::unique_hash(path).map(|hash| (hash, None))
+    }
+}
+
+fn create_ref_with_hash<'a, RPG>(store: &'a Store, hash: String, snapshot: Option<Snapshot>, path: &Path, config: &Config)
+    -> Result<FileLockEntry<'a>>
+    where RPG: UniqueRefPathGenerator
+{
+    let pathbuf = PathBuf::from(format!("{}/{}", RPG::collection(), hash));
+    let sid     = StoreId::new_baseless(pathbuf)?;
+
+    debug!("Creating: {:?}", sid);
+    let mut fle = store.create(sid)?;
+    fle.make_ref(hash, path, config)?;
+
+    // The ref is usable without the metadata, it can be recorded later with a refresh
+    if let Err(e) = fle.update_metadata(config) {
+        warn!("Could not record the metadata of the file referenced by '{}': {}", fle.get_location(), e);
+    }
+
+    if let Some(snapshot) = snapshot {
+        fle.record_tree(store, &snapshot)?;
+    }
+
+    Ok(fle)
+}
@@ -0,0 +1,497 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+//! Snapshots of referenced directory trees
+//!
+//! A snapshot maps the path of each file below a directory, relative to the directory, to the hash
+//! of the file. It is recorded when a ref to a directory is created, so `Ref::has_changed()` can
+//! later tell which files were added, removed or modified.
+//!
+//! Snapshots of large trees do not belong into the header of the ref, so each snapshot is stored in
+//! an entry of its own in the `reftree` collection, see `Snapshot::save()`. The header of the ref
+//! only records the number of files in the snapshot, in `ref.tree.files`.
+//!
+//! Files can be excluded by listing glob patterns in a `.imagignore` file in the referenced
+//! directory, one per line. Patterns without a `/` match the name of a file or directory anywhere
+//! in the tree (like `target` or `*.o`), patterns with a `/` match the path relative to the
+//! referenced directory (like `build/*.log`). Empty lines and lines starting with `#` are ignored.
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::path::Path;
+use std::path::PathBuf;
+
+use glob::MatchOptions;
+use glob::Pattern;
+use toml::Value;
+use toml_query::read::TomlValueReadExt;
+use toml_query::insert::TomlValueInsertExt;
+use walkdir::WalkDir;
+use failure::Fallible as Result;
+use failure::Error;
+
+use libimagerror::errors::ErrorMsg as EM;
+use libimagstore::store::Store;
+use libimagstore::storeid::StoreId;
+
+use refstore::UniqueRefPathGenerator;
+
+/// The name of the file in a referenced directory which lists the ignore patterns
+pub const IGNORE_FILE : &str = ".imagignore";
+
+/// The collection the snapshots are stored in
+pub const SNAPSHOT_COLLECTION : &str = "reftree";
+
+/// The hashes of all files below a directory, by path relative to the directory
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Snapshot(BTreeMap<String, String>);
+
+impl Snapshot {
+
+    /// Take a snapshot of `path`, hashing each file with `RPG`
+    ///
+    /// If `path` is a file, the snapshot contains only that file, by its name.
+    pub fn of<RPG: UniqueRefPathGenerator, P: AsRef<Path>>(path: P) -> Result<Snapshot> {
+        let root = path.as_ref();
+
+        if !root.is_dir() {
+            return Snapshot::of_file_with_hash(root, RPG::unique_hash(root)?)
+        }
+
+        let ignore    = Ignore::from_dir(root)?;
+        let mut files = BTreeMap::new();
+        let walker    = WalkDir::new(root)
+            .follow_links(false)
+            .min_depth(1)
+            .into_iter()
+            .filter_entry(|entry| {
+                entry.path()
+                    .strip_prefix(root)
+                    .ok()
+                    .and_then(relative_path)
+                    .map(|rel| !ignore.is_ignored(&rel))
+                    .unwrap_or(true)
+            });
+
+        for entry in walker {
+            let entry = entry?;
+            if !entry.file_type().is_file() {
+                continue;
+            }
+
+            let rel = entry.path()
+                .strip_prefix(root)
+                .ok()
+                .and_then(relative_path)
+                .ok_or_else(|| Error::from(EM::UTF8Error))?;
+
+            files.insert(rel, RPG::unique_hash(entry.path())?);
+        }
+
+        Ok(Snapshot(files))
+    }
+
+    /// Build the snapshot of the file at `path` with a known hash, without reading the file
+    pub fn of_file_with_hash<P: AsRef<Path>>(path: P, hash: String) -> Result<Snapshot> {
+        let name = path.as_ref()
+            .file_name()
+            .and_then(|n| n.to_str())
+            .map(String::from)
+            .ok_or_else(|| Error::from(EM::UTF8Error))?;
+
+        let mut files = BTreeMap::new();
+        files.insert(name, hash);
+        Ok(Snapshot(files))
+    }
+
+    /// The files in the snapshot with their hashes
+    pub fn files(&self) -> &BTreeMap<String, String> {
+        &self.0
+    }
+
+    /// The number of files in the snapshot
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The id of the entry the snapshot for the ref `ref_id` is stored in
+    ///
+    /// This is the id of the ref in the `reftree` collection, for example `reftree/ref/<hash>`.
+    pub fn id_for(ref_id: &StoreId) -> Result<StoreId> {
+        StoreId::new_baseless(PathBuf::from(SNAPSHOT_COLLECTION).join(ref_id.local()))
+    }
+
+    /// Store the snapshot for the ref `ref_id`, replacing the one stored before
+    pub fn save(&self, store: &Store, ref_id: &StoreId) -> Result<()> {
+        let tree = self.0
+            .iter()
+            .map(|(path, hash)| (path.clone(), Value::String(hash.clone())))
+            .collect();
+
+        let mut entry = store.retrieve(Snapshot::id_for(ref_id)?)?;
+        let _ = entry.get_header_mut().insert("reftree.files", Value::Table(tree))?;
+        Ok(())
+    }
+
+    /// Load the snapshot stored for the ref `ref_id`
+    ///
+    /// Returns `None` if no snapshot was stored.
+    pub fn load(store: &Store, ref_id: &StoreId) -> Result<Option<Snapshot>> {
+        let entry = match store.get(Snapshot::id_for(ref_id)?)? {
+            Some(entry) => entry,
+            None        => return Ok(None),
+        };
+
+        let tree = match entry.get_header().read("reftree.files").map_err(Error::from)? {
+            Some(&Value::Table(ref tree)) => tree.clone(),
+            Some(_) => return Err(Error::from(EM::EntryHeaderTypeError2("reftree.files", "table"))),
+            None    => return Err(Error::from(EM::EntryHeaderFieldMissing("reftree.files"))),
+        };
+
+        tree.into_iter()
+            .map(|(path, hash)| match hash {
+                Value::String(hash) => Ok((path, hash)),
+                _ => Err(Error::from(EM::EntryHeaderTypeError2("reftree.files", "table of strings"))),
+            })
+            .collect::<Result<BTreeMap<_, _>>>()
+            .map(Snapshot)
+            .map(Some)
+    }
+
+    /// Delete the snapshot stored for the ref `ref_id`, if there is one
+    pub fn delete(store: &Store, ref_id: &StoreId) -> Result<()> {
+        let id = Snapshot::id_for(ref_id)?;
+        if store.exists(id.clone())? {
+            let _ = store.delete(id)?;
+        }
+        Ok(())
+    }
+
+    /// Compare this snapshot to a more recent one
+    pub fn diff(&self, current: &Snapshot) -> Changes {
+        let mut changes = Changes::default();
+
+        for (path, hash) in current.0.iter() {
+            match self.0.get(path) {
+                None                     => changes.added.push(path.clone()),
+                Some(old) if old != hash => changes.modified.push(path.clone()),
+                Some(_)                  => {},
+            }
+        }
+
+        changes.removed = self.0
+            .keys()
+            .filter(|path| !current.0.contains_key(*path))
+            .cloned()
+            .collect();
+
+        changes
+    }
+
+}
+
+/// The files which changed between two snapshots, by path relative to the referenced directory
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Changes {
+    added: Vec<String>,
+    removed: Vec<String>,
+    modified: Vec<String>,
+}
+
+impl Changes {
+
+    pub fn added(&self) -> &[String] {
+        &self.added
+    }
+
+    pub fn removed(&self) -> &[String] {
+        &self.removed
+    }
+
+    pub fn modified(&self) -> &[String] {
+        &self.modified
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+
+}
+
+/// The ignore patterns of a referenced directory, read from its `.imagignore` file
+///
+/// Each pattern is stored with whether it matches the whole relative path or only the name.
+#[derive(Debug, Default)]
+pub struct Ignore(Vec<(Pattern, bool)>);
+
+impl Ignore {
+
+    /// Read the ignore patterns of `dir`
+    ///
+    /// If there is no `.imagignore` file, nothing is ignored.
+    pub fn from_dir<P: AsRef<Path>>(dir: P) -> Result<Ignore> {
+        let path = dir.as_ref().join(IGNORE_FILE);
+        if !path.is_file() {
+            return Ok(Ignore::default())
+        }
+
+        let mut patterns = vec![];
+        for line in BufReader::new(File::open(path)?).lines() {
+            let line = line?;
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let pattern = line.trim_end_matches('/');
+            patterns.push((Pattern::new(pattern)?, pattern.contains('/')));
+        }
+
+        Ok(Ignore(patterns))
+    }
+
+    /// Check whether the path, relative to the referenced directory, is ignored
+    pub fn is_ignored(&self, rel: &str) -> bool {
+        let options = MatchOptions {
+            case_sensitive: true,
+            require_literal_separator: true,
+            require_literal_leading_dot: false,
+        };
+
+        let name = rel.rsplit('/').next().unwrap_or(rel);
+
+        self.0.iter().any(|&(ref pattern, whole_path)| if whole_path {
+            pattern.matches_with(rel, &options)
+        } else {
+            pattern.matches_with(name, &options)
+        })
+    }
+
+}
+
+/// Build the `/` separated relative path used as key in snapshots
+fn relative_path(path: &Path) -> Option<String> {
+    path.components()
+        .map(|c| c.as_os_str().to_str())
+        .collect::<Option<Vec<_>>>()
+        .map(|parts| parts.join("/"))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    use tempdir::TempDir;
+
+    use libimagstore::store::Store;
+    use libimagstore::file_abstraction::InMemoryFileAbstraction;
+
+    use reference::Config;
+    use reference::Ref;
+    use refstore::RefStore;
+    use refstore::UniqueRefPathGenerator;
+    use super::*;
+
+    /// Uses the content of a file as its hash, so the tests do not depend on a generator feature
+    struct ContentHash;
+
+    impl UniqueRefPathGenerator for ContentHash {
+        fn unique_hash<A: AsRef<Path>>(path: A) -> Result<String> {
+            fs::read_to_string(path).map_err(Error::from)
+        }
+
+        fn unique_hash_of_snapshot<A: AsRef<Path>>(_path: A, snapshot: &Snapshot) -> Result<String> {
+            Ok(format!("tree-{}", snapshot.len()))
+        }
+    }
+
+    fn get_store() -> Store {
+        let backend = Arc::new(InMemoryFileAbstraction::default());
+        Store::new_with_backend(PathBuf::from("/"), &None, backend).unwrap()
+    }
+
+    fn write(dir: &Path, name: &str, content: &str) {
+        let path = dir.join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, content).unwrap();
+    }
+
+    fn snapshot(files: &[(&str, &str)]) -> Snapshot {
+        Snapshot(files.iter().map(|&(path, hash)| (String::from(path), String::from(hash))).collect())
+    }
+
+    fn strings(v: &[&str]) -> Vec<String> {
+        v.iter().map(|s| String::from(*s)).collect()
+    }
+
+    #[test]
+    fn test_ignore() {
+        let tmp = TempDir::new("imag-ref-tree").unwrap();
+        write(tmp.path(), IGNORE_FILE, "# build output\n\ntarget\n*.o\nbuild/*.log\ndocs/\n");
+
+        let ignore = Ignore::from_dir(tmp.path()).unwrap();
+
+        // Patterns without a slash match names anywhere
+        assert!(ignore.is_ignored("target"));
+        assert!(ignore.is_ignored("src/target"));
+        assert!(ignore.is_ignored("a.o"));
+        assert!(ignore.is_ignored("src/a.o"));
+        assert!(ignore.is_ignored(".hidden.o"));
+        assert!(ignore.is_ignored("docs"));
+        assert!(ignore.is_ignored("src/docs"));
+
+        // Patterns with a slash match the whole relative path
+        assert!(ignore.is_ignored("build/x.log"));
+        assert!(!ignore.is_ignored("build/sub/x.log"));
+        assert!(!ignore.is_ignored("src/build/x.log"));
+
+        assert!(!ignore.is_ignored("README"));
+        assert!(!ignore.is_ignored("target.txt"));
+        assert!(!ignore.is_ignored("# build output"));
+    }
+
+    #[test]
+    fn test_ignore_without_file() {
+        let tmp    = TempDir::new("imag-ref-tree").unwrap();
+        let ignore = Ignore::from_dir(tmp.path()).unwrap();
+
+        assert!(!ignore.is_ignored("target"));
+        assert!(!ignore.is_ignored("a/b.o"));
+    }
+
+    #[test]
+    fn test_ignore_invalid_pattern() {
+        let tmp = TempDir::new("imag-ref-tree").unwrap();
+        write(tmp.path(), IGNORE_FILE, "[\n");
+
+        assert!(Ignore::from_dir(tmp.path()).is_err());
+    }
+
+    #[test]
+    fn test_snapshot_of() {
+        let tmp = TempDir::new("imag-ref-tree").unwrap();
+        write(tmp.path(), IGNORE_FILE, "target\n*.o\n");
+        write(tmp.path(), "a.txt", "a");
+        write(tmp.path(), "src/main.rs", "main");
+        write(tmp.path(), "src/main.o", "object");
+        write(tmp.path(), "target/debug/out", "out");
+        fs::create_dir_all(tmp.path().join("empty")).unwrap();
+
+        let tree = Snapshot::of::<ContentHash, _>(tmp.path()).unwrap();
+
+        assert_eq!(tree, snapshot(&[
+            (IGNORE_FILE, "target\n*.o\n"),
+            ("a.txt", "a"),
+            ("src/main.rs", "main"),
+        ]));
+
+        let file = Snapshot::of::<ContentHash, _>(tmp.path().join("src/main.rs")).unwrap();
+        assert_eq!(file, snapshot(&[("main.rs", "main")]));
+    }
+
+    #[test]
+    fn test_diff() {
+        let old = snapshot(&[("a", "1"), ("b", "2"), ("c", "3"), ("dir/e", "5")]);
+        let new = snapshot(&[("a", "1"), ("b", "9"), ("d", "4"), ("dir/e", "5"), ("dir/f", "6")]);
+
+        let changes = old.diff(&new);
+        assert_eq!(changes.added(), &strings(&["d", "dir/f"])[..]);
+        assert_eq!(changes.removed(), &strings(&["c"])[..]);
+        assert_eq!(changes.modified(), &strings(&["b"])[..]);
+        assert!(!changes.is_empty());
+
+        assert!(old.diff(&old).is_empty());
+        assert_eq!(Snapshot::default().diff(&old).added().len(), 4);
+        assert_eq!(old.diff(&Snapshot::default()).removed().len(), 4);
+    }
+
+    #[test]
+    fn test_save_load_delete() {
+        let store  = get_store();
+        let ref_id = StoreId::new_baseless(PathBuf::from("ref/abc")).unwrap();
+
+        assert_eq!(Snapshot::id_for(&ref_id).unwrap(), StoreId::new_baseless(PathBuf::from("reftree/ref/abc")).unwrap());
+        assert_eq!(Snapshot::load(&store, &ref_id).unwrap(), None);
+
+        let first = snapshot(&[("a", "1"), ("dir/b", "2")]);
+        first.save(&store, &ref_id).unwrap();
+        assert_eq!(Snapshot::load(&store, &ref_id).unwrap(), Some(first));
+
+        // Saving again replaces the snapshot, files which are gone do not linger
+        let second = snapshot(&[("dir/b", "3")]);
+        second.save(&store, &ref_id).unwrap();
+        assert_eq!(Snapshot::load(&store, &ref_id).unwrap(), Some(second));
+
+        Snapshot::delete(&store, &ref_id).unwrap();
+        assert_eq!(Snapshot::load(&store, &ref_id).unwrap(), None);
+        assert!(Snapshot::delete(&store, &ref_id).is_ok());
+    }
+
+    #[test]
+    fn test_ref_to_dir_has_changed() {
+        let tmp    = TempDir::new("imag-ref-tree").unwrap();
+        let store  = get_store();
+        let config = Config::default();
+        write(tmp.path(), "a.txt", "a");
+        write(tmp.path(), "b.txt", "b");
+        write(tmp.path(), "sub/c.txt", "c");
+
+        let entry = store.create_ref::<ContentHash, _>(tmp.path(), &config).unwrap();
+        assert_eq!(entry.get_hash().unwrap(), "tree-3");
+
+        // Only the number of files is in the header, the snapshot is an entry of its own
+        assert_eq!(entry.get_header().read("ref.tree.files").unwrap(), Some(&Value::Integer(3)));
+        assert!(store.exists(Snapshot::id_for(entry.get_location()).unwrap()).unwrap());
+        assert!(entry.has_changed::<ContentHash>(&store, &config).unwrap().is_empty());
+
+        write(tmp.path(), "a.txt", "changed");
+        write(tmp.path(), "sub/d.txt", "d");
+        fs::remove_file(tmp.path().join("b.txt")).unwrap();
+
+        let changes = entry.has_changed::<ContentHash>(&store, &config).unwrap();
+        assert_eq!(changes.added(), &strings(&["sub/d.txt"])[..]);
+        assert_eq!(changes.removed(), &strings(&["b.txt"])[..]);
+        assert_eq!(changes.modified(), &strings(&["a.txt"])[..]);
+    }
+
+    #[test]
+    fn test_ref_to_dir_without_snapshot() {
+        let tmp    = TempDir::new("imag-ref-tree").unwrap();
+        let store  = get_store();
+        let config = Config::default();
+        write(tmp.path(), "a.txt", "a");
+
+        let entry = store.create_ref::<ContentHash, _>(tmp.path(), &config).unwrap();
+        Snapshot::delete(&store, entry.get_location()).unwrap();
+
+        assert!(entry.has_changed::<ContentHash>(&store, &config).is_err());
+    }
+
+}
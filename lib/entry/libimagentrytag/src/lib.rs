@@ -52,4 +52,5 @@ extern crate libimagerror;
 
 pub mod tag;
 pub mod tagable;
+pub mod store;
 
@@ -0,0 +1,303 @@
+//
+// imag - the personal information management suite for the commandline
+// Copyright (C) 2015-2018 Matthias Beyer <mail@beyermatthias.de> and contributors
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; version
+// 2.1 of the License.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+//
+
+
+use std::collections::BTreeMap;
+
+use libimagstore::store::Store;
+use libimagstore::storeid::StoreId;
+
+use failure::Fallible as Result;
+
+use tag::{Tag, TagSlice};
+use tag::is_tag_str;
+use tag::is_in_subtree;
+use tagable::Tagable;
+
+/// How often tags are used, per collection
+///
+/// Entries which are not in a collection are counted for the collection "".
+#[derive(Debug, Default)]
+pub struct TagStats(BTreeMap<Tag, BTreeMap<String, usize>>);
+
+impl TagStats {
+
+    /// The tags which are used by at least one entry
+    pub fn tags(&self) -> Vec<&Tag> {
+        self.0.keys().collect()
+    }
+
+    /// The number of entries which are tagged with `tag`
+    pub fn count(&self, tag: TagSlice) -> usize {
+        self.0.get(tag).map(|c| c.values().sum()).unwrap_or(0)
+    }
+
+    /// The number of entries which are tagged with `tag`, per collection
+    pub fn count_by_collection(&self, tag: TagSlice) -> Option<&BTreeMap<String, usize>> {
+        self.0.get(tag)
+    }
+
+    /// The tags which are used by at most `max` entries
+    pub fn rare(&self, max: usize) -> Vec<&Tag> {
+        self.0.keys().filter(|tag| self.count(tag) <= max).collect()
+    }
+
+    /// The tags of `known` which are not used by any entry
+    ///
+    /// A tag counts as used if any tag in its subtree is used.
+    pub fn unused<'a>(&self, known: &'a [Tag]) -> Vec<&'a Tag> {
+        known
+            .iter()
+            .filter(|k| !self.0.keys().any(|tag| is_in_subtree(tag, k)))
+            .collect()
+    }
+
+}
+
+pub trait TagStore {
+
+    /// Rename the tag `old` to `new` in all entries
+    ///
+    /// The tags below `old` are moved below `new`, so renaming "work" to "job" renames
+    /// "work/meetings" to "job/meetings". Returns the IDs of the changed entries.
+    fn rename_tag(&self, old: TagSlice, new: TagSlice) -> Result<Vec<StoreId>>;
+
+    /// Replace the tags `tags` (and the tags below them) with `into` in all entries
+    ///
+    /// Returns the IDs of the changed entries.
+    fn merge_tags(&self, tags: &[Tag], into: TagSlice) -> Result<Vec<StoreId>>;
+
+    /// Count how often each tag is used
+    fn tag_stats(&self) -> Result<TagStats>;
+
+}
+
+impl TagStore for Store {
+
+    fn rename_tag(&self, old: TagSlice, new: TagSlice) -> Result<Vec<StoreId>> {
+        self.merge_tags(&[String::from(old)], new)
+    }
+
+    fn merge_tags(&self, tags: &[Tag], into: TagSlice) -> Result<Vec<StoreId>> {
+        let into_tag = String::from(into);
+        for tag in tags.iter().chain(Some(&into_tag)) {
+            let _ = is_tag_str(tag).map_err(|s| format_err!("{}", s))?;
+        }
+
+        // Collect all changes before altering any entry, so that an entry with invalid tags does
+        // not leave the store with the tags changed only partially
+        let mut changes = vec![];
+        let mut counter = 0;
+        for entry in self.entries()?.into_get_iter() {
+            if let Some(entry) = entry? {
+                let old_tags = entry.get_tags()?;
+                let new_tags = old_tags.iter().map(|t| retag(t, tags, into)).collect::<Vec<_>>();
+
+                if new_tags != old_tags {
+                    debug!("Retagging {}: {:?} -> {:?}", entry.get_location(), old_tags, new_tags);
+                    changes.push((entry.get_location().clone(), new_tags));
+                }
+            }
+
+            // Do not let the cache grow indefinitely on large stores
+            counter = flush_cache_periodically(self, counter)?;
+        }
+
+        let mut changed = vec![];
+        for (id, new_tags) in changes {
+            {
+                let mut entry = self
+                    .get(id.clone())?
+                    .ok_or_else(|| format_err!("Entry vanished: {}", id))?;
+
+                entry.set_tags(&new_tags)?;
+            }
+
+            changed.push(id);
+            counter = flush_cache_periodically(self, counter)?;
+        }
+
+        Ok(changed)
+    }
+
+    fn tag_stats(&self) -> Result<TagStats> {
+        let mut stats   = TagStats::default();
+        let mut counter = 0;
+
+        for entry in self.entries()?.into_get_iter() {
+            if let Some(entry) = entry? {
+                let collection = collection_of(entry.get_location());
+                for tag in entry.get_tags()? {
+                    *stats.0
+                        .entry(tag)
+                        .or_insert_with(BTreeMap::new)
+                        .entry(collection.clone())
+                        .or_insert(0) += 1;
+                }
+            }
+
+            // Do not let the cache grow indefinitely on large stores
+            counter = flush_cache_periodically(self, counter)?;
+        }
+
+        Ok(stats)
+    }
+
+}
+
+/// Count the entries read from the store and flush the cache of the store each 100 entries
+///
+/// Returns the new count. No entry may be borrowed when this is called.
+fn flush_cache_periodically(store: &Store, counter: usize) -> Result<usize> {
+    if counter >= 100 {
+        let _ = store.flush_cache()?;
+        Ok(0)
+    } else {
+        Ok(counter + 1)
+    }
+}
+
+/// Replace the part of `tag` which is in the subtree of one of `from` with `into`
+fn retag(tag: &Tag, from: &[Tag], into: TagSlice) -> Tag {
+    from.iter()
+        .find(|f| is_in_subtree(tag, f))
+        .map(|f| format!("{}{}", into, &tag[f.len()..]))
+        .unwrap_or_else(|| tag.clone())
+}
+
+/// Get the name of the collection of an entry, or "" if it is not in a collection
+fn collection_of(id: &StoreId) -> String {
+    let local = id.local();
+
+    if local.components().count() > 1 {
+        local.components()
+            .next()
+            .map(|c| c.as_os_str().to_string_lossy().into_owned())
+            .unwrap_or_else(String::new)
+    } else {
+        String::new()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    use libimagstore::store::Store;
+    use libimagstore::file_abstraction::InMemoryFileAbstraction;
+
+    use tagable::Tagable;
+    use super::*;
+
+    fn get_store() -> Store {
+        let backend = Arc::new(InMemoryFileAbstraction::default());
+        Store::new_with_backend(PathBuf::from("/"), &None, backend).unwrap()
+    }
+
+    fn tag(store: &Store, id: &str, tags: &[&str]) {
+        let tags = tags.iter().map(|t| String::from(*t)).collect::<Vec<_>>();
+        store.create(PathBuf::from(id)).unwrap().set_tags(&tags).unwrap();
+    }
+
+    fn tags_of(store: &Store, id: &str) -> Vec<Tag> {
+        let mut tags = store.get(PathBuf::from(id)).unwrap().unwrap().get_tags().unwrap();
+        tags.sort();
+        tags
+    }
+
+    fn tagged_store() -> Store {
+        let store = get_store();
+        tag(&store, "notes/a", &["work", "work/meeting"]);
+        tag(&store, "notes/b", &["work"]);
+        tag(&store, "c", &["home"]);
+        store
+    }
+
+    #[test]
+    fn test_tag_stats() {
+        let store = tagged_store();
+        let stats = store.tag_stats().unwrap();
+
+        assert_eq!(stats.tags(), vec!["home", "work", "work/meeting"]);
+        assert_eq!(stats.count("work"), 2);
+        assert_eq!(stats.count("home"), 1);
+        assert_eq!(stats.count("travel"), 0);
+
+        let by_collection = stats.count_by_collection("work").unwrap();
+        assert_eq!(by_collection.get("notes"), Some(&2));
+        assert_eq!(stats.count_by_collection("home").unwrap().get(""), Some(&1));
+    }
+
+    #[test]
+    fn test_rare_and_unused() {
+        let store = tagged_store();
+        let stats = store.tag_stats().unwrap();
+
+        assert_eq!(stats.rare(1), vec!["home", "work/meeting"]);
+        assert!(stats.rare(0).is_empty());
+
+        let known = vec![String::from("work"), String::from("travel"), String::from("work/review")];
+        assert_eq!(stats.unused(&known), vec!["travel", "work/review"]);
+        assert!(stats.unused(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_merge_tags() {
+        let store   = tagged_store();
+        let mut ids = store
+            .merge_tags(&[String::from("work"), String::from("home")], "job")
+            .unwrap()
+            .into_iter()
+            .map(|id| id.local_display_string())
+            .collect::<Vec<_>>();
+        ids.sort();
+
+        assert_eq!(ids, vec!["c", "notes/a", "notes/b"]);
+        assert_eq!(tags_of(&store, "notes/a"), vec!["job", "job/meeting"]);
+        assert_eq!(tags_of(&store, "notes/b"), vec!["job"]);
+        assert_eq!(tags_of(&store, "c"), vec!["job"]);
+    }
+
+    #[test]
+    fn test_rename_tag_keeps_similar_tags() {
+        let store = get_store();
+        tag(&store, "a", &["work", "workshop"]);
+
+        let changed = store.rename_tag("work", "job").unwrap();
+        assert_eq!(changed.len(), 1);
+        assert_eq!(tags_of(&store, "a"), vec!["job", "workshop"]);
+
+        assert!(store.rename_tag("job", "not a tag").is_err());
+        assert_eq!(tags_of(&store, "a"), vec!["job", "workshop"]);
+    }
+
+    #[test]
+    fn test_stats_and_merge_on_many_entries() {
+        let store = get_store();
+        for i in 0..250 {
+            tag(&store, &format!("entry-{}", i), &["many"]);
+        }
+
+        assert_eq!(store.tag_stats().unwrap().count("many"), 250);
+        assert_eq!(store.merge_tags(&[String::from("many")], "all").unwrap().len(), 250);
+        assert_eq!(store.tag_stats().unwrap().count("all"), 250);
+    }
+}
@@ -24,23 +24,158 @@ use regex::Regex;
 pub type Tag = String;
 pub type TagSlice<'a> = &'a str;
 
+/// The separator of the parts of a hierarchical tag, as in "project/imag/bugs"
+pub const TAG_SEPARATOR: char = '/';
+
 /// validator which can be used by clap to validate that a string is a valid tag
 pub fn is_tag(s: String) -> Result<(), String> {
     is_tag_str(&s)
 }
 
+/// Check whether a string is a valid tag
+///
+/// A tag consists of one or more parts, separated by `TAG_SEPARATOR`. Each part is a lowercase
+/// word.
 pub fn is_tag_str(s: &String) -> Result<(), String> {
+    if s.split(TAG_SEPARATOR).all(is_tag_part) {
+        Ok(())
+    } else {
+        Err(format!("The string '{}' is not a valid tag", s))
+    }
+}
+
+fn is_tag_part(s: &str) -> bool {
     use filters::filter::Filter;
 
-    let is_lower      = |s: &String| s.chars().all(|c| c.is_lowercase());
-    let no_whitespace = |s: &String| s.chars().all(|c| !c.is_whitespace());
-    let is_alphanum   = |s: &String| s.chars().all(|c| c.is_alphanumeric());
-    let matches_regex = |s: &String| Regex::new("^[a-zA-Z]([a-zA-Z0-9_-]*)$").unwrap().captures(s).is_some();
+    let is_lower      = |s: &&str| s.chars().all(|c| c.is_lowercase());
+    let no_whitespace = |s: &&str| s.chars().all(|c| !c.is_whitespace());
+    let is_alphanum   = |s: &&str| s.chars().all(|c| c.is_alphanumeric());
+    let matches_regex = |s: &&str| Regex::new("^[a-zA-Z]([a-zA-Z0-9_-]*)$").unwrap().captures(s).is_some();
+
+    is_lower.and(no_whitespace).and(is_alphanum).and(matches_regex).filter(&s)
+}
+
+/// validator which can be used by clap to validate that a string is a valid tag pattern
+///
+/// See `tag_matches()` for the pattern syntax.
+pub fn is_tag_pattern(s: String) -> Result<(), String> {
+    let parts = s.split(TAG_SEPARATOR).collect::<Vec<_>>();
+    let valid = parts
+        .iter()
+        .enumerate()
+        .all(|(i, part)| *part == "*" || (*part == "**" && i == parts.len() - 1) || is_tag_part(part));
 
-    if is_lower.and(no_whitespace).and(is_alphanum).and(matches_regex).filter(s) {
+    if valid {
         Ok(())
     } else {
-        Err(format!("The string '{}' is not a valid tag", s))
+        Err(format!("The string '{}' is not a valid tag pattern", s))
     }
 }
 
+/// Get the parent of a hierarchical tag, for example "project/imag" for "project/imag/bugs"
+pub fn parent_tag(t: TagSlice) -> Option<TagSlice> {
+    t.rfind(TAG_SEPARATOR).map(|i| &t[..i])
+}
+
+/// Check whether `t` is `root` or a tag below `root`
+///
+/// "project/imag" is in the subtree of "project", but "projects" is not.
+pub fn is_in_subtree(t: TagSlice, root: TagSlice) -> bool {
+    t == root || (t.starts_with(root) && t[root.len()..].starts_with(TAG_SEPARATOR))
+}
+
+/// Check whether `t` matches `pattern`
+///
+/// A pattern is a tag in which parts can be replaced by `*`, which matches exactly one part. If
+/// the last part of the pattern is `**`, it matches the rest of the pattern and everything below
+/// it, so "project/**" matches "project", "project/imag" and "project/imag/bugs".
+/// A pattern without wildcards matches only the tag itself.
+pub fn tag_matches(t: TagSlice, pattern: &str) -> bool {
+    let mut tag_parts     = t.split(TAG_SEPARATOR);
+    let mut pattern_parts = pattern.split(TAG_SEPARATOR).peekable();
+
+    loop {
+        match (pattern_parts.next(), tag_parts.next()) {
+            (Some("**"), _) if pattern_parts.peek().is_none() => return true,
+            (Some("*"), Some(_))                              => continue,
+            (Some(p), Some(part))                             => if p != part { return false },
+            (None, None)                                      => return true,
+            _                                                 => return false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_tag() {
+        assert!(is_tag(String::from("work")).is_ok());
+        assert!(is_tag(String::from("project/imag/bugs")).is_ok());
+        assert!(is_tag(String::from("Work")).is_err());
+        assert!(is_tag(String::from("project//imag")).is_err());
+        assert!(is_tag(String::from("project/")).is_err());
+        assert!(is_tag(String::from("project/*")).is_err());
+    }
+
+    #[test]
+    fn test_is_tag_pattern() {
+        assert!(is_tag_pattern(String::from("work")).is_ok());
+        assert!(is_tag_pattern(String::from("project/*/bugs")).is_ok());
+        assert!(is_tag_pattern(String::from("project/**")).is_ok());
+        assert!(is_tag_pattern(String::from("**")).is_ok());
+        assert!(is_tag_pattern(String::from("project/**/bugs")).is_err());
+        assert!(is_tag_pattern(String::from("project/i*")).is_err());
+        assert!(is_tag_pattern(String::from("project/")).is_err());
+    }
+
+    #[test]
+    fn test_parent_tag() {
+        assert_eq!(parent_tag("project/imag/bugs"), Some("project/imag"));
+        assert_eq!(parent_tag("project/imag"), Some("project"));
+        assert_eq!(parent_tag("project"), None);
+    }
+
+    #[test]
+    fn test_is_in_subtree() {
+        assert!(is_in_subtree("project", "project"));
+        assert!(is_in_subtree("project/imag", "project"));
+        assert!(is_in_subtree("project/imag/bugs", "project"));
+        assert!(is_in_subtree("project/imag/bugs", "project/imag"));
+        assert!(!is_in_subtree("projects", "project"));
+        assert!(!is_in_subtree("project", "project/imag"));
+        assert!(!is_in_subtree("work/project", "project"));
+    }
+
+    #[test]
+    fn test_tag_matches_without_wildcards() {
+        assert!(tag_matches("project", "project"));
+        assert!(tag_matches("project/imag", "project/imag"));
+        assert!(!tag_matches("project/imag", "project"));
+        assert!(!tag_matches("project", "project/imag"));
+        assert!(!tag_matches("projects", "project"));
+    }
+
+    #[test]
+    fn test_tag_matches_single_wildcard() {
+        assert!(tag_matches("project/imag", "project/*"));
+        assert!(tag_matches("project/imag/bugs", "project/*/bugs"));
+        assert!(tag_matches("work", "*"));
+        assert!(!tag_matches("project", "project/*"));
+        assert!(!tag_matches("project/imag/bugs", "project/*"));
+        assert!(!tag_matches("project/imag/features", "project/*/bugs"));
+    }
+
+    #[test]
+    fn test_tag_matches_subtree_wildcard() {
+        assert!(tag_matches("project", "project/**"));
+        assert!(tag_matches("project/imag", "project/**"));
+        assert!(tag_matches("project/imag/bugs", "project/**"));
+        assert!(tag_matches("work", "**"));
+        assert!(tag_matches("project/imag/bugs", "project/*/**"));
+        assert!(!tag_matches("projects", "project/**"));
+        assert!(!tag_matches("work/project", "project/**"));
+    }
+
+}
@@ -31,6 +31,8 @@ use failure::Fallible as Result;
 use failure::err_msg;
 use tag::{Tag, TagSlice};
 use tag::is_tag_str;
+use tag::is_in_subtree;
+use tag::tag_matches;
 
 use toml::Value;
 
@@ -45,6 +47,17 @@ pub trait Tagable {
     fn has_tag(&self, t: TagSlice) -> Result<bool>;
     fn has_tags(&self, ts: &[Tag]) -> Result<bool>;
 
+    /// Check whether the object has the tag `t` or a tag below it
+    ///
+    /// For example, an object tagged with "project/imag/bugs" has a tag in the subtree of
+    /// "project".
+    fn has_tag_in_subtree(&self, t: TagSlice) -> Result<bool>;
+
+    /// Check whether the object has a tag which matches `pattern`
+    ///
+    /// See `tag::tag_matches()` for the syntax of patterns.
+    fn has_tag_matching(&self, pattern: &str) -> Result<bool>;
+
 }
 
 impl Tagable for Value {
@@ -132,20 +145,7 @@ impl Tagable for Value {
     }
 
     fn has_tag(&self, t: TagSlice) -> Result<bool> {
-        let tags = self.read("tag.values").context(EM::EntryHeaderReadError)?;
-
-        if !tags.iter().all(|t| is_match!(*t, &Value::String(_))) {
-            return Err(err_msg("Tag type error"))
-        }
-
-        Ok(tags
-           .iter()
-           .any(|tag| {
-               match *tag {
-                   &Value::String(ref s) => { s == t },
-                   _ => unreachable!()
-               }
-           }))
+        Ok(self.get_tags()?.iter().any(|tag| tag == t))
     }
 
     fn has_tags(&self, tags: &[Tag]) -> Result<bool> {
@@ -157,6 +157,14 @@ impl Tagable for Value {
         Ok(result)
     }
 
+    fn has_tag_in_subtree(&self, t: TagSlice) -> Result<bool> {
+        Ok(self.get_tags()?.iter().any(|tag| is_in_subtree(tag, t)))
+    }
+
+    fn has_tag_matching(&self, pattern: &str) -> Result<bool> {
+        Ok(self.get_tags()?.iter().any(|tag| tag_matches(tag, pattern)))
+    }
+
 }
 
 impl Tagable for Entry {
@@ -185,5 +193,13 @@ impl Tagable for Entry {
         self.get_header().has_tags(ts)
     }
 
+    fn has_tag_in_subtree(&self, t: TagSlice) -> Result<bool> {
+        self.get_header().has_tag_in_subtree(t)
+    }
+
+    fn has_tag_matching(&self, pattern: &str) -> Result<bool> {
+        self.get_header().has_tag_matching(pattern)
+    }
+
 }
 
@@ -38,6 +38,7 @@
 )]
 
 extern crate interactor;
+#[macro_use] extern crate log;
 extern crate ansi_term;
 #[macro_use] extern crate lazy_static;
 extern crate regex;
@@ -45,7 +46,9 @@ extern crate clap;
 extern crate toml;
 extern crate handlebars;
 extern crate serde_json;
-extern crate failure;
+#[macro_use] extern crate failure;
+extern crate rustyline;
+extern crate toml_query;
 
 extern crate libimagstore;
 extern crate libimagerror;
@@ -53,5 +56,6 @@ extern crate libimagerror;
 pub mod ask;
 pub mod filter;
 pub mod format;
+pub mod readline;
 pub mod ui;
 
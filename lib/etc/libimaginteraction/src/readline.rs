@@ -17,74 +17,85 @@
 // Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
 //
 
+use std::path::PathBuf;
+use std::fs::OpenOptions;
 
+use failure::Error;
 use failure::ResultExt;
+use failure::Fallible as Result;
+use failure::err_msg;
 use toml::Value;
+use toml_query::read::TomlValueReadTypeExt;
 
-use rustyline::{Config, Editor};
+use rustyline::{Config, Editor, Helper};
+use rustyline::error::ReadlineError;
 
-pub struct Readline {
-    editor: Editor,
+/// A readline abstraction
+///
+/// Configured via the `ui.cli` section of the imag configuration file. The `Helper` type can be
+/// used to provide completion for the line which is read.
+pub struct Readline<H: Helper> {
+    editor: Editor<H>,
     history_file: PathBuf,
     prompt: String,
 }
 
-impl Readline {
+impl<H: Helper> Readline<H> {
+
+    /// Build a new Readline object from the configuration
+    ///
+    /// The following configuration settings are required:
+    ///
+    /// * `ui.cli.readline_history_file`
+    /// * `ui.cli.readline_history_size`
+    /// * `ui.cli.readline_history_ignore_dups`
+    /// * `ui.cli.readline_history_ignore_space`
+    /// * `ui.cli.readline_prompt`
+    ///
+    /// The history file is created if it does not exist.
+    pub fn new(config: &Value, helper: Option<H>) -> Result<Readline<H>> {
+        let histfile = config
+            .read_string("ui.cli.readline_history_file")?
+            .map(PathBuf::from)
+            .ok_or_else(|| err_msg("Missing config: 'ui.cli.readline_history_file'"))?;
 
-    pub fn new(rt: &Runtime) -> Result<Readline> {
-        let c = rt.config().ok_or(IEK::NoConfigError)?;
+        let histsize = config
+            .read_int("ui.cli.readline_history_size")?
+            .ok_or_else(|| err_msg("Missing config: 'ui.cli.readline_history_size'"))?;
 
-        let histfile     = c.lookup("ui.cli.readline_history_file").ok_or(IEK::ConfigError)?;
-        let histsize     = c.lookup("ui.cli.readline_history_size").ok_or(IEK::ConfigError)?;
-        let histigndups  = c.lookup("ui.cli.readline_history_ignore_dups").ok_or(IEK::ConfigError)?;
-        let histignspace = c.lookup("ui.cli.readline_history_ignore_space").ok_or(IEK::ConfigError)?;
-        let prompt       = c.lookup("ui.cli.readline_prompt").ok_or(IEK::ConfigError)?;
+        let histigndups = config
+            .read_bool("ui.cli.readline_history_ignore_dups")?
+            .ok_or_else(|| err_msg("Missing config: 'ui.cli.readline_history_ignore_dups'"))?;
 
-        let histfile = histfile
-            .as_str()
-            .map(PathBuf::from)
-            .ok_or(IE::from_kind(IEK::ConfigTypeError))
-            .context(IEK::ConfigError)
-            .context(IEK::ReadlineError)?;
-
-        let histsize = histsize
-            .as_int()
-            .ok_or(IE::from_kind(IEK::ConfigTypeError))
-            .context(IEK::ConfigError)
-            .context(IEK::ReadlineError)?;
-
-        let histigndups = histigndups
-            .as_bool()
-            .ok_or(IE::from_kind(IEK::ConfigTypeError))
-            .context(IEK::ConfigError)
-            .context(IEK::ReadlineError)?;
-
-        let histignspace = histignspace
-            .as_bool()
-            .ok_or(IE::from_kind(IEK::ConfigTypeError))
-            .context(IEK::ConfigError)
-            .context(IEK::ReadlineError)?;
-
-        let prompt = prompt
-            .as_str()
-            .ok_or(IE::from_kind(IEK::ConfigTypeError))
-            .context(IEK::ConfigError)
-            .context(IEK::ReadlineError)?;
-
-        let config = Config::builder().
-            .max_history_size(histsize)
+        let histignspace = config
+            .read_bool("ui.cli.readline_history_ignore_space")?
+            .ok_or_else(|| err_msg("Missing config: 'ui.cli.readline_history_ignore_space'"))?;
+
+        let prompt = config
+            .read_string("ui.cli.readline_prompt")?
+            .ok_or_else(|| err_msg("Missing config: 'ui.cli.readline_prompt'"))?;
+
+        let config = Config::builder()
+            .max_history_size(histsize as usize)
             .history_ignore_dups(histigndups)
             .history_ignore_space(histignspace)
             .build();
 
-        let mut editor = Editor::new(config);
+        let mut editor = Editor::with_config(config);
+        editor.set_helper(helper);
 
         if !histfile.exists() {
-            let _ = File::create(histfile.clone())
-                         .context(IEK::ReadlineHistoryFileCreationError)?;
+            let _ = OpenOptions::new()
+                .write(true)
+                .create(true)
+                .open(&histfile)
+                .context(err_msg("Could not create readline history file"))?;
         }
 
-        let _ = editor.load_history(&histfile).context(ReadlineError)?;
+        let _ = editor
+            .load_history(&histfile)
+            .map_err(|e| format_err!("{}", e))
+            .context(err_msg("Could not load readline history file"))?;
 
         Ok(Readline {
             editor: editor,
@@ -93,29 +104,32 @@ impl Readline {
         })
     }
 
+    /// Read a line
+    ///
+    /// Returns `None` if the user hit CTRL-D. If the user hit CTRL-C, an empty line is returned.
+    ///
+    /// The line is added to the history, which is saved to the history file right away.
     pub fn read_line(&mut self) -> Result<Option<String>> {
-        use rustyline::ReadlineError;
-        use libimagutil::warn_result::*;
-
         match self.editor.readline(&self.prompt) {
             Ok(line) => {
-                self.editor.add_history_line(&line);
-                self.editor
-                    .save_history(&self.history_file)
-                    .map_warn_err_str(|e| format!("Could not save history file {} -> {:?}",
-                                                  self.history_file.display(), e));
-                return line;
+                self.editor.add_history_entry(line.as_str());
+                if let Err(e) = self.editor.save_history(&self.history_file) {
+                    warn!("Could not save history file {} -> {:?}", self.history_file.display(), e);
+                }
+
+                Ok(Some(line))
             },
             Err(ReadlineError::Interrupted) => {
                 info!("CTRL-C");
-                Ok(None)
+                Ok(Some(String::new()))
             },
             Err(ReadlineError::Eof) => {
                 info!("CTRL-D");
                 Ok(None)
             },
-            Err(err) => Err(err).map_err_into(ReadlineError),
-
+            Err(e) => Err(format_err!("{}", e))
+                .context(err_msg("Readline error"))
+                .map_err(Error::from),
         }
     }
 
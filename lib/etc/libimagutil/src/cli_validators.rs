@@ -42,6 +42,13 @@ pub fn is_integer<A: AsRef<str>>(s: A) -> Result<(), String> {
     i.map(|_| ()).map_err(|_| format!("Not an integer: {}", s.as_ref()))
 }
 
+pub fn is_unsigned_integer<A: AsRef<str>>(s: A) -> Result<(), String> {
+    use std::str::FromStr;
+
+    let i : Result<u64, _> = FromStr::from_str(s.as_ref());
+    i.map(|_| ()).map_err(|_| format!("Not an unsigned integer: {}", s.as_ref()))
+}
+
 pub fn is_float<A: AsRef<str>>(s: A) -> Result<(), String> {
     use std::str::FromStr;
 
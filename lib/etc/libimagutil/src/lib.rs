@@ -43,6 +43,8 @@ extern crate boolinator;
 extern crate tempfile;
 extern crate chrono;
 
+extern crate libimagerror;
+
 #[macro_use] mod log_result;
 #[macro_use] mod log_option;
 pub mod cli_validators;
@@ -33,7 +33,7 @@
 /// ```
 ///
 pub fn warn_exit(s: &str, code: i32) -> ! {
-    use std::process::exit;
+    use libimagerror::exit::exit;
 
     warn!("{}", s);
     exit(code);
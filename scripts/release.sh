@@ -54,6 +54,7 @@ CRATES=(
     ./bin/core/imag-mv
     ./bin/core/imag-store
     ./bin/core/imag-tag
+    ./bin/core/imag-undo
     ./bin/core/imag-grep
     ./bin/core/imag-annotate
     ./bin/core/imag-link